@@ -0,0 +1,544 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use bincode::{Decode, Encode};
+
+use crate::{
+    disk_management::{
+        buffer_pool::{BufferPool, RawPage, PAGE_SIZE},
+        page_latch::{PageLatch, PageLatchGuard},
+    },
+    recovery::log_record::TransactionId,
+};
+
+use super::b_plus_tree_page::BPlusTreePage;
+use std::fmt::Debug;
+
+/// A B+ tree of (key, value) entries that may be used by several threads at once. The entries are
+/// ordered by key and value, so a key can occur any number of times with different values.
+///
+/// Threads synchronize with latch crabbing: a thread latches a child before it releases the
+/// latch of the parent, so that the child cannot be split in between. Readers and inserts into
+/// leaves with free space only read latch the inner nodes. An insert that has to split a leaf
+/// starts over and write latches the path from the header page down, releasing the ancestors
+/// of each node that does not split. Latches are only taken from the top down and from left to
+/// right, so they cannot deadlock.
+///
+/// Entries are removed without merging nodes, so underfull or empty leaves stay in the tree.
+///
+/// The header page stores | ROOT_PAGE_ID (4) | LEAF_MAX_SIZE (2) | INTERNAL_MAX_SIZE (2) |.
+pub struct BPlusTree<
+    K: Ord + Clone + Debug + Encode + Decode,
+    V: Ord + Clone + Debug + Encode + Decode,
+> {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    pub header_page_id: u32,
+    leaf_max_size: usize,
+    internal_max_size: usize,
+    phantom_data: PhantomData<(K, V)>,
+}
+
+impl<K: Ord + Clone + Debug + Encode + Decode, V: Ord + Clone + Debug + Encode + Decode>
+    BPlusTree<K, V>
+{
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>, header_page_id: u32) -> BPlusTree<K, V> {
+        let header = LatchedPage::read(&buffer_pool, header_page_id as usize);
+        let data = header.raw_data();
+        drop(header);
+        BPlusTree {
+            buffer_pool,
+            header_page_id,
+            leaf_max_size: u16::from_be_bytes(data[4..6].try_into().unwrap()) as usize,
+            internal_max_size: u16::from_be_bytes(data[6..8].try_into().unwrap()) as usize,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Creates an empty tree whose nodes fill whole pages.
+    pub fn setup_new_tree(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Option<TransactionId>,
+    ) -> Result<BPlusTree<K, V>, &'static str> {
+        BPlusTree::setup_new_tree_with_max_sizes(
+            buffer_pool,
+            BPlusTreePage::<K, V>::leaf_capacity(),
+            BPlusTreePage::<K, V>::internal_capacity(),
+            transaction,
+        )
+    }
+
+    /// Creates an empty tree whose nodes hold at most the given number of entries.
+    pub fn setup_new_tree_with_max_sizes(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        leaf_max_size: usize,
+        internal_max_size: usize,
+        transaction: Option<TransactionId>,
+    ) -> Result<BPlusTree<K, V>, &'static str> {
+        if !(2..=BPlusTreePage::<K, V>::leaf_capacity()).contains(&leaf_max_size)
+            || !(2..=BPlusTreePage::<K, V>::internal_capacity()).contains(&internal_max_size)
+        {
+            return Err("The node sizes of the B+ tree do not fit into a page");
+        }
+        let header = LatchedPage::new_page(&buffer_pool)?;
+        let root = LatchedPage::new_page(&buffer_pool)?;
+        root.store(&BPlusTreePage::<K, V>::new_leaf(), transaction);
+
+        let mut data = [0; PAGE_SIZE];
+        data[0..4].copy_from_slice(&(root.page_id as u32).to_be_bytes());
+        data[4..6].copy_from_slice(&(leaf_max_size as u16).to_be_bytes());
+        data[6..8].copy_from_slice(&(internal_max_size as u16).to_be_bytes());
+        header.store_raw(RawPage::new(data), transaction);
+
+        Ok(BPlusTree {
+            buffer_pool,
+            header_page_id: header.page_id as u32,
+            leaf_max_size,
+            internal_max_size,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Returns the values of all entries with the given key, in ascending order.
+    pub fn get_values(&self, key: &K) -> Vec<V> {
+        self.get_range(key, key)
+    }
+
+    /// Returns the values of all entries whose key is at least `low` and at most `high`, in the
+    /// order of their entries.
+    pub fn get_range(&self, low: &K, high: &K) -> Vec<V> {
+        let header = LatchedPage::read(&self.buffer_pool, self.header_page_id as usize);
+        let mut page = LatchedPage::read(&self.buffer_pool, root_page_id(&header));
+        drop(header);
+        let mut node = page.node::<K, V>();
+        while !node.is_leaf {
+            // The leftmost child that may contain the lowest key, as the entries with the key
+            // may start in front of a separator with the key.
+            let child = node.children[node.entries.partition_point(|(k, _)| k < low)];
+            page = LatchedPage::read(&self.buffer_pool, child as usize);
+            node = page.node();
+        }
+
+        let mut values = vec![];
+        loop {
+            values.extend(
+                node.entries
+                    .iter()
+                    .filter(|(k, _)| low <= k && k <= high)
+                    .map(|(_, value)| value.clone()),
+            );
+            if node.entries.last().is_some_and(|(k, _)| k > high) {
+                return values;
+            }
+            match node.next_page_id {
+                Some(next_page_id) => {
+                    page = LatchedPage::read(&self.buffer_pool, next_page_id as usize);
+                    node = page.node();
+                }
+                None => return values,
+            }
+        }
+    }
+
+    /// Inserts the entry. Fails if the tree already contains the same key with the same value.
+    pub fn insert(
+        &self,
+        key: K,
+        value: V,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let entry = (key, value);
+        let leaf = self.write_latch_leaf(&entry);
+        let mut node = leaf.node::<K, V>();
+        let position = node
+            .entries
+            .binary_search(&entry)
+            .err()
+            .ok_or("The entry already exists in the B+ tree")?;
+        if node.entries.len() < self.leaf_max_size {
+            node.entries.insert(position, entry);
+            leaf.store(&node, transaction);
+            return Ok(());
+        }
+        drop(leaf);
+        self.insert_with_splits(entry, transaction)
+    }
+
+    /// Inserts the entry into a full leaf. Write latches the path from the header page down to
+    /// the leaf, but keeps only the latches of the nodes that are changed by the splits.
+    fn insert_with_splits(
+        &self,
+        entry: (K, V),
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let mut header = Some(LatchedPage::write(
+            &self.buffer_pool,
+            self.header_page_id as usize,
+        ));
+        let mut page_id = root_page_id(header.as_ref().unwrap());
+        let mut path: Vec<LatchedNode<K, V>> = vec![];
+        loop {
+            let page = LatchedPage::write(&self.buffer_pool, page_id);
+            let node = page.node::<K, V>();
+            if node.entries.len() < self.max_size(&node) {
+                // The node does not split, so its ancestors do not change.
+                header = None;
+                path.clear();
+            }
+            if node.is_leaf {
+                path.push((page, node));
+                break;
+            }
+            page_id = node.children[node.entries.partition_point(|e| e <= &entry)] as usize;
+            path.push((page, node));
+        }
+
+        let (mut page, mut node) = path.pop().unwrap();
+        let position = node
+            .entries
+            .binary_search(&entry)
+            .err()
+            .ok_or("The entry already exists in the B+ tree")?;
+        node.entries.insert(position, entry);
+        loop {
+            let split = self.split(&mut node, transaction)?;
+            page.store(&node, transaction);
+            let Some((separator, sibling_page_id)) = split else {
+                return Ok(());
+            };
+            match path.pop() {
+                Some((parent, mut parent_node)) => {
+                    let position = parent_node.entries.partition_point(|e| e < &separator);
+                    parent_node.entries.insert(position, separator);
+                    parent_node.children.insert(position + 1, sibling_page_id);
+                    (page, node) = (parent, parent_node);
+                }
+                None => {
+                    let header = header
+                        .take()
+                        .expect("The root split without a latched header");
+                    let root = LatchedPage::new_page(&self.buffer_pool)?;
+                    root.store(
+                        &BPlusTreePage::new_internal(
+                            vec![page.page_id as u32, sibling_page_id],
+                            vec![separator],
+                        ),
+                        transaction,
+                    );
+                    let mut data = header.raw_data();
+                    data[0..4].copy_from_slice(&(root.page_id as u32).to_be_bytes());
+                    header.store_raw(RawPage::new(data), transaction);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Moves the upper half of an overflowing node into a new sibling. Returns the separator
+    /// that has to be inserted into the parent together with the page id of the sibling.
+    fn split(
+        &self,
+        node: &mut BPlusTreePage<K, V>,
+        transaction: Option<TransactionId>,
+    ) -> Result<Option<Split<K, V>>, &'static str> {
+        if node.entries.len() <= self.max_size(node) {
+            return Ok(None);
+        }
+        let sibling_page = LatchedPage::new_page(&self.buffer_pool)?;
+        let middle = node.entries.len() / 2;
+        let (separator, sibling) = match node.is_leaf {
+            true => {
+                let mut sibling = BPlusTreePage::new_leaf();
+                sibling.entries = node.entries.split_off(middle);
+                sibling.next_page_id = node.next_page_id;
+                node.next_page_id = Some(sibling_page.page_id as u32);
+                (sibling.entries[0].clone(), sibling)
+            }
+            false => {
+                let entries = node.entries.split_off(middle + 1);
+                let children = node.children.split_off(middle + 1);
+                let separator = node.entries.pop().unwrap();
+                (separator, BPlusTreePage::new_internal(children, entries))
+            }
+        };
+        sibling_page.store(&sibling, transaction);
+        Ok(Some((separator, sibling_page.page_id as u32)))
+    }
+
+    /// Removes the entry with the given key and value. Returns false if there is no such entry.
+    pub fn remove(&self, key: &K, value: &V, transaction: Option<TransactionId>) -> bool {
+        let entry = (key.clone(), value.clone());
+        let leaf = self.write_latch_leaf(&entry);
+        let mut node = leaf.node::<K, V>();
+        match node.entries.binary_search(&entry) {
+            Ok(position) => {
+                node.entries.remove(position);
+                leaf.store(&node, transaction);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the write latched leaf that contains the entry or would contain it. The inner
+    /// nodes are only read latched on the way down.
+    fn write_latch_leaf(&self, entry: &(K, V)) -> LatchedPage {
+        let mut _parent = LatchedPage::read(&self.buffer_pool, self.header_page_id as usize);
+        let mut page_id = root_page_id(&_parent);
+        loop {
+            let page = LatchedPage::read(&self.buffer_pool, page_id);
+            let node = page.node::<K, V>();
+            if node.is_leaf {
+                // The parent stays latched, so the leaf cannot be split until it is latched
+                // again for writing.
+                drop(page);
+                return LatchedPage::write(&self.buffer_pool, page_id);
+            }
+            page_id = node.children[node.entries.partition_point(|e| e <= entry)] as usize;
+            _parent = page;
+        }
+    }
+
+    fn max_size(&self, node: &BPlusTreePage<K, V>) -> usize {
+        match node.is_leaf {
+            true => self.leaf_max_size,
+            false => self.internal_max_size,
+        }
+    }
+}
+
+/// The separator that a split inserts into the parent and the page id of the new sibling.
+type Split<K, V> = ((K, V), u32);
+
+/// A latched page together with the node it contains.
+type LatchedNode<K, V> = (LatchedPage, BPlusTreePage<K, V>);
+
+fn root_page_id(header: &LatchedPage) -> usize {
+    u32::from_be_bytes(header.raw_data()[0..4].try_into().unwrap()) as usize
+}
+
+/// A page that is pinned in the buffer pool and latched by the current thread. The buffer pool
+/// is only locked while the page is loaded, copied or written, and never while waiting for a
+/// latch. Dropping the page releases the latch and unpins the page.
+struct LatchedPage {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    page_id: usize,
+    guard: Option<PageLatchGuard>,
+}
+
+impl LatchedPage {
+    fn read(buffer_pool: &Arc<Mutex<BufferPool>>, page_id: usize) -> LatchedPage {
+        LatchedPage::fetch(buffer_pool, page_id, PageLatch::read)
+    }
+
+    fn write(buffer_pool: &Arc<Mutex<BufferPool>>, page_id: usize) -> LatchedPage {
+        LatchedPage::fetch(buffer_pool, page_id, PageLatch::write)
+    }
+
+    fn fetch(
+        buffer_pool: &Arc<Mutex<BufferPool>>,
+        page_id: usize,
+        latch_page: fn(&Arc<PageLatch>) -> PageLatchGuard,
+    ) -> LatchedPage {
+        let latch = {
+            let mut buffer_pool_lock = buffer_pool.lock().expect("Could not lock buffer pool");
+            buffer_pool_lock
+                .load_page(page_id)
+                .expect("Could not load a page of the B+ tree");
+            buffer_pool_lock.get_page_latch(page_id).unwrap()
+        };
+        LatchedPage {
+            buffer_pool: buffer_pool.clone(),
+            page_id,
+            guard: Some(latch_page(&latch)),
+        }
+    }
+
+    /// Allocates a new page and latches it for writing.
+    fn new_page(buffer_pool: &Arc<Mutex<BufferPool>>) -> Result<LatchedPage, &'static str> {
+        let (page_id, _) = buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .load_new_page()
+            .or(Err("Could not allocate a page of the B+ tree"))?;
+        let latch = buffer_pool.lock().unwrap().get_page_latch(page_id).unwrap();
+        Ok(LatchedPage {
+            buffer_pool: buffer_pool.clone(),
+            page_id,
+            guard: Some(PageLatch::write(&latch)),
+        })
+    }
+
+    fn raw_data(&self) -> [u8; PAGE_SIZE] {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let frame_index = buffer_pool_lock.page_table[&self.page_id].frame_index;
+        let data = *buffer_pool_lock
+            .get_raw_page(frame_index)
+            .expect("The latched page is not loaded")
+            .data
+            .read()
+            .unwrap();
+        data
+    }
+
+    fn node<K: Clone + Debug + Encode + Decode, V: Clone + Debug + Encode + Decode>(
+        &self,
+    ) -> BPlusTreePage<K, V> {
+        BPlusTreePage::from_raw_page(&RawPage::new(self.raw_data()))
+    }
+
+    fn store_raw(&self, raw_page: RawPage, transaction: Option<TransactionId>) {
+        assert!(
+            self.guard.as_ref().unwrap().is_exclusive(),
+            "The page is not latched for writing"
+        );
+        self.buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .set_page_data(self.page_id, raw_page, transaction)
+            .expect("Could not update the page");
+    }
+
+    fn store<K: Clone + Debug + Encode + Decode, V: Clone + Debug + Encode + Decode>(
+        &self,
+        node: &BPlusTreePage<K, V>,
+        transaction: Option<TransactionId>,
+    ) {
+        self.store_raw(node.to_raw_page(), transaction);
+    }
+}
+
+impl Drop for LatchedPage {
+    fn drop(&mut self) {
+        // The latch is released first, as the latch of an unpinned page may be replaced.
+        self.guard = None;
+        self.buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .unload_page_id(self.page_id)
+            .expect("Could not unload the page");
+    }
+}
+
+#[cfg(test)]
+mod b_plus_tree_tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use rand::Rng;
+
+    use crate::disk_management::test_utils::new_test_buffer_pool;
+
+    use super::BPlusTree;
+
+    #[test]
+    fn insert_get_and_remove() {
+        let buffer_pool = new_test_buffer_pool("b_plus_tree_insert_get_remove");
+        let tree = BPlusTree::<u64, u64>::setup_new_tree(buffer_pool.clone(), None).unwrap();
+        // Enough entries to need more pages than there are frames in the buffer pool.
+        for i in (0..30_000).rev() {
+            tree.insert(i, i * 2, None).unwrap();
+        }
+        // More duplicates than fit into a page.
+        for i in 0..1_000 {
+            tree.insert(7, 100_000 + i, None).unwrap();
+        }
+        assert!(tree.insert(7, 14, None).is_err());
+
+        for i in (0..30_000).step_by(97) {
+            assert_eq!(tree.get_values(&i).len(), if i == 7 { 1001 } else { 1 });
+        }
+        let values = tree.get_values(&7);
+        assert_eq!(values[0], 14);
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(tree.get_values(&30_000).is_empty());
+
+        assert!(tree.remove(&7, &14, None));
+        assert!(!tree.remove(&7, &14, None));
+        assert_eq!(tree.get_values(&7).len(), 1000);
+        assert_eq!(tree.get_values(&8), vec![16]);
+
+        assert_eq!(tree.get_range(&5, &9).len(), 1004);
+        assert_eq!(tree.get_range(&29_998, &u64::MAX), vec![59_996, 59_998]);
+        assert_eq!(tree.get_range(&0, &2), vec![0, 2, 4]);
+        assert!(tree.get_range(&9, &5).is_empty());
+
+        let reopened = BPlusTree::<u64, u64>::new(buffer_pool, tree.header_page_id);
+        assert_eq!(reopened.get_values(&29_999), vec![59_998]);
+    }
+
+    #[test]
+    fn concurrent_inserts_removals_and_lookups() {
+        const WRITERS: u64 = 4;
+        const READERS: usize = 2;
+        const KEYS_PER_WRITER: u64 = 600;
+        const DUPLICATE_KEY: u64 = u64::MAX;
+
+        // Small nodes make the tree deep and split nodes all the time.
+        let tree = Arc::new(
+            BPlusTree::<u64, u64>::setup_new_tree_with_max_sizes(
+                new_test_buffer_pool("b_plus_tree_concurrent"),
+                4,
+                4,
+                None,
+            )
+            .unwrap(),
+        );
+        let finished_writers = Arc::new(AtomicUsize::new(0));
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let tree = tree.clone();
+                let finished_writers = finished_writers.clone();
+                thread::spawn(move || {
+                    for i in 0..KEYS_PER_WRITER {
+                        let key = i * WRITERS + writer;
+                        tree.insert(key, key * 2, None).unwrap();
+                        tree.insert(DUPLICATE_KEY, key, None).unwrap();
+                        assert_eq!(tree.get_values(&key), vec![key * 2]);
+                        if i % 3 == 0 {
+                            assert!(tree.remove(&key, &(key * 2), None));
+                            assert!(tree.get_values(&key).is_empty());
+                        }
+                    }
+                    finished_writers.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                let tree = tree.clone();
+                let finished_writers = finished_writers.clone();
+                thread::spawn(move || {
+                    let mut rng = rand::thread_rng();
+                    while finished_writers.load(Ordering::SeqCst) < WRITERS as usize {
+                        let key = rng.gen_range(0..WRITERS * KEYS_PER_WRITER);
+                        let values = tree.get_values(&key);
+                        assert!(values.is_empty() || values == vec![key * 2]);
+                    }
+                })
+            })
+            .collect();
+        for thread in writers.into_iter().chain(readers) {
+            thread.join().unwrap();
+        }
+
+        for key in 0..WRITERS * KEYS_PER_WRITER {
+            let expected = match (key / WRITERS) % 3 {
+                0 => vec![],
+                _ => vec![key * 2],
+            };
+            assert_eq!(tree.get_values(&key), expected);
+        }
+        assert_eq!(
+            tree.get_values(&DUPLICATE_KEY),
+            (0..WRITERS * KEYS_PER_WRITER).collect::<Vec<_>>()
+        );
+    }
+}
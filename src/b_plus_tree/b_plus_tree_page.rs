@@ -0,0 +1,155 @@
+use bincode::{Decode, Encode};
+
+use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
+use std::fmt::Debug;
+
+/// The id stored in place of the next page id of the last leaf.
+const NO_NEXT_PAGE_ID: u32 = u32::MAX;
+/// | IS_LEAF (1) | KEY_COUNT (2) | NEXT_PAGE_ID (4) |
+const HEADER_SIZE: usize = 1 + 2 + 4;
+
+/// A node of a B+ tree. A leaf holds the entries of the tree in ascending order and links to
+/// the next leaf. An internal node holds one child more than entries; the entries separate the
+/// children, i.e. all entries in a child are at least the entry before it and less than the
+/// entry after it.
+///
+/// Leaves store their entries behind the header, internal nodes the first child followed by
+/// (key, value, child) triples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BPlusTreePage<K, V> {
+    pub is_leaf: bool,
+    pub next_page_id: Option<u32>,
+    pub entries: Vec<(K, V)>,
+    pub children: Vec<u32>,
+}
+
+impl<K: Clone + Debug + Encode + Decode, V: Clone + Debug + Encode + Decode> BPlusTreePage<K, V> {
+    pub fn new_leaf() -> BPlusTreePage<K, V> {
+        BPlusTreePage {
+            is_leaf: true,
+            next_page_id: None,
+            entries: vec![],
+            children: vec![],
+        }
+    }
+
+    pub fn new_internal(children: Vec<u32>, entries: Vec<(K, V)>) -> BPlusTreePage<K, V> {
+        BPlusTreePage {
+            is_leaf: false,
+            next_page_id: None,
+            entries,
+            children,
+        }
+    }
+
+    /// The number of entries a leaf fits.
+    pub fn leaf_capacity() -> usize {
+        (PAGE_SIZE - HEADER_SIZE) / entry_size::<K, V>()
+    }
+
+    /// The number of entries an internal node fits.
+    pub fn internal_capacity() -> usize {
+        (PAGE_SIZE - HEADER_SIZE - 4) / (entry_size::<K, V>() + 4)
+    }
+
+    pub fn from_raw_page(raw_page: &RawPage) -> BPlusTreePage<K, V> {
+        let data = raw_page
+            .data
+            .read()
+            .expect("Could not read the raw_page data");
+        let is_leaf = data[0] != 0;
+        let key_count = u16::from_be_bytes(data[1..3].try_into().unwrap()) as usize;
+        let next_page_id = match u32::from_be_bytes(data[3..7].try_into().unwrap()) {
+            NO_NEXT_PAGE_ID => None,
+            page_id => Some(page_id),
+        };
+
+        let mut offset = HEADER_SIZE;
+        let mut children = vec![];
+        if !is_leaf {
+            children.push(read_page_id(&data[..], &mut offset));
+        }
+        let mut entries = Vec::with_capacity(key_count);
+        for _ in 0..key_count {
+            let entry_size = entry_size::<K, V>();
+            let entry: (K, V) = bincode::decode_from_slice(
+                &data[offset..offset + entry_size],
+                bincode::config::standard().with_fixed_int_encoding(),
+            )
+            .expect("Could not decode key and value from slice.")
+            .0;
+            offset += entry_size;
+            entries.push(entry);
+            if !is_leaf {
+                children.push(read_page_id(&data[..], &mut offset));
+            }
+        }
+
+        BPlusTreePage {
+            is_leaf,
+            next_page_id,
+            entries,
+            children,
+        }
+    }
+
+    pub fn to_raw_page(&self) -> RawPage {
+        let mut data = Vec::with_capacity(PAGE_SIZE);
+        data.push(self.is_leaf as u8);
+        data.extend_from_slice(&(self.entries.len() as u16).to_be_bytes());
+        data.extend_from_slice(&self.next_page_id.unwrap_or(NO_NEXT_PAGE_ID).to_be_bytes());
+        if !self.is_leaf {
+            data.extend_from_slice(&self.children[0].to_be_bytes());
+        }
+        for (i, entry) in self.entries.iter().enumerate() {
+            data.append(
+                &mut bincode::encode_to_vec(
+                    entry,
+                    bincode::config::standard().with_fixed_int_encoding(),
+                )
+                .expect("Could not encode value to binary"),
+            );
+            if !self.is_leaf {
+                data.extend_from_slice(&self.children[i + 1].to_be_bytes());
+            }
+        }
+
+        data.append(&mut vec![0; PAGE_SIZE - data.len()]);
+        RawPage::new(data.try_into().expect("The node does not fit into a page"))
+    }
+}
+
+fn entry_size<K, V>() -> usize {
+    std::mem::size_of::<K>() + std::mem::size_of::<V>()
+}
+
+fn read_page_id(data: &[u8], offset: &mut usize) -> u32 {
+    let page_id = u32::from_be_bytes(data[*offset..*offset + 4].try_into().unwrap());
+    *offset += 4;
+    page_id
+}
+
+#[cfg(test)]
+mod b_plus_tree_page_tests {
+    use super::BPlusTreePage;
+
+    #[test]
+    fn full_pages_survive_a_round_trip() {
+        let mut leaf = BPlusTreePage::<u64, u64>::new_leaf();
+        leaf.next_page_id = Some(12);
+        leaf.entries = (0..BPlusTreePage::<u64, u64>::leaf_capacity() as u64)
+            .map(|i| (i, u64::MAX - i))
+            .collect();
+        assert_eq!(BPlusTreePage::from_raw_page(&leaf.to_raw_page()), leaf);
+
+        let capacity = BPlusTreePage::<u64, u64>::internal_capacity() as u64;
+        let internal = BPlusTreePage::<u64, u64>::new_internal(
+            (0..=capacity as u32).collect(),
+            (0..capacity).map(|i| (i, i)).collect(),
+        );
+        assert_eq!(
+            BPlusTreePage::from_raw_page(&internal.to_raw_page()),
+            internal
+        );
+    }
+}
@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod b_plus_tree;
+pub mod b_plus_tree_page;
@@ -0,0 +1,241 @@
+use std::fmt::Display;
+
+use crate::{
+    sql::ast::{BinaryOperator, UnaryOperator},
+    types::{schema::DataType, value::Value},
+};
+
+/// An expression whose column references have been resolved to positions in the input row and
+/// whose operands have been converted to matching types.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoundExpression {
+    Constant {
+        value: Value,
+        data_type: DataType,
+    },
+    Column {
+        /// The position of the column in the input row.
+        index: usize,
+        data_type: DataType,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<BoundExpression>,
+        data_type: DataType,
+    },
+    /// Both operands of a binary expression have the same type, except for string operands that
+    /// may differ in their maximum length.
+    Binary {
+        left: Box<BoundExpression>,
+        operator: BinaryOperator,
+        right: Box<BoundExpression>,
+        data_type: DataType,
+    },
+    IsNull {
+        operand: Box<BoundExpression>,
+        negated: bool,
+    },
+    Cast {
+        operand: Box<BoundExpression>,
+        data_type: DataType,
+    },
+}
+
+impl BoundExpression {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            BoundExpression::Constant { data_type, .. }
+            | BoundExpression::Column { data_type, .. }
+            | BoundExpression::Unary { data_type, .. }
+            | BoundExpression::Binary { data_type, .. }
+            | BoundExpression::Cast { data_type, .. } => *data_type,
+            BoundExpression::IsNull { .. } => DataType::Boolean,
+        }
+    }
+
+    /// Returns the positions of all columns the expression reads.
+    pub fn referenced_columns(&self) -> Vec<usize> {
+        let mut columns = Vec::new();
+        self.visit_columns(&mut |index| columns.push(index));
+        columns
+    }
+
+    fn visit_columns(&self, visitor: &mut impl FnMut(usize)) {
+        match self {
+            BoundExpression::Constant { .. } => {}
+            BoundExpression::Column { index, .. } => visitor(*index),
+            BoundExpression::Unary { operand, .. }
+            | BoundExpression::IsNull { operand, .. }
+            | BoundExpression::Cast { operand, .. } => operand.visit_columns(visitor),
+            BoundExpression::Binary { left, right, .. } => {
+                left.visit_columns(visitor);
+                right.visit_columns(visitor);
+            }
+        }
+    }
+
+    /// Returns a copy of the expression whose column positions are replaced by `map`, e.g. to
+    /// evaluate it over one input of a join instead of the joined row.
+    pub fn map_columns(&self, map: &impl Fn(usize) -> usize) -> BoundExpression {
+        let mut expression = self.clone();
+        expression.map_columns_in_place(map);
+        expression
+    }
+
+    fn map_columns_in_place(&mut self, map: &impl Fn(usize) -> usize) {
+        match self {
+            BoundExpression::Constant { .. } => {}
+            BoundExpression::Column { index, .. } => *index = map(*index),
+            BoundExpression::Unary { operand, .. }
+            | BoundExpression::IsNull { operand, .. }
+            | BoundExpression::Cast { operand, .. } => operand.map_columns_in_place(map),
+            BoundExpression::Binary { left, right, .. } => {
+                left.map_columns_in_place(map);
+                right.map_columns_in_place(map);
+            }
+        }
+    }
+
+    /// Returns a copy of the expression whose column references are replaced by the expressions
+    /// returned by `replace`, e.g. to move a predicate below a projection that computes the
+    /// columns.
+    pub fn replace_columns(&self, replace: &impl Fn(usize) -> BoundExpression) -> BoundExpression {
+        match self {
+            BoundExpression::Constant { .. } => self.clone(),
+            BoundExpression::Column { index, .. } => replace(*index),
+            BoundExpression::Unary {
+                operator,
+                operand,
+                data_type,
+            } => BoundExpression::Unary {
+                operator: *operator,
+                operand: Box::new(operand.replace_columns(replace)),
+                data_type: *data_type,
+            },
+            BoundExpression::Binary {
+                left,
+                operator,
+                right,
+                data_type,
+            } => BoundExpression::Binary {
+                left: Box::new(left.replace_columns(replace)),
+                operator: *operator,
+                right: Box::new(right.replace_columns(replace)),
+                data_type: *data_type,
+            },
+            BoundExpression::IsNull { operand, negated } => BoundExpression::IsNull {
+                operand: Box::new(operand.replace_columns(replace)),
+                negated: *negated,
+            },
+            BoundExpression::Cast { operand, data_type } => BoundExpression::Cast {
+                operand: Box::new(operand.replace_columns(replace)),
+                data_type: *data_type,
+            },
+        }
+    }
+
+    pub fn is_null_constant(&self) -> bool {
+        matches!(
+            self,
+            BoundExpression::Constant {
+                value: Value::Null,
+                ..
+            }
+        )
+    }
+}
+
+/// Formats the expression as SQL, with columns written as their position in the input row,
+/// e.g. `(#0 > 3)`.
+impl Display for BoundExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoundExpression::Constant {
+                value: Value::String(value),
+                ..
+            } => write!(f, "'{}'", value.replace('\'', "''")),
+            BoundExpression::Constant {
+                value: value @ (Value::Date(_) | Value::Timestamp(_)),
+                data_type,
+            } => write!(f, "{} '{}'", data_type, value),
+            BoundExpression::Constant { value, .. } => write!(f, "{}", value),
+            BoundExpression::Column { index, .. } => write!(f, "#{}", index),
+            BoundExpression::Unary {
+                operator, operand, ..
+            } => write!(f, "{}{}", operator, operand),
+            BoundExpression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => write!(f, "({} {} {})", left, operator, right),
+            BoundExpression::IsNull { operand, negated } => match negated {
+                true => write!(f, "{} IS NOT NULL", operand),
+                false => write!(f, "{} IS NULL", operand),
+            },
+            BoundExpression::Cast { operand, data_type } => {
+                write!(f, "CAST({} AS {})", operand, data_type)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    /// Returns the aggregate function with the given case-insensitive name.
+    pub fn from_name(name: &str) -> Option<AggregateFunction> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AggregateFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An aggregate function call over the input rows of an aggregation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundAggregate {
+    pub function: AggregateFunction,
+    /// An expression over the input row, or None for COUNT(*). NULL arguments are ignored.
+    pub argument: Option<BoundExpression>,
+    /// Whether duplicate arguments within a group are only aggregated once.
+    pub distinct: bool,
+    /// The type of the aggregate result.
+    pub data_type: DataType,
+}
+
+impl Display for BoundAggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.argument {
+            None => write!(f, "{}(*)", self.function),
+            Some(argument) if self.distinct => {
+                write!(f, "{}(DISTINCT {})", self.function, argument)
+            }
+            Some(argument) => write!(f, "{}({})", self.function, argument),
+        }
+    }
+}
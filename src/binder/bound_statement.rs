@@ -0,0 +1,122 @@
+use crate::{
+    catalog::system_catalog::IndexType, sql::ast::JoinType,
+    transaction::transaction_state::IsolationLevel, types::schema::Schema,
+};
+
+use super::bound_expression::{BoundAggregate, BoundExpression};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoundStatement {
+    CreateTable {
+        name: String,
+        schema: Schema,
+    },
+    DropTable {
+        name: String,
+        if_exists: bool,
+    },
+    CreateIndex {
+        name: String,
+        table_name: String,
+        column_names: Vec<String>,
+        unique: bool,
+        index_type: IndexType,
+    },
+    Insert(BoundInsert),
+    Select(Box<BoundSelect>),
+    Update(BoundUpdate),
+    Delete(BoundDelete),
+    /// Gathers the statistics of the tables.
+    Analyze {
+        table_ids: Vec<u32>,
+    },
+    /// Removes the dead versions of the rows of the tables.
+    Vacuum {
+        table_ids: Vec<u32>,
+    },
+    Explain {
+        statement: Box<BoundStatement>,
+        analyze: bool,
+    },
+    Begin {
+        isolation_level: Option<IsolationLevel>,
+    },
+    Commit,
+    Rollback,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundInsert {
+    pub table_id: u32,
+    /// One expression per column of the table, in schema order. Each expression has the type of
+    /// its column.
+    pub rows: Vec<Vec<BoundExpression>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundSelect {
+    /// The tables to read from, or None for a SELECT without FROM clause.
+    pub from: Option<BoundTableReference>,
+    /// A boolean expression over the rows produced by `from`.
+    pub filter: Option<BoundExpression>,
+    /// Expressions over the rows produced by `from` whose values form the groups of an aggregate
+    /// query.
+    pub group_by: Vec<BoundExpression>,
+    /// The aggregates computed per group. Their arguments are expressions over the rows produced
+    /// by `from`.
+    pub aggregates: Vec<BoundAggregate>,
+    /// A boolean expression over the aggregated rows.
+    pub having: Option<BoundExpression>,
+    /// Expressions over the input rows, one per output column. The input rows are the rows
+    /// produced by `from`, or the aggregated rows if the query is an aggregate query.
+    pub projection: Vec<BoundExpression>,
+    /// Expressions over the input rows together with the sort direction.
+    pub order_by: Vec<(BoundExpression, bool)>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    /// The names and types of the output columns.
+    pub output_schema: Schema,
+}
+
+impl BoundSelect {
+    /// Returns whether the query aggregates its rows. An aggregated row consists of the values of
+    /// the `group_by` expressions followed by the values of the aggregates. Without GROUP BY, all
+    /// rows form a single group, even if there are none.
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty() || !self.aggregates.is_empty() || self.having.is_some()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoundTableReference {
+    /// A table scan producing all columns of the table.
+    Table {
+        table_id: u32,
+        /// The alias or name the table is referenced by in the query.
+        name: String,
+        schema: Schema,
+    },
+    /// A join producing the columns of the left input followed by the columns of the right
+    /// input. Semi and anti joins only produce the columns of the left input.
+    Join {
+        left: Box<BoundTableReference>,
+        right: Box<BoundTableReference>,
+        join_type: JoinType,
+        /// A boolean expression over the joined row. It is None for cross joins.
+        condition: Option<BoundExpression>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundUpdate {
+    pub table_id: u32,
+    /// The positions of the updated columns and their new values, computed from the old row.
+    pub assignments: Vec<(usize, BoundExpression)>,
+    pub filter: Option<BoundExpression>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundDelete {
+    pub table_id: u32,
+    pub filter: Option<BoundExpression>,
+}
@@ -0,0 +1,3 @@
+pub mod bound_expression;
+pub mod bound_statement;
+pub mod statement_binder;
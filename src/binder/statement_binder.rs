@@ -0,0 +1,1343 @@
+use std::fmt::Display;
+
+use crate::{
+    catalog::system_catalog::{Catalog, CatalogError, TableInfo},
+    sql::ast::{
+        BinaryOperator, CreateTable, Delete, Expression, Insert, JoinType, Literal, Select,
+        SelectItem, Statement, TableReference, UnaryOperator, Update,
+    },
+    types::{
+        schema::{Column, DataType, Schema},
+        value::Value,
+    },
+};
+
+use super::{
+    bound_expression::{AggregateFunction, BoundAggregate, BoundExpression},
+    bound_statement::{
+        BoundDelete, BoundInsert, BoundSelect, BoundStatement, BoundTableReference, BoundUpdate,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub enum BindError {
+    Catalog(CatalogError),
+    AmbiguousColumn(String),
+    DuplicateTableName(String),
+    TypeMismatch(String),
+    InvalidValue(String),
+    NotNullViolation(String),
+    InvalidStatement(String),
+    Unsupported(String),
+}
+
+impl Display for BindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindError::Catalog(error) => write!(f, "{}", error),
+            BindError::AmbiguousColumn(name) => write!(f, "column reference {} is ambiguous", name),
+            BindError::DuplicateTableName(name) => {
+                write!(f, "table name {} is specified more than once", name)
+            }
+            BindError::NotNullViolation(name) => {
+                write!(f, "column {} must not be NULL", name)
+            }
+            BindError::TypeMismatch(message)
+            | BindError::InvalidValue(message)
+            | BindError::InvalidStatement(message)
+            | BindError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<CatalogError> for BindError {
+    fn from(error: CatalogError) -> Self {
+        BindError::Catalog(error)
+    }
+}
+
+/// The tables visible to an expression. The columns of all tables form the input row of the
+/// expression, in the order of the tables.
+#[derive(Default)]
+struct Scope {
+    tables: Vec<ScopeTable>,
+    /// The aggregation producing the rows of the scope, if they are the aggregated rows of an
+    /// aggregate query.
+    grouping: Option<Box<Grouping>>,
+}
+
+/// The aggregation of an aggregate query. Expressions over the aggregated rows can only read the
+/// aggregated input rows through the grouping keys and the aggregates.
+struct Grouping {
+    /// The scope of the rows that are aggregated.
+    input: Scope,
+    keys: Vec<BoundExpression>,
+    aggregates: Vec<BoundAggregate>,
+}
+
+struct ScopeTable {
+    name: String,
+    columns: Vec<Column>,
+    /// The position of the first column of the table in the input row.
+    offset: usize,
+}
+
+impl Scope {
+    fn width(&self) -> usize {
+        self.tables.iter().map(|table| table.columns.len()).sum()
+    }
+
+    /// Appends the tables of the other scope. Their columns follow the columns of this scope.
+    fn extend(&mut self, other: Scope) -> Result<(), BindError> {
+        let width = self.width();
+        for mut table in other.tables {
+            if self.find_table(&table.name).is_some() {
+                return Err(BindError::DuplicateTableName(table.name));
+            }
+            table.offset += width;
+            self.tables.push(table);
+        }
+        Ok(())
+    }
+
+    fn find_table(&self, name: &str) -> Option<&ScopeTable> {
+        self.tables
+            .iter()
+            .find(|table| table.name.eq_ignore_ascii_case(name))
+    }
+
+    fn resolve(&self, table: Option<&str>, name: &str) -> Result<BoundExpression, BindError> {
+        let qualified_name = match table {
+            Some(table) => format!("{}.{}", table, name),
+            None => name.to_string(),
+        };
+        let mut candidates = self
+            .tables
+            .iter()
+            .filter(|scope_table| {
+                table.is_none_or(|table| scope_table.name.eq_ignore_ascii_case(table))
+            })
+            .flat_map(|scope_table| {
+                scope_table
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, column)| column.name.eq_ignore_ascii_case(name))
+                    .map(|(i, column)| BoundExpression::Column {
+                        index: scope_table.offset + i,
+                        data_type: column.data_type,
+                    })
+            });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(column), None) => Ok(column),
+            (Some(_), Some(_)) => Err(BindError::AmbiguousColumn(qualified_name)),
+            (None, _) => match table {
+                Some(table) if self.find_table(table).is_none() => Err(BindError::Catalog(
+                    CatalogError::TableNotFound(table.to_string()),
+                )),
+                _ => Err(BindError::Catalog(CatalogError::ColumnNotFound(
+                    qualified_name,
+                ))),
+            },
+        }
+    }
+
+    fn column(&self, index: usize) -> &Column {
+        let table = self
+            .tables
+            .iter()
+            .rev()
+            .find(|table| table.offset <= index)
+            .unwrap();
+        &table.columns[index - table.offset]
+    }
+}
+
+/// Resolves the names of a parsed statement against the catalog and checks the types of all
+/// expressions. Operands of different types are converted by inserting implicit casts.
+pub struct Binder<'a> {
+    catalog: &'a Catalog,
+}
+
+impl<'a> Binder<'a> {
+    pub fn new(catalog: &'a Catalog) -> Binder<'a> {
+        Binder { catalog }
+    }
+
+    pub fn bind(&self, statement: &Statement) -> Result<BoundStatement, BindError> {
+        match statement {
+            Statement::CreateTable(create_table) => self.bind_create_table(create_table),
+            Statement::Analyze { table } => Ok(BoundStatement::Analyze {
+                table_ids: self.table_ids(table.as_deref())?,
+            }),
+            Statement::Vacuum { table } => Ok(BoundStatement::Vacuum {
+                table_ids: self.table_ids(table.as_deref())?,
+            }),
+            Statement::Explain { statement, analyze } => Ok(BoundStatement::Explain {
+                statement: Box::new(self.bind(statement)?),
+                analyze: *analyze,
+            }),
+            Statement::Begin { isolation_level } => Ok(BoundStatement::Begin {
+                isolation_level: *isolation_level,
+            }),
+            Statement::Commit => Ok(BoundStatement::Commit),
+            Statement::Rollback => Ok(BoundStatement::Rollback),
+            Statement::DropTable { name, if_exists } => {
+                if !if_exists && self.catalog.get_table(name).is_none() {
+                    return Err(CatalogError::TableNotFound(name.clone()).into());
+                }
+                Ok(BoundStatement::DropTable {
+                    name: name.clone(),
+                    if_exists: *if_exists,
+                })
+            }
+            Statement::CreateIndex(create_index) => {
+                if self.catalog.get_index(&create_index.name).is_some() {
+                    return Err(CatalogError::IndexAlreadyExists(create_index.name.clone()).into());
+                }
+                let table = self.get_table(&create_index.table)?;
+                for (i, column_name) in create_index.columns.iter().enumerate() {
+                    if table.schema.column_index(column_name).is_none() {
+                        return Err(CatalogError::ColumnNotFound(column_name.clone()).into());
+                    }
+                    if create_index.columns[..i]
+                        .iter()
+                        .any(|other| other.eq_ignore_ascii_case(column_name))
+                    {
+                        return Err(CatalogError::DuplicateColumn(column_name.clone()).into());
+                    }
+                }
+                Ok(BoundStatement::CreateIndex {
+                    name: create_index.name.clone(),
+                    table_name: table.name.clone(),
+                    column_names: create_index.columns.clone(),
+                    unique: create_index.unique,
+                    index_type: create_index.index_type,
+                })
+            }
+            Statement::Insert(insert) => self.bind_insert(insert),
+            Statement::Select(select) => {
+                Ok(BoundStatement::Select(Box::new(self.bind_select(select)?)))
+            }
+            Statement::Update(update) => self.bind_update(update),
+            Statement::Delete(delete) => self.bind_delete(delete),
+        }
+    }
+
+    fn get_table(&self, name: &str) -> Result<&'a TableInfo, BindError> {
+        self.catalog
+            .get_table(name)
+            .ok_or_else(|| CatalogError::TableNotFound(name.to_string()).into())
+    }
+
+    /// Returns the id of the named table, or the ids of all tables if no table is named.
+    fn table_ids(&self, name: Option<&str>) -> Result<Vec<u32>, BindError> {
+        Ok(match name {
+            Some(name) => vec![self.get_table(name)?.table_id],
+            None => self
+                .catalog
+                .get_tables()
+                .into_iter()
+                .map(|table| table.table_id)
+                .collect(),
+        })
+    }
+
+    fn table_scope(&self, name: &str) -> Result<(u32, Scope), BindError> {
+        let table = self.get_table(name)?;
+        let scope = Scope {
+            tables: vec![ScopeTable {
+                name: table.name.clone(),
+                columns: table.schema.columns.clone(),
+                offset: 0,
+            }],
+            grouping: None,
+        };
+        Ok((table.table_id, scope))
+    }
+
+    fn bind_create_table(&self, create_table: &CreateTable) -> Result<BoundStatement, BindError> {
+        if self.catalog.get_table(&create_table.name).is_some() {
+            return Err(CatalogError::TableAlreadyExists(create_table.name.clone()).into());
+        }
+        let schema = Schema::new(
+            create_table
+                .columns
+                .iter()
+                .map(|column| Column::new(&column.name, column.data_type, column.nullable))
+                .collect(),
+        );
+        for (i, column) in schema.columns.iter().enumerate() {
+            if schema.column_index(&column.name) != Some(i) {
+                return Err(CatalogError::DuplicateColumn(column.name.clone()).into());
+            }
+        }
+        Ok(BoundStatement::CreateTable {
+            name: create_table.name.clone(),
+            schema,
+        })
+    }
+
+    fn bind_insert(&self, insert: &Insert) -> Result<BoundStatement, BindError> {
+        let table = self.get_table(&insert.table)?;
+        let columns = &table.schema.columns;
+        let positions: Vec<usize> = match &insert.columns {
+            None => (0..columns.len()).collect(),
+            Some(column_names) => {
+                let mut positions = Vec::with_capacity(column_names.len());
+                for column_name in column_names {
+                    let position = table
+                        .schema
+                        .column_index(column_name)
+                        .ok_or_else(|| CatalogError::ColumnNotFound(column_name.clone()))?;
+                    if positions.contains(&position) {
+                        return Err(CatalogError::DuplicateColumn(column_name.clone()).into());
+                    }
+                    positions.push(position);
+                }
+                positions
+            }
+        };
+        if let Some(column) = columns
+            .iter()
+            .enumerate()
+            .find(|(i, column)| !column.nullable && !positions.contains(i))
+            .map(|(_, column)| column)
+        {
+            return Err(BindError::NotNullViolation(column.name.clone()));
+        }
+
+        let empty_scope = Scope::default();
+        let mut rows = Vec::with_capacity(insert.rows.len());
+        for values in &insert.rows {
+            if values.len() != positions.len() {
+                return Err(BindError::InvalidStatement(format!(
+                    "INSERT has {} target columns but {} values",
+                    positions.len(),
+                    values.len()
+                )));
+            }
+            let mut row: Vec<BoundExpression> = columns
+                .iter()
+                .map(|column| null_constant(column.data_type))
+                .collect();
+            for (value, position) in values.iter().zip(&positions) {
+                let value = self.bind_expression(value, &empty_scope)?;
+                row[*position] = assign(value, &columns[*position])?;
+            }
+            rows.push(row);
+        }
+        Ok(BoundStatement::Insert(BoundInsert {
+            table_id: table.table_id,
+            rows,
+        }))
+    }
+
+    fn bind_update(&self, update: &Update) -> Result<BoundStatement, BindError> {
+        let (table_id, scope) = self.table_scope(&update.table)?;
+        let schema = &self.get_table(&update.table)?.schema;
+        let mut assignments: Vec<(usize, BoundExpression)> = Vec::new();
+        for (column_name, value) in &update.assignments {
+            let position = schema
+                .column_index(column_name)
+                .ok_or_else(|| CatalogError::ColumnNotFound(column_name.clone()))?;
+            if assignments.iter().any(|(other, _)| *other == position) {
+                return Err(CatalogError::DuplicateColumn(column_name.clone()).into());
+            }
+            let value = self.bind_expression(value, &scope)?;
+            assignments.push((position, assign(value, &schema.columns[position])?));
+        }
+        Ok(BoundStatement::Update(BoundUpdate {
+            table_id,
+            assignments,
+            filter: self.bind_filter(update.where_clause.as_ref(), &scope, "WHERE")?,
+        }))
+    }
+
+    fn bind_delete(&self, delete: &Delete) -> Result<BoundStatement, BindError> {
+        let (table_id, scope) = self.table_scope(&delete.table)?;
+        Ok(BoundStatement::Delete(BoundDelete {
+            table_id,
+            filter: self.bind_filter(delete.where_clause.as_ref(), &scope, "WHERE")?,
+        }))
+    }
+
+    fn bind_select(&self, select: &Select) -> Result<BoundSelect, BindError> {
+        let mut scope = Scope::default();
+        let mut from: Option<BoundTableReference> = None;
+        for table_reference in &select.from {
+            let (bound, table_scope) = self.bind_table_reference(table_reference)?;
+            scope.extend(table_scope)?;
+            from = Some(match from {
+                None => bound,
+                Some(left) => BoundTableReference::Join {
+                    left: Box::new(left),
+                    right: Box::new(bound),
+                    join_type: JoinType::Cross,
+                    condition: None,
+                },
+            });
+        }
+        let filter = self.bind_filter(select.where_clause.as_ref(), &scope, "WHERE")?;
+
+        let mut aggregate_calls = Vec::new();
+        for item in &select.projection {
+            if let SelectItem::Expression { expression, .. } = item {
+                collect_aggregates(expression, &mut aggregate_calls);
+            }
+        }
+        if let Some(having) = &select.having {
+            collect_aggregates(having, &mut aggregate_calls);
+        }
+        for item in &select.order_by {
+            collect_aggregates(&item.expression, &mut aggregate_calls);
+        }
+        let mut having = None;
+        if !select.group_by.is_empty() || select.having.is_some() || !aggregate_calls.is_empty() {
+            scope = self.bind_grouping(&select.group_by, &aggregate_calls, scope)?;
+            having = self.bind_filter(select.having.as_ref(), &scope, "HAVING")?;
+        }
+
+        let mut projection = Vec::new();
+        let mut output_columns = Vec::new();
+        let mut aliases: Vec<(&String, usize)> = Vec::new();
+        for item in &select.projection {
+            match item {
+                SelectItem::Wildcard | SelectItem::QualifiedWildcard(_)
+                    if scope.grouping.is_some() =>
+                {
+                    return Err(BindError::InvalidStatement(
+                        "SELECT * is not allowed in an aggregate query".to_string(),
+                    ));
+                }
+                SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => {
+                    let tables: Vec<&ScopeTable> = match item {
+                        SelectItem::QualifiedWildcard(name) => vec![scope
+                            .find_table(name)
+                            .ok_or_else(|| CatalogError::TableNotFound(name.clone()))?],
+                        _ => scope.tables.iter().collect(),
+                    };
+                    if tables.is_empty() {
+                        return Err(BindError::InvalidStatement(
+                            "SELECT * requires a FROM clause".to_string(),
+                        ));
+                    }
+                    for table in tables {
+                        for (i, column) in table.columns.iter().enumerate() {
+                            projection.push(BoundExpression::Column {
+                                index: table.offset + i,
+                                data_type: column.data_type,
+                            });
+                            output_columns.push(column.clone());
+                        }
+                    }
+                }
+                SelectItem::Expression { expression, alias } => {
+                    let bound = self.bind_expression(expression, &scope)?;
+                    let (name, nullable) = match (alias, &bound) {
+                        (Some(alias), BoundExpression::Column { index, .. }) => {
+                            (alias.clone(), scope.column(*index).nullable)
+                        }
+                        (Some(alias), _) => (alias.clone(), true),
+                        (None, BoundExpression::Column { index, .. }) => {
+                            let column = scope.column(*index);
+                            (column.name.clone(), column.nullable)
+                        }
+                        (None, _) => (expression_name(expression), true),
+                    };
+                    if let Some(alias) = alias {
+                        aliases.push((alias, projection.len()));
+                    }
+                    output_columns.push(Column::new(&name, bound.data_type(), nullable));
+                    projection.push(bound);
+                }
+            }
+        }
+
+        let mut order_by = Vec::with_capacity(select.order_by.len());
+        for item in &select.order_by {
+            let expression = match &item.expression {
+                Expression::Literal(Literal::Integer(position)) => {
+                    let index = usize::try_from(*position)
+                        .ok()
+                        .filter(|position| (1..=projection.len()).contains(position))
+                        .ok_or_else(|| {
+                            BindError::InvalidStatement(format!(
+                                "ORDER BY position {} is not in the select list",
+                                position
+                            ))
+                        })?;
+                    projection[index - 1].clone()
+                }
+                Expression::Column { table: None, name } => {
+                    match aliases
+                        .iter()
+                        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+                    {
+                        Some((_, position)) => projection[*position].clone(),
+                        None => self.bind_expression(&item.expression, &scope)?,
+                    }
+                }
+                expression => self.bind_expression(expression, &scope)?,
+            };
+            order_by.push((expression, item.ascending));
+        }
+
+        let (group_by, aggregates) = match scope.grouping {
+            Some(grouping) => (grouping.keys, grouping.aggregates),
+            None => (Vec::new(), Vec::new()),
+        };
+        Ok(BoundSelect {
+            from,
+            filter,
+            group_by,
+            aggregates,
+            having,
+            projection,
+            order_by,
+            limit: select.limit,
+            offset: select.offset,
+            output_schema: Schema::new(output_columns),
+        })
+    }
+
+    /// Binds the grouping keys and aggregates of an aggregate query over the rows of the given
+    /// scope. Returns the scope of the aggregated rows.
+    fn bind_grouping(
+        &self,
+        group_by: &[Expression],
+        aggregate_calls: &[&Expression],
+        input: Scope,
+    ) -> Result<Scope, BindError> {
+        let mut keys = Vec::with_capacity(group_by.len());
+        let mut columns = Vec::new();
+        for expression in group_by {
+            let key = self.bind_expression(expression, &input)?;
+            let nullable = match &key {
+                BoundExpression::Column { index, .. } => input.column(*index).nullable,
+                _ => true,
+            };
+            columns.push(Column::new(
+                &expression_name(expression),
+                key.data_type(),
+                nullable,
+            ));
+            keys.push(key);
+        }
+
+        let mut aggregates: Vec<BoundAggregate> = Vec::new();
+        for call in aggregate_calls {
+            let aggregate = self.bind_aggregate(call, &input)?;
+            if !aggregates.contains(&aggregate) {
+                let nullable = aggregate.function != AggregateFunction::Count;
+                columns.push(Column::new(
+                    &expression_name(call),
+                    aggregate.data_type,
+                    nullable,
+                ));
+                aggregates.push(aggregate);
+            }
+        }
+
+        Ok(Scope {
+            tables: vec![ScopeTable {
+                name: String::new(),
+                columns,
+                offset: 0,
+            }],
+            grouping: Some(Box::new(Grouping {
+                input,
+                keys,
+                aggregates,
+            })),
+        })
+    }
+
+    /// Binds an aggregate function call whose argument is an expression over the given scope.
+    fn bind_aggregate(
+        &self,
+        expression: &Expression,
+        scope: &Scope,
+    ) -> Result<BoundAggregate, BindError> {
+        let (name, arguments, distinct) = match expression {
+            Expression::Function {
+                name,
+                arguments,
+                distinct,
+            } => (name, arguments, *distinct),
+            _ => unreachable!("Expected an aggregate function call"),
+        };
+        let function = AggregateFunction::from_name(name).expect("Expected an aggregate function");
+        let argument = match arguments.as_slice() {
+            [Expression::Wildcard] if function == AggregateFunction::Count && !distinct => None,
+            [argument] => Some(self.bind_expression(argument, scope)?),
+            _ => {
+                return Err(BindError::InvalidStatement(format!(
+                    "function {} expects exactly one argument",
+                    function
+                )))
+            }
+        };
+        let argument_type = argument.as_ref().map(BoundExpression::data_type);
+        let data_type = match (function, argument_type) {
+            (AggregateFunction::Count, _) => DataType::BigInt,
+            (AggregateFunction::Sum, Some(DataType::Float)) => DataType::Float,
+            (AggregateFunction::Sum, Some(data_type)) if data_type.is_numeric() => DataType::BigInt,
+            (AggregateFunction::Avg, Some(data_type)) if data_type.is_numeric() => DataType::Float,
+            (AggregateFunction::Min | AggregateFunction::Max, Some(data_type)) => data_type,
+            (_, data_type) => {
+                return Err(BindError::TypeMismatch(format!(
+                    "function {} cannot be applied to {}",
+                    function,
+                    data_type.map_or("*".to_string(), |data_type| data_type.to_string())
+                )))
+            }
+        };
+        Ok(BoundAggregate {
+            function,
+            argument,
+            distinct,
+            data_type,
+        })
+    }
+
+    /// Binds an expression over the aggregated rows if it is one of the grouping keys or an
+    /// aggregate, which are read from the aggregated row.
+    fn bind_grouped(
+        &self,
+        expression: &Expression,
+        grouping: &Grouping,
+    ) -> Result<Option<BoundExpression>, BindError> {
+        let (index, data_type) = match expression {
+            Expression::Function { name, .. } if AggregateFunction::from_name(name).is_some() => {
+                let aggregate = self.bind_aggregate(expression, &grouping.input)?;
+                let position = grouping
+                    .aggregates
+                    .iter()
+                    .position(|other| *other == aggregate)
+                    .expect("The aggregates of the query have not been collected");
+                (grouping.keys.len() + position, aggregate.data_type)
+            }
+            _ if contains_aggregate(expression) => return Ok(None),
+            _ => {
+                let bound = self.bind_expression(expression, &grouping.input)?;
+                match grouping.keys.iter().position(|key| *key == bound) {
+                    Some(position) => (position, bound.data_type()),
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(BoundExpression::Column { index, data_type }))
+    }
+
+    /// Binds a table reference. The offsets of the returned scope are relative to the rows
+    /// produced by the table reference.
+    fn bind_table_reference(
+        &self,
+        table_reference: &TableReference,
+    ) -> Result<(BoundTableReference, Scope), BindError> {
+        match table_reference {
+            TableReference::Table { name, alias } => {
+                let table = self.get_table(name)?;
+                let name = alias.clone().unwrap_or_else(|| table.name.clone());
+                let scope = Scope {
+                    tables: vec![ScopeTable {
+                        name: name.clone(),
+                        columns: table.schema.columns.clone(),
+                        offset: 0,
+                    }],
+                    grouping: None,
+                };
+                let bound = BoundTableReference::Table {
+                    table_id: table.table_id,
+                    name,
+                    schema: table.schema.clone(),
+                };
+                Ok((bound, scope))
+            }
+            TableReference::Join {
+                left,
+                right,
+                join_type,
+                condition,
+            } => {
+                let (left, mut scope) = self.bind_table_reference(left)?;
+                let (right, right_scope) = self.bind_table_reference(right)?;
+                let left_table_count = scope.tables.len();
+                scope.extend(right_scope)?;
+                let condition = self.bind_filter(condition.as_ref(), &scope, "ON")?;
+                // The right input of semi and anti joins is only visible in the condition.
+                if matches!(join_type, JoinType::Semi | JoinType::Anti) {
+                    scope.tables.truncate(left_table_count);
+                }
+                let bound = BoundTableReference::Join {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    join_type: *join_type,
+                    condition,
+                };
+                Ok((bound, scope))
+            }
+        }
+    }
+
+    fn bind_filter(
+        &self,
+        expression: Option<&Expression>,
+        scope: &Scope,
+        clause: &str,
+    ) -> Result<Option<BoundExpression>, BindError> {
+        expression
+            .map(|expression| {
+                let bound = self.bind_expression(expression, scope)?;
+                let data_type = bound.data_type();
+                coerce(bound, DataType::Boolean).map_err(|error| match error {
+                    BindError::TypeMismatch(_) => BindError::TypeMismatch(format!(
+                        "argument of {} must be BOOLEAN, not {}",
+                        clause, data_type
+                    )),
+                    error => error,
+                })
+            })
+            .transpose()
+    }
+
+    fn bind_expression(
+        &self,
+        expression: &Expression,
+        scope: &Scope,
+    ) -> Result<BoundExpression, BindError> {
+        if let Some(grouping) = &scope.grouping {
+            if let Some(bound) = self.bind_grouped(expression, grouping)? {
+                return Ok(bound);
+            }
+        }
+        match expression {
+            Expression::Literal(literal) => Ok(bind_literal(literal)),
+            Expression::Column { table, name } if scope.grouping.is_some() => {
+                Err(BindError::InvalidStatement(format!(
+                    "column {} must appear in the GROUP BY clause or be used in an aggregate \
+                     function",
+                    table
+                        .as_ref()
+                        .map_or(name.clone(), |table| format!("{}.{}", table, name))
+                )))
+            }
+            Expression::Column { table, name } => scope.resolve(table.as_deref(), name),
+            Expression::Unary { operator, operand } => {
+                let operand = self.bind_expression(operand, scope)?;
+                let data_type = match operator {
+                    UnaryOperator::Not => DataType::Boolean,
+                    UnaryOperator::Minus if operand.is_null_constant() => DataType::Integer,
+                    UnaryOperator::Minus => operand.data_type(),
+                };
+                if *operator == UnaryOperator::Minus && !data_type.is_numeric() {
+                    return Err(BindError::TypeMismatch(format!(
+                        "operator - cannot be applied to {}",
+                        operand.data_type()
+                    )));
+                }
+                let operand_type = operand.data_type();
+                let operand = coerce(operand, data_type).map_err(|error| match error {
+                    BindError::TypeMismatch(_) => BindError::TypeMismatch(format!(
+                        "argument of NOT must be BOOLEAN, not {}",
+                        operand_type
+                    )),
+                    error => error,
+                })?;
+                Ok(BoundExpression::Unary {
+                    operator: *operator,
+                    operand: Box::new(operand),
+                    data_type,
+                })
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.bind_expression(left, scope)?;
+                let right = self.bind_expression(right, scope)?;
+                bind_binary(left, *operator, right)
+            }
+            Expression::IsNull { operand, negated } => Ok(BoundExpression::IsNull {
+                operand: Box::new(self.bind_expression(operand, scope)?),
+                negated: *negated,
+            }),
+            Expression::Cast { operand, data_type } => {
+                let operand = self.bind_expression(operand, scope)?;
+                if !operand.is_null_constant() && !operand.data_type().can_cast_to(data_type) {
+                    return Err(BindError::TypeMismatch(format!(
+                        "cannot cast {} to {}",
+                        operand.data_type(),
+                        data_type
+                    )));
+                }
+                cast(operand, *data_type)
+            }
+            Expression::Function { name, .. } if AggregateFunction::from_name(name).is_some() => {
+                Err(BindError::InvalidStatement(format!(
+                    "aggregate function {} is not allowed here",
+                    name.to_lowercase()
+                )))
+            }
+            Expression::Function { name, .. } => {
+                Err(BindError::Unsupported(format!("unknown function {}", name)))
+            }
+            Expression::Wildcard => Err(BindError::Unsupported(
+                "* is only allowed as argument of COUNT".to_string(),
+            )),
+        }
+    }
+}
+
+fn bind_literal(literal: &Literal) -> BoundExpression {
+    let (value, data_type) = match literal {
+        Literal::Null => return null_constant(DataType::Integer),
+        Literal::Boolean(value) => (Value::Boolean(*value), DataType::Boolean),
+        Literal::Integer(value) => match i32::try_from(*value) {
+            Ok(value) => (Value::Integer(value), DataType::Integer),
+            Err(_) => (Value::BigInt(*value), DataType::BigInt),
+        },
+        Literal::Float(value) => (Value::Float(*value), DataType::Float),
+        Literal::String(value) => (Value::String(value.clone()), DataType::Text),
+    };
+    BoundExpression::Constant { value, data_type }
+}
+
+fn bind_binary(
+    left: BoundExpression,
+    operator: BinaryOperator,
+    right: BoundExpression,
+) -> Result<BoundExpression, BindError> {
+    let (left_type, right_type) = (left.data_type(), right.data_type());
+    let mismatch = |error: BindError| match error {
+        BindError::TypeMismatch(_) => BindError::TypeMismatch(format!(
+            "operator {:?} cannot be applied to {} and {}",
+            operator, left_type, right_type
+        )),
+        error => error,
+    };
+
+    let (left, right, data_type) = match operator {
+        BinaryOperator::And | BinaryOperator::Or => {
+            let left = coerce(left, DataType::Boolean).map_err(mismatch)?;
+            let right = coerce(right, DataType::Boolean).map_err(mismatch)?;
+            (left, right, DataType::Boolean)
+        }
+        BinaryOperator::Plus
+        | BinaryOperator::Minus
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo => {
+            let (left, right) = unify(left, right).map_err(mismatch)?;
+            let data_type = left.data_type();
+            if !data_type.is_numeric() {
+                return Err(mismatch(BindError::TypeMismatch(String::new())));
+            }
+            (left, right, data_type)
+        }
+        BinaryOperator::Equal
+        | BinaryOperator::NotEqual
+        | BinaryOperator::Less
+        | BinaryOperator::LessEqual
+        | BinaryOperator::Greater
+        | BinaryOperator::GreaterEqual => {
+            let (left, right) = unify(left, right).map_err(|error| match error {
+                BindError::TypeMismatch(_) => BindError::TypeMismatch(format!(
+                    "cannot compare {} with {}",
+                    left_type, right_type
+                )),
+                error => error,
+            })?;
+            (left, right, DataType::Boolean)
+        }
+    };
+    Ok(BoundExpression::Binary {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+        data_type,
+    })
+}
+
+/// Converts two operands to a common type. String operands are compatible with each other
+/// regardless of their maximum length.
+fn unify(
+    left: BoundExpression,
+    right: BoundExpression,
+) -> Result<(BoundExpression, BoundExpression), BindError> {
+    let (left_type, right_type) = (left.data_type(), right.data_type());
+    if left.is_null_constant() {
+        return Ok((null_constant(right_type), right));
+    }
+    if right.is_null_constant() {
+        return Ok((left, null_constant(left_type)));
+    }
+    if left_type == right_type || (left_type.is_string() && right_type.is_string()) {
+        return Ok((left, right));
+    }
+    if let Some(common_type) = left_type.common_numeric_type(&right_type) {
+        return Ok((coerce(left, common_type)?, coerce(right, common_type)?));
+    }
+    if left_type == DataType::Date && right_type == DataType::Timestamp {
+        return Ok((coerce(left, right_type)?, right));
+    }
+    if is_string_constant(&left) {
+        return Ok((coerce(left, right_type)?, right));
+    }
+    Ok((left, coerce(right, left_type)?))
+}
+
+/// Implicitly converts the expression to the given type. Besides the conversions between
+/// numeric types and from DATE to TIMESTAMP, string constants are converted to any type.
+fn coerce(expression: BoundExpression, data_type: DataType) -> Result<BoundExpression, BindError> {
+    let source_type = expression.data_type();
+    if source_type == data_type {
+        return Ok(expression);
+    }
+    if expression.is_null_constant() {
+        return Ok(null_constant(data_type));
+    }
+    let implicit = (source_type.is_numeric() && data_type.is_numeric())
+        || (source_type.is_string() && data_type.is_string())
+        || (source_type == DataType::Date && data_type == DataType::Timestamp)
+        || is_string_constant(&expression);
+    if !implicit {
+        return Err(BindError::TypeMismatch(format!(
+            "cannot convert {} to {}",
+            source_type, data_type
+        )));
+    }
+    cast(expression, data_type)
+}
+
+/// Converts the value of an INSERT or UPDATE to the type of its column.
+fn assign(value: BoundExpression, column: &Column) -> Result<BoundExpression, BindError> {
+    if value.is_null_constant() && !column.nullable {
+        return Err(BindError::NotNullViolation(column.name.clone()));
+    }
+    let source_type = value.data_type();
+    if is_string_constant(&value) {
+        return cast(value, column.data_type);
+    }
+    coerce(value, column.data_type).map_err(|error| match error {
+        BindError::TypeMismatch(_) => BindError::TypeMismatch(format!(
+            "column {} is of type {} but the expression is of type {}",
+            column.name, column.data_type, source_type
+        )),
+        error => error,
+    })
+}
+
+/// Wraps the expression in a cast. Casts of constants are evaluated immediately.
+fn cast(expression: BoundExpression, data_type: DataType) -> Result<BoundExpression, BindError> {
+    if let BoundExpression::Constant { value, .. } = &expression {
+        let value = value.cast_to(&data_type).map_err(BindError::InvalidValue)?;
+        return Ok(BoundExpression::Constant { value, data_type });
+    }
+    Ok(BoundExpression::Cast {
+        operand: Box::new(expression),
+        data_type,
+    })
+}
+
+fn null_constant(data_type: DataType) -> BoundExpression {
+    BoundExpression::Constant {
+        value: Value::Null,
+        data_type,
+    }
+}
+
+fn is_string_constant(expression: &BoundExpression) -> bool {
+    matches!(
+        expression,
+        BoundExpression::Constant {
+            value: Value::String(_),
+            ..
+        }
+    )
+}
+
+/// Appends the aggregate function calls of the expression. The arguments of aggregate function
+/// calls are not searched.
+fn collect_aggregates<'e>(expression: &'e Expression, aggregates: &mut Vec<&'e Expression>) {
+    match expression {
+        Expression::Function { name, .. } if AggregateFunction::from_name(name).is_some() => {
+            aggregates.push(expression)
+        }
+        Expression::Function { arguments, .. } => {
+            for argument in arguments {
+                collect_aggregates(argument, aggregates);
+            }
+        }
+        Expression::Unary { operand, .. }
+        | Expression::IsNull { operand, .. }
+        | Expression::Cast { operand, .. } => collect_aggregates(operand, aggregates),
+        Expression::Binary { left, right, .. } => {
+            collect_aggregates(left, aggregates);
+            collect_aggregates(right, aggregates);
+        }
+        Expression::Literal(_) | Expression::Column { .. } | Expression::Wildcard => {}
+    }
+}
+
+fn contains_aggregate(expression: &Expression) -> bool {
+    let mut aggregates = Vec::new();
+    collect_aggregates(expression, &mut aggregates);
+    !aggregates.is_empty()
+}
+
+fn expression_name(expression: &Expression) -> String {
+    match expression {
+        Expression::Column { name, .. } => name.clone(),
+        Expression::Function { name, .. } => name.to_lowercase(),
+        Expression::Cast { operand, .. } => expression_name(operand),
+        _ => "?column?".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod binder_tests {
+    use chrono::NaiveDate;
+
+    use crate::{
+        binder::{
+            bound_expression::{AggregateFunction, BoundAggregate, BoundExpression},
+            bound_statement::{BoundSelect, BoundStatement},
+        },
+        catalog::system_catalog::{Catalog, CatalogError},
+        disk_management::test_utils::new_test_buffer_pool,
+        sql::{ast::BinaryOperator, parser::parse_statement},
+        types::{
+            schema::{Column, DataType, Schema},
+            value::Value,
+        },
+    };
+
+    use super::{BindError, Binder};
+
+    fn test_catalog(name: &str) -> Catalog {
+        let mut catalog = Catalog::open(new_test_buffer_pool(name), None).unwrap();
+        catalog
+            .create_table(
+                "users",
+                Schema::new(vec![
+                    Column::new("id", DataType::Integer, false),
+                    Column::new("name", DataType::Varchar(5), true),
+                    Column::new("born", DataType::Date, true),
+                ]),
+                None,
+            )
+            .unwrap();
+        catalog
+            .create_table(
+                "orders",
+                Schema::new(vec![
+                    Column::new("id", DataType::Integer, false),
+                    Column::new("user_id", DataType::BigInt, false),
+                    Column::new("amount", DataType::Float, true),
+                ]),
+                None,
+            )
+            .unwrap();
+        catalog
+    }
+
+    fn bind(catalog: &Catalog, sql: &str) -> Result<BoundStatement, BindError> {
+        Binder::new(catalog).bind(&parse_statement(sql).unwrap())
+    }
+
+    fn bind_select(catalog: &Catalog, sql: &str) -> BoundSelect {
+        match bind(catalog, sql).unwrap() {
+            BoundStatement::Select(select) => *select,
+            _ => panic!("Expected a SELECT statement"),
+        }
+    }
+
+    fn column(index: usize, data_type: DataType) -> BoundExpression {
+        BoundExpression::Column { index, data_type }
+    }
+
+    fn constant(value: Value, data_type: DataType) -> BoundExpression {
+        BoundExpression::Constant { value, data_type }
+    }
+
+    fn binary(
+        left: BoundExpression,
+        operator: BinaryOperator,
+        right: BoundExpression,
+        data_type: DataType,
+    ) -> BoundExpression {
+        BoundExpression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            data_type,
+        }
+    }
+
+    #[test]
+    fn resolve_columns_of_joined_tables() {
+        let catalog = test_catalog("binder_resolve_columns");
+        let select = bind_select(
+            &catalog,
+            "SELECT o.id, name AS user_name, u.* FROM users u JOIN orders o ON u.id = o.user_id",
+        );
+        assert_eq!(select.projection[0], column(3, DataType::Integer));
+        assert_eq!(select.projection[1], column(1, DataType::Varchar(5)));
+        assert_eq!(select.projection.len(), 5);
+        assert_eq!(select.output_schema.columns[1].name, "user_name");
+        assert_eq!(select.output_schema.columns[0].name, "id");
+
+        assert_eq!(
+            bind(&catalog, "SELECT id FROM users, orders"),
+            Err(BindError::AmbiguousColumn("id".to_string()))
+        );
+        assert_eq!(
+            bind(&catalog, "SELECT users.id FROM users u"),
+            Err(BindError::Catalog(CatalogError::TableNotFound(
+                "users".to_string()
+            )))
+        );
+        assert_eq!(
+            bind(&catalog, "SELECT age FROM users"),
+            Err(BindError::Catalog(CatalogError::ColumnNotFound(
+                "age".to_string()
+            )))
+        );
+        assert_eq!(
+            bind(&catalog, "SELECT * FROM users, users"),
+            Err(BindError::DuplicateTableName("users".to_string()))
+        );
+        assert!(bind(&catalog, "SELECT * FROM missing").is_err());
+
+        let select = bind_select(
+            &catalog,
+            "SELECT * FROM users u LEFT SEMI JOIN orders o ON u.id = o.user_id",
+        );
+        assert_eq!(select.projection.len(), 3);
+        assert_eq!(
+            bind(
+                &catalog,
+                "SELECT o.id FROM users u LEFT ANTI JOIN orders o ON u.id = o.user_id"
+            ),
+            Err(BindError::Catalog(CatalogError::TableNotFound(
+                "o".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn implicit_casts() {
+        let catalog = test_catalog("binder_implicit_casts");
+        let select = bind_select(
+            &catalog,
+            "SELECT amount + o.id FROM users u, orders o \
+             WHERE user_id = 1 AND born > '2020-01-01'",
+        );
+        assert_eq!(
+            select.projection[0],
+            binary(
+                column(5, DataType::Float),
+                BinaryOperator::Plus,
+                BoundExpression::Cast {
+                    operand: Box::new(column(3, DataType::Integer)),
+                    data_type: DataType::Float,
+                },
+                DataType::Float
+            )
+        );
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        assert_eq!(
+            select.filter,
+            Some(binary(
+                binary(
+                    column(4, DataType::BigInt),
+                    BinaryOperator::Equal,
+                    constant(Value::BigInt(1), DataType::BigInt),
+                    DataType::Boolean
+                ),
+                BinaryOperator::And,
+                binary(
+                    column(2, DataType::Date),
+                    BinaryOperator::Greater,
+                    constant(Value::Date(date), DataType::Date),
+                    DataType::Boolean
+                ),
+                DataType::Boolean
+            ))
+        );
+    }
+
+    #[test]
+    fn type_errors() {
+        let catalog = test_catalog("binder_type_errors");
+        assert_eq!(
+            bind(&catalog, "SELECT * FROM users WHERE name = 1"),
+            Err(BindError::TypeMismatch(
+                "cannot compare VARCHAR(5) with INTEGER".to_string()
+            ))
+        );
+        assert!(matches!(
+            bind(&catalog, "SELECT * FROM users WHERE id"),
+            Err(BindError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            bind(&catalog, "SELECT name * 2 FROM users"),
+            Err(BindError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            bind(&catalog, "SELECT * FROM users WHERE born = 'yesterday'"),
+            Err(BindError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            bind(&catalog, "SELECT CAST(born AS BOOLEAN) FROM users"),
+            Err(BindError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn bind_insert_and_update() {
+        let catalog = test_catalog("binder_insert_update");
+        match bind(
+            &catalog,
+            "INSERT INTO users (born, id) VALUES ('2000-02-02', 7)",
+        )
+        .unwrap()
+        {
+            BoundStatement::Insert(insert) => {
+                let date = NaiveDate::from_ymd_opt(2000, 2, 2).unwrap();
+                assert_eq!(
+                    insert.rows[0],
+                    vec![
+                        constant(Value::Integer(7), DataType::Integer),
+                        constant(Value::Null, DataType::Varchar(5)),
+                        constant(Value::Date(date), DataType::Date),
+                    ]
+                );
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+        assert_eq!(
+            bind(&catalog, "INSERT INTO users (name) VALUES ('a')"),
+            Err(BindError::NotNullViolation("id".to_string()))
+        );
+        assert!(matches!(
+            bind(&catalog, "INSERT INTO users VALUES (1, 'too long', NULL)"),
+            Err(BindError::InvalidValue(_))
+        ));
+        assert!(matches!(
+            bind(&catalog, "INSERT INTO users VALUES (1, 'a')"),
+            Err(BindError::InvalidStatement(_))
+        ));
+
+        match bind(&catalog, "UPDATE orders SET amount = user_id WHERE id = 3").unwrap() {
+            BoundStatement::Update(update) => assert_eq!(
+                update.assignments,
+                vec![(
+                    2,
+                    BoundExpression::Cast {
+                        operand: Box::new(column(1, DataType::BigInt)),
+                        data_type: DataType::Float,
+                    }
+                )]
+            ),
+            _ => panic!("Expected an UPDATE statement"),
+        }
+        assert!(matches!(
+            bind(&catalog, "UPDATE users SET id = NULL"),
+            Err(BindError::NotNullViolation(_))
+        ));
+    }
+
+    #[test]
+    fn order_by_aliases_and_positions() {
+        let catalog = test_catalog("binder_order_by");
+        let select = bind_select(
+            &catalog,
+            "SELECT id * 2 AS double_id, name FROM users ORDER BY double_id, 2 DESC, born",
+        );
+        assert_eq!(select.order_by[0].0, select.projection[0]);
+        assert_eq!(select.order_by[1], (column(1, DataType::Varchar(5)), false));
+        assert_eq!(select.order_by[2].0, column(2, DataType::Date));
+        assert!(bind(&catalog, "SELECT id FROM users ORDER BY 2").is_err());
+    }
+
+    #[test]
+    fn bind_aggregates() {
+        let catalog = test_catalog("binder_aggregates");
+        let select = bind_select(
+            &catalog,
+            "SELECT user_id, count(*), sum(amount) AS total FROM orders
+             GROUP BY user_id HAVING count(*) > 1 ORDER BY sum(amount) DESC",
+        );
+        assert!(select.is_aggregate());
+        assert_eq!(select.group_by, vec![column(1, DataType::BigInt)]);
+        assert_eq!(
+            select.aggregates,
+            vec![
+                BoundAggregate {
+                    function: AggregateFunction::Count,
+                    argument: None,
+                    distinct: false,
+                    data_type: DataType::BigInt,
+                },
+                BoundAggregate {
+                    function: AggregateFunction::Sum,
+                    argument: Some(column(2, DataType::Float)),
+                    distinct: false,
+                    data_type: DataType::Float,
+                },
+            ]
+        );
+        assert_eq!(
+            select.projection,
+            vec![
+                column(0, DataType::BigInt),
+                column(1, DataType::BigInt),
+                column(2, DataType::Float),
+            ]
+        );
+        assert!(select.having.is_some());
+        assert_eq!(select.order_by, vec![(column(2, DataType::Float), false)]);
+        assert_eq!(
+            select.output_schema,
+            Schema::new(vec![
+                Column::new("user_id", DataType::BigInt, false),
+                Column::new("count", DataType::BigInt, false),
+                Column::new("total", DataType::Float, true),
+            ])
+        );
+
+        let select = bind_select(
+            &catalog,
+            "SELECT user_id + 1, count(DISTINCT id) FROM orders GROUP BY user_id",
+        );
+        assert!(matches!(
+            &select.projection[0],
+            BoundExpression::Binary { left, .. } if **left == column(0, DataType::BigInt)
+        ));
+        assert!(select.aggregates[0].distinct);
+        assert!(bind_select(&catalog, "SELECT max(name) FROM users").is_aggregate());
+
+        for sql in [
+            "SELECT id, count(*) FROM orders",
+            "SELECT amount FROM orders GROUP BY user_id",
+            "SELECT * FROM orders GROUP BY user_id",
+            "SELECT id FROM orders WHERE count(*) > 1",
+            "SELECT count(sum(amount)) FROM orders",
+        ] {
+            assert!(
+                matches!(bind(&catalog, sql), Err(BindError::InvalidStatement(_))),
+                "{}",
+                sql
+            );
+        }
+        assert!(matches!(
+            bind(&catalog, "SELECT sum(name) FROM users"),
+            Err(BindError::TypeMismatch(_))
+        ));
+    }
+}
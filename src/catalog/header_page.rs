@@ -0,0 +1,67 @@
+use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
+
+// PAGE FORMAT:
+// | MAGIC [u32] | TABLES DIRECTORY [u32] | COLUMNS DIRECTORY [u32] | INDEXES DIRECTORY [u32] |
+// | TABLE STATISTICS DIRECTORY [u32] | COLUMN STATISTICS DIRECTORY [u32] |
+//
+// The header page is always the first page of the database file. It stores the first directory
+// pages of the table heaps that make up the catalog. Files created before statistics were added
+// store 0 as the directories of the statistics heaps. Files whose B+ tree indexes store hashed
+// keys start with MDB1 and are rejected.
+
+pub const HEADER_PAGE_ID: u32 = 0;
+const MAGIC: u32 = u32::from_le_bytes(*b"MDB2");
+
+#[derive(Debug, PartialEq)]
+pub struct HeaderPage {
+    pub tables_directory: u32,
+    pub columns_directory: u32,
+    pub indexes_directory: u32,
+    pub table_statistics_directory: u32,
+    pub column_statistics_directory: u32,
+}
+
+impl HeaderPage {
+    pub fn from_raw_page(raw_page: &RawPage) -> Result<HeaderPage, &str> {
+        let bytes = raw_page.data.read().unwrap();
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        if read_u32(0) != MAGIC {
+            return Err("The file does not start with a database header page");
+        }
+        Ok(HeaderPage {
+            tables_directory: read_u32(4),
+            columns_directory: read_u32(8),
+            indexes_directory: read_u32(12),
+            table_statistics_directory: read_u32(16),
+            column_statistics_directory: read_u32(20),
+        })
+    }
+
+    pub fn to_raw_page(&self) -> RawPage {
+        let mut bytes = [0; PAGE_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.tables_directory.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.columns_directory.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.indexes_directory.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.table_statistics_directory.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.column_statistics_directory.to_le_bytes());
+        RawPage::new(bytes)
+    }
+}
+
+#[test]
+fn header_page_round_trip() {
+    let header_page = HeaderPage {
+        tables_directory: 1,
+        columns_directory: 2,
+        indexes_directory: 3,
+        table_statistics_directory: 4,
+        column_statistics_directory: 5,
+    };
+    assert_eq!(
+        HeaderPage::from_raw_page(&header_page.to_raw_page()).unwrap(),
+        header_page
+    );
+    assert!(HeaderPage::from_raw_page(&RawPage::new([0; PAGE_SIZE])).is_err());
+}
@@ -0,0 +1,3 @@
+pub mod header_page;
+pub mod statistics;
+pub mod system_catalog;
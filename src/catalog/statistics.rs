@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::{
+    execution::executor::Visibility,
+    table::table_heap::TableHeap,
+    types::{row::Row, schema::Schema, value::Value},
+};
+
+/// The number of rows ANALYZE samples from every table.
+pub const SAMPLE_SIZE: usize = 3000;
+/// The largest number of most common values kept per column.
+pub const MAX_MOST_COMMON_VALUES: usize = 10;
+/// The largest number of buckets of a histogram.
+pub const HISTOGRAM_BUCKETS: usize = 20;
+
+/// The statistics ANALYZE gathers about a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableStatistics {
+    pub row_count: u64,
+    pub page_count: u64,
+    /// The statistics of every column, in schema order.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+/// The distribution of the values of a column. Frequencies are fractions of all rows of the
+/// table, including those where the column is NULL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStatistics {
+    /// The estimated number of distinct values other than NULL.
+    pub distinct_count: f64,
+    pub null_fraction: f64,
+    /// The most common values with their frequencies, most common first.
+    pub most_common_values: Vec<(Value, f64)>,
+    /// The bounds of an equi-depth histogram of the values that are neither NULL nor among the
+    /// most common values: every bucket between two consecutive bounds holds about as many
+    /// values. Empty if there are fewer than two such values in the sample.
+    pub histogram_bounds: Vec<Value>,
+}
+
+impl ColumnStatistics {
+    /// The fraction of rows whose value is neither NULL nor one of the most common values.
+    pub fn histogram_fraction(&self) -> f64 {
+        let most_common_fraction: f64 = self
+            .most_common_values
+            .iter()
+            .map(|(_, frequency)| frequency)
+            .sum();
+        (1.0 - self.null_fraction - most_common_fraction).max(0.0)
+    }
+}
+
+/// Reads the rows of the table heap that are visible to the analyzing transaction and gathers
+/// statistics about their values from a uniform sample of at most [SAMPLE_SIZE] rows.
+pub fn analyze_table(
+    table_heap: &TableHeap,
+    schema: &Schema,
+    visibility: &Visibility,
+) -> Result<TableStatistics, &'static str> {
+    // Reservoir sampling: the i-th row replaces a random sampled row with probability
+    // SAMPLE_SIZE / i, so that every row is equally likely to be part of the sample.
+    let mut rng = rand::thread_rng();
+    let mut sample = Vec::new();
+    let mut row_count = 0;
+    // Only the versions in the snapshot are counted, like in a sequential scan.
+    for entry in table_heap.iter() {
+        let (_, tuple) = entry?;
+        if !visibility.is_visible(tuple.version) {
+            continue;
+        }
+        row_count += 1;
+        if sample.len() < SAMPLE_SIZE {
+            sample.push(tuple.data);
+        } else {
+            let position = rng.gen_range(0..row_count as usize);
+            if position < SAMPLE_SIZE {
+                sample[position] = tuple.data;
+            }
+        }
+    }
+    let rows = sample
+        .iter()
+        .map(|data| Row::from_tuple_data(data, schema))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = (0..schema.columns.len())
+        .map(|column| {
+            let values = rows.iter().map(|row| row.values[column].clone()).collect();
+            analyze_column(values, row_count)
+        })
+        .collect();
+    Ok(TableStatistics {
+        row_count,
+        page_count: table_heap.get_page_ids().len() as u64,
+        columns,
+    })
+}
+
+/// Gathers the statistics of a column from the values of the sampled rows.
+fn analyze_column(values: Vec<Value>, row_count: u64) -> ColumnStatistics {
+    let sample_size = values.len();
+    let mut counts: HashMap<Value, usize> = HashMap::new();
+    let mut null_count = 0;
+    for value in values {
+        if value.is_null() {
+            null_count += 1;
+        } else {
+            *counts.entry(value).or_default() += 1;
+        }
+    }
+    if sample_size == 0 {
+        return ColumnStatistics {
+            distinct_count: 0.0,
+            null_fraction: 0.0,
+            most_common_values: Vec::new(),
+            histogram_bounds: Vec::new(),
+        };
+    }
+
+    let null_fraction = null_count as f64 / sample_size as f64;
+    let sampled = (sample_size - null_count) as f64;
+    let sampled_distinct = counts.len() as f64;
+    let distinct_count = if sample_size as u64 == row_count {
+        sampled_distinct
+    } else {
+        // The estimator of Haas and Stokes, which extrapolates from the number of values that
+        // occur exactly once in the sample.
+        let total = row_count as f64 * (1.0 - null_fraction);
+        let singletons = counts.values().filter(|count| **count == 1).count() as f64;
+        let estimate = sampled * sampled_distinct
+            / (sampled - singletons + singletons * sampled / total.max(1.0));
+        estimate.clamp(sampled_distinct, total.max(sampled_distinct))
+    };
+
+    // A value is among the most common values if it occurs more than once and clearly more
+    // often than the average value, or if all values fit.
+    let mut candidates: Vec<(Value, usize)> = counts.into_iter().collect();
+    candidates.sort_by(|(left, left_count), (right, right_count)| {
+        right_count.cmp(left_count).then_with(|| left.cmp(right))
+    });
+    let all_fit = candidates.len() <= MAX_MOST_COMMON_VALUES;
+    let average_count = sampled / sampled_distinct.max(1.0);
+    let most_common_count = candidates
+        .iter()
+        .take(MAX_MOST_COMMON_VALUES)
+        .take_while(|(_, count)| *count > 1 && (all_fit || *count as f64 > 1.25 * average_count))
+        .count();
+    let remaining = candidates.split_off(most_common_count);
+    let most_common_values = candidates
+        .into_iter()
+        .map(|(value, count)| (value, count as f64 / sample_size as f64))
+        .collect();
+
+    let mut histogram_values: Vec<Value> = remaining
+        .into_iter()
+        .flat_map(|(value, count)| std::iter::repeat_n(value, count))
+        .collect();
+    histogram_values.sort();
+    let histogram_bounds = if histogram_values.len() < 2 {
+        Vec::new()
+    } else {
+        let buckets = HISTOGRAM_BUCKETS.min(histogram_values.len() - 1);
+        (0..=buckets)
+            .map(|bucket| histogram_values[bucket * (histogram_values.len() - 1) / buckets].clone())
+            .collect()
+    };
+
+    ColumnStatistics {
+        distinct_count,
+        null_fraction,
+        most_common_values,
+        histogram_bounds,
+    }
+}
+
+#[cfg(test)]
+mod statistics_tests {
+    use crate::{
+        disk_management::test_utils::new_test_buffer_pool,
+        execution::executor::ExecutionContext,
+        table::table_heap::TableHeap,
+        types::{
+            row::Row,
+            schema::{Column, DataType, Schema},
+            value::Value,
+        },
+    };
+
+    use super::{analyze_table, HISTOGRAM_BUCKETS, SAMPLE_SIZE};
+
+    #[test]
+    fn analyze_sampled_table() {
+        let buffer_pool = new_test_buffer_pool("statistics_analyze");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let visibility = ExecutionContext::new(buffer_pool).read_table(0).unwrap();
+        let schema = Schema::new(vec![
+            Column::new("id", DataType::Integer, false),
+            Column::new("category", DataType::Integer, true),
+        ]);
+        // Every tenth category is NULL, and half of all rows have category 0.
+        let row_count = 2 * SAMPLE_SIZE;
+        for id in 0..row_count {
+            let category = match id % 10 {
+                0 => Value::Null,
+                1..=5 => Value::Integer(0),
+                _ => Value::Integer(id as i32 % 400 + 1),
+            };
+            let data = Row::new(vec![Value::Integer(id as i32), category])
+                .to_tuple_data(&schema)
+                .unwrap();
+            table_heap.insert(data, None).unwrap();
+        }
+
+        let statistics = analyze_table(&table_heap, &schema, &visibility).unwrap();
+        assert_eq!(statistics.row_count, row_count as u64);
+        assert_eq!(
+            statistics.page_count,
+            table_heap.get_page_ids().len() as u64
+        );
+
+        let id = &statistics.columns[0];
+        assert_eq!(id.null_fraction, 0.0);
+        assert!(id.most_common_values.is_empty());
+        assert!(id.distinct_count > 0.9 * row_count as f64);
+        assert_eq!(id.histogram_bounds.len(), HISTOGRAM_BUCKETS + 1);
+        assert!(id
+            .histogram_bounds
+            .windows(2)
+            .all(|pair| pair[0] <= pair[1]));
+
+        let category = &statistics.columns[1];
+        assert!((category.null_fraction - 0.1).abs() < 0.05);
+        let (value, frequency) = &category.most_common_values[0];
+        assert_eq!(value, &Value::Integer(0));
+        assert!((frequency - 0.5).abs() < 0.05);
+        assert!(category.distinct_count > 100.0 && category.distinct_count <= 401.0);
+        assert!((category.histogram_fraction() - 0.4).abs() < 0.1);
+    }
+
+    #[test]
+    fn analyze_small_table_exactly() {
+        let buffer_pool = new_test_buffer_pool("statistics_small");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let visibility = ExecutionContext::new(buffer_pool).read_table(0).unwrap();
+        let schema = Schema::new(vec![Column::new("name", DataType::Text, true)]);
+        for name in ["a", "b", "b", "c", "c", "c"] {
+            let data = Row::new(vec![Value::String(name.to_string())])
+                .to_tuple_data(&schema)
+                .unwrap();
+            table_heap.insert(data, None).unwrap();
+        }
+
+        let statistics = analyze_table(&table_heap, &schema, &visibility).unwrap();
+        let name = &statistics.columns[0];
+        assert_eq!(name.distinct_count, 3.0);
+        assert_eq!(
+            name.most_common_values,
+            vec![
+                (Value::String("c".to_string()), 0.5),
+                (Value::String("b".to_string()), 2.0 / 6.0),
+            ]
+        );
+        assert!(name.histogram_bounds.is_empty());
+    }
+}
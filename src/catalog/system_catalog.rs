@@ -0,0 +1,1056 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    b_plus_tree::b_plus_tree::BPlusTree,
+    disk_management::{buffer_pool::BufferPool, disk_manager::DiskError},
+    extendible_hashing::extendible_hashing::ExtendibleHashing,
+    index::ordered_key::OrderedKey,
+    recovery::log_record::TransactionId,
+    table::{table_heap::TableHeap, table_page::Rid},
+    types::{
+        row::Row,
+        schema::{Column, DataType, Schema},
+        value::Value,
+    },
+};
+
+use super::{
+    header_page::{HeaderPage, HEADER_PAGE_ID},
+    statistics::{ColumnStatistics, TableStatistics},
+};
+
+// The catalog is stored in five table heaps whose first directory pages are recorded in the
+// header page:
+// - tables:            | TABLE_ID | NAME | FIRST_DIRECTORY_PAGE |
+// - columns:           | TABLE_ID | POSITION | NAME | TYPE_ID | TYPE_LENGTH | NULLABLE |
+// - indexes:           | INDEX_ID | NAME | TABLE_ID | INDEX_TYPE | KEY_COLUMNS | UNIQUE | ROOT_PAGE |
+// - table statistics:  | TABLE_ID | ROW_COUNT | PAGE_COUNT |
+// - column statistics: | TABLE_ID | POSITION | DISTINCT_COUNT | NULL_FRACTION |
+//                        MOST_COMMON_VALUES | MOST_COMMON_FREQUENCIES | HISTOGRAM_BOUNDS |
+//
+// Lists of values are stored as blobs: | COUNT [u32] | ROW OF COUNT VALUES |, where the row is
+// encoded with a schema of COUNT columns of the type of the values.
+//
+// All entries are cached in memory when the catalog is opened. Every change is written to the
+// catalog heaps immediately. As pages cannot be deallocated yet, the pages of dropped tables and
+// indexes are not reused.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexType {
+    Hash,
+    BPlusTree,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableInfo {
+    pub table_id: u32,
+    pub name: String,
+    pub schema: Schema,
+    pub first_directory_page_id: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexInfo {
+    pub index_id: u32,
+    pub name: String,
+    pub table_id: u32,
+    pub index_type: IndexType,
+    /// The positions of the key columns in the schema of the table.
+    pub key_columns: Vec<usize>,
+    pub unique: bool,
+    /// The directory page of a hash index or the header page of a B+ tree.
+    pub root_page_id: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CatalogError {
+    TableAlreadyExists(String),
+    TableNotFound(String),
+    IndexAlreadyExists(String),
+    IndexNotFound(String),
+    ColumnNotFound(String),
+    DuplicateColumn(String),
+    Unsupported(&'static str),
+    Storage(&'static str),
+    Disk(DiskError),
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::TableAlreadyExists(name) => write!(f, "table {} already exists", name),
+            CatalogError::TableNotFound(name) => write!(f, "table {} does not exist", name),
+            CatalogError::IndexAlreadyExists(name) => write!(f, "index {} already exists", name),
+            CatalogError::IndexNotFound(name) => write!(f, "index {} does not exist", name),
+            CatalogError::ColumnNotFound(name) => write!(f, "column {} does not exist", name),
+            CatalogError::DuplicateColumn(name) => {
+                write!(f, "column {} is specified more than once", name)
+            }
+            CatalogError::Unsupported(message) | CatalogError::Storage(message) => {
+                write!(f, "{}", message)
+            }
+            CatalogError::Disk(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<DiskError> for CatalogError {
+    fn from(error: DiskError) -> Self {
+        CatalogError::Disk(error)
+    }
+}
+
+pub struct Catalog {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    tables_heap: TableHeap,
+    columns_heap: TableHeap,
+    indexes_heap: TableHeap,
+    table_statistics_heap: TableHeap,
+    column_statistics_heap: TableHeap,
+    tables: HashMap<u32, TableInfo>,
+    indexes: HashMap<u32, IndexInfo>,
+    statistics: HashMap<u32, TableStatistics>,
+    next_table_id: u32,
+    next_index_id: u32,
+}
+
+impl Catalog {
+    /// Opens the catalog of the database file behind the buffer pool. The header page and the
+    /// catalog heaps are created if the file is empty.
+    pub fn open(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Option<TransactionId>,
+    ) -> Result<Catalog, CatalogError> {
+        let page_count = buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .get_page_count()?;
+        if page_count == 0 {
+            Catalog::bootstrap(buffer_pool, transaction)
+        } else {
+            Catalog::load(buffer_pool, transaction)
+        }
+    }
+
+    fn bootstrap(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Option<TransactionId>,
+    ) -> Result<Catalog, CatalogError> {
+        let header_page_id = buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .allocate_new_page()?;
+        if header_page_id != HEADER_PAGE_ID as usize {
+            return Err(CatalogError::Storage(
+                "The header page has to be the first page of the file",
+            ));
+        }
+
+        let setup_heap = || setup_heap(&buffer_pool, transaction);
+        let (tables_heap, columns_heap, indexes_heap) =
+            (setup_heap()?, setup_heap()?, setup_heap()?);
+        let (table_statistics_heap, column_statistics_heap) = (setup_heap()?, setup_heap()?);
+        let header_page = HeaderPage {
+            tables_directory: tables_heap.first_directory_page_id,
+            columns_directory: columns_heap.first_directory_page_id,
+            indexes_directory: indexes_heap.first_directory_page_id,
+            table_statistics_directory: table_statistics_heap.first_directory_page_id,
+            column_statistics_directory: column_statistics_heap.first_directory_page_id,
+        };
+        write_header_page(&buffer_pool, &header_page, transaction)?;
+
+        Ok(Catalog {
+            buffer_pool,
+            tables_heap,
+            columns_heap,
+            indexes_heap,
+            table_statistics_heap,
+            column_statistics_heap,
+            tables: HashMap::new(),
+            indexes: HashMap::new(),
+            statistics: HashMap::new(),
+            next_table_id: 1,
+            next_index_id: 1,
+        })
+    }
+
+    fn load(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Option<TransactionId>,
+    ) -> Result<Catalog, CatalogError> {
+        let mut buffer_pool_lock = buffer_pool.lock().expect("Could not lock buffer pool");
+        let frame_id = buffer_pool_lock.load_page(HEADER_PAGE_ID as usize)?;
+        let header_page =
+            HeaderPage::from_raw_page(buffer_pool_lock.get_raw_page(frame_id).unwrap())
+                .map_err(|_| CatalogError::Storage("The file is not a database file"));
+        buffer_pool_lock
+            .unload_page_id(HEADER_PAGE_ID as usize)
+            .expect("Could not unload the header page");
+        drop(buffer_pool_lock);
+        let mut header_page = header_page?;
+        // The header page itself is never a directory page, so the statistics heaps are missing.
+        if header_page.table_statistics_directory == HEADER_PAGE_ID {
+            header_page.table_statistics_directory =
+                setup_heap(&buffer_pool, transaction)?.first_directory_page_id;
+            header_page.column_statistics_directory =
+                setup_heap(&buffer_pool, transaction)?.first_directory_page_id;
+            write_header_page(&buffer_pool, &header_page, transaction)?;
+        }
+
+        let open_heap = |directory: u32| TableHeap::new(buffer_pool.clone(), directory);
+        let mut catalog = Catalog {
+            tables_heap: open_heap(header_page.tables_directory),
+            columns_heap: open_heap(header_page.columns_directory),
+            indexes_heap: open_heap(header_page.indexes_directory),
+            table_statistics_heap: open_heap(header_page.table_statistics_directory),
+            column_statistics_heap: open_heap(header_page.column_statistics_directory),
+            buffer_pool: buffer_pool.clone(),
+            tables: HashMap::new(),
+            indexes: HashMap::new(),
+            statistics: HashMap::new(),
+            next_table_id: 1,
+            next_index_id: 1,
+        };
+
+        let mut columns: HashMap<u32, Vec<(usize, Column)>> = HashMap::new();
+        for (_, values) in read_rows(&catalog.columns_heap, &columns_schema())? {
+            let data_type = to_data_type(as_u32(&values[3]), as_u32(&values[4]))?;
+            let column = Column::new(
+                as_str(&values[2]),
+                data_type,
+                values[5] == Value::Boolean(true),
+            );
+            columns
+                .entry(as_u32(&values[0]))
+                .or_default()
+                .push((as_u32(&values[1]) as usize, column));
+        }
+
+        for (_, values) in read_rows(&catalog.tables_heap, &tables_schema())? {
+            let table_id = as_u32(&values[0]);
+            let mut table_columns = columns.remove(&table_id).unwrap_or_default();
+            table_columns.sort_by_key(|(position, _)| *position);
+            let table = TableInfo {
+                table_id,
+                name: as_str(&values[1]).to_string(),
+                schema: Schema::new(
+                    table_columns
+                        .into_iter()
+                        .map(|(_, column)| column)
+                        .collect(),
+                ),
+                first_directory_page_id: as_u32(&values[2]),
+            };
+            catalog.next_table_id = catalog.next_table_id.max(table_id + 1);
+            catalog.tables.insert(table_id, table);
+        }
+
+        for (_, values) in read_rows(&catalog.indexes_heap, &indexes_schema())? {
+            let index_id = as_u32(&values[0]);
+            let key_columns = as_str(&values[4])
+                .split(',')
+                .map(|position| position.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .or(Err(CatalogError::Storage("Malformed index key columns")))?;
+            let index = IndexInfo {
+                index_id,
+                name: as_str(&values[1]).to_string(),
+                table_id: as_u32(&values[2]),
+                index_type: to_index_type(as_u32(&values[3]))?,
+                key_columns,
+                unique: values[5] == Value::Boolean(true),
+                root_page_id: as_u32(&values[6]),
+            };
+            catalog.next_index_id = catalog.next_index_id.max(index_id + 1);
+            catalog.indexes.insert(index_id, index);
+        }
+
+        let mut column_statistics: HashMap<u32, Vec<(usize, ColumnStatistics)>> = HashMap::new();
+        for (_, values) in read_rows(&catalog.column_statistics_heap, &column_statistics_schema())?
+        {
+            let table_id = as_u32(&values[0]);
+            let position = as_u32(&values[1]) as usize;
+            let data_type = catalog
+                .tables
+                .get(&table_id)
+                .and_then(|table| table.schema.columns.get(position))
+                .ok_or(CatalogError::Storage("Statistics of an unknown column"))?
+                .data_type;
+            let frequencies =
+                decode_values(&values[5], DataType::Float)?
+                    .into_iter()
+                    .map(|frequency| match frequency {
+                        Value::Float(frequency) => frequency,
+                        _ => unreachable!("Frequencies are decoded as FLOAT values"),
+                    });
+            let statistics = ColumnStatistics {
+                distinct_count: as_f64(&values[2]),
+                null_fraction: as_f64(&values[3]),
+                most_common_values: decode_values(&values[4], data_type)?
+                    .into_iter()
+                    .zip(frequencies)
+                    .collect(),
+                histogram_bounds: decode_values(&values[6], data_type)?,
+            };
+            column_statistics
+                .entry(table_id)
+                .or_default()
+                .push((position, statistics));
+        }
+        for (_, values) in read_rows(&catalog.table_statistics_heap, &table_statistics_schema())? {
+            let table_id = as_u32(&values[0]);
+            let mut columns = column_statistics.remove(&table_id).unwrap_or_default();
+            columns.sort_by_key(|(position, _)| *position);
+            let statistics = TableStatistics {
+                row_count: as_u64(&values[1]),
+                page_count: as_u64(&values[2]),
+                columns: columns.into_iter().map(|(_, column)| column).collect(),
+            };
+            catalog.statistics.insert(table_id, statistics);
+        }
+        Ok(catalog)
+    }
+
+    /// Creates a new, empty table heap and records the table in the catalog.
+    pub fn create_table(
+        &mut self,
+        name: &str,
+        schema: Schema,
+        transaction: Option<TransactionId>,
+    ) -> Result<&TableInfo, CatalogError> {
+        if self.get_table(name).is_some() {
+            return Err(CatalogError::TableAlreadyExists(name.to_string()));
+        }
+        for (i, column) in schema.columns.iter().enumerate() {
+            if schema.column_index(&column.name) != Some(i) {
+                return Err(CatalogError::DuplicateColumn(column.name.clone()));
+            }
+        }
+
+        let table_heap = TableHeap::setup_new_table_heap(self.buffer_pool.clone(), transaction)
+            .map_err(CatalogError::Storage)?;
+        let table = TableInfo {
+            table_id: self.next_table_id,
+            name: name.to_string(),
+            schema,
+            first_directory_page_id: table_heap.first_directory_page_id,
+        };
+
+        for (position, column) in table.schema.columns.iter().enumerate() {
+            let (type_id, type_length) = from_data_type(&column.data_type);
+            insert_row(
+                &self.columns_heap,
+                &columns_schema(),
+                vec![
+                    Value::BigInt(table.table_id as i64),
+                    Value::BigInt(position as i64),
+                    Value::String(column.name.clone()),
+                    Value::BigInt(type_id as i64),
+                    Value::BigInt(type_length as i64),
+                    Value::Boolean(column.nullable),
+                ],
+                transaction,
+            )?;
+        }
+        insert_row(
+            &self.tables_heap,
+            &tables_schema(),
+            vec![
+                Value::BigInt(table.table_id as i64),
+                Value::String(table.name.clone()),
+                Value::BigInt(table.first_directory_page_id as i64),
+            ],
+            transaction,
+        )?;
+
+        self.next_table_id += 1;
+        Ok(self.tables.entry(table.table_id).or_insert(table))
+    }
+
+    /// Returns the table with the given name. Table names are case insensitive.
+    pub fn get_table(&self, name: &str) -> Option<&TableInfo> {
+        self.tables
+            .values()
+            .find(|table| table.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_table_by_id(&self, table_id: u32) -> Option<&TableInfo> {
+        self.tables.get(&table_id)
+    }
+
+    /// Returns all tables ordered by their id.
+    pub fn get_tables(&self) -> Vec<&TableInfo> {
+        let mut tables: Vec<_> = self.tables.values().collect();
+        tables.sort_by_key(|table| table.table_id);
+        tables
+    }
+
+    /// Opens the table heap storing the tuples of the table.
+    pub fn get_table_heap(&self, table: &TableInfo) -> TableHeap {
+        TableHeap::new(self.buffer_pool.clone(), table.first_directory_page_id)
+    }
+
+    /// Removes the table and all of its indexes from the catalog.
+    pub fn drop_table(
+        &mut self,
+        name: &str,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), CatalogError> {
+        let table_id = self
+            .get_table(name)
+            .ok_or_else(|| CatalogError::TableNotFound(name.to_string()))?
+            .table_id;
+        let index_names: Vec<String> = self
+            .get_table_indexes(table_id)
+            .into_iter()
+            .map(|index| index.name.clone())
+            .collect();
+        for index_name in index_names {
+            self.drop_index(&index_name, transaction)?;
+        }
+
+        self.delete_table_statistics(table_id, transaction)?;
+        delete_rows(&self.columns_heap, &columns_schema(), table_id, transaction)?;
+        delete_rows(&self.tables_heap, &tables_schema(), table_id, transaction)?;
+        self.tables.remove(&table_id);
+        Ok(())
+    }
+
+    /// Returns the statistics gathered by the last ANALYZE of the table, if any.
+    pub fn get_table_statistics(&self, table_id: u32) -> Option<&TableStatistics> {
+        self.statistics.get(&table_id)
+    }
+
+    /// Replaces the statistics of the table.
+    pub fn set_table_statistics(
+        &mut self,
+        table_id: u32,
+        statistics: TableStatistics,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), CatalogError> {
+        let table = self
+            .get_table_by_id(table_id)
+            .ok_or_else(|| CatalogError::TableNotFound(table_id.to_string()))?;
+        if statistics.columns.len() != table.schema.columns.len() {
+            return Err(CatalogError::Storage(
+                "The statistics do not match the columns of the table",
+            ));
+        }
+        let column_rows = statistics
+            .columns
+            .iter()
+            .zip(&table.schema.columns)
+            .enumerate()
+            .map(|(position, (column_statistics, column))| {
+                let (values, frequencies): (Vec<_>, Vec<_>) = column_statistics
+                    .most_common_values
+                    .iter()
+                    .map(|(value, frequency)| (value.clone(), Value::Float(*frequency)))
+                    .unzip();
+                Ok(vec![
+                    Value::BigInt(table_id as i64),
+                    Value::BigInt(position as i64),
+                    Value::Float(column_statistics.distinct_count),
+                    Value::Float(column_statistics.null_fraction),
+                    encode_values(values, column.data_type)?,
+                    encode_values(frequencies, DataType::Float)?,
+                    encode_values(column_statistics.histogram_bounds.clone(), column.data_type)?,
+                ])
+            })
+            .collect::<Result<Vec<_>, CatalogError>>()?;
+
+        self.delete_table_statistics(table_id, transaction)?;
+        for values in column_rows {
+            insert_row(
+                &self.column_statistics_heap,
+                &column_statistics_schema(),
+                values,
+                transaction,
+            )?;
+        }
+        insert_row(
+            &self.table_statistics_heap,
+            &table_statistics_schema(),
+            vec![
+                Value::BigInt(table_id as i64),
+                Value::BigInt(statistics.row_count as i64),
+                Value::BigInt(statistics.page_count as i64),
+            ],
+            transaction,
+        )?;
+        self.statistics.insert(table_id, statistics);
+        Ok(())
+    }
+
+    fn delete_table_statistics(
+        &mut self,
+        table_id: u32,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), CatalogError> {
+        delete_rows(
+            &self.column_statistics_heap,
+            &column_statistics_schema(),
+            table_id,
+            transaction,
+        )?;
+        delete_rows(
+            &self.table_statistics_heap,
+            &table_statistics_schema(),
+            table_id,
+            transaction,
+        )?;
+        self.statistics.remove(&table_id);
+        Ok(())
+    }
+
+    /// Creates an empty index on the given columns of a table and records it in the catalog.
+    /// Existing tuples of the table are not added to the index.
+    pub fn create_index(
+        &mut self,
+        name: &str,
+        table_name: &str,
+        column_names: &[&str],
+        index_type: IndexType,
+        unique: bool,
+        transaction: Option<TransactionId>,
+    ) -> Result<&IndexInfo, CatalogError> {
+        if self.get_index(name).is_some() {
+            return Err(CatalogError::IndexAlreadyExists(name.to_string()));
+        }
+        let table = self
+            .get_table(table_name)
+            .ok_or_else(|| CatalogError::TableNotFound(table_name.to_string()))?;
+        let mut key_columns = Vec::with_capacity(column_names.len());
+        for column_name in column_names {
+            let position = table
+                .schema
+                .column_index(column_name)
+                .ok_or_else(|| CatalogError::ColumnNotFound(column_name.to_string()))?;
+            if key_columns.contains(&position) {
+                return Err(CatalogError::DuplicateColumn(column_name.to_string()));
+            }
+            key_columns.push(position);
+        }
+        if key_columns.is_empty() {
+            return Err(CatalogError::Unsupported(
+                "An index needs at least one key column",
+            ));
+        }
+
+        let root_page_id = match index_type {
+            IndexType::Hash => {
+                ExtendibleHashing::<u64, u64>::setup_new_hashmap(
+                    self.buffer_pool.clone(),
+                    transaction,
+                )
+                .map_err(CatalogError::Storage)?
+                .directory_page_id
+            }
+            IndexType::BPlusTree => {
+                BPlusTree::<OrderedKey, u64>::setup_new_tree(self.buffer_pool.clone(), transaction)
+                    .map_err(CatalogError::Storage)?
+                    .header_page_id
+            }
+        };
+        let index = IndexInfo {
+            index_id: self.next_index_id,
+            name: name.to_string(),
+            table_id: table.table_id,
+            index_type,
+            key_columns,
+            unique,
+            root_page_id,
+        };
+
+        let key_columns = index
+            .key_columns
+            .iter()
+            .map(|position| position.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        insert_row(
+            &self.indexes_heap,
+            &indexes_schema(),
+            vec![
+                Value::BigInt(index.index_id as i64),
+                Value::String(index.name.clone()),
+                Value::BigInt(index.table_id as i64),
+                Value::BigInt(from_index_type(index.index_type) as i64),
+                Value::String(key_columns),
+                Value::Boolean(index.unique),
+                Value::BigInt(index.root_page_id as i64),
+            ],
+            transaction,
+        )?;
+
+        self.next_index_id += 1;
+        Ok(self.indexes.entry(index.index_id).or_insert(index))
+    }
+
+    /// Returns the index with the given name. Index names are case insensitive.
+    pub fn get_index(&self, name: &str) -> Option<&IndexInfo> {
+        self.indexes
+            .values()
+            .find(|index| index.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns all indexes of a table ordered by their id.
+    pub fn get_table_indexes(&self, table_id: u32) -> Vec<&IndexInfo> {
+        let mut indexes: Vec<_> = self
+            .indexes
+            .values()
+            .filter(|index| index.table_id == table_id)
+            .collect();
+        indexes.sort_by_key(|index| index.index_id);
+        indexes
+    }
+
+    pub fn drop_index(
+        &mut self,
+        name: &str,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), CatalogError> {
+        let index_id = self
+            .get_index(name)
+            .ok_or_else(|| CatalogError::IndexNotFound(name.to_string()))?
+            .index_id;
+        delete_rows(&self.indexes_heap, &indexes_schema(), index_id, transaction)?;
+        self.indexes.remove(&index_id);
+        Ok(())
+    }
+}
+
+fn tables_schema() -> Schema {
+    Schema::new(vec![
+        Column::new("table_id", DataType::BigInt, false),
+        Column::new("name", DataType::Text, false),
+        Column::new("first_directory_page", DataType::BigInt, false),
+    ])
+}
+
+fn columns_schema() -> Schema {
+    Schema::new(vec![
+        Column::new("table_id", DataType::BigInt, false),
+        Column::new("position", DataType::BigInt, false),
+        Column::new("name", DataType::Text, false),
+        Column::new("type_id", DataType::BigInt, false),
+        Column::new("type_length", DataType::BigInt, false),
+        Column::new("nullable", DataType::Boolean, false),
+    ])
+}
+
+fn indexes_schema() -> Schema {
+    Schema::new(vec![
+        Column::new("index_id", DataType::BigInt, false),
+        Column::new("name", DataType::Text, false),
+        Column::new("table_id", DataType::BigInt, false),
+        Column::new("index_type", DataType::BigInt, false),
+        Column::new("key_columns", DataType::Text, false),
+        Column::new("unique", DataType::Boolean, false),
+        Column::new("root_page", DataType::BigInt, false),
+    ])
+}
+
+fn table_statistics_schema() -> Schema {
+    Schema::new(vec![
+        Column::new("table_id", DataType::BigInt, false),
+        Column::new("row_count", DataType::BigInt, false),
+        Column::new("page_count", DataType::BigInt, false),
+    ])
+}
+
+fn column_statistics_schema() -> Schema {
+    Schema::new(vec![
+        Column::new("table_id", DataType::BigInt, false),
+        Column::new("position", DataType::BigInt, false),
+        Column::new("distinct_count", DataType::Float, false),
+        Column::new("null_fraction", DataType::Float, false),
+        Column::new("most_common_values", DataType::Blob, false),
+        Column::new("most_common_frequencies", DataType::Blob, false),
+        Column::new("histogram_bounds", DataType::Blob, false),
+    ])
+}
+
+fn setup_heap(
+    buffer_pool: &Arc<Mutex<BufferPool>>,
+    transaction: Option<TransactionId>,
+) -> Result<TableHeap, CatalogError> {
+    TableHeap::setup_new_table_heap(buffer_pool.clone(), transaction).map_err(CatalogError::Storage)
+}
+
+fn write_header_page(
+    buffer_pool: &Arc<Mutex<BufferPool>>,
+    header_page: &HeaderPage,
+    transaction: Option<TransactionId>,
+) -> Result<(), CatalogError> {
+    let mut buffer_pool_lock = buffer_pool.lock().expect("Could not lock buffer pool");
+    buffer_pool_lock.load_page(HEADER_PAGE_ID as usize)?;
+    buffer_pool_lock
+        .set_page_data(
+            HEADER_PAGE_ID as usize,
+            header_page.to_raw_page(),
+            transaction,
+        )
+        .expect("Could not update the header page");
+    buffer_pool_lock
+        .unload_page_id(HEADER_PAGE_ID as usize)
+        .expect("Could not unload the header page");
+    Ok(())
+}
+
+fn insert_row(
+    table_heap: &TableHeap,
+    schema: &Schema,
+    values: Vec<Value>,
+    transaction: Option<TransactionId>,
+) -> Result<Rid, CatalogError> {
+    let data = Row::new(values)
+        .to_tuple_data(schema)
+        .map_err(CatalogError::Storage)?;
+    table_heap
+        .insert(data, transaction)
+        .map_err(CatalogError::Storage)
+}
+
+fn read_rows(
+    table_heap: &TableHeap,
+    schema: &Schema,
+) -> Result<Vec<(Rid, Vec<Value>)>, CatalogError> {
+    table_heap
+        .iter()
+        .map(|entry| {
+            let (rid, tuple) = entry.map_err(CatalogError::Storage)?;
+            Row::from_tuple_data(&tuple.data, schema)
+                .map(|row| (rid, row.values))
+                .map_err(CatalogError::Storage)
+        })
+        .collect()
+}
+
+/// Deletes all catalog rows whose first column is the given id.
+fn delete_rows(
+    table_heap: &TableHeap,
+    schema: &Schema,
+    id: u32,
+    transaction: Option<TransactionId>,
+) -> Result<(), CatalogError> {
+    for (rid, values) in read_rows(table_heap, schema)? {
+        if as_u32(&values[0]) == id {
+            table_heap.delete(rid, transaction);
+        }
+    }
+    Ok(())
+}
+
+fn as_u32(value: &Value) -> u32 {
+    match value {
+        Value::BigInt(value) => *value as u32,
+        _ => panic!("Expected a BIGINT value in the catalog"),
+    }
+}
+
+fn as_u64(value: &Value) -> u64 {
+    match value {
+        Value::BigInt(value) => *value as u64,
+        _ => panic!("Expected a BIGINT value in the catalog"),
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Float(value) => *value,
+        _ => panic!("Expected a FLOAT value in the catalog"),
+    }
+}
+
+/// Encodes a list of values of the same type as a blob.
+fn encode_values(values: Vec<Value>, data_type: DataType) -> Result<Value, CatalogError> {
+    let schema = Schema::new(vec![Column::new("", data_type, false); values.len()]);
+    let mut blob = (values.len() as u32).to_le_bytes().to_vec();
+    blob.extend(
+        Row::new(values)
+            .to_tuple_data(&schema)
+            .map_err(CatalogError::Storage)?,
+    );
+    Ok(Value::Blob(blob))
+}
+
+fn decode_values(value: &Value, data_type: DataType) -> Result<Vec<Value>, CatalogError> {
+    let malformed = CatalogError::Storage("Malformed list of values in the catalog");
+    let blob = match value {
+        Value::Blob(blob) if blob.len() >= 4 => blob,
+        _ => return Err(malformed),
+    };
+    let count = u32::from_le_bytes(blob[..4].try_into().unwrap()) as usize;
+    let schema = Schema::new(vec![Column::new("", data_type, false); count]);
+    Row::from_tuple_data(&blob[4..], &schema)
+        .map(|row| row.values)
+        .or(Err(malformed))
+}
+
+fn as_str(value: &Value) -> &str {
+    match value {
+        Value::String(value) => value,
+        _ => panic!("Expected a string value in the catalog"),
+    }
+}
+
+fn from_data_type(data_type: &DataType) -> (u32, u32) {
+    match data_type {
+        DataType::Integer => (0, 0),
+        DataType::BigInt => (1, 0),
+        DataType::Float => (2, 0),
+        DataType::Boolean => (3, 0),
+        DataType::Varchar(length) => (4, *length),
+        DataType::Text => (5, 0),
+        DataType::Blob => (6, 0),
+        DataType::Date => (7, 0),
+        DataType::Timestamp => (8, 0),
+    }
+}
+
+fn to_data_type(type_id: u32, type_length: u32) -> Result<DataType, CatalogError> {
+    match type_id {
+        0 => Ok(DataType::Integer),
+        1 => Ok(DataType::BigInt),
+        2 => Ok(DataType::Float),
+        3 => Ok(DataType::Boolean),
+        4 => Ok(DataType::Varchar(type_length)),
+        5 => Ok(DataType::Text),
+        6 => Ok(DataType::Blob),
+        7 => Ok(DataType::Date),
+        8 => Ok(DataType::Timestamp),
+        _ => Err(CatalogError::Storage("Unknown column type in the catalog")),
+    }
+}
+
+fn from_index_type(index_type: IndexType) -> u32 {
+    match index_type {
+        IndexType::Hash => 0,
+        IndexType::BPlusTree => 1,
+    }
+}
+
+fn to_index_type(index_type_id: u32) -> Result<IndexType, CatalogError> {
+    match index_type_id {
+        0 => Ok(IndexType::Hash),
+        1 => Ok(IndexType::BPlusTree),
+        _ => Err(CatalogError::Storage("Unknown index type in the catalog")),
+    }
+}
+
+#[cfg(test)]
+mod catalog_tests {
+    use chrono::NaiveDate;
+
+    use crate::{
+        catalog::statistics::{ColumnStatistics, TableStatistics},
+        disk_management::test_utils::new_test_buffer_pool,
+        types::{
+            schema::{Column, DataType, Schema},
+            value::Value,
+        },
+    };
+
+    use super::{Catalog, CatalogError, IndexType};
+
+    fn users_schema() -> Schema {
+        Schema::new(vec![
+            Column::new("id", DataType::Integer, false),
+            Column::new("name", DataType::Varchar(32), true),
+            Column::new("created", DataType::Timestamp, true),
+        ])
+    }
+
+    #[test]
+    fn create_and_look_up_objects() {
+        let mut catalog = Catalog::open(new_test_buffer_pool("catalog_create"), None).unwrap();
+        let table_id = catalog
+            .create_table("users", users_schema(), None)
+            .unwrap()
+            .table_id;
+        assert_eq!(
+            catalog.create_table("USERS", users_schema(), None),
+            Err(CatalogError::TableAlreadyExists("USERS".to_string()))
+        );
+
+        let index = catalog
+            .create_index(
+                "users_name",
+                "users",
+                &["name", "id"],
+                IndexType::Hash,
+                true,
+                None,
+            )
+            .unwrap();
+        assert_eq!(index.key_columns, vec![1, 0]);
+        assert_eq!(
+            catalog
+                .create_index(
+                    "users_missing",
+                    "users",
+                    &["age"],
+                    IndexType::Hash,
+                    false,
+                    None
+                )
+                .unwrap_err(),
+            CatalogError::ColumnNotFound("age".to_string())
+        );
+        let index = catalog
+            .create_index(
+                "users_id",
+                "users",
+                &["id"],
+                IndexType::BPlusTree,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(index.index_type, IndexType::BPlusTree);
+
+        let table = catalog.get_table("Users").unwrap();
+        assert_eq!(table.table_id, table_id);
+        assert_eq!(table.schema, users_schema());
+        assert_eq!(catalog.get_table_indexes(table_id).len(), 2);
+        assert_eq!(catalog.get_table_heap(table).iter().count(), 0);
+    }
+
+    #[test]
+    fn catalog_is_persisted() {
+        let buffer_pool = new_test_buffer_pool("catalog_is_persisted");
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        catalog.create_table("users", users_schema(), None).unwrap();
+        catalog
+            .create_table(
+                "orders",
+                Schema::new(vec![Column::new("user_id", DataType::Integer, false)]),
+                None,
+            )
+            .unwrap();
+        catalog
+            .create_index(
+                "orders_user",
+                "orders",
+                &["user_id"],
+                IndexType::Hash,
+                false,
+                None,
+            )
+            .unwrap();
+        let users = catalog.get_table("users").unwrap().clone();
+        let orders_index = catalog.get_index("orders_user").unwrap().clone();
+        buffer_pool
+            .lock()
+            .unwrap()
+            .unload_all_pages_and_write_to_file()
+            .unwrap();
+
+        let mut catalog = Catalog::open(buffer_pool, None).unwrap();
+        assert_eq!(catalog.get_table("users"), Some(&users));
+        assert_eq!(catalog.get_index("orders_user"), Some(&orders_index));
+        let table_id = catalog
+            .create_table("items", users_schema(), None)
+            .unwrap()
+            .table_id;
+        assert_eq!(table_id, 3);
+    }
+
+    #[test]
+    fn statistics_are_persisted() {
+        let buffer_pool = new_test_buffer_pool("catalog_statistics");
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        let table_id = catalog
+            .create_table("users", users_schema(), None)
+            .unwrap()
+            .table_id;
+        assert!(catalog.get_table_statistics(table_id).is_none());
+        let created = Value::Timestamp(
+            NaiveDate::from_ymd_opt(2024, 5, 1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        );
+        let statistics = TableStatistics {
+            row_count: 1000,
+            page_count: 12,
+            columns: vec![
+                ColumnStatistics {
+                    distinct_count: 1000.0,
+                    null_fraction: 0.0,
+                    most_common_values: Vec::new(),
+                    histogram_bounds: vec![Value::Integer(1), Value::Integer(1000)],
+                },
+                ColumnStatistics {
+                    distinct_count: 2.0,
+                    null_fraction: 0.25,
+                    most_common_values: vec![
+                        (Value::String("alice".to_string()), 0.5),
+                        (Value::String("bob".to_string()), 0.25),
+                    ],
+                    histogram_bounds: Vec::new(),
+                },
+                ColumnStatistics {
+                    distinct_count: 1.0,
+                    null_fraction: 0.5,
+                    most_common_values: vec![(created, 0.5)],
+                    histogram_bounds: Vec::new(),
+                },
+            ],
+        };
+        catalog
+            .set_table_statistics(table_id, statistics.clone(), None)
+            .unwrap();
+        let mut updated = statistics.clone();
+        updated.row_count = 2000;
+        catalog
+            .set_table_statistics(table_id, updated.clone(), None)
+            .unwrap();
+        buffer_pool
+            .lock()
+            .unwrap()
+            .unload_all_pages_and_write_to_file()
+            .unwrap();
+
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        assert_eq!(catalog.get_table_statistics(table_id), Some(&updated));
+        catalog.drop_table("users", None).unwrap();
+        let catalog = Catalog::open(buffer_pool, None).unwrap();
+        assert!(catalog.get_table_statistics(table_id).is_none());
+    }
+
+    #[test]
+    fn drop_objects() {
+        let buffer_pool = new_test_buffer_pool("catalog_drop");
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        catalog.create_table("users", users_schema(), None).unwrap();
+        catalog
+            .create_table("orders", users_schema(), None)
+            .unwrap();
+        catalog
+            .create_index("users_id", "users", &["id"], IndexType::Hash, true, None)
+            .unwrap();
+        catalog
+            .create_index("orders_id", "orders", &["id"], IndexType::Hash, true, None)
+            .unwrap();
+
+        catalog.drop_table("users", None).unwrap();
+        assert_eq!(
+            catalog.drop_table("users", None),
+            Err(CatalogError::TableNotFound("users".to_string()))
+        );
+        catalog.drop_index("orders_id", None).unwrap();
+        assert!(catalog.get_index("users_id").is_none());
+
+        let catalog = Catalog::open(buffer_pool, None).unwrap();
+        assert!(catalog.get_table("users").is_none());
+        assert!(catalog.get_table("orders").is_some());
+        assert!(catalog.get_index("users_id").is_none());
+        assert!(catalog.get_index("orders_id").is_none());
+    }
+}
@@ -0,0 +1,1407 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Instant,
+};
+
+use crate::{
+    binder::{
+        bound_statement::BoundStatement,
+        statement_binder::{BindError, Binder},
+    },
+    catalog::{
+        statistics::analyze_table,
+        system_catalog::{Catalog, CatalogError, IndexInfo},
+    },
+    disk_management::{
+        buffer_pool::BufferPool,
+        disk_manager::{DiskError, DiskManager, EncryptionKey},
+    },
+    execution::{
+        executor::{collect_rows, create_executor, ExecutionContext, ExecutionError},
+        instrument::{create_instrumented_executor, OperatorMetrics},
+    },
+    index::table_index::{index_key, Index},
+    planner::{explain::explain_plan, physical_planner::Planner},
+    recovery::{
+        checkpoint::checkpoint, log_manager::LogManager, log_record::TransactionId,
+        recovery_manager::recover,
+    },
+    sql::parser::{parse, ParseError},
+    table::table_page::Rid,
+    transaction::{
+        lock_manager::{LockError, LockMode},
+        transaction_manager::TransactionManager,
+        transaction_state::{IsolationLevel, Transaction},
+        vacuum::vacuum_table,
+    },
+    types::{
+        row::Row,
+        schema::{Column, DataType, Schema},
+        value::Value,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub enum DatabaseError {
+    Parse(ParseError),
+    Bind(BindError),
+    Catalog(CatalogError),
+    Execution(ExecutionError),
+    Disk(DiskError),
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseError::Parse(error) => write!(f, "{}", error),
+            DatabaseError::Bind(error) => write!(f, "{}", error),
+            DatabaseError::Catalog(error) => write!(f, "{}", error),
+            DatabaseError::Execution(error) => write!(f, "{}", error),
+            DatabaseError::Disk(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<ParseError> for DatabaseError {
+    fn from(error: ParseError) -> Self {
+        DatabaseError::Parse(error)
+    }
+}
+
+impl From<BindError> for DatabaseError {
+    fn from(error: BindError) -> Self {
+        DatabaseError::Bind(error)
+    }
+}
+
+impl From<CatalogError> for DatabaseError {
+    fn from(error: CatalogError) -> Self {
+        DatabaseError::Catalog(error)
+    }
+}
+
+impl From<ExecutionError> for DatabaseError {
+    fn from(error: ExecutionError) -> Self {
+        DatabaseError::Execution(error)
+    }
+}
+
+impl From<DiskError> for DatabaseError {
+    fn from(error: DiskError) -> Self {
+        DatabaseError::Disk(error)
+    }
+}
+
+/// The result of a single statement.
+#[derive(Debug, PartialEq)]
+pub enum QueryResult {
+    /// The rows produced by a query.
+    Rows { schema: Schema, rows: Vec<Row> },
+    /// The number of rows inserted, updated or deleted by a statement.
+    Count(u64),
+    /// A DDL statement completed.
+    Ok,
+}
+
+/// A session of a database stored in a single file. SQL statements are parsed, bound against
+/// the catalog, planned and run by the executors.
+///
+/// Further sessions of the same database are opened with [Database::connect]. Each session runs
+/// its own transactions and can be moved to another thread; the row locks of the transactions
+/// keep concurrent sessions from overwriting each other's changes.
+pub struct Database {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    catalog: Arc<RwLock<Catalog>>,
+    transaction_manager: Arc<Mutex<TransactionManager>>,
+    /// The transaction started by BEGIN in this session, if any. Outside of it, every statement
+    /// runs in its own transaction.
+    transaction: Option<Arc<Transaction>>,
+}
+
+impl Database {
+    /// Opens the database file at the path, creating it if it does not exist. Changes of pages
+    /// are recorded in the write-ahead log next to it, at the path followed by `.wal`. If the
+    /// database was not closed cleanly, it is recovered from the log first.
+    pub fn open(db_file_path: &str) -> Result<Database, DatabaseError> {
+        let disk_manager = DiskManager::new(db_file_path.to_string())?;
+        let log_manager =
+            LogManager::open(&format!("{}.wal", db_file_path)).map_err(ExecutionError::Storage)?;
+        Database::open_with(disk_manager, log_manager)
+    }
+
+    /// Opens the database file like [Database::open], but encrypts it and its log with the given
+    /// key. A new file is created encrypted; an existing file must have been encrypted with the
+    /// same key.
+    pub fn open_encrypted(
+        db_file_path: &str,
+        key: &EncryptionKey,
+    ) -> Result<Database, DatabaseError> {
+        // The key is checked with the database file before the log is read with it.
+        let disk_manager = DiskManager::new_encrypted(db_file_path.to_string(), key)?;
+        let log_manager = LogManager::open_encrypted(&format!("{}.wal", db_file_path), key)
+            .map_err(ExecutionError::Storage)?;
+        Database::open_with(disk_manager, log_manager)
+    }
+
+    fn open_with(
+        disk_manager: DiskManager,
+        log_manager: LogManager,
+    ) -> Result<Database, DatabaseError> {
+        let buffer_pool = Arc::new(Mutex::new(BufferPool::with_log_manager(
+            Arc::new(Mutex::new(disk_manager)),
+            Arc::new(Mutex::new(log_manager)),
+        )));
+        recover(&buffer_pool).map_err(ExecutionError::Storage)?;
+        Database::new(buffer_pool)
+    }
+
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>) -> Result<Database, DatabaseError> {
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+        // Opening the catalog creates its pages in a new database file.
+        let transaction = transaction_manager.begin(IsolationLevel::default());
+        let catalog = Catalog::open(buffer_pool.clone(), Some(transaction.id()));
+        transaction_manager.commit(&transaction)?;
+        Ok(Database {
+            buffer_pool,
+            catalog: Arc::new(RwLock::new(catalog?)),
+            transaction_manager: Arc::new(Mutex::new(transaction_manager)),
+            transaction: None,
+        })
+    }
+
+    /// Opens another session of the database. It sees the changes committed in all sessions.
+    pub fn connect(&self) -> Database {
+        Database {
+            buffer_pool: self.buffer_pool.clone(),
+            catalog: self.catalog.clone(),
+            transaction_manager: self.transaction_manager.clone(),
+            transaction: None,
+        }
+    }
+
+    pub fn catalog(&self) -> RwLockReadGuard<'_, Catalog> {
+        self.catalog.read().expect("Could not lock catalog")
+    }
+
+    fn catalog_mut(&self) -> RwLockWriteGuard<'_, Catalog> {
+        self.catalog.write().expect("Could not lock catalog")
+    }
+
+    fn transaction_manager(&self) -> MutexGuard<'_, TransactionManager> {
+        self.transaction_manager
+            .lock()
+            .expect("Could not lock transaction manager")
+    }
+
+    /// Runs all statements of the SQL string in order and returns their results. Execution stops
+    /// at the first failing statement, whose changes of rows are undone. Outside of a transaction
+    /// started by BEGIN, the changes of the statements before it are committed; inside of it,
+    /// they are kept until COMMIT or ROLLBACK.
+    pub fn execute(&mut self, sql: &str) -> Result<Vec<QueryResult>, DatabaseError> {
+        let statements = parse(sql)?;
+        let mut results = Vec::with_capacity(statements.len());
+        for statement in &statements {
+            // Every statement is bound just before it runs, as it may depend on the DDL
+            // statements before it.
+            let bound_statement = Binder::new(&self.catalog()).bind(statement)?;
+            let result = match bound_statement {
+                BoundStatement::Begin { isolation_level } => self
+                    .begin_with_isolation_level(isolation_level.unwrap_or_default())
+                    .map(|_| QueryResult::Ok),
+                BoundStatement::Commit => self.commit().map(|_| QueryResult::Ok),
+                BoundStatement::Rollback => self.rollback().map(|_| QueryResult::Ok),
+                statement => self.execute_in_transaction(statement),
+            }?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Starts a transaction with snapshot isolation that all following statements run in, until
+    /// it is committed or rolled back.
+    pub fn begin(&mut self) -> Result<(), DatabaseError> {
+        self.begin_with_isolation_level(IsolationLevel::default())
+    }
+
+    pub fn begin_with_isolation_level(
+        &mut self,
+        isolation_level: IsolationLevel,
+    ) -> Result<(), DatabaseError> {
+        if self.transaction.is_some() {
+            return Err(
+                ExecutionError::InvalidTransactionState("A transaction is already active").into(),
+            );
+        }
+        let transaction = self.transaction_manager().begin(isolation_level);
+        self.transaction = Some(transaction);
+        Ok(())
+    }
+
+    /// Commits the active transaction. If it cannot be serialized, it is rolled back instead.
+    pub fn commit(&mut self) -> Result<(), DatabaseError> {
+        let transaction =
+            self.transaction
+                .take()
+                .ok_or(ExecutionError::InvalidTransactionState(
+                    "There is no active transaction to commit",
+                ))?;
+        self.commit_transaction(&transaction)
+    }
+
+    fn commit_transaction(&self, transaction: &Transaction) -> Result<(), DatabaseError> {
+        let result = self.transaction_manager().commit(transaction);
+        if let Err(ExecutionError::SerializationFailure) = result {
+            self.rollback_transaction(transaction)?;
+        }
+        Ok(result?)
+    }
+
+    /// Undoes all changes of rows made since the transaction began.
+    pub fn rollback(&mut self) -> Result<(), DatabaseError> {
+        let transaction =
+            self.transaction
+                .take()
+                .ok_or(ExecutionError::InvalidTransactionState(
+                    "There is no active transaction to roll back",
+                ))?;
+        self.rollback_transaction(&transaction)?;
+        Ok(())
+    }
+
+    fn rollback_transaction(&self, transaction: &Transaction) -> Result<(), ExecutionError> {
+        // The catalog is always locked before the transaction manager.
+        let catalog = self.catalog();
+        self.transaction_manager().rollback(transaction, &catalog)
+    }
+
+    /// Runs a statement in the active transaction, or in a transaction of its own that commits
+    /// if the statement succeeds. A failing statement leaves the rows as they were before it.
+    fn execute_in_transaction(
+        &mut self,
+        statement: BoundStatement,
+    ) -> Result<QueryResult, DatabaseError> {
+        if let Some(transaction) = self.transaction.clone() {
+            if is_ddl(&statement) {
+                return Err(ExecutionError::InvalidTransactionState(
+                    "DDL statements cannot run in a transaction",
+                )
+                .into());
+            }
+            let savepoint = transaction.savepoint();
+            let result = self.execute_bound(statement, &transaction);
+            match result {
+                // The victim of a deadlock must give up all of its locks, and a transaction that
+                // cannot be serialized can only be rolled back.
+                Err(DatabaseError::Execution(
+                    ExecutionError::Lock(LockError::Deadlock)
+                    | ExecutionError::SerializationFailure,
+                )) => {
+                    self.transaction = None;
+                    self.rollback_transaction(&transaction)?;
+                }
+                Err(_) => {
+                    let catalog = self.catalog();
+                    self.transaction_manager().rollback_to_savepoint(
+                        &transaction,
+                        savepoint,
+                        &catalog,
+                    )?
+                }
+                Ok(_) => {}
+            }
+            return result;
+        }
+        let transaction = self.transaction_manager().begin(IsolationLevel::default());
+        let result = self.execute_bound(statement, &transaction);
+        match result {
+            Ok(_) => self.commit_transaction(&transaction)?,
+            // The changes of the catalog made by a failing DDL statement are kept.
+            Err(_) => self.rollback_transaction(&transaction)?,
+        }
+        result
+    }
+
+    /// Writes all modified pages to the database file. The checkpoint taken afterwards leaves
+    /// nothing to recover from the log.
+    pub fn flush(&self) -> Result<(), DatabaseError> {
+        let mut buffer_pool = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        buffer_pool.unload_all_pages_and_write_to_file()?;
+        checkpoint(&buffer_pool).map_err(ExecutionError::Storage)?;
+        Ok(())
+    }
+
+    /// Runs a statement in the transaction. The catalog is only locked for writing while DDL
+    /// statements change it, and never while waiting for a lock of the transaction, so that
+    /// waiting sessions do not block the others.
+    fn execute_bound(
+        &self,
+        statement: BoundStatement,
+        transaction: &Arc<Transaction>,
+    ) -> Result<QueryResult, DatabaseError> {
+        let context = ExecutionContext::with_transaction(
+            self.buffer_pool.clone(),
+            transaction.clone(),
+            self.transaction_manager().lock_manager(),
+        );
+        let transaction_id = Some(transaction.id());
+        match statement {
+            BoundStatement::CreateTable { name, schema } => {
+                self.catalog_mut()
+                    .create_table(&name, schema, transaction_id)?;
+                Ok(QueryResult::Ok)
+            }
+            BoundStatement::DropTable { name, if_exists } => {
+                let table_id = self.catalog().get_table(&name).map(|table| table.table_id);
+                if let Some(table_id) = table_id {
+                    context.lock_table(table_id, LockMode::Exclusive)?;
+                }
+                let mut catalog = self.catalog_mut();
+                if !if_exists || catalog.get_table(&name).is_some() {
+                    catalog.drop_table(&name, transaction_id)?;
+                }
+                Ok(QueryResult::Ok)
+            }
+            BoundStatement::CreateIndex {
+                name,
+                table_name,
+                column_names,
+                unique,
+                index_type,
+            } => {
+                let table_id = self
+                    .catalog()
+                    .get_table(&table_name)
+                    .map(|table| table.table_id);
+                if let Some(table_id) = table_id {
+                    context.lock_table(table_id, LockMode::Shared)?;
+                }
+                let column_names: Vec<&str> = column_names.iter().map(String::as_str).collect();
+                let mut catalog = self.catalog_mut();
+                let index_info = catalog
+                    .create_index(
+                        &name,
+                        &table_name,
+                        &column_names,
+                        index_type,
+                        unique,
+                        transaction_id,
+                    )?
+                    .clone();
+                if let Err(error) = self.fill_index(&catalog, &index_info, transaction_id) {
+                    catalog.drop_index(&name, transaction_id)?;
+                    return Err(error.into());
+                }
+                Ok(QueryResult::Ok)
+            }
+            BoundStatement::Analyze { table_ids } => {
+                for table_id in table_ids {
+                    context.lock_table(table_id, LockMode::Shared)?;
+                    let visibility = context.read_table(table_id)?;
+                    let statistics = {
+                        let catalog = self.catalog();
+                        let table = catalog
+                            .get_table_by_id(table_id)
+                            .ok_or_else(|| CatalogError::TableNotFound(table_id.to_string()))?;
+                        analyze_table(&catalog.get_table_heap(table), &table.schema, &visibility)
+                            .map_err(ExecutionError::Storage)?
+                    };
+                    self.catalog_mut().set_table_statistics(
+                        table_id,
+                        statistics,
+                        transaction_id,
+                    )?;
+                }
+                Ok(QueryResult::Ok)
+            }
+            BoundStatement::Vacuum { table_ids } => {
+                let horizon = self.transaction_manager().vacuum_horizon();
+                for table_id in table_ids {
+                    // No transaction may change the table while its index entries are removed.
+                    context.lock_table(table_id, LockMode::Shared)?;
+                    let catalog = self.catalog();
+                    let table = catalog
+                        .get_table_by_id(table_id)
+                        .ok_or_else(|| CatalogError::TableNotFound(table_id.to_string()))?;
+                    let indexes: Vec<IndexInfo> = catalog
+                        .get_table_indexes(table_id)
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    vacuum_table(&self.buffer_pool, table, &indexes, horizon, transaction_id)?;
+                }
+                Ok(QueryResult::Ok)
+            }
+            BoundStatement::Explain { statement, analyze } => {
+                let plan = Planner::new(&self.catalog()).plan(&statement)?;
+                let lines = if analyze {
+                    let (mut executor, metrics) = create_instrumented_executor(&plan, &context)?;
+                    let start = Instant::now();
+                    collect_rows(executor.as_mut())?;
+                    let elapsed = start.elapsed();
+                    let metrics: Vec<OperatorMetrics> = metrics
+                        .iter()
+                        .map(|metrics| metrics.lock().expect("Could not lock metrics").clone())
+                        .collect();
+                    let mut lines = explain_plan(&self.catalog(), &plan, Some(&metrics));
+                    lines.push(format!(
+                        "Execution time: {:.3} ms",
+                        elapsed.as_secs_f64() * 1000.0
+                    ));
+                    lines
+                } else {
+                    explain_plan(&self.catalog(), &plan, None)
+                };
+                Ok(QueryResult::Rows {
+                    schema: Schema::new(vec![Column::new("QUERY PLAN", DataType::Text, false)]),
+                    rows: lines
+                        .into_iter()
+                        .map(|line| Row::new(vec![Value::String(line)]))
+                        .collect(),
+                })
+            }
+            BoundStatement::Select(select) => {
+                let schema = select.output_schema.clone();
+                let plan = Planner::new(&self.catalog()).plan(&BoundStatement::Select(select))?;
+                let mut executor = create_executor(&plan, &context)?;
+                let rows = collect_rows(executor.as_mut())?;
+                Ok(QueryResult::Rows { schema, rows })
+            }
+            statement @ (BoundStatement::Insert(_)
+            | BoundStatement::Update(_)
+            | BoundStatement::Delete(_)) => {
+                let plan = Planner::new(&self.catalog()).plan(&statement)?;
+                let mut executor = create_executor(&plan, &context)?;
+                let rows = collect_rows(executor.as_mut())?;
+                match rows.first().map(|row| &row.values[..]) {
+                    Some([Value::BigInt(count)]) => Ok(QueryResult::Count(*count as u64)),
+                    _ => Err(ExecutionError::Storage("The statement returned no row count").into()),
+                }
+            }
+            // Transaction statements are run by execute.
+            BoundStatement::Begin { .. } | BoundStatement::Commit | BoundStatement::Rollback => {
+                unreachable!()
+            }
+        }
+    }
+
+    /// Adds the versions of the rows of the table to a new index. Only the current versions must
+    /// have unique keys.
+    fn fill_index(
+        &self,
+        catalog: &Catalog,
+        index_info: &IndexInfo,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), ExecutionError> {
+        let table = catalog
+            .get_table_by_id(index_info.table_id)
+            .ok_or_else(|| CatalogError::TableNotFound(index_info.table_id.to_string()))?;
+        let index =
+            Index::open(self.buffer_pool.clone(), index_info).map_err(ExecutionError::Storage)?;
+        let mut versions = Vec::new();
+        for entry in catalog.get_table_heap(table).iter() {
+            let (rid, tuple) = entry.map_err(ExecutionError::Storage)?;
+            let row = Row::from_tuple_data(&tuple.data, &table.schema)
+                .map_err(ExecutionError::Storage)?;
+            versions.push((rid, tuple.version, index_key(index_info, &row.values)));
+        }
+        // Only the newest version of a row with a key gets an entry, the versions it replaced
+        // are reached through it.
+        let replaced: HashSet<(Rid, &Vec<Value>)> = versions
+            .iter()
+            .filter_map(|(_, version, key)| version.previous.map(|previous| (previous, key)))
+            .collect();
+        let mut keys = HashSet::new();
+        for (rid, version, key) in &versions {
+            if index_info.unique
+                && version.deleted_by.is_none()
+                && !key.iter().any(Value::is_null)
+                && !keys.insert(key)
+            {
+                return Err(ExecutionError::UniqueViolation(index_info.name.clone()));
+            }
+            if !replaced.contains(&(*rid, key)) {
+                index
+                    .insert_entry(key, *rid, transaction)
+                    .map_err(ExecutionError::Storage)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the statement changes the catalog or removes versions of rows. Such changes
+/// cannot be rolled back.
+fn is_ddl(statement: &BoundStatement) -> bool {
+    matches!(
+        statement,
+        BoundStatement::CreateTable { .. }
+            | BoundStatement::DropTable { .. }
+            | BoundStatement::CreateIndex { .. }
+            | BoundStatement::Analyze { .. }
+            | BoundStatement::Vacuum { .. }
+    )
+}
+
+#[cfg(test)]
+mod database_tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Barrier,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        disk_management::{
+            disk_manager::{DiskError, EncryptionKey},
+            test_utils::{new_test_buffer_pool, temp_db_path},
+        },
+        execution::executor::ExecutionError,
+        index::table_index::Index,
+        table::table_page::Rid,
+        transaction::lock_manager::{LockError, LockMode, LockTarget},
+        types::{row::Row, value::Value},
+    };
+
+    use super::{Database, DatabaseError, QueryResult};
+
+    fn query(database: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+        match database.execute(sql).unwrap().pop() {
+            Some(QueryResult::Rows { rows, .. }) => {
+                rows.into_iter().map(|row: Row| row.values).collect()
+            }
+            result => panic!("expected rows, got {:?}", result),
+        }
+    }
+
+    fn users_database(name: &str) -> Database {
+        let mut database = Database::new(new_test_buffer_pool(name)).unwrap();
+        let results = database
+            .execute(
+                "CREATE TABLE users (id INTEGER NOT NULL, name VARCHAR(20), age INTEGER);
+                 INSERT INTO users VALUES (1, 'ada', 36), (2, 'alan', 41), (3, 'grace', NULL);
+                 INSERT INTO users (id, name) VALUES (4, 'edsger');",
+            )
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                QueryResult::Ok,
+                QueryResult::Count(3),
+                QueryResult::Count(1)
+            ]
+        );
+        database
+    }
+
+    #[test]
+    fn select_rows() {
+        let mut database = users_database("select_rows");
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT name, age + 1 FROM users WHERE age > 30 OR id = 4 ORDER BY age DESC"
+            ),
+            vec![
+                vec![Value::String("edsger".to_string()), Value::Null],
+                vec![Value::String("alan".to_string()), Value::Integer(42)],
+                vec![Value::String("ada".to_string()), Value::Integer(37)],
+            ]
+        );
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT id FROM users ORDER BY id LIMIT 2 OFFSET 1"
+            ),
+            vec![vec![Value::Integer(2)], vec![Value::Integer(3)]]
+        );
+        assert_eq!(
+            query(&mut database, "SELECT 1 + 2 AS three"),
+            vec![vec![Value::Integer(3)]]
+        );
+        assert!(matches!(
+            database.execute("SELECT id / 0 FROM users"),
+            Err(DatabaseError::Execution(ExecutionError::InvalidValue(_)))
+        ));
+    }
+
+    #[test]
+    fn aggregate_rows() {
+        let mut database = users_database("aggregate_rows");
+        database
+            .execute("INSERT INTO users VALUES (5, 'ada', 20), (6, 'alan', 41)")
+            .unwrap();
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT name, count(*), count(age), count(DISTINCT age), sum(age), min(age)
+                 FROM users GROUP BY name HAVING count(*) > 1 ORDER BY name"
+            ),
+            vec![
+                vec![
+                    Value::String("ada".to_string()),
+                    Value::BigInt(2),
+                    Value::BigInt(2),
+                    Value::BigInt(2),
+                    Value::BigInt(56),
+                    Value::Integer(20),
+                ],
+                vec![
+                    Value::String("alan".to_string()),
+                    Value::BigInt(2),
+                    Value::BigInt(2),
+                    Value::BigInt(1),
+                    Value::BigInt(82),
+                    Value::Integer(41),
+                ],
+            ]
+        );
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT count(*), avg(age), max(name) FROM users WHERE id < 5"
+            ),
+            vec![vec![
+                Value::BigInt(4),
+                Value::Float(38.5),
+                Value::String("grace".to_string()),
+            ]]
+        );
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT count(*), sum(age) FROM users WHERE id > 100"
+            ),
+            vec![vec![Value::BigInt(0), Value::Null]]
+        );
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT age IS NULL, count(*) AS users FROM users GROUP BY age IS NULL
+                 ORDER BY users DESC"
+            ),
+            vec![
+                vec![Value::Boolean(false), Value::BigInt(4)],
+                vec![Value::Boolean(true), Value::BigInt(2)],
+            ]
+        );
+    }
+
+    #[test]
+    fn modify_rows_through_indexes() {
+        let mut database = users_database("modify_rows_through_indexes");
+        database
+            .execute("CREATE UNIQUE INDEX users_id ON users (id)")
+            .unwrap();
+        assert_eq!(
+            database.execute("INSERT INTO users VALUES (2, 'barbara', 50)"),
+            Err(DatabaseError::Execution(ExecutionError::UniqueViolation(
+                "users_id".to_string()
+            )))
+        );
+        assert_eq!(
+            database.execute("UPDATE users SET id = age WHERE id = 3"),
+            Err(DatabaseError::Execution(ExecutionError::NotNullViolation(
+                "id".to_string()
+            )))
+        );
+
+        assert_eq!(
+            database
+                .execute("UPDATE users SET id = id + 10, age = 1 WHERE id = 2")
+                .unwrap(),
+            vec![QueryResult::Count(1)]
+        );
+        assert_eq!(
+            query(&mut database, "SELECT name, age FROM users WHERE id = 12"),
+            vec![vec![Value::String("alan".to_string()), Value::Integer(1)]]
+        );
+        assert!(query(&mut database, "SELECT name FROM users WHERE id = 2").is_empty());
+        assert!(database
+            .execute("UPDATE users SET id = 1 WHERE id = 3")
+            .is_err());
+
+        assert_eq!(
+            database.execute("DELETE FROM users WHERE id = 1").unwrap(),
+            vec![QueryResult::Count(1)]
+        );
+        database
+            .execute("INSERT INTO users VALUES (1, 'barbara', 50)")
+            .unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT name FROM users WHERE id = 1"),
+            vec![vec![Value::String("barbara".to_string())]]
+        );
+        assert_eq!(
+            database.execute("DELETE FROM users").unwrap(),
+            vec![QueryResult::Count(4)]
+        );
+        assert!(query(&mut database, "SELECT * FROM users").is_empty());
+    }
+
+    #[test]
+    fn unique_index_on_duplicates_is_rejected() {
+        let mut database = users_database("unique_index_on_duplicates");
+        database
+            .execute("INSERT INTO users VALUES (1, 'ada', 36)")
+            .unwrap();
+        assert!(database
+            .execute("CREATE UNIQUE INDEX users_id ON users (id)")
+            .is_err());
+        assert!(database.catalog().get_index("users_id").is_none());
+    }
+
+    #[test]
+    fn indexes_hold_many_rows_with_the_same_key() {
+        let mut database = users_database("indexes_hold_many_rows_with_the_same_key");
+        database
+            .execute(
+                "CREATE INDEX users_name ON users USING HASH (name);
+                 CREATE INDEX users_age ON users USING BTREE (age);",
+            )
+            .unwrap();
+        let values: Vec<_> = (10..1_010).map(|id| format!("({}, 'x', 20)", id)).collect();
+        database
+            .execute(&format!("INSERT INTO users VALUES {}", values.join(", ")))
+            .unwrap();
+
+        for filter in ["name = 'x'", "age = 20"] {
+            assert_eq!(
+                query(
+                    &mut database,
+                    &format!("SELECT count(*) FROM users WHERE {}", filter)
+                ),
+                vec![vec![Value::BigInt(1_000)]]
+            );
+        }
+        assert_eq!(
+            database
+                .execute("DELETE FROM users WHERE age = 20 AND id < 510")
+                .unwrap(),
+            vec![QueryResult::Count(500)]
+        );
+        assert_eq!(
+            query(&mut database, "SELECT count(*) FROM users WHERE name = 'x'"),
+            vec![vec![Value::BigInt(500)]]
+        );
+    }
+
+    #[test]
+    fn b_plus_tree_indexes_serve_range_queries() {
+        let mut database = Database::new(new_test_buffer_pool("b_plus_tree_range")).unwrap();
+        // The names share a prefix longer than an index key.
+        let values: Vec<String> = (0..2_000)
+            .map(|i| match i % 100 {
+                0 => format!("({}, NULL)", i),
+                _ => format!("({}, '{}{:04}')", i, "n".repeat(40), i),
+            })
+            .collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE items (id INTEGER NOT NULL, name TEXT);
+                 CREATE INDEX items_id ON items USING BTREE (id);
+                 CREATE INDEX items_name ON items USING BTREE (name);
+                 INSERT INTO items VALUES {};
+                 ANALYZE items;",
+                values.join(", ")
+            ))
+            .unwrap();
+        let ids = |database: &mut Database, filter: &str| {
+            let sql = format!("SELECT id FROM items WHERE {} ORDER BY id", filter);
+            assert!(
+                plan_lines(database, &format!("EXPLAIN {}", sql))
+                    .iter()
+                    .any(|line| line.contains("IndexRangeScan")),
+                "{} does not use an index range scan",
+                sql
+            );
+            query(database, &sql)
+                .into_iter()
+                .map(|row| match row[..] {
+                    [Value::Integer(id)] => id,
+                    _ => panic!("expected an id, got {:?}", row),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(&mut database, "id < 3"), vec![0, 1, 2]);
+        assert_eq!(ids(&mut database, "id <= 2"), vec![0, 1, 2]);
+        assert_eq!(ids(&mut database, "1997 < id"), vec![1998, 1999]);
+        assert_eq!(ids(&mut database, "id >= 1998"), vec![1998, 1999]);
+        assert_eq!(ids(&mut database, "id > 5 AND id <= 8"), vec![6, 7, 8]);
+        assert!(ids(&mut database, "id > 8 AND id < 8").is_empty());
+        let name = |id: i32| format!("'{}{:04}'", "n".repeat(40), id);
+        assert_eq!(
+            ids(
+                &mut database,
+                &format!("name >= {} AND name < {}", name(1_101), name(1_104))
+            ),
+            vec![1_101, 1_102, 1_103]
+        );
+        assert_eq!(ids(&mut database, &format!("name <= {}", name(1))), vec![1]);
+
+        // Rows moved out of and into the range are found under their new keys.
+        database
+            .execute("UPDATE items SET id = id + 10000 WHERE id < 2; UPDATE items SET id = 1 WHERE id = 1500")
+            .unwrap();
+        assert_eq!(ids(&mut database, "id < 3"), vec![1, 2]);
+        assert_eq!(ids(&mut database, "id >= 10000"), vec![10_000, 10_001]);
+    }
+
+    fn plan_lines(database: &mut Database, sql: &str) -> Vec<String> {
+        query(database, sql)
+            .into_iter()
+            .map(|row| match &row[..] {
+                [Value::String(line)] => line.clone(),
+                row => panic!("expected a line of the plan, got {:?}", row),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn explain_plans() {
+        let mut database = users_database("explain_plans");
+
+        let lines = plan_lines(
+            &mut database,
+            "EXPLAIN SELECT name FROM users WHERE age > 30 ORDER BY name",
+        );
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("Projection #0  (rows="));
+        assert!(lines[1].starts_with("  -> Sort #0 ASC  (rows="));
+        assert!(lines[3].starts_with("            -> Filter (#2 > 30)  (rows="));
+        assert!(lines[4].starts_with("                 -> SeqScan on users  (rows="));
+        assert!(lines.iter().all(|line| !line.contains("actual")));
+
+        let lines = plan_lines(
+            &mut database,
+            "EXPLAIN ANALYZE SELECT u.name, v.name FROM users u JOIN users v ON u.id = v.age",
+        );
+        assert!(lines[0].contains("(actual rows=0 loops=1 time="));
+        // Only the scans request pages, the nodes above them do not count them again.
+        assert!(lines[0].ends_with("(own buffers hits=0 misses=0)"));
+        assert!(lines[1].ends_with("(own buffers hits=0 misses=0)"));
+        assert!(lines.iter().any(|line| line.contains("SeqScan on users")
+            && line.contains("actual rows=4 loops=1")
+            && line.contains("own buffers hits=")
+            && !line.contains("own buffers hits=0 misses=0")));
+        assert!(lines.last().unwrap().starts_with("Execution time: "));
+
+        // EXPLAIN ANALYZE executes data modifying statements.
+        let lines = plan_lines(
+            &mut database,
+            "EXPLAIN ANALYZE DELETE FROM users WHERE id = 4",
+        );
+        assert!(lines[0].starts_with("Delete from users  (rows=1 "));
+        assert!(lines[1].starts_with("  -> Filter (#0 = 4)"));
+        assert!(lines[1].contains("actual rows=1 loops=1"));
+        assert!(
+            lines[2].contains("SeqScan on users") && lines[2].contains("actual rows=4 loops=1")
+        );
+        assert!(query(&mut database, "SELECT * FROM users WHERE id = 4").is_empty());
+        assert!(database
+            .execute("EXPLAIN CREATE TABLE t (a INTEGER)")
+            .is_err());
+    }
+
+    #[test]
+    fn rollback_transaction() {
+        let mut database = users_database("rollback_transaction");
+        database
+            .execute("CREATE UNIQUE INDEX users_id ON users (id)")
+            .unwrap();
+        let results = database
+            .execute(
+                "BEGIN;
+                 INSERT INTO users VALUES (5, 'barbara', 50);
+                 UPDATE users SET id = id + 10, name = 'x' WHERE id < 3;
+                 DELETE FROM users WHERE id = 3;",
+            )
+            .unwrap();
+        assert_eq!(results[0], QueryResult::Ok);
+        assert_eq!(
+            query(&mut database, "SELECT id FROM users ORDER BY id"),
+            vec![
+                vec![Value::Integer(4)],
+                vec![Value::Integer(5)],
+                vec![Value::Integer(11)],
+                vec![Value::Integer(12)]
+            ]
+        );
+        assert!(matches!(
+            database.execute("CREATE TABLE t (a INTEGER)"),
+            Err(DatabaseError::Execution(
+                ExecutionError::InvalidTransactionState(_)
+            ))
+        ));
+        assert!(database.execute("BEGIN").is_err());
+        // The locks are kept until the transaction ends.
+        let transaction = database.transaction.as_ref().unwrap().id();
+        let users = LockTarget::Table(database.catalog().get_table("users").unwrap().table_id);
+        let lock_manager = database.transaction_manager().lock_manager();
+        assert_eq!(
+            lock_manager.held_mode(transaction, users),
+            Some(LockMode::IntentionExclusive)
+        );
+
+        database.execute("ROLLBACK").unwrap();
+        assert_eq!(lock_manager.held_mode(transaction, users), None);
+        assert_eq!(
+            query(&mut database, "SELECT id, name FROM users ORDER BY id"),
+            vec![
+                vec![Value::Integer(1), Value::String("ada".to_string())],
+                vec![Value::Integer(2), Value::String("alan".to_string())],
+                vec![Value::Integer(3), Value::String("grace".to_string())],
+                vec![Value::Integer(4), Value::String("edsger".to_string())],
+            ]
+        );
+        // The index points at the rows again.
+        assert_eq!(
+            query(&mut database, "SELECT name FROM users WHERE id = 3"),
+            vec![vec![Value::String("grace".to_string())]]
+        );
+        assert!(query(&mut database, "SELECT name FROM users WHERE id = 11").is_empty());
+        assert!(database.execute("ROLLBACK").is_err());
+        assert!(database.execute("COMMIT").is_err());
+
+        database
+            .execute("BEGIN; DELETE FROM users WHERE id = 4")
+            .unwrap();
+        database.commit().unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT count(*) FROM users"),
+            vec![vec![Value::BigInt(3)]]
+        );
+    }
+
+    #[test]
+    fn serializable_transaction_fails_on_concurrent_change() {
+        let mut database = users_database("serializable_transaction");
+        database
+            .execute(
+                "BEGIN ISOLATION LEVEL SERIALIZABLE;
+                 UPDATE users SET age = 0 WHERE id = 1",
+            )
+            .unwrap();
+
+        // Another session inserts a row into the table the update read and commits first.
+        database
+            .connect()
+            .execute("INSERT INTO users VALUES (5, 'barbara', 50)")
+            .unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT count(*) FROM users"),
+            vec![vec![Value::BigInt(4)]]
+        );
+
+        assert_eq!(
+            database.execute("COMMIT"),
+            Err(DatabaseError::Execution(
+                ExecutionError::SerializationFailure
+            ))
+        );
+        assert!(database.transaction.is_none());
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT age FROM users WHERE id = 1 OR id = 5 ORDER BY id"
+            ),
+            vec![vec![Value::Integer(36)], vec![Value::Integer(50)]]
+        );
+    }
+
+    #[test]
+    fn concurrent_sessions_do_not_lose_updates() {
+        let mut database = users_database("concurrent_sessions");
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mut session = database.connect();
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        // An update that waited for a concurrent update of the row to commit
+                        // cannot be serialized, so it is retried.
+                        loop {
+                            match session.execute("UPDATE users SET age = age + 1 WHERE id = 2") {
+                                Ok(_) => break,
+                                Err(DatabaseError::Execution(
+                                    ExecutionError::SerializationFailure,
+                                )) => {}
+                                Err(error) => panic!("{}", error),
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(
+            query(&mut database, "SELECT age FROM users WHERE id = 2"),
+            vec![vec![Value::Integer(141)]]
+        );
+    }
+
+    #[test]
+    fn deadlocked_sessions_abort_one_transaction() {
+        let mut database = users_database("deadlocked_sessions");
+        let barrier = Arc::new(Barrier::new(2));
+        let threads: Vec<_> = [(1, 2), (2, 1)]
+            .into_iter()
+            .map(|(first, second)| {
+                let mut session = database.connect();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    session
+                        .execute(&format!(
+                            "BEGIN; UPDATE users SET age = {} WHERE id = {}",
+                            first, first
+                        ))
+                        .unwrap();
+                    // Both transactions hold the lock of one row before they wait for the other.
+                    barrier.wait();
+                    let result = session.execute(&format!(
+                        "UPDATE users SET age = {} WHERE id = {}",
+                        first, second
+                    ));
+                    if result.is_ok() {
+                        session.commit().unwrap();
+                    }
+                    result.map(|_| first)
+                })
+            })
+            .collect();
+        let results: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        let deadlocks = results
+            .iter()
+            .filter(|result| {
+                matches!(
+                    result,
+                    Err(DatabaseError::Execution(ExecutionError::Lock(
+                        LockError::Deadlock
+                    )))
+                )
+            })
+            .count();
+        assert_eq!(deadlocks, 1);
+        // The victim was rolled back, so both rows have the age set by the other transaction.
+        let survivor = results.into_iter().find_map(Result::ok).unwrap();
+        assert_eq!(
+            query(
+                &mut database,
+                "SELECT age FROM users WHERE id = 1 OR id = 2 ORDER BY id"
+            ),
+            vec![
+                vec![Value::Integer(survivor)],
+                vec![Value::Integer(survivor)]
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_keep_one_index_entry_per_key() {
+        let mut database = users_database("updates_keep_index_entries");
+        database
+            .execute("CREATE UNIQUE INDEX users_id ON users USING HASH (id)")
+            .unwrap();
+        // A transaction that began before the updates keeps reading the first version.
+        let mut reader = database.connect();
+        reader.execute("BEGIN").unwrap();
+        for age in 0..3000 {
+            database
+                .execute(&format!("UPDATE users SET age = {} WHERE id = 1", age))
+                .unwrap();
+        }
+        assert_eq!(
+            query(&mut reader, "SELECT age FROM users WHERE id = 1"),
+            vec![vec![Value::Integer(36)]]
+        );
+        reader.execute("COMMIT").unwrap();
+        let index_info = database.catalog().get_index("users_id").unwrap().clone();
+        let index = Index::open(database.buffer_pool.clone(), &index_info).unwrap();
+        assert_eq!(index.scan_key(&[Value::Integer(1)]).len(), 1);
+
+        // A rolled back update moves the entry back to the version it replaced.
+        database
+            .execute(
+                "BEGIN;
+                 UPDATE users SET age = 1 WHERE id = 1;
+                 UPDATE users SET age = 2 WHERE id = 1;
+                 ROLLBACK",
+            )
+            .unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT age FROM users WHERE id = 1"),
+            vec![vec![Value::Integer(2999)]]
+        );
+        assert!(database
+            .execute("UPDATE users SET id = 1 WHERE id = 2")
+            .is_err());
+
+        database.execute("VACUUM users").unwrap();
+        assert_eq!(index.scan_key(&[Value::Integer(1)]).len(), 1);
+        assert_eq!(
+            query(&mut database, "SELECT age FROM users WHERE id = 1"),
+            vec![vec![Value::Integer(2999)]]
+        );
+    }
+
+    #[test]
+    fn vacuum_removes_dead_versions() {
+        let mut database = users_database("vacuum_dead_versions");
+        database
+            .execute(
+                "CREATE UNIQUE INDEX users_id ON users (id);
+                 UPDATE users SET age = 0 WHERE id < 3;
+                 DELETE FROM users WHERE id = 4",
+            )
+            .unwrap();
+        let users = database.catalog().get_table("users").unwrap().clone();
+        assert_eq!(database.catalog().get_table_heap(&users).iter().count(), 6);
+
+        assert!(database.execute("BEGIN; VACUUM").is_err());
+        database.execute("ROLLBACK; VACUUM users").unwrap();
+        assert_eq!(database.catalog().get_table_heap(&users).iter().count(), 3);
+        let index_info = database.catalog().get_index("users_id").unwrap().clone();
+        let index = Index::open(database.buffer_pool.clone(), &index_info).unwrap();
+        assert_eq!(index.scan_key(&[Value::Integer(1)]).len(), 1);
+        assert!(index.scan_key(&[Value::Integer(4)]).is_empty());
+        assert_eq!(
+            query(&mut database, "SELECT id, age FROM users ORDER BY id"),
+            vec![
+                vec![Value::Integer(1), Value::Integer(0)],
+                vec![Value::Integer(2), Value::Integer(0)],
+                vec![Value::Integer(3), Value::Null],
+            ]
+        );
+    }
+
+    #[test]
+    fn failing_statement_changes_nothing() {
+        let mut database = users_database("failing_statement_changes_nothing");
+        database
+            .execute("CREATE UNIQUE INDEX users_id ON users (id)")
+            .unwrap();
+        // The first row is inserted before the second one fails.
+        assert!(database
+            .execute("INSERT INTO users VALUES (5, 'barbara', 50), (1, 'ada', 36)")
+            .is_err());
+        assert!(query(&mut database, "SELECT * FROM users WHERE id = 5").is_empty());
+
+        // Within a transaction, only the failing statement is undone.
+        database.begin().unwrap();
+        database.execute("DELETE FROM users WHERE id = 4").unwrap();
+        assert!(database
+            .execute("UPDATE users SET id = 3 WHERE id < 3")
+            .is_err());
+        assert_eq!(
+            query(&mut database, "SELECT id FROM users ORDER BY id"),
+            vec![
+                vec![Value::Integer(1)],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(3)]
+            ]
+        );
+        database.rollback().unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT count(*) FROM users WHERE id = 4"),
+            vec![vec![Value::BigInt(1)]]
+        );
+    }
+
+    #[test]
+    fn recover_after_crash() {
+        let path = temp_db_path("recover_after_crash");
+        let log_path = format!("{}.wal", path);
+        let _ = std::fs::remove_dir_all(&log_path);
+        let mut database = Database::open(&path).unwrap();
+        database
+            .execute(
+                "CREATE TABLE numbers (n INTEGER);
+                 CREATE INDEX numbers_n ON numbers USING HASH (n);
+                 INSERT INTO numbers VALUES (1), (2);",
+            )
+            .unwrap();
+        database.flush().unwrap();
+        // The pages of a committed statement are not written before the crash.
+        database.execute("INSERT INTO numbers VALUES (3)").unwrap();
+
+        // A statement crashes while filling the index. The buckets it split were already
+        // written to disk.
+        let index_info = database.catalog().get_index("numbers_n").unwrap().clone();
+        let index = Index::open(database.buffer_pool.clone(), &index_info).unwrap();
+        database.begin().unwrap();
+        let transaction_id = database
+            .transaction
+            .as_ref()
+            .map(|transaction| transaction.id());
+        for n in 100..2100 {
+            index
+                .insert_entry(&[Value::Integer(n)], Rid::new(1, n as u32), transaction_id)
+                .unwrap();
+        }
+        database
+            .buffer_pool
+            .lock()
+            .unwrap()
+            .unload_all_pages_and_write_to_file()
+            .unwrap();
+        drop(index);
+        drop(database);
+
+        let mut database = Database::open(&path).unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT n FROM numbers ORDER BY n"),
+            vec![
+                vec![Value::Integer(1)],
+                vec![Value::Integer(2)],
+                vec![Value::Integer(3)]
+            ]
+        );
+        let index = Index::open(database.buffer_pool.clone(), &index_info).unwrap();
+        assert_eq!(index.scan_key(&[Value::Integer(3)]).len(), 1);
+        assert!(index.scan_key(&[Value::Integer(100)]).is_empty());
+        for n in 2100..4100 {
+            index
+                .insert_entry(&[Value::Integer(n)], Rid::new(1, n as u32), None)
+                .unwrap();
+        }
+        assert_eq!(index.scan_key(&[Value::Integer(4000)]).len(), 1);
+        drop(index);
+        drop(database);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_dir_all(log_path).unwrap();
+    }
+
+    #[test]
+    fn recovery_keeps_the_committed_rows_of_other_sessions() {
+        let path = temp_db_path("recovery_keeps_committed_rows");
+        let log_path = format!("{}.wal", path);
+        let _ = std::fs::remove_dir_all(&log_path);
+        let mut first = Database::open(&path).unwrap();
+        first
+            .execute(
+                "CREATE TABLE t (n INTEGER);
+                 CREATE INDEX t_n ON t USING HASH (n);
+                 INSERT INTO t VALUES (0);",
+            )
+            .unwrap();
+        let mut second = first.connect();
+
+        // The changes of the unfinished transaction share their pages with the committed insert.
+        first
+            .execute("BEGIN; INSERT INTO t VALUES (1); DELETE FROM t WHERE n = 0;")
+            .unwrap();
+        second.execute("INSERT INTO t VALUES (2)").unwrap();
+        drop(first);
+        drop(second);
+
+        let mut database = Database::open(&path).unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT n FROM t ORDER BY n"),
+            vec![vec![Value::Integer(0)], vec![Value::Integer(2)]]
+        );
+        let index_info = database.catalog().get_index("t_n").unwrap().clone();
+        let index = Index::open(database.buffer_pool.clone(), &index_info).unwrap();
+        assert!(index.scan_key(&[Value::Integer(1)]).is_empty());
+        assert_eq!(index.scan_key(&[Value::Integer(2)]).len(), 1);
+        database.execute("INSERT INTO t VALUES (3)").unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT n FROM t WHERE n >= 2 ORDER BY n"),
+            vec![vec![Value::Integer(2)], vec![Value::Integer(3)]]
+        );
+        drop(index);
+        drop(database);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_dir_all(log_path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_database_needs_its_key() {
+        let path = temp_db_path("encrypted_database_needs_its_key");
+        let log_path = format!("{}.wal", path);
+        let _ = std::fs::remove_dir_all(&log_path);
+        let key = EncryptionKey::new([9; 32]);
+        let mut database = Database::open_encrypted(&path, &key).unwrap();
+        database
+            .execute("CREATE TABLE secrets (s TEXT); INSERT INTO secrets VALUES ('hidden');")
+            .unwrap();
+        database.flush().unwrap();
+        drop(database);
+
+        for segment in std::fs::read_dir(&log_path).unwrap() {
+            let content = std::fs::read(segment.unwrap().path()).unwrap();
+            assert!(!content.windows(6).any(|window| window == b"hidden"));
+        }
+        assert_eq!(
+            Database::open(&path).err(),
+            Some(DatabaseError::Disk(DiskError::EncryptedFile))
+        );
+        assert_eq!(
+            Database::open_encrypted(&path, &EncryptionKey::new([8; 32])).err(),
+            Some(DatabaseError::Disk(DiskError::WrongKey))
+        );
+        let mut database = Database::open_encrypted(&path, &key).unwrap();
+        assert_eq!(
+            query(&mut database, "SELECT s FROM secrets"),
+            vec![vec![Value::String("hidden".to_string())]]
+        );
+        drop(database);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_dir_all(log_path).unwrap();
+    }
+
+    #[test]
+    fn analyze_counts_the_rows_of_its_snapshot() {
+        let database = users_database("analyze_snapshot");
+        let mut writer = database.connect();
+        writer
+            .execute("BEGIN; DELETE FROM users WHERE id < 3; INSERT INTO users (id) VALUES (5)")
+            .unwrap();
+        // ANALYZE takes its snapshot before it waits for the writer, so it must not see the
+        // changes the writer commits in the meantime.
+        let mut analyzer = database.connect();
+        let analyze = thread::spawn(move || analyzer.execute("ANALYZE users").unwrap());
+        thread::sleep(Duration::from_millis(200));
+        writer.commit().unwrap();
+        analyze.join().unwrap();
+
+        let catalog = database.catalog();
+        let users = catalog.get_table("users").unwrap();
+        let statistics = catalog.get_table_statistics(users.table_id).unwrap();
+        assert_eq!(statistics.row_count, 4);
+    }
+
+    #[test]
+    fn explain_analyze_counts_the_page_requests_of_its_session() {
+        let mut database = users_database("explain_analyze_session");
+        let done = Arc::new(AtomicBool::new(false));
+        let mut session = database.connect();
+        let reader = {
+            let done = done.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    session.execute("SELECT * FROM users").unwrap();
+                }
+            })
+        };
+        for _ in 0..20 {
+            let lines = plan_lines(
+                &mut database,
+                "EXPLAIN ANALYZE SELECT name FROM users ORDER BY name",
+            );
+            assert!(lines[0].ends_with("(own buffers hits=0 misses=0)"));
+            assert!(lines[1].ends_with("(own buffers hits=0 misses=0)"));
+        }
+        done.store(true, Ordering::SeqCst);
+        reader.join().unwrap();
+    }
+}
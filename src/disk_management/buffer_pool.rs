@@ -1,9 +1,19 @@
 use std::{
+    cell::Cell,
     collections::HashMap,
     sync::{Arc, Mutex, RwLock},
 };
 
-use super::{disk_manager::DiskManager, lru_replacer::LRUReplacer};
+use crate::recovery::{
+    log_manager::LogManager,
+    log_record::{LogRecordBody, LogicalChange, Lsn, TransactionId},
+};
+
+use super::{
+    disk_manager::{DiskError, DiskManager},
+    lru_replacer::LRUReplacer,
+    page_latch::PageLatch,
+};
 
 pub const PAGE_SIZE: usize = 4096;
 const POOL_SIZE: usize = 100;
@@ -13,83 +23,277 @@ pub struct BufferPool {
     pub page_table: HashMap<usize, PageTableEntry>,
     lru_replacer: LRUReplacer,
     file_manager: Arc<Mutex<DiskManager>>,
+    /// The write-ahead log every page change is recorded in, if there is one.
+    log_manager: Option<Arc<Mutex<LogManager>>>,
+    statistics: BufferPoolStatistics,
+    /// Freed temporary pages, which are reused by the next temporary pages. The list is not
+    /// stored, pages freed before the buffer pool is dropped stay unused in the database file.
+    free_temp_page_ids: Vec<usize>,
+}
+
+/// Counts how often pages were requested from the buffer pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferPoolStatistics {
+    /// The number of requests for pages that were already loaded.
+    pub hits: u64,
+    /// The number of requests that read the page from disk.
+    pub misses: u64,
+}
+
+thread_local! {
+    /// The page requests of the current thread, of all buffer pools. A session runs its
+    /// statements on the thread that calls it, so the requests of a statement are not mixed up
+    /// with those of other sessions.
+    static THREAD_STATISTICS: Cell<BufferPoolStatistics> = Cell::default();
 }
 
 impl BufferPool {
     pub fn get_raw_page(&mut self, frame_id: usize) -> Option<&RawPage> {
-        return self.data[frame_id].as_ref();
+        self.data[frame_id].as_ref()
     }
     pub fn new(file_manager: Arc<Mutex<DiskManager>>) -> BufferPool {
         let vec: Vec<Option<RawPage>> = vec![None; POOL_SIZE];
-        return BufferPool {
+        BufferPool {
             data: vec,
             page_table: HashMap::new(),
             lru_replacer: LRUReplacer::new(POOL_SIZE),
             file_manager,
-        };
+            log_manager: None,
+            statistics: BufferPoolStatistics::default(),
+            free_temp_page_ids: Vec::new(),
+        }
+    }
+
+    /// Creates a buffer pool that records every change of a page in the write-ahead log, and
+    /// writes a modified page to disk only once the log records of its changes are flushed.
+    pub fn with_log_manager(
+        file_manager: Arc<Mutex<DiskManager>>,
+        log_manager: Arc<Mutex<LogManager>>,
+    ) -> BufferPool {
+        let mut buffer_pool = BufferPool::new(file_manager);
+        buffer_pool.log_manager = Some(log_manager);
+        buffer_pool
+    }
+
+    pub fn log_manager(&self) -> Option<Arc<Mutex<LogManager>>> {
+        self.log_manager.clone()
+    }
+
+    /// Starts a transaction. The page changes passed with its id belong to it, until it is
+    /// committed or ended.
+    pub fn begin_transaction(&self, transaction: TransactionId) {
+        self.log(transaction, LogRecordBody::Begin);
+    }
+
+    /// Commits the transaction, so that its page changes survive a crash.
+    pub fn commit_transaction(&self, transaction: TransactionId) -> Result<(), &'static str> {
+        if let Some(log_manager) = &self.log_manager {
+            log_manager.lock().unwrap().commit(transaction)?;
+        }
+        Ok(())
+    }
+
+    /// Marks the transaction as rolled back. The page changes that undo its changes are logged
+    /// outside of it, since a crash before it ended undoes its logical changes again.
+    pub fn abort_transaction(&self, transaction: TransactionId) {
+        self.log(transaction, LogRecordBody::Abort);
+    }
+
+    /// Ends the transaction after all of its changes were undone.
+    pub fn end_transaction(&self, transaction: TransactionId) {
+        self.log(transaction, LogRecordBody::End);
+    }
+
+    /// Returns the LSN of the last record of the transaction, which undoing a change the
+    /// transaction is about to make continues at. Returns None without a transaction or log.
+    pub fn last_lsn(&self, transaction: Option<TransactionId>) -> Option<Lsn> {
+        let transaction = transaction?;
+        let log_manager = self.log_manager.as_ref()?.lock().unwrap();
+        log_manager
+            .active_transactions()
+            .get(&transaction)
+            .map(|lsns| lsns.last_lsn)
+    }
+
+    /// Logs a change of a row or an index entry by the transaction after the page writes that
+    /// made it, which followed the record at `undo_next_lsn`. Does nothing without a transaction
+    /// or log.
+    pub fn log_logical_change(
+        &self,
+        transaction: Option<TransactionId>,
+        change: LogicalChange,
+        undo_next_lsn: Option<Lsn>,
+    ) {
+        if let Some(transaction) = transaction {
+            self.log(
+                transaction,
+                LogRecordBody::LogicalChange {
+                    change,
+                    undo_next_lsn,
+                },
+            );
+        }
+    }
+
+    /// Appends a record of the transaction to the log, if there is one.
+    fn log(&self, transaction: TransactionId, body: LogRecordBody) {
+        if let Some(log_manager) = &self.log_manager {
+            log_manager.lock().unwrap().append(Some(transaction), body);
+        }
     }
 
-    pub fn load_page(&mut self, page_id: usize) -> Option<usize> {
+    pub fn load_page(&mut self, page_id: usize) -> Result<usize, DiskError> {
         let possible_page_table = self.page_table.get_mut(&page_id);
         if let Some(page_table) = possible_page_table {
+            self.statistics.hits += 1;
+            THREAD_STATISTICS.with(|statistics| {
+                statistics.set(BufferPoolStatistics {
+                    hits: statistics.get().hits + 1,
+                    ..statistics.get()
+                })
+            });
             page_table.ref_count += 1;
             if page_table.ref_count == 1 {
                 self.lru_replacer.drop_page(page_id);
             }
-            return Some(page_table.frame_index);
-        }
-
-        // No free frame, evicting page is necessary
-        if self.page_table.len() == POOL_SIZE {
-            let index_to_remove = self.lru_replacer.pop_least_recently_used();
-            if let Some(index) = index_to_remove {
-                let page_table_entry = self
-                    .page_table
-                    .get(&index)
-                    .expect("Could not find the page table entry");
-                let frame_index = page_table_entry.frame_index;
-                if page_table_entry.dirty {
-                    self.file_manager.lock().unwrap().write_page(
-                        index,
-                        &self.data[frame_index]
-                            .as_ref()
-                            .expect("Expected a filled page that isn't filled"),
-                    );
-                }
-
-                return Some(self.load_page_from_disk(page_id, frame_index));
-            }
-            return None;
+            return Ok(page_table.frame_index);
         }
 
-        let frame_index = self
-            .data
-            .iter()
-            .enumerate()
-            .filter(|(_, value)| value.is_none())
-            .next()
-            .expect("could not find a none-value")
-            .0;
+        // The page is read before another page is evicted, so that a failed read leaves the
+        // buffer pool unchanged.
+        let (new_data, page_lsn) = self.file_manager.lock().unwrap().read_page(page_id)?;
+        let frame_index = match self.page_table.len() == POOL_SIZE {
+            // No free frame, evicting page is necessary
+            true => self.evict_page()?,
+            false => self
+                .data
+                .iter()
+                .position(|value| value.is_none())
+                .expect("could not find a none-value"),
+        };
+        self.statistics.misses += 1;
+        THREAD_STATISTICS.with(|statistics| {
+            statistics.set(BufferPoolStatistics {
+                misses: statistics.get().misses + 1,
+                ..statistics.get()
+            })
+        });
+        let mut page_table_entry = PageTableEntry::new(frame_index);
+        page_table_entry.page_lsn = page_lsn;
+        self.page_table.insert(page_id, page_table_entry);
+        self.data[frame_index] = Some(RawPage::new(new_data));
+        Ok(frame_index)
+    }
 
-        return Some(self.load_page_from_disk(page_id, frame_index));
+    /// Removes the least recently used page that is not pinned from the buffer pool and returns
+    /// its frame. The page is written to disk first if it is dirty.
+    fn evict_page(&mut self) -> Result<usize, DiskError> {
+        let page_id = self
+            .lru_replacer
+            .pop_least_recently_used()
+            .ok_or(DiskError::AllFramesPinned)?;
+        let page_table_entry = &self.page_table[&page_id];
+        let frame_index = page_table_entry.frame_index;
+        if page_table_entry.dirty {
+            self.flush_log(page_table_entry.page_lsn);
+            let written = self.file_manager.lock().unwrap().write_page(
+                page_id,
+                self.data[frame_index]
+                    .as_ref()
+                    .expect("Expected a filled page that isn't filled"),
+                page_table_entry.page_lsn,
+            );
+            if let Err(error) = written {
+                self.lru_replacer.add_page(page_id);
+                return Err(error);
+            }
+        }
+        self.page_table.remove(&page_id);
+        Ok(frame_index)
     }
 
     /// Allocates a new page and loads it. Returns a tuple with the following format: (page_id, frame_id)
-    pub fn load_new_page(&mut self) -> Option<(usize, usize)> {
-        let page_id = self.allocate_new_page();
+    pub fn load_new_page(&mut self) -> Result<(usize, usize), DiskError> {
+        let page_id = self.allocate_new_page()?;
         let frame_id = self.load_page(page_id)?;
-        return Some((page_id, frame_id));
+        Ok((page_id, frame_id))
     }
 
-    pub fn allocate_new_page(&mut self) -> usize {
+    pub fn allocate_new_page(&mut self) -> Result<usize, DiskError> {
         let mut lock = self.file_manager.lock().unwrap();
-        let page_id = lock.get_file_length() as usize / PAGE_SIZE;
-        lock.write_page(page_id, &RawPage::new([0; PAGE_SIZE]));
-        return page_id;
+        let page_id = lock.get_page_count()?;
+        lock.write_page(page_id, &RawPage::new([0; PAGE_SIZE]), None)?;
+        Ok(page_id)
+    }
+
+    /// Loads a page for temporary data, e.g. rows an executor spilled, like
+    /// [BufferPool::load_new_page]. A freed temporary page is reused if there is one, its content
+    /// is undefined. Returns a tuple with the following format: (page_id, frame_id)
+    pub fn load_new_temp_page(&mut self) -> Result<(usize, usize), DiskError> {
+        let page_id = match self.free_temp_page_ids.pop() {
+            Some(page_id) => page_id,
+            None => self.allocate_new_page()?,
+        };
+        match self.load_page(page_id) {
+            Ok(frame_id) => Ok((page_id, frame_id)),
+            Err(error) => {
+                self.free_temp_page_ids.push(page_id);
+                Err(error)
+            }
+        }
+    }
+
+    /// Sets the data of a loaded temporary page. Unlike [BufferPool::set_page_data], the change
+    /// is not logged: temporary pages are not needed after a crash.
+    pub fn set_temp_page_data(&mut self, page_id: usize, new_data: RawPage) -> Result<(), &str> {
+        let page_table = self
+            .page_table
+            .get_mut(&page_id)
+            .ok_or("The page is not loaded")?;
+        page_table.log_change(None);
+        self.data[page_table.frame_index] = Some(new_data);
+        Ok(())
+    }
+
+    /// Frees a temporary page that is not pinned, so that it is reused by the next temporary
+    /// page. Its data is dropped without writing it to disk.
+    pub fn free_temp_page(&mut self, page_id: usize) -> Result<(), &str> {
+        if let Some(page_table) = self.page_table.get(&page_id) {
+            if page_table.ref_count > 0 {
+                return Err("The page is still pinned");
+            }
+            self.data[page_table.frame_index] = None;
+            self.page_table.remove(&page_id);
+            self.lru_replacer.drop_page(page_id);
+        }
+        self.free_temp_page_ids.push(page_id);
+        Ok(())
+    }
+
+    /// Returns the latch of a loaded page. The latch stays the same as long as the page is
+    /// pinned, so a page has to be pinned before it is latched and unpinned after the latch is
+    /// released.
+    pub fn get_page_latch(&self, page_id: usize) -> Option<Arc<PageLatch>> {
+        Some(self.page_table.get(&page_id)?.latch.clone())
+    }
+
+    /// Returns the number of page requests since the buffer pool was created.
+    pub fn statistics(&self) -> BufferPoolStatistics {
+        self.statistics
+    }
+
+    /// Returns the number of page requests the current thread made since it started.
+    pub fn thread_statistics() -> BufferPoolStatistics {
+        THREAD_STATISTICS.with(Cell::get)
+    }
+
+    /// Returns the number of pages that have been allocated in the database file.
+    pub fn get_page_count(&mut self) -> Result<usize, DiskError> {
+        self.file_manager.lock().unwrap().get_page_count()
     }
 
     pub fn unload_page_id(&mut self, page_id: usize) -> Result<(), &str> {
-        let mut page_entry = self
+        let page_entry = self
             .page_table
             .get_mut(&page_id)
             .ok_or("Cannot find the specified page index")?;
@@ -100,48 +304,152 @@ impl BufferPool {
         if page_entry.ref_count == 0 {
             self.lru_replacer.add_page(page_id);
         }
-        return Ok(());
+        Ok(())
     }
 
-    pub fn unload_all_pages_and_write_to_file(&mut self) {
-        for (page_id, page_table) in self.page_table.drain() {
+    pub fn unload_all_pages_and_write_to_file(&mut self) -> Result<(), DiskError> {
+        if let Some(log_manager) = &self.log_manager {
+            log_manager
+                .lock()
+                .unwrap()
+                .flush_all()
+                .expect("Could not flush the log");
+        }
+        for (page_id, page_table) in self.page_table.iter_mut() {
             if page_table.dirty {
                 self.file_manager.lock().unwrap().write_page(
-                    page_id,
-                    &self
-                        .data
+                    *page_id,
+                    self.data
                         .get(page_table.frame_index)
                         .expect("The loaded frame index is out of bounds")
                         .as_ref()
                         .expect("The frame was not loaded"),
-                );
+                    page_table.page_lsn,
+                )?;
+                page_table.dirty = false;
             }
         }
 
+        self.page_table.clear();
         self.data.fill(None);
         self.lru_replacer.drop_all_pages();
+        Ok(())
     }
 
-    /// Updates the page at a given page id.
-    pub fn update_page(&mut self, page_id: usize, new_data: RawPage) -> Result<(), &str> {
-        if let Some(frame_id) = self.load_page(page_id) {
+    /// Updates the page at a given page id. The change is logged for the transaction, or
+    /// outside of any transaction if it is None.
+    pub fn update_page(
+        &mut self,
+        page_id: usize,
+        new_data: RawPage,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &str> {
+        if let Ok(frame_id) = self.load_page(page_id) {
+            let page_lsn = self.log_page_write(page_id, frame_id, &new_data, transaction);
             if let Some(page_table) = self.page_table.get_mut(&page_id) {
-                page_table.dirty = true;
+                page_table.log_change(page_lsn);
             }
 
             self.data[frame_id] = Some(new_data);
             return Ok(());
         }
-        return Err("Could not update the page value");
+        Err("Could not update the page value")
+    }
+
+    /// Replaces the data of a page that is currently loaded and marks it as dirty. Unlike
+    /// [BufferPool::update_page], the pin count of the page is not changed.
+    pub fn set_page_data(
+        &mut self,
+        page_id: usize,
+        new_data: RawPage,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &str> {
+        let frame_index = self
+            .page_table
+            .get(&page_id)
+            .ok_or("The page is not loaded")?
+            .frame_index;
+        let page_lsn = self.log_page_write(page_id, frame_index, &new_data, transaction);
+        let page_table = self.page_table.get_mut(&page_id).unwrap();
+        page_table.log_change(page_lsn);
+        self.data[frame_index] = Some(new_data);
+        Ok(())
     }
 
-    fn load_page_from_disk(&mut self, page_id: usize, frame_index: usize) -> usize {
-        let new_data = self.file_manager.lock().unwrap().read_page(page_id);
-        let raw_page = RawPage::new(new_data);
+    /// Appends the change of the page in the frame to the new data to the log, as a change of
+    /// the transaction. Returns the LSN of the log record, or None if nothing is logged.
+    fn log_page_write(
+        &self,
+        page_id: usize,
+        frame_index: usize,
+        new_data: &RawPage,
+        transaction: Option<TransactionId>,
+    ) -> Option<Lsn> {
+        let log_manager = self.log_manager.as_ref()?;
+        let old_data = self.data[frame_index].as_ref()?.data.read().unwrap();
+        let body =
+            LogRecordBody::page_write(page_id as u32, &old_data, &new_data.data.read().unwrap())?;
+        Some(log_manager.lock().unwrap().append(transaction, body))
+    }
+
+    /// Returns the loaded pages with logged changes that are not written to disk yet, with the
+    /// LSN of the first of these changes.
+    pub fn dirty_pages(&self) -> Vec<(u32, Lsn)> {
         self.page_table
-            .insert(page_id, PageTableEntry::new(frame_index));
-        self.data[frame_index] = Some(raw_page);
-        return frame_index;
+            .iter()
+            .filter_map(|(page_id, page_table)| Some((*page_id as u32, page_table.recovery_lsn?)))
+            .collect()
+    }
+
+    /// Writes the bytes into the page at the offset without logging the change, as it is
+    /// already recorded by the log record at the LSN. Used to redo and undo changes. Pages that
+    /// do not exist in the database file yet are allocated. Nothing is written if the page
+    /// already contains the change, i.e. its page LSN is not lower than the LSN.
+    pub fn apply_logged_change(
+        &mut self,
+        page_id: usize,
+        offset: usize,
+        bytes: &[u8],
+        lsn: Lsn,
+    ) -> Result<(), &'static str> {
+        let allocation_error = Err("Could not allocate the page of a log record");
+        while self.get_page_count().or(allocation_error)? <= page_id {
+            self.allocate_new_page().or(allocation_error)?;
+        }
+        let frame_index = self
+            .load_page(page_id)
+            .or(Err("Could not load the page of a log record"))?;
+        if self.page_table[&page_id]
+            .page_lsn
+            .is_some_and(|page_lsn| page_lsn >= lsn)
+        {
+            return self
+                .unload_page_id(page_id)
+                .or(Err("Could not unpin the page of a log record"));
+        }
+        let mut data = *self.data[frame_index]
+            .as_ref()
+            .unwrap()
+            .data
+            .read()
+            .unwrap();
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        let page_table = self.page_table.get_mut(&page_id).unwrap();
+        page_table.log_change(Some(lsn));
+        self.data[frame_index] = Some(RawPage::new(data));
+        self.unload_page_id(page_id)
+            .or(Err("Could not unpin the page of a log record"))
+    }
+
+    /// Flushes the log up to the LSN, so that a page changed by the log record can be written.
+    fn flush_log(&self, page_lsn: Option<Lsn>) {
+        if let (Some(log_manager), Some(page_lsn)) = (&self.log_manager, page_lsn) {
+            log_manager
+                .lock()
+                .unwrap()
+                .flush(page_lsn)
+                .expect("Could not flush the log");
+        }
     }
 }
 
@@ -151,9 +459,9 @@ pub struct RawPage {
 }
 impl RawPage {
     pub fn new(data: [u8; PAGE_SIZE]) -> RawPage {
-        return RawPage {
+        RawPage {
             data: Arc::new(RwLock::new(data)),
-        };
+        }
     }
 }
 
@@ -161,14 +469,71 @@ pub struct PageTableEntry {
     pub frame_index: usize,
     dirty: bool,
     ref_count: usize,
+    /// The LSN of the last log record whose change the page contains. It is stored with the
+    /// page when it is written to disk.
+    page_lsn: Option<Lsn>,
+    /// The LSN of the first log record that changed the page since it was loaded. Recovery has
+    /// to redo the log from here if the page is not written before a crash.
+    recovery_lsn: Option<Lsn>,
+    latch: Arc<PageLatch>,
 }
 
 impl PageTableEntry {
     pub fn new(frame_id: usize) -> PageTableEntry {
-        return PageTableEntry {
+        PageTableEntry {
             frame_index: frame_id,
             dirty: false,
             ref_count: 1,
-        };
+            page_lsn: None,
+            recovery_lsn: None,
+            latch: Arc::new(PageLatch::default()),
+        }
+    }
+
+    /// Marks the page as dirty after a change logged at the LSN.
+    fn log_change(&mut self, lsn: Option<Lsn>) {
+        self.dirty = true;
+        self.page_lsn = lsn.or(self.page_lsn);
+        self.recovery_lsn = self.recovery_lsn.or(lsn);
+    }
+}
+
+#[cfg(test)]
+mod buffer_pool_tests {
+    use crate::disk_management::{disk_manager::DiskError, test_utils::new_test_buffer_pool};
+
+    use super::{RawPage, PAGE_SIZE, POOL_SIZE};
+
+    #[test]
+    fn failed_reads_do_not_evict_pages() {
+        let buffer_pool = new_test_buffer_pool("failed_reads_do_not_evict_pages");
+        let mut buffer_pool = buffer_pool.lock().unwrap();
+        let page_ids: Vec<usize> = (0..POOL_SIZE)
+            .map(|_| buffer_pool.load_new_page().unwrap().0)
+            .collect();
+        buffer_pool
+            .set_page_data(page_ids[0], RawPage::new([5; PAGE_SIZE]), None)
+            .unwrap();
+        for page_id in &page_ids {
+            buffer_pool.unload_page_id(*page_id).unwrap();
+        }
+
+        // The page lies behind the end of the file.
+        assert_eq!(
+            buffer_pool.load_page(POOL_SIZE),
+            Err(DiskError::TruncatedPage(POOL_SIZE))
+        );
+        let frame_ids: Vec<usize> = page_ids
+            .iter()
+            .map(|page_id| buffer_pool.load_page(*page_id).unwrap())
+            .collect();
+        let first_page = buffer_pool.get_raw_page(frame_ids[0]).unwrap();
+        assert_eq!(first_page.data.read().unwrap()[0], 5);
+
+        let page_id = buffer_pool.allocate_new_page().unwrap();
+        assert_eq!(
+            buffer_pool.load_page(page_id),
+            Err(DiskError::AllFramesPinned)
+        );
     }
 }
@@ -1,56 +1,561 @@
 use std::{
+    fmt::Display,
     fs::File,
-    io::{Read, Seek, SeekFrom, Write},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
 };
 
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use rand::Rng;
+
+use crate::recovery::log_record::Lsn;
+
 use super::buffer_pool::{RawPage, PAGE_SIZE};
 
+/// Number of random bytes stored in front of every encrypted page. Together with the four bytes
+/// of the page id they form the 96 bit AES-GCM nonce.
+const NONCE_RANDOM_SIZE: usize = 8;
+/// Size of the AES-GCM authentication tag appended to every encrypted page.
+const TAG_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+/// Marks the start of an unencrypted database file in the current format.
+const FORMAT_MAGIC: &[u8; 8] = b"DBMS-PG2";
+/// Marks the start of an encrypted database file in the current format.
+const ENCRYPTION_MAGIC: &[u8; 8] = b"DBMS-AE2";
+/// The plaintext that is encrypted in the file header to check the key a file is opened with.
+const KEY_CHECK: &[u8; 16] = b"DBMS KEY CHECK 1";
+const NONCE_SIZE: usize = 12;
+const FILE_HEADER_SIZE: usize = ENCRYPTION_MAGIC.len() + NONCE_SIZE + KEY_CHECK.len() + TAG_SIZE;
+/// Size of the header stored in front of the data of every page.
+const PAGE_HEADER_SIZE: usize = 8;
+/// Stored as the page LSN of a page that was never changed by a logged change.
+const NO_LSN: u64 = u64::MAX;
+
+// PAGE FORMAT:
+// | PAGE_LSN [u64] | DATA [PAGE_SIZE bytes] |
+//
+// PAGE_LSN is the LSN of the last log record whose change the data contains, or u64::MAX if
+// there is none. Recovery does not redo changes that are already on disk.
+//
+// UNENCRYPTED FILE FORMAT:
+// | MAGIC [8 bytes] | PAGE (0) | ... | PAGE (n) |
+//
+// ENCRYPTED FILE FORMAT:
+// | FILE HEADER | PAGE (0) | ... | PAGE (n) |
+//
+// FILE HEADER:
+// | MAGIC [8 bytes] | KEY CHECK NONCE [12 bytes] | ENCRYPTED KEY CHECK [16 bytes] | TAG [16 bytes] |
+//
+// ENCRYPTED PAGE FORMAT:
+// | NONCE RANDOM PART [8 bytes] | CIPHERTEXT [8 + PAGE_SIZE bytes] | TAG [16 bytes] |
+//
+// The full nonce is PAGE_ID [u32, little endian] + NONCE RANDOM PART. The page id is also passed
+// as associated data, so a page that was copied to another position fails authentication. The
+// page LSN is encrypted together with the data.
+//
+// The magic numbers change with the page format, so files written in an older format, which had
+// no page LSNs, are rejected instead of being read at the wrong offsets.
+
+#[derive(Debug, PartialEq)]
+pub enum DiskError {
+    /// The page could not be authenticated. Either the file has been modified, or it was opened
+    /// with the wrong key.
+    CorruptedPage(usize),
+    /// The file ends before the end of the page.
+    TruncatedPage(usize),
+    /// The file is encrypted, but was opened without a key.
+    EncryptedFile,
+    /// The file is not encrypted, but was opened with a key.
+    UnencryptedFile,
+    /// The file was encrypted with another key.
+    WrongKey,
+    /// The file was written in an older format, or is no database file.
+    UnsupportedFormat,
+    /// Every frame of the buffer pool holds a pinned page, so no other page can be loaded.
+    AllFramesPinned,
+    /// Reading or writing the file failed.
+    Io(ErrorKind),
+}
+
+impl Display for DiskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskError::CorruptedPage(page_id) => write!(f, "page {} is corrupted", page_id),
+            DiskError::TruncatedPage(page_id) => {
+                write!(f, "the database file ends within page {}", page_id)
+            }
+            DiskError::EncryptedFile => write!(f, "the database file is encrypted"),
+            DiskError::UnencryptedFile => write!(f, "the database file is not encrypted"),
+            DiskError::WrongKey => write!(f, "the database file was encrypted with another key"),
+            DiskError::UnsupportedFormat => {
+                write!(f, "the database file has an unsupported format")
+            }
+            DiskError::AllFramesPinned => write!(f, "all pages of the buffer pool are pinned"),
+            DiskError::Io(kind) => write!(f, "could not access the database file: {}", kind),
+        }
+    }
+}
+
+impl From<std::io::Error> for DiskError {
+    fn from(error: std::io::Error) -> Self {
+        DiskError::Io(error.kind())
+    }
+}
+
+/// A 256 bit key used to encrypt all pages of a database file.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    key: [u8; KEY_SIZE],
+}
+
+impl EncryptionKey {
+    pub fn new(key: [u8; KEY_SIZE]) -> EncryptionKey {
+        EncryptionKey { key }
+    }
+
+    /// Reads a key from a file. The file must either contain exactly 32 raw bytes or 64
+    /// hexadecimal characters, optionally surrounded by whitespace.
+    pub fn from_key_file(key_file_path: &str) -> Result<EncryptionKey, &'static str> {
+        let content = std::fs::read(key_file_path).or(Err("Could not read the key file"))?;
+        if content.len() == KEY_SIZE {
+            return Ok(EncryptionKey::new(content.try_into().unwrap()));
+        }
+
+        let hex = std::str::from_utf8(&content)
+            .or(Err("The key file is neither raw nor hex encoded"))?
+            .trim();
+        if hex.len() != KEY_SIZE * 2 {
+            return Err("The key file must contain a 256 bit key");
+        }
+        let mut key = [0; KEY_SIZE];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .or(Err("The key file contains invalid hex characters"))?;
+        }
+        Ok(EncryptionKey::new(key))
+    }
+
+    /// Encrypts the plaintext with a random nonce, which is stored in front of the ciphertext.
+    /// The associated data is authenticated, but not encrypted.
+    pub fn encrypt(&self, plaintext: &[u8], associated_data: &[u8]) -> Vec<u8> {
+        let nonce: [u8; NONCE_SIZE] = rand::thread_rng().gen();
+        let ciphertext = Aes256Gcm::new(&self.key.into())
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: associated_data,
+                },
+            )
+            .expect("Could not encrypt the data");
+        let mut bytes = nonce.to_vec();
+        bytes.extend(ciphertext);
+        bytes
+    }
+
+    /// Decrypts bytes returned by [EncryptionKey::encrypt]. Returns None if they could not be
+    /// authenticated together with the associated data.
+    pub fn decrypt(&self, bytes: &[u8], associated_data: &[u8]) -> Option<Vec<u8>> {
+        if bytes.len() < NONCE_SIZE {
+            return None;
+        }
+        Aes256Gcm::new(&self.key.into())
+            .decrypt(
+                Nonce::from_slice(&bytes[..NONCE_SIZE]),
+                Payload {
+                    msg: &bytes[NONCE_SIZE..],
+                    aad: associated_data,
+                },
+            )
+            .ok()
+    }
+}
+
 pub struct DiskManager {
-    db_file_path: String,
     file: File,
+    cipher: Option<Aes256Gcm>,
 }
 
 impl DiskManager {
-    pub fn new(db_file_path: String) -> DiskManager {
-        if !std::path::Path::new(&db_file_path).exists() {
-            File::create(db_file_path.to_owned())
-                .expect("Could not create the database file that did not exist");
+    /// Opens the database file, creating it if it does not exist. Returns an error if the file
+    /// is encrypted or has another format.
+    pub fn new(db_file_path: String) -> Result<DiskManager, DiskError> {
+        let mut disk_manager = DiskManager::open(db_file_path)?;
+        if disk_manager.get_file_length()? == 0 {
+            disk_manager.file.write_all(FORMAT_MAGIC)?;
+            disk_manager.file.flush()?;
+        }
+        match disk_manager.read_magic()? {
+            Some(magic) if &magic == FORMAT_MAGIC => Ok(disk_manager),
+            Some(magic) if &magic == ENCRYPTION_MAGIC => Err(DiskError::EncryptedFile),
+            _ => Err(DiskError::UnsupportedFormat),
+        }
+    }
+
+    /// Opens the database file like [DiskManager::new], but encrypts every page written to and
+    /// authenticates every page read from the file with the given key. A new file is marked as
+    /// encrypted with the key; an existing file must have been encrypted with the same key.
+    pub fn new_encrypted(
+        db_file_path: String,
+        key: &EncryptionKey,
+    ) -> Result<DiskManager, DiskError> {
+        let mut disk_manager = DiskManager::open(db_file_path)?;
+        let cipher = Aes256Gcm::new(&key.key.into());
+        if disk_manager.get_file_length()? == 0 {
+            let nonce: [u8; NONCE_SIZE] = rand::thread_rng().gen();
+            let key_check = cipher
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: KEY_CHECK,
+                        aad: ENCRYPTION_MAGIC,
+                    },
+                )
+                .expect("Could not encrypt the key check");
+            let mut header = ENCRYPTION_MAGIC.to_vec();
+            header.extend(nonce);
+            header.extend(key_check);
+            disk_manager.file.write_all(&header)?;
+            disk_manager.file.flush()?;
+        } else {
+            match disk_manager.read_magic()? {
+                Some(magic) if &magic == ENCRYPTION_MAGIC => {}
+                Some(magic) if &magic == FORMAT_MAGIC => return Err(DiskError::UnencryptedFile),
+                _ => return Err(DiskError::UnsupportedFormat),
+            }
+            let mut header = [0; FILE_HEADER_SIZE];
+            disk_manager.file.seek(SeekFrom::Start(0))?;
+            disk_manager
+                .file
+                .read_exact(&mut header)
+                .or(Err(DiskError::UnencryptedFile))?;
+            let nonce = &header[ENCRYPTION_MAGIC.len()..ENCRYPTION_MAGIC.len() + NONCE_SIZE];
+            let key_check = cipher
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: &header[ENCRYPTION_MAGIC.len() + NONCE_SIZE..],
+                        aad: ENCRYPTION_MAGIC,
+                    },
+                )
+                .or(Err(DiskError::WrongKey))?;
+            if key_check != KEY_CHECK {
+                return Err(DiskError::WrongKey);
+            }
         }
+        disk_manager.cipher = Some(cipher);
+        Ok(disk_manager)
+    }
+
+    fn open(db_file_path: String) -> Result<DiskManager, DiskError> {
         let file = File::options()
+            .create(true)
+            .truncate(false)
             .write(true)
             .read(true)
-            .open(db_file_path.to_owned())
-            .expect("Could not open the database file");
-        return DiskManager { db_file_path, file };
+            .open(&db_file_path)?;
+        Ok(DiskManager { file, cipher: None })
     }
 
-    pub fn get_file_length(&mut self) -> u64 {
-        return self.file.metadata().unwrap().len();
+    /// Returns the first bytes of the file, if it is long enough to have a format marker.
+    fn read_magic(&mut self) -> Result<Option<[u8; ENCRYPTION_MAGIC.len()]>, DiskError> {
+        let mut magic = [0; ENCRYPTION_MAGIC.len()];
+        self.file.seek(SeekFrom::Start(0))?;
+        match self.file.read_exact(&mut magic) {
+            Ok(()) => Ok(Some(magic)),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error.into()),
+        }
     }
 
-    pub fn read_page(&mut self, page_id: usize) -> [u8; PAGE_SIZE] {
-        let mut buffer = [0; PAGE_SIZE];
-        self.file
-            .seek(SeekFrom::Start((page_id * PAGE_SIZE) as u64))
-            .unwrap();
+    pub fn get_file_length(&mut self) -> Result<u64, DiskError> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Returns the number of pages currently stored in the file.
+    pub fn get_page_count(&mut self) -> Result<usize, DiskError> {
+        let file_length = self.get_file_length()? as usize;
+        Ok(file_length.saturating_sub(self.file_header_size()) / self.physical_page_size())
+    }
+
+    /// The number of bytes a single page occupies on disk.
+    fn physical_page_size(&self) -> usize {
+        match self.cipher {
+            Some(_) => NONCE_RANDOM_SIZE + PAGE_HEADER_SIZE + PAGE_SIZE + TAG_SIZE,
+            None => PAGE_HEADER_SIZE + PAGE_SIZE,
+        }
+    }
+
+    /// The number of bytes in front of the first page.
+    fn file_header_size(&self) -> usize {
+        match self.cipher {
+            Some(_) => FILE_HEADER_SIZE,
+            None => FORMAT_MAGIC.len(),
+        }
+    }
+
+    fn seek_page(&mut self, page_id: usize) -> Result<(), DiskError> {
+        let offset = self.file_header_size() + page_id * self.physical_page_size();
+        self.file.seek(SeekFrom::Start(offset as u64))?;
+        Ok(())
+    }
+
+    /// Reads the data of the page together with its page LSN.
+    pub fn read_page(
+        &mut self,
+        page_id: usize,
+    ) -> Result<([u8; PAGE_SIZE], Option<Lsn>), DiskError> {
+        let mut buffer = vec![0; self.physical_page_size()];
+        self.seek_page(page_id)?;
         self.file
             .read_exact(&mut buffer)
-            .expect("Could not read page contents");
-        return buffer;
+            .map_err(|error| match error.kind() {
+                ErrorKind::UnexpectedEof => DiskError::TruncatedPage(page_id),
+                _ => error.into(),
+            })?;
+
+        let plaintext = match &self.cipher {
+            Some(cipher) => {
+                let nonce = build_nonce(page_id, &buffer[0..NONCE_RANDOM_SIZE]);
+                cipher
+                    .decrypt(
+                        Nonce::from_slice(&nonce),
+                        Payload {
+                            msg: &buffer[NONCE_RANDOM_SIZE..],
+                            aad: &(page_id as u32).to_le_bytes(),
+                        },
+                    )
+                    .or(Err(DiskError::CorruptedPage(page_id)))?
+            }
+            None => buffer,
+        };
+        let (header, data) = plaintext.split_at(PAGE_HEADER_SIZE);
+        let page_lsn =
+            Some(u64::from_le_bytes(header.try_into().unwrap())).filter(|lsn| *lsn != NO_LSN);
+        let data = data.try_into().or(Err(DiskError::CorruptedPage(page_id)))?;
+        Ok((data, page_lsn))
     }
 
-    pub fn write_page(&mut self, page_id: usize, data: &RawPage) {
-        self.file
-            .seek(SeekFrom::Start((page_id * PAGE_SIZE) as u64))
+    /// Writes the data of the page. The page LSN is the LSN of the last logged change the data
+    /// contains.
+    pub fn write_page(
+        &mut self,
+        page_id: usize,
+        data: &RawPage,
+        page_lsn: Option<Lsn>,
+    ) -> Result<(), DiskError> {
+        let mut plaintext = page_lsn.unwrap_or(NO_LSN).to_le_bytes().to_vec();
+        plaintext.extend(
+            *data
+                .data
+                .read()
+                .expect("Could not get the value behind the RwLock"),
+        );
+        let bytes = match &self.cipher {
+            Some(cipher) => {
+                let nonce_random: [u8; NONCE_RANDOM_SIZE] = rand::thread_rng().gen();
+                let nonce = build_nonce(page_id, &nonce_random);
+                let ciphertext = cipher
+                    .encrypt(
+                        Nonce::from_slice(&nonce),
+                        Payload {
+                            msg: &plaintext,
+                            aad: &(page_id as u32).to_le_bytes(),
+                        },
+                    )
+                    .expect("Could not encrypt the page");
+                let mut bytes = nonce_random.to_vec();
+                bytes.extend(ciphertext);
+                bytes
+            }
+            None => plaintext,
+        };
+
+        self.seek_page(page_id)?;
+        self.file.write_all(&bytes)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+fn build_nonce(page_id: usize, nonce_random: &[u8]) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[0..4].copy_from_slice(&(page_id as u32).to_le_bytes());
+    nonce[4..].copy_from_slice(nonce_random);
+    nonce
+}
+
+#[cfg(test)]
+mod disk_manager_tests {
+    use crate::disk_management::{
+        buffer_pool::{RawPage, PAGE_SIZE},
+        test_utils::temp_db_path,
+    };
+
+    use super::{DiskError, DiskManager, EncryptionKey, FILE_HEADER_SIZE};
+
+    fn page_with(value: u8) -> RawPage {
+        let mut data = [0; PAGE_SIZE];
+        data[0] = value;
+        data[PAGE_SIZE - 1] = value;
+        RawPage::new(data)
+    }
+
+    #[test]
+    fn encrypted_round_trip() {
+        let path = temp_db_path("encrypted_round_trip");
+        let key = EncryptionKey::new([7; 32]);
+        let mut disk_manager = DiskManager::new_encrypted(path.clone(), &key).unwrap();
+        disk_manager.write_page(0, &page_with(1), None).unwrap();
+        disk_manager.write_page(1, &page_with(2), None).unwrap();
+
+        assert_eq!(disk_manager.get_page_count().unwrap(), 2);
+        assert_eq!(disk_manager.read_page(1).unwrap().0[PAGE_SIZE - 1], 2);
+        assert_eq!(disk_manager.read_page(0).unwrap().0[0], 1);
+
+        let raw_content = std::fs::read(&path).unwrap();
+        assert!(raw_content.iter().filter(|byte| **byte == 0).count() < raw_content.len() / 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn page_lsns_are_stored() {
+        let path = temp_db_path("page_lsns_are_stored");
+        let mut disk_manager = DiskManager::new(path.clone()).unwrap();
+        disk_manager.write_page(0, &page_with(1), Some(42)).unwrap();
+        disk_manager.write_page(1, &page_with(2), None).unwrap();
+        assert_eq!(disk_manager.read_page(0).unwrap().1, Some(42));
+        assert_eq!(disk_manager.read_page(1).unwrap().1, None);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut disk_manager =
+            DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32])).unwrap();
+        disk_manager.write_page(0, &page_with(1), Some(0)).unwrap();
+        assert_eq!(disk_manager.read_page(0).unwrap().1, Some(0));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn wrong_key_is_detected() {
+        let path = temp_db_path("wrong_key_is_detected");
+        DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32]))
+            .unwrap()
+            .write_page(0, &page_with(1), None)
             .unwrap();
-        self.file
-            .write(
-                &*data
-                    .data
-                    .read()
-                    .expect("Could not get the value behind the RwLock"),
-            )
+
+        let wrong_key = DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([8; 32]));
+        assert_eq!(wrong_key.err(), Some(DiskError::WrongKey));
+        assert_eq!(
+            DiskManager::new(path.clone()).err(),
+            Some(DiskError::EncryptedFile)
+        );
+        let mut disk_manager =
+            DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32])).unwrap();
+        assert_eq!(disk_manager.read_page(0).unwrap().0[0], 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unencrypted_files_are_not_opened_with_a_key() {
+        let path = temp_db_path("unencrypted_with_key");
+        DiskManager::new(path.clone())
+            .unwrap()
+            .write_page(0, &page_with(1), None)
             .unwrap();
-        self.file.flush().expect("Could not flush the page content");
+        let encrypted = DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32]));
+        assert_eq!(encrypted.err(), Some(DiskError::UnencryptedFile));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncated_pages_are_detected() {
+        let path = temp_db_path("truncated_pages_are_detected");
+        let mut disk_manager = DiskManager::new(path.clone()).unwrap();
+        disk_manager.write_page(0, &page_with(1), None).unwrap();
+        disk_manager.write_page(1, &page_with(2), None).unwrap();
+
+        let content = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &content[..PAGE_SIZE + 100]).unwrap();
+        assert_eq!(disk_manager.read_page(1), Err(DiskError::TruncatedPage(1)));
+        assert_eq!(disk_manager.read_page(2), Err(DiskError::TruncatedPage(2)));
+        assert_eq!(disk_manager.read_page(0).unwrap().0[0], 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let path = temp_db_path("tampering_is_detected");
+        let mut disk_manager =
+            DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32])).unwrap();
+        disk_manager.write_page(0, &page_with(1), None).unwrap();
+
+        let mut content = std::fs::read(&path).unwrap();
+        content[FILE_HEADER_SIZE + 20] ^= 1;
+        std::fs::write(&path, &content).unwrap();
+        assert_eq!(disk_manager.read_page(0), Err(DiskError::CorruptedPage(0)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn swapped_pages_are_detected() {
+        let path = temp_db_path("swapped_pages_are_detected");
+        let mut disk_manager =
+            DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32])).unwrap();
+        disk_manager.write_page(0, &page_with(1), None).unwrap();
+        disk_manager.write_page(1, &page_with(1), None).unwrap();
+
+        let mut content = std::fs::read(&path).unwrap();
+        let pages = &mut content[FILE_HEADER_SIZE..];
+        let physical_page_size = pages.len() / 2;
+        let (first, second) = pages.split_at_mut(physical_page_size);
+        first.swap_with_slice(second);
+        std::fs::write(&path, &content).unwrap();
+        assert_eq!(disk_manager.read_page(0), Err(DiskError::CorruptedPage(0)));
+        assert_eq!(disk_manager.read_page(1), Err(DiskError::CorruptedPage(1)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn files_of_other_formats_are_rejected() {
+        let path = temp_db_path("files_of_other_formats_are_rejected");
+        // An encrypted and an unencrypted file written before pages had a page LSN.
+        let mut old_encrypted = b"DBMS-AES".to_vec();
+        old_encrypted.extend([0; FILE_HEADER_SIZE + PAGE_SIZE]);
+        for content in [old_encrypted, vec![1; PAGE_SIZE]] {
+            std::fs::write(&path, content).unwrap();
+            assert_eq!(
+                DiskManager::new(path.clone()).err(),
+                Some(DiskError::UnsupportedFormat)
+            );
+            assert_eq!(
+                DiskManager::new_encrypted(path.clone(), &EncryptionKey::new([7; 32])).err(),
+                Some(DiskError::UnsupportedFormat)
+            );
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn example_database_file_is_readable() {
+        let mut disk_manager =
+            DiskManager::new("resources/db_save_files/test.mdb".to_string()).unwrap();
+        assert!(disk_manager.get_page_count().unwrap() > 0);
+        disk_manager.read_page(0).unwrap();
+    }
+
+    #[test]
+    fn key_file_formats() {
+        let path = temp_db_path("key_file_formats");
+        std::fs::write(&path, [3; 32]).unwrap();
+        assert_eq!(EncryptionKey::from_key_file(&path).unwrap().key, [3; 32]);
+
+        std::fs::write(&path, format!("{}\n", "0a".repeat(32))).unwrap();
+        assert_eq!(EncryptionKey::from_key_file(&path).unwrap().key, [10; 32]);
+
+        std::fs::write(&path, "0a0b").unwrap();
+        assert!(EncryptionKey::from_key_file(&path).is_err());
+        std::fs::remove_file(path).unwrap();
     }
 }
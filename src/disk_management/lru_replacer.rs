@@ -10,14 +10,9 @@ pub struct LRUReplacer {
 impl LRUReplacer {
     /// Allocates a new LRUReplacer with a given capacity.
     pub fn new(capacity: usize) -> LRUReplacer {
-        return LRUReplacer {
+        LRUReplacer {
             current_pages: PriorityQueue::with_capacity(capacity),
-        };
-    }
-
-    /// Returns the current number of available pages.
-    pub fn current_size(&self) -> usize {
-        return self.current_pages.len();
+        }
     }
 
     /// Adds a page_index to the available page indices and annotates it with the current
@@ -31,12 +26,9 @@ impl LRUReplacer {
     /// Removes the page index from the available list and returns its index.
     /// Returns None if the page index was not present in the list.
     pub fn drop_page(&mut self, page_index: usize) -> Option<usize> {
-        return self
-            .current_pages
-            .remove(&page_index)
-            .and_then(|page| Some(page.0));
+        self.current_pages.remove(&page_index).map(|page| page.0)
     }
-    pub fn drop_all_pages(&mut self){
+    pub fn drop_all_pages(&mut self) {
         self.current_pages.clear();
     }
 
@@ -44,7 +36,7 @@ impl LRUReplacer {
     /// longest.
     /// If there is no page available, [None] is returned.
     pub fn pop_least_recently_used(&mut self) -> Option<usize> {
-        return self.current_pages.pop().and_then(|page| Some(page.0));
+        self.current_pages.pop().map(|page| page.0)
     }
 }
 
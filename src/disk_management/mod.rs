@@ -1,3 +1,6 @@
 pub mod buffer_pool;
-mod lru_replacer;
 pub mod disk_manager;
+mod lru_replacer;
+pub mod page_latch;
+#[cfg(test)]
+pub mod test_utils;
@@ -0,0 +1,117 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+/// A reader-writer latch of a page in the buffer pool. Unlike the lock of its data, which is only
+/// held while the bytes are copied, a latch is held while a thread works with the page, e.g. on
+/// the path from the root of a B+ tree to a leaf. The guards own the latch, so that a thread can
+/// hold the latches of several pages and release them in any order.
+#[derive(Default)]
+pub struct PageLatch {
+    state: Mutex<LatchState>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct LatchState {
+    readers: usize,
+    writer: bool,
+}
+
+impl PageLatch {
+    /// Waits until no thread holds the latch for writing and latches the page for reading.
+    pub fn read(latch: &Arc<PageLatch>) -> PageLatchGuard {
+        let mut state = latch.state.lock().expect("Could not lock the page latch");
+        while state.writer {
+            state = latch
+                .released
+                .wait(state)
+                .expect("Could not lock the page latch");
+        }
+        state.readers += 1;
+        PageLatchGuard {
+            latch: latch.clone(),
+            exclusive: false,
+        }
+    }
+
+    /// Waits until no other thread holds the latch and latches the page for writing.
+    pub fn write(latch: &Arc<PageLatch>) -> PageLatchGuard {
+        let mut state = latch.state.lock().expect("Could not lock the page latch");
+        while state.writer || state.readers > 0 {
+            state = latch
+                .released
+                .wait(state)
+                .expect("Could not lock the page latch");
+        }
+        state.writer = true;
+        PageLatchGuard {
+            latch: latch.clone(),
+            exclusive: true,
+        }
+    }
+}
+
+/// A held latch of a page. The latch is released when the guard is dropped.
+pub struct PageLatchGuard {
+    latch: Arc<PageLatch>,
+    exclusive: bool,
+}
+
+impl PageLatchGuard {
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+}
+
+impl Drop for PageLatchGuard {
+    fn drop(&mut self) {
+        let mut state = self
+            .latch
+            .state
+            .lock()
+            .expect("Could not lock the page latch");
+        match self.exclusive {
+            true => state.writer = false,
+            false => state.readers -= 1,
+        }
+        self.latch.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod page_latch_tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::PageLatch;
+
+    #[test]
+    fn writers_wait_for_readers() {
+        let latch = Arc::new(PageLatch::default());
+        let first = PageLatch::read(&latch);
+        let second = PageLatch::read(&latch);
+        let written = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let latch = latch.clone();
+            let written = written.clone();
+            thread::spawn(move || {
+                let guard = PageLatch::write(&latch);
+                assert!(guard.is_exclusive());
+                written.store(true, Ordering::SeqCst);
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        drop(first);
+        thread::sleep(Duration::from_millis(50));
+        assert!(!written.load(Ordering::SeqCst));
+        drop(second);
+        writer.join().unwrap();
+        assert!(written.load(Ordering::SeqCst));
+        assert!(!PageLatch::read(&latch).is_exclusive());
+    }
+}
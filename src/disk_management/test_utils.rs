@@ -0,0 +1,19 @@
+use std::sync::{Arc, Mutex};
+
+use super::{buffer_pool::BufferPool, disk_manager::DiskManager};
+
+/// Returns a path in the temporary directory that is unique for the given name and the current
+/// process. A file that already exists at that path is removed.
+pub fn temp_db_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("dbms_{}_{}.mdb", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path.to_str().unwrap().to_string()
+}
+
+/// Creates a buffer pool on top of a fresh database file in the temporary directory.
+pub fn new_test_buffer_pool(name: &str) -> Arc<Mutex<BufferPool>> {
+    let disk_manager = DiskManager::new(temp_db_path(name)).unwrap();
+    Arc::new(Mutex::new(BufferPool::new(Arc::new(Mutex::new(
+        disk_manager,
+    )))))
+}
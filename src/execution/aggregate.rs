@@ -0,0 +1,442 @@
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    binder::bound_expression::{AggregateFunction, BoundAggregate, BoundExpression},
+    disk_management::buffer_pool::BufferPool,
+    table::table_page::Rid,
+    types::{row::Row, schema::DataType, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    expression::{compare, evaluate},
+    spill::{row_memory_size, SpillFile},
+};
+
+/// The number of partitions the rows of new groups are split into once the hash table exceeds the
+/// memory limit.
+const PARTITION_COUNT: usize = 16;
+/// The number of times a partition is split again if its groups do not fit into memory either.
+/// Partitions at this depth are aggregated in memory regardless of their size.
+const MAX_PARTITION_DEPTH: u32 = 4;
+
+/// Groups the rows of its child with a hash table and computes the aggregates of every group.
+///
+/// The child rows are reduced to their group values followed by one argument value per
+/// aggregate. Once the hash table exceeds the memory limit, groups already in the table are still
+/// updated, but reduced rows of new groups are written to spill files partitioned by the hash of
+/// their group values. After the groups in the table have been emitted, every partition is
+/// aggregated the same way.
+pub struct HashAggregateExecutor {
+    child: Box<dyn Executor>,
+    group_by: Vec<BoundExpression>,
+    aggregates: Vec<BoundAggregate>,
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    memory_limit: usize,
+    /// The types of the reduced rows.
+    input_types: Vec<DataType>,
+    /// The spilled partitions that still need to be aggregated, with their partition depth.
+    partitions: Vec<(SpillFile, u32)>,
+    output: VecDeque<Row>,
+}
+
+/// The groups of one aggregation pass over the child rows or over a partition.
+struct GroupTable {
+    groups: HashMap<Vec<Value>, Vec<Accumulator>>,
+    memory_size: usize,
+    /// The partitions of the rows of groups that did not fit into memory, or None while all
+    /// groups fit.
+    partitions: Option<Vec<SpillFile>>,
+    depth: u32,
+}
+
+impl GroupTable {
+    fn new(depth: u32) -> GroupTable {
+        GroupTable {
+            groups: HashMap::new(),
+            memory_size: 0,
+            partitions: None,
+            depth,
+        }
+    }
+}
+
+/// The running state of one aggregate in one group.
+struct Accumulator {
+    function: AggregateFunction,
+    data_type: DataType,
+    /// Whether NULL arguments are counted, which is only the case for COUNT(*).
+    counts_nulls: bool,
+    count: i64,
+    /// The sum for SUM and AVG or the current extreme for MIN and MAX. It is NULL until the first
+    /// argument has been added.
+    value: Value,
+    /// The arguments added so far, for DISTINCT aggregates.
+    distinct_values: Option<HashSet<Value>>,
+}
+
+impl Accumulator {
+    fn new(aggregate: &BoundAggregate) -> Accumulator {
+        Accumulator {
+            function: aggregate.function,
+            data_type: aggregate.data_type,
+            counts_nulls: aggregate.argument.is_none(),
+            count: 0,
+            value: Value::Null,
+            distinct_values: aggregate.distinct.then(HashSet::new),
+        }
+    }
+
+    /// Adds an argument to the aggregate. Returns the number of bytes the accumulator has grown.
+    fn update(&mut self, argument: &Value) -> Result<usize, ExecutionError> {
+        if argument.is_null() && !self.counts_nulls {
+            return Ok(0);
+        }
+        let mut growth = 0;
+        if let Some(distinct_values) = &mut self.distinct_values {
+            if !distinct_values.insert(argument.clone()) {
+                return Ok(0);
+            }
+            growth = row_memory_size(&Row::new(vec![argument.clone()]));
+        }
+        self.count += 1;
+        match self.function {
+            AggregateFunction::Count => {}
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                let argument = argument
+                    .cast_to(&self.data_type)
+                    .map_err(ExecutionError::InvalidValue)?;
+                self.value = match (&self.value, argument) {
+                    (Value::Null, argument) => argument,
+                    (Value::BigInt(sum), Value::BigInt(argument)) => {
+                        Value::BigInt(sum.checked_add(argument).ok_or_else(|| {
+                            ExecutionError::InvalidValue("numeric value out of range".to_string())
+                        })?)
+                    }
+                    (Value::Float(sum), Value::Float(argument)) => Value::Float(sum + argument),
+                    _ => unreachable!("The sum has an unexpected type"),
+                };
+            }
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let replaces = match compare(argument, &self.value) {
+                    _ if self.value.is_null() => true,
+                    Ordering::Less => self.function == AggregateFunction::Min,
+                    Ordering::Greater => self.function == AggregateFunction::Max,
+                    Ordering::Equal => false,
+                };
+                if replaces {
+                    self.value = argument.clone();
+                }
+            }
+        }
+        Ok(growth)
+    }
+
+    fn finish(self) -> Value {
+        match (self.function, self.value) {
+            (AggregateFunction::Count, _) => Value::BigInt(self.count),
+            (AggregateFunction::Avg, Value::Float(sum)) => Value::Float(sum / self.count as f64),
+            (_, value) => value,
+        }
+    }
+}
+
+impl HashAggregateExecutor {
+    pub fn new(
+        child: Box<dyn Executor>,
+        group_by: Vec<BoundExpression>,
+        aggregates: Vec<BoundAggregate>,
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        memory_limit: usize,
+    ) -> HashAggregateExecutor {
+        let input_types = group_by
+            .iter()
+            .map(BoundExpression::data_type)
+            .chain(aggregates.iter().map(|aggregate| {
+                aggregate
+                    .argument
+                    .as_ref()
+                    .map_or(DataType::Boolean, BoundExpression::data_type)
+            }))
+            .collect();
+        HashAggregateExecutor {
+            child,
+            group_by,
+            aggregates,
+            buffer_pool,
+            memory_limit,
+            input_types,
+            partitions: Vec::new(),
+            output: VecDeque::new(),
+        }
+    }
+
+    /// Reduces a child row to its group values followed by the arguments of the aggregates.
+    /// COUNT(*) has a NULL argument. Negative zero is replaced by zero, so that equal floats fall
+    /// into the same group.
+    fn reduce(&self, row: &Row) -> Result<Row, ExecutionError> {
+        let mut values = Vec::with_capacity(self.input_types.len());
+        for expression in &self.group_by {
+            values.push(evaluate(expression, &row.values)?);
+        }
+        for aggregate in &self.aggregates {
+            values.push(match &aggregate.argument {
+                Some(argument) => evaluate(argument, &row.values)?,
+                None => Value::Null,
+            });
+        }
+        for value in &mut values {
+            if matches!(value, Value::Float(0.0)) {
+                *value = Value::Float(0.0);
+            }
+        }
+        Ok(Row::new(values))
+    }
+
+    /// Adds a reduced row to its group, or spills it if the group is not in the table and the
+    /// table is full.
+    fn accumulate(&self, table: &mut GroupTable, row: Row) -> Result<(), ExecutionError> {
+        let group_count = self.group_by.len();
+        let (group, arguments) = row.values.split_at(group_count);
+        if let Some(accumulators) = table.groups.get_mut(group) {
+            for (accumulator, argument) in accumulators.iter_mut().zip(arguments) {
+                table.memory_size += accumulator.update(argument)?;
+            }
+            return Ok(());
+        }
+
+        if table.memory_size > self.memory_limit && table.depth < MAX_PARTITION_DEPTH {
+            let partitions = table.partitions.get_or_insert_with(|| {
+                (0..PARTITION_COUNT)
+                    .map(|_| SpillFile::new(&self.buffer_pool, &self.input_types))
+                    .collect()
+            });
+            partitions[partition_of(group, table.depth)].append(&row)?;
+            return Ok(());
+        }
+
+        let mut accumulators: Vec<Accumulator> =
+            self.aggregates.iter().map(Accumulator::new).collect();
+        table.memory_size +=
+            row_memory_size(&row) + accumulators.len() * std::mem::size_of::<Accumulator>();
+        for (accumulator, argument) in accumulators.iter_mut().zip(arguments) {
+            table.memory_size += accumulator.update(argument)?;
+        }
+        let mut group = row.values;
+        group.truncate(group_count);
+        table.groups.insert(group, accumulators);
+        Ok(())
+    }
+
+    /// Queues the rows of the groups in the table and remembers its partitions.
+    fn finish(&mut self, table: GroupTable) {
+        for (mut values, accumulators) in table.groups {
+            values.extend(accumulators.into_iter().map(Accumulator::finish));
+            self.output.push_back(Row::new(values));
+        }
+        for partition in table.partitions.into_iter().flatten() {
+            if !partition.is_empty() {
+                self.partitions.push((partition, table.depth + 1));
+            }
+        }
+    }
+}
+
+/// Returns the partition of a group. The depth is part of the hash, so that the groups of a
+/// partition are spread over all partitions when it is split again.
+fn partition_of(group: &[Value], depth: u32) -> usize {
+    let mut hasher = DefaultHasher::new();
+    depth.hash(&mut hasher);
+    group.hash(&mut hasher);
+    hasher.finish() as usize % PARTITION_COUNT
+}
+
+impl Executor for HashAggregateExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.partitions.clear();
+        self.output.clear();
+        self.child.init()?;
+
+        let mut table = GroupTable::new(0);
+        // Without GROUP BY, there is exactly one group, even if the child has no rows.
+        if self.group_by.is_empty() {
+            let accumulators = self.aggregates.iter().map(Accumulator::new).collect();
+            table.groups.insert(Vec::new(), accumulators);
+        }
+        while let Some((row, _)) = self.child.next()? {
+            let row = self.reduce(&row)?;
+            self.accumulate(&mut table, row)?;
+        }
+        self.finish(table);
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        loop {
+            if let Some(row) = self.output.pop_front() {
+                return Ok(Some((row, None)));
+            }
+            let (partition, depth) = match self.partitions.pop() {
+                Some(partition) => partition,
+                None => return Ok(None),
+            };
+            let mut table = GroupTable::new(depth);
+            for row in partition.iter() {
+                self.accumulate(&mut table, row?)?;
+            }
+            self.finish(table);
+        }
+    }
+}
+
+#[cfg(test)]
+mod aggregate_tests {
+    use crate::{
+        binder::bound_expression::{AggregateFunction, BoundAggregate, BoundExpression},
+        disk_management::test_utils::new_test_buffer_pool,
+        execution::{
+            executor::{collect_rows, Executor},
+            values::ValuesExecutor,
+        },
+        sql::ast::BinaryOperator,
+        types::{schema::DataType, value::Value},
+    };
+
+    use super::HashAggregateExecutor;
+
+    fn constant(value: Value, data_type: DataType) -> BoundExpression {
+        BoundExpression::Constant { value, data_type }
+    }
+
+    fn column(index: usize, data_type: DataType) -> BoundExpression {
+        BoundExpression::Column { index, data_type }
+    }
+
+    fn aggregate(
+        function: AggregateFunction,
+        argument: Option<BoundExpression>,
+        distinct: bool,
+        data_type: DataType,
+    ) -> BoundAggregate {
+        BoundAggregate {
+            function,
+            argument,
+            distinct,
+            data_type,
+        }
+    }
+
+    #[test]
+    fn groups_spill_when_they_exceed_the_memory_limit() {
+        let buffer_pool = new_test_buffer_pool("aggregate_spill");
+        // 2000 groups of five rows each, with the values 0, 1, 2, 3 and NULL.
+        let rows = (0..10_000)
+            .map(|i| {
+                let value = match i % 5 {
+                    4 => Value::Null,
+                    value => Value::Integer(value),
+                };
+                vec![
+                    constant(Value::Integer(i / 5), DataType::Integer),
+                    constant(value, DataType::Integer),
+                ]
+            })
+            .collect();
+        let aggregates = vec![
+            aggregate(AggregateFunction::Count, None, false, DataType::BigInt),
+            aggregate(
+                AggregateFunction::Count,
+                Some(column(1, DataType::Integer)),
+                false,
+                DataType::BigInt,
+            ),
+            aggregate(
+                AggregateFunction::Sum,
+                Some(column(1, DataType::Integer)),
+                false,
+                DataType::BigInt,
+            ),
+            aggregate(
+                AggregateFunction::Avg,
+                Some(column(1, DataType::Integer)),
+                false,
+                DataType::Float,
+            ),
+            aggregate(
+                AggregateFunction::Max,
+                Some(column(1, DataType::Integer)),
+                false,
+                DataType::Integer,
+            ),
+            aggregate(
+                AggregateFunction::Count,
+                Some(BoundExpression::Binary {
+                    left: Box::new(column(1, DataType::Integer)),
+                    operator: BinaryOperator::Modulo,
+                    right: Box::new(constant(Value::Integer(2), DataType::Integer)),
+                    data_type: DataType::Integer,
+                }),
+                true,
+                DataType::BigInt,
+            ),
+        ];
+        let mut executor = HashAggregateExecutor::new(
+            Box::new(ValuesExecutor::new(rows)),
+            vec![column(0, DataType::Integer)],
+            aggregates,
+            buffer_pool,
+            16 * 1024,
+        );
+
+        executor.init().unwrap();
+        assert!(!executor.partitions.is_empty());
+        let mut rows = Vec::new();
+        while let Some((row, _)) = executor.next().unwrap() {
+            rows.push(row);
+        }
+        rows.sort_by(|left, right| left.values[0].cmp(&right.values[0]));
+        assert_eq!(rows.len(), 2000);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(
+                row.values,
+                vec![
+                    Value::Integer(i as i32),
+                    Value::BigInt(5),
+                    Value::BigInt(4),
+                    Value::BigInt(6),
+                    Value::Float(1.5),
+                    Value::Integer(3),
+                    Value::BigInt(2),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn single_group_without_rows() {
+        let buffer_pool = new_test_buffer_pool("aggregate_empty");
+        let mut executor = HashAggregateExecutor::new(
+            Box::new(ValuesExecutor::new(Vec::new())),
+            Vec::new(),
+            vec![
+                aggregate(AggregateFunction::Count, None, false, DataType::BigInt),
+                aggregate(
+                    AggregateFunction::Min,
+                    Some(column(0, DataType::Integer)),
+                    false,
+                    DataType::Integer,
+                ),
+            ],
+            buffer_pool,
+            1024,
+        );
+        let rows = collect_rows(&mut executor).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, vec![Value::BigInt(0), Value::Null]);
+    }
+}
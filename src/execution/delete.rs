@@ -0,0 +1,45 @@
+use crate::{table::table_page::Rid, types::row::Row};
+
+use super::executor::{count_row, ExecutionError, Executor, TableModifier};
+
+/// Deletes the rows produced by its child, which must read them from the table, and produces a
+/// single row with the number of deleted rows.
+pub struct DeleteExecutor {
+    child: Box<dyn Executor>,
+    table: TableModifier,
+    done: bool,
+}
+
+impl DeleteExecutor {
+    pub fn new(child: Box<dyn Executor>, table: TableModifier) -> DeleteExecutor {
+        DeleteExecutor {
+            child,
+            table,
+            done: false,
+        }
+    }
+}
+
+impl Executor for DeleteExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.done = false;
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        // Collect the rows first, the scan must not observe its own deletions.
+        let mut rows = Vec::new();
+        while let Some((row, rid)) = self.child.next()? {
+            let rid = rid.ok_or(ExecutionError::Storage("The deleted row has no Rid"))?;
+            rows.push((row, rid));
+        }
+        for (row, rid) in &rows {
+            self.table.delete(*rid, row)?;
+        }
+        Ok(Some((count_row(rows.len() as u64), None)))
+    }
+}
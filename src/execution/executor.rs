@@ -0,0 +1,834 @@
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    catalog::system_catalog::{CatalogError, IndexInfo, TableInfo},
+    disk_management::buffer_pool::{BufferPool, PAGE_SIZE},
+    index::table_index::{index_key, Index},
+    planner::plan::PlanNode,
+    recovery::log_record::TransactionId,
+    table::{
+        table_heap::TableHeap,
+        table_page::{Rid, TupleVersion},
+    },
+    transaction::{
+        lock_manager::{LockError, LockManager, LockMode},
+        transaction_state::{Transaction, WriteRecord},
+    },
+    types::{row::Row, schema::Schema, value::Value},
+};
+
+use super::{
+    aggregate::HashAggregateExecutor, delete::DeleteExecutor, expression::compare,
+    filter::FilterExecutor, hash_join::HashJoinExecutor,
+    index_nested_loop_join::IndexNestedLoopJoinExecutor, index_range_scan::IndexRangeScanExecutor,
+    index_scan::IndexScanExecutor, insert::InsertExecutor, limit::LimitExecutor,
+    nested_loop_join::NestedLoopJoinExecutor, projection::ProjectionExecutor,
+    seq_scan::SeqScanExecutor, sort::SortExecutor, sort_merge_join::SortMergeJoinExecutor,
+    update::UpdateExecutor, values::ValuesExecutor,
+};
+
+/// The memory an executor may use for the rows it holds before it spills them to temporary
+/// pages.
+pub const WORK_MEMORY: usize = 64 * PAGE_SIZE;
+
+#[derive(Debug, PartialEq)]
+pub enum ExecutionError {
+    Storage(&'static str),
+    Catalog(CatalogError),
+    InvalidValue(String),
+    NotNullViolation(String),
+    UniqueViolation(String),
+    Unsupported(String),
+    /// The statement does not fit the state of the transaction, e.g. COMMIT without a
+    /// transaction.
+    InvalidTransactionState(&'static str),
+    Lock(LockError),
+    /// A concurrent transaction committed a change the transaction conflicts with.
+    SerializationFailure,
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionError::Storage(message) | ExecutionError::InvalidTransactionState(message) => {
+                write!(f, "{}", message)
+            }
+            ExecutionError::Catalog(error) => write!(f, "{}", error),
+            ExecutionError::Lock(error) => write!(f, "{}", error),
+            ExecutionError::SerializationFailure => {
+                write!(f, "could not serialize access due to a concurrent update")
+            }
+            ExecutionError::NotNullViolation(name) => {
+                write!(f, "column {} must not be NULL", name)
+            }
+            ExecutionError::UniqueViolation(name) => {
+                write!(f, "duplicate key violates unique index {}", name)
+            }
+            ExecutionError::InvalidValue(message) | ExecutionError::Unsupported(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl From<CatalogError> for ExecutionError {
+    fn from(error: CatalogError) -> Self {
+        ExecutionError::Catalog(error)
+    }
+}
+
+impl From<LockError> for ExecutionError {
+    fn from(error: LockError) -> Self {
+        ExecutionError::Lock(error)
+    }
+}
+
+/// An operator of a query plan. Executors form a tree and pull rows from their children one at a
+/// time: `init` prepares the executor and its children, every call to `next` produces the next
+/// row until None is returned.
+///
+/// Rows read from a table heap are returned together with their Rid, so that executors above
+/// them can modify the tuples. Operators that compute new rows return no Rid.
+pub trait Executor {
+    fn init(&mut self) -> Result<(), ExecutionError>;
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError>;
+}
+
+/// What the executors of a plan run with.
+#[derive(Clone)]
+pub struct ExecutionContext {
+    pub buffer_pool: Arc<Mutex<BufferPool>>,
+    /// The transaction the plan runs in. The executors record their changes of rows in its
+    /// write set.
+    pub transaction: Option<Arc<Transaction>>,
+    /// Grants the locks the executors take for the transaction.
+    pub lock_manager: Option<Arc<LockManager>>,
+}
+
+impl ExecutionContext {
+    /// Creates a context without a transaction, whose changes cannot be rolled back and which
+    /// takes no locks.
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>) -> ExecutionContext {
+        ExecutionContext {
+            buffer_pool,
+            transaction: None,
+            lock_manager: None,
+        }
+    }
+
+    pub fn with_transaction(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Arc<Transaction>,
+        lock_manager: Arc<LockManager>,
+    ) -> ExecutionContext {
+        ExecutionContext {
+            buffer_pool,
+            transaction: Some(transaction),
+            lock_manager: Some(lock_manager),
+        }
+    }
+
+    /// Locks the table for the transaction and returns the locks of its rows.
+    pub fn lock_table(&self, table_id: u32, mode: LockMode) -> Result<RowLocks, ExecutionError> {
+        let owner = match (&self.lock_manager, &self.transaction) {
+            (Some(lock_manager), Some(transaction)) => {
+                lock_manager.lock_table(transaction.id(), table_id, mode)?;
+                Some((lock_manager.clone(), transaction.id()))
+            }
+            _ => None,
+        };
+        Ok(RowLocks { owner, table_id })
+    }
+
+    /// Locks the table for reading and returns which versions of its rows the transaction sees.
+    /// Reads take no locks on rows: they never wait for writers.
+    pub fn read_table(&self, table_id: u32) -> Result<Visibility, ExecutionError> {
+        self.lock_table(table_id, LockMode::IntentionShared)?;
+        if let Some(transaction) = &self.transaction {
+            transaction.record_read(table_id);
+        }
+        Ok(Visibility {
+            transaction: self.transaction.clone(),
+        })
+    }
+}
+
+/// Decides which versions of rows a scan returns: those in the snapshot of the transaction, or
+/// the current versions if the plan runs without a transaction.
+#[derive(Clone)]
+pub struct Visibility {
+    transaction: Option<Arc<Transaction>>,
+}
+
+impl Visibility {
+    pub fn is_visible(&self, version: TupleVersion) -> bool {
+        match &self.transaction {
+            Some(transaction) => transaction.snapshot().is_visible(version),
+            None => version.deleted_by.is_none(),
+        }
+    }
+
+    /// Returns true if the previous version of a row may be visible instead of the given one,
+    /// because the transaction that created the given version is not visible.
+    pub fn may_see_previous(&self, version: TupleVersion) -> bool {
+        match &self.transaction {
+            Some(transaction) => !transaction.snapshot().sees_creation(version),
+            None => false,
+        }
+    }
+}
+
+/// Locks rows of a table for the transaction that locked the table, or does nothing if the plan
+/// runs without a transaction.
+#[derive(Clone)]
+pub struct RowLocks {
+    owner: Option<(Arc<LockManager>, TransactionId)>,
+    table_id: u32,
+}
+
+impl RowLocks {
+    pub fn lock(&self, rid: Rid, mode: LockMode) -> Result<(), ExecutionError> {
+        if let Some((lock_manager, transaction)) = &self.owner {
+            lock_manager.lock_row(*transaction, self.table_id, rid, mode)?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates the executor tree of a physical plan.
+pub fn create_executor(
+    plan: &PlanNode,
+    context: &ExecutionContext,
+) -> Result<Box<dyn Executor>, ExecutionError> {
+    create_wrapped_executor(plan, context, &mut |executor| executor)
+}
+
+/// Creates the executor tree of a physical plan and replaces every executor by the result of
+/// `wrap`. Executors are wrapped after their inputs, in the order of [PlanNode::inputs].
+pub fn create_wrapped_executor(
+    plan: &PlanNode,
+    context: &ExecutionContext,
+    wrap: &mut dyn FnMut(Box<dyn Executor>) -> Box<dyn Executor>,
+) -> Result<Box<dyn Executor>, ExecutionError> {
+    let buffer_pool = &context.buffer_pool;
+    let executor: Box<dyn Executor> = match plan {
+        PlanNode::SeqScan { table } => Box::new(SeqScanExecutor::new(
+            TableHeap::new(buffer_pool.clone(), table.first_directory_page_id),
+            table.schema.clone(),
+            context.read_table(table.table_id)?,
+        )),
+        PlanNode::IndexScan { table, index, key } => Box::new(IndexScanExecutor::new(
+            TableHeap::new(buffer_pool.clone(), table.first_directory_page_id),
+            table.schema.clone(),
+            TableIndex::open(buffer_pool, index)?,
+            key.clone(),
+            context.read_table(table.table_id)?,
+        )),
+        PlanNode::IndexRangeScan {
+            table,
+            index,
+            lower,
+            upper,
+        } => Box::new(IndexRangeScanExecutor::new(
+            TableHeap::new(buffer_pool.clone(), table.first_directory_page_id),
+            table.schema.clone(),
+            TableIndex::open(buffer_pool, index)?,
+            lower.clone(),
+            upper.clone(),
+            context.read_table(table.table_id)?,
+        )),
+        PlanNode::Values { rows } => Box::new(ValuesExecutor::new(rows.clone())),
+        PlanNode::Filter { input, predicate } => Box::new(FilterExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            predicate.clone(),
+        )),
+        PlanNode::Projection { input, expressions } => Box::new(ProjectionExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            expressions.clone(),
+        )),
+        PlanNode::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => Box::new(HashAggregateExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            group_by.clone(),
+            aggregates.clone(),
+            buffer_pool.clone(),
+            WORK_MEMORY,
+        )),
+        PlanNode::Sort {
+            input,
+            order_by,
+            limit,
+        } => Box::new(SortExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            order_by.clone(),
+            *limit,
+            input.output_types(),
+            buffer_pool.clone(),
+            WORK_MEMORY,
+        )),
+        PlanNode::Limit {
+            input,
+            limit,
+            offset,
+        } => Box::new(LimitExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            *limit,
+            *offset,
+        )),
+        PlanNode::NestedLoopJoin {
+            left,
+            right,
+            join_type,
+            condition,
+        } => Box::new(NestedLoopJoinExecutor::new(
+            create_wrapped_executor(left, context, wrap)?,
+            create_wrapped_executor(right, context, wrap)?,
+            *join_type,
+            condition.clone(),
+            left.output_types().len(),
+            right.output_types().len(),
+        )),
+        PlanNode::IndexNestedLoopJoin {
+            left,
+            table,
+            index,
+            join_type,
+            left_keys,
+            condition,
+        } => Box::new(IndexNestedLoopJoinExecutor::new(
+            create_wrapped_executor(left, context, wrap)?,
+            TableHeap::new(buffer_pool.clone(), table.first_directory_page_id),
+            table.schema.clone(),
+            TableIndex::open(buffer_pool, index)?,
+            context.read_table(table.table_id)?,
+            *join_type,
+            left_keys.clone(),
+            condition.clone(),
+        )?),
+        PlanNode::HashJoin {
+            left,
+            right,
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+        } => Box::new(HashJoinExecutor::new(
+            create_wrapped_executor(left, context, wrap)?,
+            create_wrapped_executor(right, context, wrap)?,
+            *join_type,
+            left_keys.clone(),
+            right_keys.clone(),
+            condition.clone(),
+            left.output_types(),
+            right.output_types(),
+            buffer_pool.clone(),
+            WORK_MEMORY,
+        )),
+        PlanNode::SortMergeJoin {
+            left,
+            right,
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+        } => Box::new(SortMergeJoinExecutor::new(
+            create_wrapped_executor(left, context, wrap)?,
+            create_wrapped_executor(right, context, wrap)?,
+            *join_type,
+            left_keys.clone(),
+            right_keys.clone(),
+            condition.clone(),
+            left.output_types().len(),
+            right.output_types().len(),
+        )),
+        PlanNode::Insert {
+            table,
+            indexes,
+            input,
+        } => Box::new(InsertExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            TableModifier::new(context, table, indexes)?,
+        )),
+        PlanNode::Update {
+            table,
+            indexes,
+            input,
+            assignments,
+        } => Box::new(UpdateExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            TableModifier::new(context, table, indexes)?,
+            assignments.clone(),
+        )),
+        PlanNode::Delete {
+            table,
+            indexes,
+            input,
+        } => Box::new(DeleteExecutor::new(
+            create_wrapped_executor(input, context, wrap)?,
+            TableModifier::new(context, table, indexes)?,
+        )),
+    };
+    Ok(wrap(executor))
+}
+
+/// Runs an executor to completion and returns all of its rows.
+pub fn collect_rows(executor: &mut dyn Executor) -> Result<Vec<Row>, ExecutionError> {
+    executor.init()?;
+    let mut rows = Vec::new();
+    while let Some((row, _)) = executor.next()? {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// An opened index together with its catalog entry.
+pub struct TableIndex {
+    pub info: IndexInfo,
+    pub index: Index,
+}
+
+impl TableIndex {
+    pub fn open(
+        buffer_pool: &Arc<Mutex<BufferPool>>,
+        info: &IndexInfo,
+    ) -> Result<TableIndex, ExecutionError> {
+        Ok(TableIndex {
+            info: info.clone(),
+            index: Index::open(buffer_pool.clone(), info).map_err(ExecutionError::Storage)?,
+        })
+    }
+
+    /// Returns the Rids and the rows of the visible versions of the rows with the key.
+    ///
+    /// An index entry points at the newest version of a row with its key, as an update that
+    /// keeps the key moves the entry to the new version. Older versions with the key are reached
+    /// through the previous versions, as long as the creation of a newer version is not visible.
+    pub fn lookup(
+        &self,
+        table_heap: &TableHeap,
+        schema: &Schema,
+        visibility: &Visibility,
+        key: &[Value],
+    ) -> Result<Vec<(Rid, Row)>, ExecutionError> {
+        self.visible_rows(
+            table_heap,
+            schema,
+            visibility,
+            self.index.scan_key(key),
+            |row| index_key(&self.info, &row.values) == key,
+        )
+    }
+
+    /// Returns the Rids and the rows of the visible versions of the rows whose first key column
+    /// lies between the bounds, like [TableIndex::lookup]. The index must be a B+ tree index.
+    pub fn lookup_range(
+        &self,
+        table_heap: &TableHeap,
+        schema: &Schema,
+        visibility: &Visibility,
+        lower: Option<(&Value, bool)>,
+        upper: Option<(&Value, bool)>,
+    ) -> Result<Vec<(Rid, Row)>, ExecutionError> {
+        let rids = self
+            .index
+            .scan_range(lower.map(|(value, _)| value), upper.map(|(value, _)| value))
+            .map_err(ExecutionError::Storage)?;
+        let column = self.info.key_columns[0];
+        self.visible_rows(table_heap, schema, visibility, rids, |row| {
+            let value = &row.values[column];
+            !value.is_null()
+                && lower.is_none_or(|(lower, inclusive)| match compare(value, lower) {
+                    Ordering::Greater => true,
+                    Ordering::Equal => inclusive,
+                    Ordering::Less => false,
+                })
+                && upper.is_none_or(|(upper, inclusive)| match compare(value, upper) {
+                    Ordering::Less => true,
+                    Ordering::Equal => inclusive,
+                    Ordering::Greater => false,
+                })
+        })
+    }
+
+    /// Follows the index entries to the visible versions of the rows whose key matches.
+    fn visible_rows(
+        &self,
+        table_heap: &TableHeap,
+        schema: &Schema,
+        visibility: &Visibility,
+        rids: Vec<Rid>,
+        matches_key: impl Fn(&Row) -> bool,
+    ) -> Result<Vec<(Rid, Row)>, ExecutionError> {
+        let mut rows = Vec::new();
+        let mut found = HashSet::new();
+        for mut rid in rids {
+            while let Some(tuple) = table_heap.get(rid).map_err(ExecutionError::Storage)? {
+                let row =
+                    Row::from_tuple_data(&tuple.data, schema).map_err(ExecutionError::Storage)?;
+                // Different keys can share an index entry and older versions of a row can have
+                // another key.
+                if !matches_key(&row) {
+                    break;
+                }
+                if visibility.is_visible(tuple.version) {
+                    // While an entry is moved to a new version, both entries lead to the same
+                    // visible version.
+                    if found.insert(rid) {
+                        rows.push((rid, row));
+                    }
+                    break;
+                }
+                match tuple.version.previous {
+                    Some(previous) if visibility.may_see_previous(tuple.version) => rid = previous,
+                    _ => break,
+                }
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Writes rows into a table heap and keeps all indexes of the table up to date. Used by the
+/// executors of INSERT, UPDATE and DELETE statements. The table is locked with an IX lock and
+/// every changed row with an X lock.
+///
+/// In a transaction, rows are versioned: an update inserts a new version of the row that links
+/// to the one it replaced, and a delete only marks the version as deleted by the transaction, so
+/// that concurrent transactions keep reading the versions in their snapshots. The index entries
+/// of keys an update keeps are moved to the new version, while those of changed keys stay with
+/// the old one, so a row has as many entries as keys it had rather than as versions. A
+/// transaction that changes a version which a concurrent transaction already replaced fails
+/// with a serialization failure: the first committer wins.
+pub struct TableModifier {
+    pub table: TableInfo,
+    pub table_heap: TableHeap,
+    pub indexes: Vec<TableIndex>,
+    /// The transaction whose write set records the changes.
+    transaction: Option<Arc<Transaction>>,
+    /// The transaction the changes of pages are logged for.
+    logged_transaction: Option<TransactionId>,
+    row_locks: RowLocks,
+}
+
+impl TableModifier {
+    pub fn new(
+        context: &ExecutionContext,
+        table: &TableInfo,
+        indexes: &[IndexInfo],
+    ) -> Result<TableModifier, ExecutionError> {
+        let buffer_pool = &context.buffer_pool;
+        Ok(TableModifier {
+            table: table.clone(),
+            table_heap: TableHeap::new(buffer_pool.clone(), table.first_directory_page_id),
+            indexes: indexes
+                .iter()
+                .map(|index| TableIndex::open(buffer_pool, index))
+                .collect::<Result<_, _>>()?,
+            transaction: context.transaction.clone(),
+            logged_transaction: context
+                .transaction
+                .as_ref()
+                .map(|transaction| transaction.id()),
+            row_locks: context.lock_table(table.table_id, LockMode::IntentionExclusive)?,
+        })
+    }
+
+    fn record_write(&self, record: WriteRecord) {
+        if let Some(transaction) = &self.transaction {
+            transaction.record_write(record);
+        }
+    }
+
+    pub fn insert(&self, row: &Row) -> Result<Rid, ExecutionError> {
+        self.check_row(row, None)?;
+        let rid = self.insert_version(row, None)?;
+        self.record_write(WriteRecord::Insert {
+            table_id: self.table.table_id,
+            rid,
+            row: row.clone(),
+        });
+        Ok(rid)
+    }
+
+    /// Inserts a version of a row created by the transaction, which replaces the previous version
+    /// if there is one, locks it and adds it to all indexes.
+    fn insert_version(&self, row: &Row, previous: Option<Rid>) -> Result<Rid, ExecutionError> {
+        let tuple_data = row
+            .to_tuple_data(&self.table.schema)
+            .map_err(ExecutionError::Storage)?;
+        let version = TupleVersion {
+            created_by: self
+                .transaction
+                .as_ref()
+                .map(|transaction| transaction.id()),
+            deleted_by: None,
+            previous,
+        };
+        let rid = self
+            .table_heap
+            .insert_version(tuple_data, version, self.logged_transaction)
+            .map_err(ExecutionError::Storage)?;
+        if let Err(error) = self.row_locks.lock(rid, LockMode::Exclusive) {
+            self.table_heap.delete(rid, self.logged_transaction);
+            return Err(error);
+        }
+        for (position, table_index) in self.indexes.iter().enumerate() {
+            let key = index_key(&table_index.info, &row.values);
+            if let Err(error) = table_index
+                .index
+                .insert_entry(&key, rid, self.logged_transaction)
+            {
+                for inserted_index in &self.indexes[..position] {
+                    let key = index_key(&inserted_index.info, &row.values);
+                    inserted_index
+                        .index
+                        .delete_entry(&key, rid, self.logged_transaction);
+                }
+                self.table_heap.delete(rid, self.logged_transaction);
+                return Err(ExecutionError::Storage(error));
+            }
+        }
+        Ok(rid)
+    }
+
+    /// Locks the version at the Rid for a change by the transaction and returns it. Fails if a
+    /// concurrent transaction deleted or replaced the version.
+    fn lock_version(
+        &self,
+        rid: Rid,
+        transaction: &Transaction,
+    ) -> Result<TupleVersion, ExecutionError> {
+        self.row_locks.lock(rid, LockMode::Exclusive)?;
+        let version = self
+            .table_heap
+            .get(rid)
+            .map_err(ExecutionError::Storage)?
+            .ok_or(ExecutionError::Storage("The tuple does not exist"))?
+            .version;
+        match version.deleted_by {
+            None => Ok(version),
+            Some(deleted_by) if deleted_by == transaction.id() => {
+                Err(ExecutionError::Storage("The tuple does not exist"))
+            }
+            Some(_) => Err(ExecutionError::SerializationFailure),
+        }
+    }
+
+    /// Replaces the tuple at the Rid, whose current values are `old_row`, with the new row and
+    /// returns the Rid of the new row. In a transaction, the new row is a new version at another
+    /// Rid.
+    pub fn update(&self, rid: Rid, old_row: &Row, new_row: &Row) -> Result<Rid, ExecutionError> {
+        let Some(transaction) = &self.transaction else {
+            return self.update_in_place(rid, old_row, new_row).map(|_| rid);
+        };
+        let version = self.lock_version(rid, transaction)?;
+        self.check_row(new_row, Some(rid))?;
+        let new_rid = self.insert_version(new_row, Some(rid))?;
+        self.table_heap
+            .set_version(
+                rid,
+                TupleVersion {
+                    deleted_by: Some(transaction.id()),
+                    ..version
+                },
+                self.logged_transaction,
+            )
+            .map_err(ExecutionError::Storage)?;
+        // The old version is reached from the new one through the entries of the kept keys.
+        for table_index in &self.indexes {
+            let key = index_key(&table_index.info, &old_row.values);
+            if key == index_key(&table_index.info, &new_row.values) {
+                table_index
+                    .index
+                    .delete_entry(&key, rid, self.logged_transaction);
+            }
+        }
+        self.record_write(WriteRecord::Update {
+            table_id: self.table.table_id,
+            rid,
+            new_rid,
+            old_row: old_row.clone(),
+            new_row: new_row.clone(),
+        });
+        Ok(new_rid)
+    }
+
+    fn update_in_place(
+        &self,
+        rid: Rid,
+        old_row: &Row,
+        new_row: &Row,
+    ) -> Result<(), ExecutionError> {
+        self.check_row(new_row, Some(rid))?;
+        let tuple_data = new_row
+            .to_tuple_data(&self.table.schema)
+            .map_err(ExecutionError::Storage)?;
+        self.table_heap
+            .update(rid, tuple_data, self.logged_transaction)
+            .map_err(ExecutionError::Storage)?;
+        for table_index in &self.indexes {
+            let old_key = index_key(&table_index.info, &old_row.values);
+            let new_key = index_key(&table_index.info, &new_row.values);
+            if old_key != new_key {
+                table_index
+                    .index
+                    .delete_entry(&old_key, rid, self.logged_transaction);
+                table_index
+                    .index
+                    .insert_entry(&new_key, rid, self.logged_transaction)
+                    .map_err(ExecutionError::Storage)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the tuple at the Rid, whose current values are `row`. In a transaction, the tuple
+    /// is only marked as deleted.
+    pub fn delete(&self, rid: Rid, row: &Row) -> Result<(), ExecutionError> {
+        let Some(transaction) = &self.transaction else {
+            return self.remove_version(rid, row);
+        };
+        let version = self.lock_version(rid, transaction)?;
+        self.table_heap
+            .set_version(
+                rid,
+                TupleVersion {
+                    deleted_by: Some(transaction.id()),
+                    ..version
+                },
+                self.logged_transaction,
+            )
+            .map_err(ExecutionError::Storage)?;
+        self.record_write(WriteRecord::Delete {
+            table_id: self.table.table_id,
+            rid,
+            row: row.clone(),
+        });
+        Ok(())
+    }
+
+    /// Removes the version at the Rid, whose values are `row`, from the table heap and from all
+    /// indexes.
+    pub fn remove_version(&self, rid: Rid, row: &Row) -> Result<(), ExecutionError> {
+        self.table_heap
+            .delete(rid, self.logged_transaction)
+            .ok_or(ExecutionError::Storage("The tuple does not exist"))?;
+        for table_index in &self.indexes {
+            let key = index_key(&table_index.info, &row.values);
+            table_index
+                .index
+                .delete_entry(&key, rid, self.logged_transaction);
+        }
+        Ok(())
+    }
+
+    /// Undoes the update of the version at the Rid, whose values are `old_row`, by the version at
+    /// `new_rid` with the values `new_row`: the old version becomes current again, gets back the
+    /// entries of the keys the update kept, and the new version is removed.
+    pub fn undo_update(
+        &self,
+        rid: Rid,
+        old_row: &Row,
+        new_rid: Rid,
+        new_row: &Row,
+    ) -> Result<(), ExecutionError> {
+        self.restore_version(rid)?;
+        for table_index in &self.indexes {
+            let key = index_key(&table_index.info, &old_row.values);
+            if key == index_key(&table_index.info, &new_row.values) {
+                table_index
+                    .index
+                    .insert_entry(&key, rid, self.logged_transaction)
+                    .map_err(ExecutionError::Storage)?;
+            }
+        }
+        self.remove_version(new_rid, new_row)
+    }
+
+    /// Makes the version at the Rid current again after the transaction that deleted it rolled
+    /// back.
+    pub fn restore_version(&self, rid: Rid) -> Result<(), ExecutionError> {
+        let version = self
+            .table_heap
+            .get(rid)
+            .map_err(ExecutionError::Storage)?
+            .ok_or(ExecutionError::Storage("The tuple does not exist"))?
+            .version;
+        self.table_heap
+            .set_version(
+                rid,
+                TupleVersion {
+                    deleted_by: None,
+                    ..version
+                },
+                self.logged_transaction,
+            )
+            .map_err(ExecutionError::Storage)
+    }
+
+    /// Checks the NOT NULL constraints of the table and the unique indexes for a row that will be
+    /// stored at `own_rid`, or at a new Rid if it is None.
+    fn check_row(&self, row: &Row, own_rid: Option<Rid>) -> Result<(), ExecutionError> {
+        for (column, value) in self.table.schema.columns.iter().zip(&row.values) {
+            if !column.nullable && value.is_null() {
+                return Err(ExecutionError::NotNullViolation(column.name.clone()));
+            }
+        }
+        for table_index in self.indexes.iter().filter(|index| index.info.unique) {
+            let key = index_key(&table_index.info, &row.values);
+            // Keys containing NULL are never equal to another key.
+            if key.iter().any(Value::is_null) {
+                continue;
+            }
+            // The entries point at the newest versions of the rows with the key. An update that
+            // keeps the key moves the entry to its new version, so the entries are read again
+            // until no entry was added while waiting for a lock.
+            let mut checked = HashSet::new();
+            loop {
+                let rids: Vec<Rid> = table_index
+                    .index
+                    .scan_key(&key)
+                    .into_iter()
+                    .filter(|rid| Some(*rid) != own_rid && checked.insert(*rid))
+                    .collect();
+                if rids.is_empty() {
+                    break;
+                }
+                for rid in rids {
+                    // Wait for the transaction that changed the row, so that the check sees
+                    // whether it committed. Rows that are deleted by now no longer conflict.
+                    self.row_locks.lock(rid, LockMode::Shared)?;
+                    let Some(tuple) = self.table_heap.get(rid).map_err(ExecutionError::Storage)?
+                    else {
+                        continue;
+                    };
+                    if tuple.version.deleted_by.is_some() {
+                        continue;
+                    }
+                    let existing_row = Row::from_tuple_data(&tuple.data, &self.table.schema)
+                        .map_err(ExecutionError::Storage)?;
+                    if index_key(&table_index.info, &existing_row.values) == key {
+                        return Err(ExecutionError::UniqueViolation(
+                            table_index.info.name.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the row holding the number of rows affected by a data modifying statement.
+pub fn count_row(count: u64) -> Row {
+    Row::new(vec![Value::BigInt(count as i64)])
+}
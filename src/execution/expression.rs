@@ -0,0 +1,270 @@
+use std::cmp::Ordering;
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    sql::ast::{BinaryOperator, UnaryOperator},
+    types::value::Value,
+};
+
+use super::executor::ExecutionError;
+
+/// Evaluates a bound expression over an input row. NULL operands make the result NULL, except
+/// for AND and OR, which follow the three-valued logic of SQL.
+pub fn evaluate(expression: &BoundExpression, row: &[Value]) -> Result<Value, ExecutionError> {
+    match expression {
+        BoundExpression::Constant { value, .. } => Ok(value.clone()),
+        BoundExpression::Column { index, .. } => row.get(*index).cloned().ok_or(
+            ExecutionError::Storage("The column is not part of the input row"),
+        ),
+        BoundExpression::Unary {
+            operator, operand, ..
+        } => evaluate_unary(*operator, evaluate(operand, row)?),
+        BoundExpression::Binary {
+            left,
+            operator,
+            right,
+            ..
+        } => match operator {
+            BinaryOperator::And | BinaryOperator::Or => {
+                // OR is decided by the first TRUE operand, AND by the first FALSE operand.
+                let deciding_value = Value::Boolean(*operator == BinaryOperator::Or);
+                let left = evaluate(left, row)?;
+                if left == deciding_value {
+                    return Ok(left);
+                }
+                let right = evaluate(right, row)?;
+                if right == deciding_value {
+                    return Ok(right);
+                }
+                if left.is_null() || right.is_null() {
+                    return Ok(Value::Null);
+                }
+                Ok(left)
+            }
+            _ => evaluate_binary(evaluate(left, row)?, *operator, evaluate(right, row)?),
+        },
+        BoundExpression::IsNull { operand, negated } => Ok(Value::Boolean(
+            evaluate(operand, row)?.is_null() != *negated,
+        )),
+        BoundExpression::Cast { operand, data_type } => evaluate(operand, row)?
+            .cast_to(data_type)
+            .map_err(ExecutionError::InvalidValue),
+    }
+}
+
+/// Evaluates a condition. Rows for which the condition is NULL do not qualify.
+pub fn evaluate_predicate(
+    expression: &BoundExpression,
+    row: &[Value],
+) -> Result<bool, ExecutionError> {
+    Ok(evaluate(expression, row)? == Value::Boolean(true))
+}
+
+fn evaluate_unary(operator: UnaryOperator, value: Value) -> Result<Value, ExecutionError> {
+    let result = match (operator, value) {
+        (_, Value::Null) => Value::Null,
+        (UnaryOperator::Not, Value::Boolean(value)) => Value::Boolean(!value),
+        (UnaryOperator::Minus, Value::Integer(value)) => {
+            Value::Integer(value.checked_neg().ok_or_else(overflow)?)
+        }
+        (UnaryOperator::Minus, Value::BigInt(value)) => {
+            Value::BigInt(value.checked_neg().ok_or_else(overflow)?)
+        }
+        (UnaryOperator::Minus, Value::Float(value)) => Value::Float(-value),
+        (operator, value) => {
+            return Err(ExecutionError::Unsupported(format!(
+                "operator {:?} cannot be applied to {}",
+                operator, value
+            )))
+        }
+    };
+    Ok(result)
+}
+
+fn evaluate_binary(
+    left: Value,
+    operator: BinaryOperator,
+    right: Value,
+) -> Result<Value, ExecutionError> {
+    if left.is_null() || right.is_null() {
+        return Ok(Value::Null);
+    }
+    let result = match operator {
+        BinaryOperator::Equal => Value::Boolean(compare(&left, &right) == Ordering::Equal),
+        BinaryOperator::NotEqual => Value::Boolean(compare(&left, &right) != Ordering::Equal),
+        BinaryOperator::Less => Value::Boolean(compare(&left, &right) == Ordering::Less),
+        BinaryOperator::LessEqual => Value::Boolean(compare(&left, &right) != Ordering::Greater),
+        BinaryOperator::Greater => Value::Boolean(compare(&left, &right) == Ordering::Greater),
+        BinaryOperator::GreaterEqual => Value::Boolean(compare(&left, &right) != Ordering::Less),
+        _ => evaluate_arithmetic(left, operator, right)?,
+    };
+    Ok(result)
+}
+
+/// Compares two non-NULL values of the same type. Unlike the total order of [Value], positive and
+/// negative zero are equal.
+pub fn compare(left: &Value, right: &Value) -> Ordering {
+    match (left, right) {
+        (Value::Float(left), Value::Float(right)) => left
+            .partial_cmp(right)
+            .unwrap_or_else(|| left.total_cmp(right)),
+        _ => left.cmp(right),
+    }
+}
+
+fn evaluate_arithmetic(
+    left: Value,
+    operator: BinaryOperator,
+    right: Value,
+) -> Result<Value, ExecutionError> {
+    let division_by_zero = || ExecutionError::InvalidValue("division by zero".to_string());
+    let result = match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => Value::Integer(
+            match operator {
+                BinaryOperator::Plus => left.checked_add(right),
+                BinaryOperator::Minus => left.checked_sub(right),
+                BinaryOperator::Multiply => left.checked_mul(right),
+                BinaryOperator::Divide if right == 0 => return Err(division_by_zero()),
+                BinaryOperator::Divide => left.checked_div(right),
+                BinaryOperator::Modulo if right == 0 => return Err(division_by_zero()),
+                BinaryOperator::Modulo => left.checked_rem(right),
+                _ => None,
+            }
+            .ok_or_else(overflow)?,
+        ),
+        (Value::BigInt(left), Value::BigInt(right)) => Value::BigInt(
+            match operator {
+                BinaryOperator::Plus => left.checked_add(right),
+                BinaryOperator::Minus => left.checked_sub(right),
+                BinaryOperator::Multiply => left.checked_mul(right),
+                BinaryOperator::Divide if right == 0 => return Err(division_by_zero()),
+                BinaryOperator::Divide => left.checked_div(right),
+                BinaryOperator::Modulo if right == 0 => return Err(division_by_zero()),
+                BinaryOperator::Modulo => left.checked_rem(right),
+                _ => None,
+            }
+            .ok_or_else(overflow)?,
+        ),
+        (Value::Float(left), Value::Float(right)) => Value::Float(match operator {
+            BinaryOperator::Plus => left + right,
+            BinaryOperator::Minus => left - right,
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide if right == 0.0 => return Err(division_by_zero()),
+            BinaryOperator::Divide => left / right,
+            BinaryOperator::Modulo if right == 0.0 => return Err(division_by_zero()),
+            BinaryOperator::Modulo => left % right,
+            _ => unreachable!("comparisons are evaluated before arithmetic operators"),
+        }),
+        (left, right) => {
+            return Err(ExecutionError::Unsupported(format!(
+                "operator {:?} cannot be applied to {} and {}",
+                operator, left, right
+            )))
+        }
+    };
+    Ok(result)
+}
+
+fn overflow() -> ExecutionError {
+    ExecutionError::InvalidValue("numeric value out of range".to_string())
+}
+
+#[cfg(test)]
+mod expression_tests {
+    use crate::{
+        binder::bound_expression::BoundExpression,
+        sql::ast::BinaryOperator,
+        types::{schema::DataType, value::Value},
+    };
+
+    use super::evaluate;
+
+    fn constant(value: Value, data_type: DataType) -> BoundExpression {
+        BoundExpression::Constant { value, data_type }
+    }
+
+    fn binary(
+        left: BoundExpression,
+        operator: BinaryOperator,
+        right: BoundExpression,
+    ) -> BoundExpression {
+        let data_type = match operator {
+            BinaryOperator::Plus | BinaryOperator::Divide => left.data_type(),
+            _ => DataType::Boolean,
+        };
+        BoundExpression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            data_type,
+        }
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let null = constant(Value::Null, DataType::Boolean);
+        let true_ = constant(Value::Boolean(true), DataType::Boolean);
+        let false_ = constant(Value::Boolean(false), DataType::Boolean);
+        let cases = [
+            (
+                null.clone(),
+                BinaryOperator::And,
+                false_.clone(),
+                Value::Boolean(false),
+            ),
+            (
+                null.clone(),
+                BinaryOperator::And,
+                true_.clone(),
+                Value::Null,
+            ),
+            (
+                true_.clone(),
+                BinaryOperator::And,
+                true_.clone(),
+                Value::Boolean(true),
+            ),
+            (
+                null.clone(),
+                BinaryOperator::Or,
+                true_.clone(),
+                Value::Boolean(true),
+            ),
+            (
+                false_.clone(),
+                BinaryOperator::Or,
+                null.clone(),
+                Value::Null,
+            ),
+            (
+                false_.clone(),
+                BinaryOperator::Or,
+                false_.clone(),
+                Value::Boolean(false),
+            ),
+        ];
+        for (left, operator, right, expected) in cases {
+            assert_eq!(evaluate(&binary(left, operator, right), &[]), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn arithmetic_and_comparisons() {
+        let column = BoundExpression::Column {
+            index: 0,
+            data_type: DataType::Integer,
+        };
+        let two = constant(Value::Integer(2), DataType::Integer);
+        let row = [Value::Integer(5)];
+        let sum = binary(column.clone(), BinaryOperator::Plus, two.clone());
+        assert_eq!(evaluate(&sum, &row), Ok(Value::Integer(7)));
+        let less = binary(column.clone(), BinaryOperator::Less, two.clone());
+        assert_eq!(evaluate(&less, &row), Ok(Value::Boolean(false)));
+        assert_eq!(evaluate(&less, &[Value::Null]), Ok(Value::Null));
+
+        let zero = constant(Value::Integer(0), DataType::Integer);
+        assert!(evaluate(&binary(column.clone(), BinaryOperator::Divide, zero), &row).is_err());
+        let max = constant(Value::Integer(i32::MAX), DataType::Integer);
+        assert!(evaluate(&binary(column, BinaryOperator::Plus, max), &row).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{binder::bound_expression::BoundExpression, table::table_page::Rid, types::row::Row};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    expression::evaluate_predicate,
+};
+
+/// Passes on the rows of its child for which the predicate is true.
+pub struct FilterExecutor {
+    child: Box<dyn Executor>,
+    predicate: BoundExpression,
+}
+
+impl FilterExecutor {
+    pub fn new(child: Box<dyn Executor>, predicate: BoundExpression) -> FilterExecutor {
+        FilterExecutor { child, predicate }
+    }
+}
+
+impl Executor for FilterExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        while let Some((row, rid)) = self.child.next()? {
+            if evaluate_predicate(&self.predicate, &row.values)? {
+                return Ok(Some((row, rid)));
+            }
+        }
+        Ok(None)
+    }
+}
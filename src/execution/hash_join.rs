@@ -0,0 +1,341 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    disk_management::buffer_pool::BufferPool,
+    sql::ast::JoinType,
+    table::table_page::Rid,
+    types::{row::Row, schema::DataType, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    join::{
+        condition_holds, emits_unmatched_right, evaluate_join_key, joined_row, unmatched_left_row,
+        unmatched_right_row,
+    },
+    spill::{row_memory_size, SpillFile, SpillFileIterator},
+};
+
+/// The number of partitions both inputs are split into when the build side does not fit into
+/// memory.
+const PARTITION_COUNT: usize = 16;
+
+/// Joins the inputs with a hash table built over the right input.
+///
+/// If the right input exceeds the memory limit, both inputs are split into partitions by the hash
+/// of their keys and written to spill files (Grace hash join). The partitions are then joined one
+/// by one, so only the right rows of one partition are kept in memory at a time. A partition that
+/// is still too large is loaded anyway.
+pub struct HashJoinExecutor {
+    left: Box<dyn Executor>,
+    right: Box<dyn Executor>,
+    join_type: JoinType,
+    left_keys: Vec<BoundExpression>,
+    right_keys: Vec<BoundExpression>,
+    condition: Option<BoundExpression>,
+    left_types: Vec<DataType>,
+    right_types: Vec<DataType>,
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    memory_limit: usize,
+    table: HashTable,
+    partitions: VecDeque<(SpillFile, SpillFile)>,
+    /// The spilled left rows of the current partition, or None while the left input is probed
+    /// directly.
+    probe_rows: Option<SpillFileIterator>,
+    output: VecDeque<Row>,
+    done: bool,
+}
+
+/// The rows of the right input, or of one partition of it, grouped by their key.
+#[derive(Default)]
+struct HashTable {
+    rows: Vec<Row>,
+    matched: Vec<bool>,
+    /// Rows with a NULL key are part of `rows`, but cannot be found by any key.
+    buckets: HashMap<Vec<Value>, Vec<usize>>,
+}
+
+impl HashTable {
+    fn insert(&mut self, row: Row, key: Option<Vec<Value>>) {
+        if let Some(key) = key {
+            self.buckets.entry(key).or_default().push(self.rows.len());
+        }
+        self.rows.push(row);
+        self.matched.push(false);
+    }
+}
+
+impl HashJoinExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        right_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+        left_types: Vec<DataType>,
+        right_types: Vec<DataType>,
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        memory_limit: usize,
+    ) -> HashJoinExecutor {
+        HashJoinExecutor {
+            left,
+            right,
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+            left_types,
+            right_types,
+            buffer_pool,
+            memory_limit,
+            table: HashTable::default(),
+            partitions: VecDeque::new(),
+            probe_rows: None,
+            output: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the right input into the hash table. Partitions both inputs if the right input is
+    /// larger than the memory limit.
+    fn build(&mut self) -> Result<(), ExecutionError> {
+        let mut memory_size = 0;
+        while let Some((row, _)) = self.right.next()? {
+            memory_size += row_memory_size(&row);
+            let key = evaluate_join_key(&self.right_keys, &row)?;
+            self.table.insert(row, key);
+            if memory_size > self.memory_limit {
+                return self.partition();
+            }
+        }
+        Ok(())
+    }
+
+    fn partition(&mut self) -> Result<(), ExecutionError> {
+        let mut left_partitions: Vec<SpillFile> = (0..PARTITION_COUNT)
+            .map(|_| SpillFile::new(&self.buffer_pool, &self.left_types))
+            .collect();
+        let mut right_partitions: Vec<SpillFile> = (0..PARTITION_COUNT)
+            .map(|_| SpillFile::new(&self.buffer_pool, &self.right_types))
+            .collect();
+
+        let buffered_rows = std::mem::take(&mut self.table).rows;
+        for row in buffered_rows {
+            let key = evaluate_join_key(&self.right_keys, &row)?;
+            right_partitions[partition_of(key.as_deref())].append(&row)?;
+        }
+        while let Some((row, _)) = self.right.next()? {
+            let key = evaluate_join_key(&self.right_keys, &row)?;
+            right_partitions[partition_of(key.as_deref())].append(&row)?;
+        }
+        while let Some((row, _)) = self.left.next()? {
+            let key = evaluate_join_key(&self.left_keys, &row)?;
+            left_partitions[partition_of(key.as_deref())].append(&row)?;
+        }
+        self.partitions = left_partitions.into_iter().zip(right_partitions).collect();
+        self.load_next_partition()?;
+        Ok(())
+    }
+
+    /// Loads the right rows of the next partition into the hash table. Returns false if all
+    /// partitions have been joined.
+    fn load_next_partition(&mut self) -> Result<bool, ExecutionError> {
+        let (left_partition, right_partition) = match self.partitions.pop_front() {
+            Some(partition) => partition,
+            None => return Ok(false),
+        };
+        self.table = HashTable::default();
+        for row in right_partition.iter() {
+            let row = row?;
+            let key = evaluate_join_key(&self.right_keys, &row)?;
+            self.table.insert(row, key);
+        }
+        self.probe_rows = Some(left_partition.iter());
+        Ok(true)
+    }
+
+    fn next_probe_row(&mut self) -> Result<Option<Row>, ExecutionError> {
+        match &mut self.probe_rows {
+            Some(probe_rows) => probe_rows.next().transpose(),
+            None => Ok(self.left.next()?.map(|(row, _)| row)),
+        }
+    }
+
+    /// Queues the output rows of a left row.
+    fn probe(&mut self, left: Row) -> Result<(), ExecutionError> {
+        let key = evaluate_join_key(&self.left_keys, &left)?;
+        let candidates = key
+            .and_then(|key| self.table.buckets.get(&key))
+            .cloned()
+            .unwrap_or_default();
+        let mut matched = false;
+        for position in candidates {
+            let right = &self.table.rows[position];
+            if !condition_holds(self.condition.as_ref(), &left, right)? {
+                continue;
+            }
+            matched = true;
+            self.table.matched[position] = true;
+            match self.join_type {
+                JoinType::Semi | JoinType::Anti => break,
+                _ => self.output.push_back(joined_row(&left, right)),
+            }
+        }
+        if matched && self.join_type == JoinType::Semi {
+            self.output.push_back(left);
+        } else if !matched {
+            self.output.extend(unmatched_left_row(
+                &left,
+                self.join_type,
+                self.right_types.len(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Queues the unmatched right rows of the hash table.
+    fn finish_table(&mut self) {
+        if !emits_unmatched_right(self.join_type) {
+            return;
+        }
+        let left_width = self.left_types.len();
+        for (right, matched) in self.table.rows.iter().zip(&self.table.matched) {
+            if !matched {
+                self.output
+                    .extend(unmatched_right_row(right, self.join_type, left_width));
+            }
+        }
+    }
+}
+
+/// Returns the partition of a key. Rows with a NULL key never match, so they can be put into
+/// any partition.
+fn partition_of(key: Option<&[Value]>) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as usize % PARTITION_COUNT
+}
+
+impl Executor for HashJoinExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.table = HashTable::default();
+        self.partitions.clear();
+        self.probe_rows = None;
+        self.output.clear();
+        self.done = false;
+        self.left.init()?;
+        self.right.init()?;
+        self.build()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        loop {
+            if let Some(row) = self.output.pop_front() {
+                return Ok(Some((row, None)));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            match self.next_probe_row()? {
+                Some(left) => self.probe(left)?,
+                None => {
+                    self.finish_table();
+                    let partitioned = self.probe_rows.is_some();
+                    self.done = !partitioned || !self.load_next_partition()?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod hash_join_tests {
+    use crate::{
+        binder::bound_expression::BoundExpression,
+        disk_management::test_utils::new_test_buffer_pool,
+        execution::{executor::collect_rows, values::ValuesExecutor},
+        sql::ast::JoinType,
+        types::{schema::DataType, value::Value},
+    };
+
+    use super::HashJoinExecutor;
+
+    fn values(rows: Vec<Vec<Value>>) -> Box<ValuesExecutor> {
+        Box::new(ValuesExecutor::new(
+            rows.into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|value| BoundExpression::Constant {
+                            data_type: match value {
+                                Value::String(_) => DataType::Text,
+                                _ => DataType::Integer,
+                            },
+                            value,
+                        })
+                        .collect()
+                })
+                .collect(),
+        ))
+    }
+
+    fn key() -> Vec<BoundExpression> {
+        vec![BoundExpression::Column {
+            index: 0,
+            data_type: DataType::Integer,
+        }]
+    }
+
+    #[test]
+    fn partitions_spill_when_the_build_side_is_too_large() {
+        let buffer_pool = new_test_buffer_pool("hash_join_spill");
+        let right_rows: Vec<Vec<Value>> = (0..3000)
+            .map(|i| {
+                vec![
+                    Value::Integer(i % 1000),
+                    Value::String(format!("right {}", i)),
+                ]
+            })
+            .chain([vec![Value::Null, Value::String("null".to_string())]])
+            .collect();
+        let left_rows: Vec<Vec<Value>> = (500..1500)
+            .map(|i| vec![Value::Integer(i)])
+            .chain([vec![Value::Null]])
+            .collect();
+
+        let mut results = Vec::new();
+        for memory_limit in [usize::MAX, 4096] {
+            let mut executor = HashJoinExecutor::new(
+                values(left_rows.clone()),
+                values(right_rows.clone()),
+                JoinType::Full,
+                key(),
+                key(),
+                None,
+                vec![DataType::Integer],
+                vec![DataType::Integer, DataType::Text],
+                buffer_pool.clone(),
+                memory_limit,
+            );
+            let mut rows: Vec<Vec<Value>> = collect_rows(&mut executor)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.values)
+                .collect();
+            rows.sort();
+            if memory_limit != usize::MAX {
+                assert!(executor.probe_rows.is_some());
+            }
+            results.push(rows);
+        }
+        // 1500 matches, 500 unmatched left rows, 1500 unmatched right rows and both NULL rows.
+        assert_eq!(results[0].len(), 1500 + 501 + 1501);
+        assert_eq!(results[0], results[1]);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    sql::ast::JoinType,
+    table::{table_heap::TableHeap, table_page::Rid},
+    types::{row::Row, schema::Schema},
+};
+
+use super::{
+    executor::{ExecutionError, Executor, TableIndex, Visibility},
+    join::{condition_holds, evaluate_join_key, joined_row, unmatched_left_row},
+};
+
+/// Joins every row of the left input with the rows of a table found by an index lookup. The
+/// left keys are evaluated over the left row, one for each key column of the index, and have the
+/// types of the key columns. Right and full joins are not supported, as the unmatched rows of the
+/// table are never visited.
+pub struct IndexNestedLoopJoinExecutor {
+    left: Box<dyn Executor>,
+    table_heap: TableHeap,
+    schema: Schema,
+    index: TableIndex,
+    visibility: Visibility,
+    join_type: JoinType,
+    left_keys: Vec<BoundExpression>,
+    condition: Option<BoundExpression>,
+    output: VecDeque<Row>,
+}
+
+impl IndexNestedLoopJoinExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        left: Box<dyn Executor>,
+        table_heap: TableHeap,
+        schema: Schema,
+        index: TableIndex,
+        visibility: Visibility,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+    ) -> Result<IndexNestedLoopJoinExecutor, ExecutionError> {
+        if matches!(join_type, JoinType::Right | JoinType::Full) {
+            return Err(ExecutionError::Unsupported(
+                "an index nested loop join cannot produce unmatched rows of the table".to_string(),
+            ));
+        }
+        Ok(IndexNestedLoopJoinExecutor {
+            left,
+            table_heap,
+            schema,
+            index,
+            visibility,
+            join_type,
+            left_keys,
+            condition,
+            output: VecDeque::new(),
+        })
+    }
+
+    /// Looks up the matching rows of the table and queues the output rows of the left row.
+    fn probe(&mut self, left: Row) -> Result<(), ExecutionError> {
+        let mut matched = false;
+        if let Some(key) = evaluate_join_key(&self.left_keys, &left)? {
+            let rows = self
+                .index
+                .lookup(&self.table_heap, &self.schema, &self.visibility, &key)?;
+            for (_, right) in rows {
+                if !condition_holds(self.condition.as_ref(), &left, &right)? {
+                    continue;
+                }
+                matched = true;
+                match self.join_type {
+                    JoinType::Semi | JoinType::Anti => break,
+                    _ => self.output.push_back(joined_row(&left, &right)),
+                }
+            }
+        }
+        if matched && self.join_type == JoinType::Semi {
+            self.output.push_back(left);
+        } else if !matched {
+            self.output.extend(unmatched_left_row(
+                &left,
+                self.join_type,
+                self.schema.columns.len(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Executor for IndexNestedLoopJoinExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.output.clear();
+        self.left.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        loop {
+            if let Some(row) = self.output.pop_front() {
+                return Ok(Some((row, None)));
+            }
+            match self.left.next()? {
+                Some((left, _)) => self.probe(left)?,
+                None => return Ok(None),
+            }
+        }
+    }
+}
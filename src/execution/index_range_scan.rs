@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use crate::{
+    planner::plan::IndexBound,
+    table::{table_heap::TableHeap, table_page::Rid},
+    types::{row::Row, schema::Schema, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor, TableIndex, Visibility},
+    expression::evaluate,
+};
+
+/// Reads the rows of a table whose first key column of a B+ tree index lies between the bounds.
+/// The bounds are evaluated once, without an input row. Only the rows visible to the
+/// transaction are read, in the order of their keys.
+pub struct IndexRangeScanExecutor {
+    table_heap: TableHeap,
+    schema: Schema,
+    index: TableIndex,
+    lower: Option<IndexBound>,
+    upper: Option<IndexBound>,
+    visibility: Visibility,
+    rows: VecDeque<(Rid, Row)>,
+}
+
+impl IndexRangeScanExecutor {
+    pub fn new(
+        table_heap: TableHeap,
+        schema: Schema,
+        index: TableIndex,
+        lower: Option<IndexBound>,
+        upper: Option<IndexBound>,
+        visibility: Visibility,
+    ) -> IndexRangeScanExecutor {
+        IndexRangeScanExecutor {
+            table_heap,
+            schema,
+            index,
+            lower,
+            upper,
+            visibility,
+            rows: VecDeque::new(),
+        }
+    }
+}
+
+impl Executor for IndexRangeScanExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        let lower = evaluate_bound(self.lower.as_ref())?;
+        let upper = evaluate_bound(self.upper.as_ref())?;
+        self.rows.clear();
+        // A comparison with NULL never holds.
+        let has_null_bound = [&lower, &upper]
+            .into_iter()
+            .any(|bound| bound.as_ref().is_some_and(|(value, _)| value.is_null()));
+        if !has_null_bound {
+            self.rows.extend(self.index.lookup_range(
+                &self.table_heap,
+                &self.schema,
+                &self.visibility,
+                lower.as_ref().map(|(value, inclusive)| (value, *inclusive)),
+                upper.as_ref().map(|(value, inclusive)| (value, *inclusive)),
+            )?);
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        Ok(self.rows.pop_front().map(|(rid, row)| (row, Some(rid))))
+    }
+}
+
+fn evaluate_bound(bound: Option<&IndexBound>) -> Result<Option<(Value, bool)>, ExecutionError> {
+    bound
+        .map(|bound| Ok((evaluate(&bound.value, &[])?, bound.inclusive)))
+        .transpose()
+}
@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    table::{table_heap::TableHeap, table_page::Rid},
+    types::{row::Row, schema::Schema, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor, TableIndex, Visibility},
+    expression::evaluate,
+};
+
+/// Reads the rows of a table whose key columns of an index are equal to the key. The key
+/// expressions are evaluated once, without an input row. Only the rows visible to the
+/// transaction are read.
+pub struct IndexScanExecutor {
+    table_heap: TableHeap,
+    schema: Schema,
+    index: TableIndex,
+    key: Vec<BoundExpression>,
+    visibility: Visibility,
+    rows: VecDeque<(Rid, Row)>,
+}
+
+impl IndexScanExecutor {
+    pub fn new(
+        table_heap: TableHeap,
+        schema: Schema,
+        index: TableIndex,
+        key: Vec<BoundExpression>,
+        visibility: Visibility,
+    ) -> IndexScanExecutor {
+        IndexScanExecutor {
+            table_heap,
+            schema,
+            index,
+            key,
+            visibility,
+            rows: VecDeque::new(),
+        }
+    }
+}
+
+impl Executor for IndexScanExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        let key_values: Vec<Value> = self
+            .key
+            .iter()
+            .map(|expression| evaluate(expression, &[]))
+            .collect::<Result<_, _>>()?;
+        self.rows.clear();
+        // An equality with NULL never holds.
+        if !key_values.iter().any(Value::is_null) {
+            self.rows.extend(self.index.lookup(
+                &self.table_heap,
+                &self.schema,
+                &self.visibility,
+                &key_values,
+            )?);
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        Ok(self.rows.pop_front().map(|(rid, row)| (row, Some(rid))))
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{table::table_page::Rid, types::row::Row};
+
+use super::executor::{count_row, ExecutionError, Executor, TableModifier};
+
+/// Inserts the rows of its child into a table and produces a single row with the number of
+/// inserted rows. Rows inserted before a failing row are kept until the transaction rolls back.
+pub struct InsertExecutor {
+    child: Box<dyn Executor>,
+    table: TableModifier,
+    done: bool,
+}
+
+impl InsertExecutor {
+    pub fn new(child: Box<dyn Executor>, table: TableModifier) -> InsertExecutor {
+        InsertExecutor {
+            child,
+            table,
+            done: false,
+        }
+    }
+}
+
+impl Executor for InsertExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.done = false;
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        let mut count = 0;
+        while let Some((row, _)) = self.child.next()? {
+            self.table.insert(&row)?;
+            count += 1;
+        }
+        Ok(Some((count_row(count), None)))
+    }
+}
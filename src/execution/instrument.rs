@@ -0,0 +1,101 @@
+use std::{
+    cell::Cell,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    disk_management::buffer_pool::{BufferPool, BufferPoolStatistics},
+    planner::plan::PlanNode,
+    table::table_page::Rid,
+    types::row::Row,
+};
+
+use super::executor::{create_wrapped_executor, ExecutionContext, ExecutionError, Executor};
+
+/// What happened while an executor ran. The time includes that of the inputs of the executor.
+/// The page requests are only those the executor made itself, without those of its inputs, and
+/// only those of the session that runs it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorMetrics {
+    pub rows: u64,
+    /// The number of times the executor was initialized, e.g. once per left row for the right
+    /// input of a nested loop join.
+    pub loops: u64,
+    pub elapsed: Duration,
+    pub buffer_hits: u64,
+    pub buffer_misses: u64,
+}
+
+/// The metrics of an executor, shared between the executor and whoever reports them.
+pub type SharedMetrics = Arc<Mutex<OperatorMetrics>>;
+
+thread_local! {
+    /// The page requests that the measured executors inside the running measurement counted as
+    /// their own, so that the executor of the measurement does not count them again.
+    static NESTED_REQUESTS: Cell<BufferPoolStatistics> = Cell::default();
+}
+
+/// Measures the rows, the time and the buffer pool requests of its child.
+pub struct InstrumentedExecutor {
+    child: Box<dyn Executor>,
+    metrics: SharedMetrics,
+}
+
+impl InstrumentedExecutor {
+    pub fn new(child: Box<dyn Executor>, metrics: SharedMetrics) -> InstrumentedExecutor {
+        InstrumentedExecutor { child, metrics }
+    }
+
+    /// Runs the child. The page requests of the thread while it runs are counted for the child,
+    /// except for those of nested measured executors, i.e. of its inputs.
+    fn measure<T>(&mut self, run: impl FnOnce(&mut dyn Executor) -> T) -> T {
+        let outer_requests = NESTED_REQUESTS.take();
+        let before = BufferPool::thread_statistics();
+        let start = Instant::now();
+        let result = run(self.child.as_mut());
+        let elapsed = start.elapsed();
+        let after = BufferPool::thread_statistics();
+        let (hits, misses) = (after.hits - before.hits, after.misses - before.misses);
+        let nested_requests = NESTED_REQUESTS.replace(BufferPoolStatistics {
+            hits: outer_requests.hits + hits,
+            misses: outer_requests.misses + misses,
+        });
+        let mut metrics = self.metrics.lock().expect("Could not lock metrics");
+        metrics.elapsed += elapsed;
+        metrics.buffer_hits += hits - nested_requests.hits;
+        metrics.buffer_misses += misses - nested_requests.misses;
+        result
+    }
+}
+
+impl Executor for InstrumentedExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.metrics.lock().expect("Could not lock metrics").loops += 1;
+        self.measure(|child| child.init())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        let result = self.measure(|child| child.next());
+        if let Ok(Some(_)) = result {
+            self.metrics.lock().expect("Could not lock metrics").rows += 1;
+        }
+        result
+    }
+}
+
+/// Creates the executor tree of a physical plan in which every executor is measured. Returns
+/// the metrics of the nodes in post-order: the metrics of the inputs of a node, in the order of
+/// [PlanNode::inputs], precede those of the node.
+pub fn create_instrumented_executor(
+    plan: &PlanNode,
+    context: &ExecutionContext,
+) -> Result<(Box<dyn Executor>, Vec<SharedMetrics>), ExecutionError> {
+    let mut metrics = Vec::new();
+    let executor = create_wrapped_executor(plan, context, &mut |executor| {
+        let node_metrics = Arc::new(Mutex::new(OperatorMetrics::default()));
+        metrics.push(node_metrics.clone());
+        Box::new(InstrumentedExecutor::new(executor, node_metrics))
+    })?;
+    Ok((executor, metrics))
+}
@@ -0,0 +1,256 @@
+use crate::{
+    binder::bound_expression::BoundExpression,
+    sql::ast::JoinType,
+    types::{row::Row, value::Value},
+};
+
+use super::{
+    executor::ExecutionError,
+    expression::{evaluate, evaluate_predicate},
+};
+
+// Helpers shared by the join executors. All joins produce the columns of the left input followed
+// by the columns of the right input, except for semi and anti joins, which only produce the
+// columns of the left input. Join conditions are evaluated over the left row followed by the
+// right row.
+
+/// Returns the concatenation of a left and a right row.
+pub fn joined_row(left: &Row, right: &Row) -> Row {
+    let mut values = Vec::with_capacity(left.values.len() + right.values.len());
+    values.extend_from_slice(&left.values);
+    values.extend_from_slice(&right.values);
+    Row::new(values)
+}
+
+/// Returns the output row of a left row without a matching right row.
+pub fn unmatched_left_row(left: &Row, join_type: JoinType, right_width: usize) -> Option<Row> {
+    match join_type {
+        JoinType::Left | JoinType::Full => Some(joined_row(left, &null_row(right_width))),
+        JoinType::Anti => Some(left.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the output row of a right row without a matching left row.
+pub fn unmatched_right_row(right: &Row, join_type: JoinType, left_width: usize) -> Option<Row> {
+    match join_type {
+        JoinType::Right | JoinType::Full => Some(joined_row(&null_row(left_width), right)),
+        _ => None,
+    }
+}
+
+/// Returns true if unmatched rows of the right input are part of the output.
+pub fn emits_unmatched_right(join_type: JoinType) -> bool {
+    matches!(join_type, JoinType::Right | JoinType::Full)
+}
+
+fn null_row(width: usize) -> Row {
+    Row::new(vec![Value::Null; width])
+}
+
+/// Returns true if the rows match. A missing condition matches all pairs.
+pub fn condition_holds(
+    condition: Option<&BoundExpression>,
+    left: &Row,
+    right: &Row,
+) -> Result<bool, ExecutionError> {
+    match condition {
+        Some(condition) => evaluate_predicate(condition, &joined_row(left, right).values),
+        None => Ok(true),
+    }
+}
+
+/// Evaluates the join keys over a row. Returns None if a key is NULL, as such rows never match.
+/// Negative zero is replaced by zero, so that equal floats have equal keys.
+pub fn evaluate_join_key(
+    keys: &[BoundExpression],
+    row: &Row,
+) -> Result<Option<Vec<Value>>, ExecutionError> {
+    let mut key = Vec::with_capacity(keys.len());
+    for expression in keys {
+        match evaluate(expression, &row.values)? {
+            Value::Null => return Ok(None),
+            Value::Float(0.0) => key.push(Value::Float(0.0)),
+            value => key.push(value),
+        }
+    }
+    Ok(Some(key))
+}
+
+#[cfg(test)]
+mod join_tests {
+    use crate::{
+        binder::{bound_statement::BoundStatement, statement_binder::Binder},
+        database::engine::Database,
+        disk_management::test_utils::new_test_buffer_pool,
+        execution::executor::{collect_rows, create_executor, ExecutionContext},
+        planner::{
+            physical_planner::{JoinStrategy, Planner},
+            plan::PlanNode,
+        },
+        sql::parser::parse_statement,
+        types::value::Value,
+    };
+
+    fn contains_node(plan: &PlanNode, predicate: &dyn Fn(&PlanNode) -> bool) -> bool {
+        if predicate(plan) {
+            return true;
+        }
+        match plan {
+            PlanNode::Filter { input, .. }
+            | PlanNode::Projection { input, .. }
+            | PlanNode::Sort { input, .. }
+            | PlanNode::Limit { input, .. }
+            | PlanNode::IndexNestedLoopJoin { left: input, .. } => contains_node(input, predicate),
+            PlanNode::NestedLoopJoin { left, right, .. }
+            | PlanNode::HashJoin { left, right, .. }
+            | PlanNode::SortMergeJoin { left, right, .. } => {
+                contains_node(left, predicate) || contains_node(right, predicate)
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn join_strategies_agree() {
+        let buffer_pool = new_test_buffer_pool("join_strategies_agree");
+        let mut database = Database::new(buffer_pool.clone()).unwrap();
+        database
+            .execute(
+                "CREATE TABLE a (id INTEGER, x INTEGER, name TEXT);
+                 CREATE TABLE b (x INTEGER, y VARCHAR(10));
+                 CREATE INDEX a_x ON a (x);
+                 CREATE INDEX b_x ON b (x);
+                 INSERT INTO a VALUES (1, 1, 'one'), (2, 2, 'two'), (3, 2, 'two again'),
+                     (4, NULL, 'null'), (5, 5, 'five'), (6, 6, 'six');
+                 INSERT INTO b VALUES (1, 'p'), (2, 'q'), (2, 'r'), (NULL, 's'), (3, 't'),
+                     (6, 'skip'), (7, 'u');",
+            )
+            .unwrap();
+
+        let joins = [
+            ("JOIN", 5),
+            ("LEFT JOIN", 8),
+            ("RIGHT JOIN", 9),
+            ("FULL JOIN", 12),
+            ("LEFT SEMI JOIN", 3),
+            ("LEFT ANTI JOIN", 3),
+        ];
+        let strategies = [
+            JoinStrategy::NestedLoop,
+            JoinStrategy::IndexNestedLoop,
+            JoinStrategy::Hash,
+            JoinStrategy::SortMerge,
+        ];
+        for (join, expected_count) in joins {
+            let sql = format!("SELECT * FROM a {} b ON a.x = b.x AND b.y <> 'skip'", join);
+            let statement = Binder::new(&database.catalog())
+                .bind(&parse_statement(&sql).unwrap())
+                .unwrap();
+            let mut results = Vec::new();
+            for strategy in strategies {
+                let plan = Planner::new(&database.catalog())
+                    .with_join_strategy(strategy)
+                    .plan(&statement)
+                    .unwrap();
+                // An index lookup cannot produce the unmatched rows of the probed table, so right
+                // and full joins fall back to a hash join.
+                let probes_index = !matches!(join, "RIGHT JOIN" | "FULL JOIN");
+                let uses_strategy = |node: &PlanNode| match strategy {
+                    JoinStrategy::NestedLoop => matches!(node, PlanNode::NestedLoopJoin { .. }),
+                    JoinStrategy::IndexNestedLoop if probes_index => {
+                        matches!(node, PlanNode::IndexNestedLoopJoin { .. })
+                    }
+                    JoinStrategy::IndexNestedLoop | JoinStrategy::Hash => {
+                        matches!(node, PlanNode::HashJoin { .. })
+                    }
+                    JoinStrategy::SortMerge => matches!(node, PlanNode::SortMergeJoin { .. }),
+                };
+                assert!(contains_node(&plan, &uses_strategy), "{}: {:?}", join, plan);
+                let mut executor =
+                    create_executor(&plan, &ExecutionContext::new(buffer_pool.clone())).unwrap();
+                let mut rows: Vec<Vec<Value>> = collect_rows(executor.as_mut())
+                    .unwrap()
+                    .into_iter()
+                    .map(|row| row.values)
+                    .collect();
+                rows.sort();
+                results.push(rows);
+            }
+            assert_eq!(results[0].len(), expected_count, "{}", join);
+            for rows in &results[1..] {
+                assert_eq!(rows, &results[0], "{}", join);
+            }
+        }
+
+        // Looking up the few matching rows of a large table is cheaper than scanning it.
+        let values: Vec<String> = (0..2000).map(|x| format!("({}, {})", x, x % 7)).collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE c (x INTEGER, z INTEGER);
+                 CREATE UNIQUE INDEX c_x ON c (x);
+                 INSERT INTO c VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+        let statement = Binder::new(&database.catalog())
+            .bind(&parse_statement("SELECT * FROM a JOIN c ON a.x = c.x WHERE a.id = 2").unwrap())
+            .unwrap();
+        assert!(matches!(statement, BoundStatement::Select(_)));
+        let plan = Planner::new(&database.catalog()).plan(&statement).unwrap();
+        assert!(contains_node(&plan, &|node| matches!(
+            node,
+            PlanNode::IndexNestedLoopJoin { .. }
+        )));
+        let mut executor =
+            create_executor(&plan, &ExecutionContext::new(buffer_pool.clone())).unwrap();
+        let rows: Vec<Vec<Value>> = collect_rows(executor.as_mut())
+            .unwrap()
+            .into_iter()
+            .map(|row| row.values)
+            .collect();
+        assert_eq!(
+            rows,
+            vec![vec![
+                Value::Integer(2),
+                Value::Integer(2),
+                Value::String("two".to_string()),
+                Value::Integer(2),
+                Value::Integer(2),
+            ]]
+        );
+    }
+
+    #[test]
+    fn join_through_sql() {
+        let mut database = Database::new(new_test_buffer_pool("join_through_sql")).unwrap();
+        let results = database
+            .execute(
+                "CREATE TABLE users (id INTEGER, name TEXT);
+                 CREATE TABLE orders (user_id INTEGER, amount INTEGER);
+                 INSERT INTO users VALUES (1, 'ada'), (2, 'alan'), (3, 'grace');
+                 INSERT INTO orders VALUES (1, 10), (1, 20), (3, 5);
+                 SELECT u.name, o.amount FROM users u LEFT JOIN orders o ON u.id = o.user_id
+                     WHERE o.amount IS NULL OR o.amount > 6 ORDER BY u.name, o.amount;
+                 SELECT count.name FROM users count CROSS JOIN users other WHERE other.id = 1;",
+            )
+            .unwrap();
+        let rows = |index: usize| match &results[index] {
+            crate::database::engine::QueryResult::Rows { rows, .. } => rows
+                .iter()
+                .map(|row| row.values.clone())
+                .collect::<Vec<_>>(),
+            result => panic!("expected rows, got {:?}", result),
+        };
+        let string = |value: &str| Value::String(value.to_string());
+        assert_eq!(
+            rows(4),
+            vec![
+                vec![string("ada"), Value::Integer(10)],
+                vec![string("ada"), Value::Integer(20)],
+                vec![string("alan"), Value::Null],
+            ]
+        );
+        assert_eq!(rows(5).len(), 3);
+    }
+}
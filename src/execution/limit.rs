@@ -0,0 +1,50 @@
+use crate::{table::table_page::Rid, types::row::Row};
+
+use super::executor::{ExecutionError, Executor};
+
+/// Skips the first `offset` rows of its child and passes on at most `limit` of the remaining
+/// rows.
+pub struct LimitExecutor {
+    child: Box<dyn Executor>,
+    limit: Option<u64>,
+    offset: u64,
+    skipped: u64,
+    emitted: u64,
+}
+
+impl LimitExecutor {
+    pub fn new(child: Box<dyn Executor>, limit: Option<u64>, offset: u64) -> LimitExecutor {
+        LimitExecutor {
+            child,
+            limit,
+            offset,
+            skipped: 0,
+            emitted: 0,
+        }
+    }
+}
+
+impl Executor for LimitExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.skipped = 0;
+        self.emitted = 0;
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        if self.limit.is_some_and(|limit| self.emitted >= limit) {
+            return Ok(None);
+        }
+        while self.skipped < self.offset {
+            if self.child.next()?.is_none() {
+                return Ok(None);
+            }
+            self.skipped += 1;
+        }
+        let row = self.child.next()?;
+        if row.is_some() {
+            self.emitted += 1;
+        }
+        Ok(row)
+    }
+}
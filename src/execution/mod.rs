@@ -0,0 +1,21 @@
+pub mod aggregate;
+pub mod delete;
+pub mod executor;
+pub mod expression;
+pub mod filter;
+pub mod hash_join;
+pub mod index_nested_loop_join;
+pub mod index_range_scan;
+pub mod index_scan;
+pub mod insert;
+pub mod instrument;
+pub mod join;
+pub mod limit;
+pub mod nested_loop_join;
+pub mod projection;
+pub mod seq_scan;
+pub mod sort;
+pub mod sort_merge_join;
+pub mod spill;
+pub mod update;
+pub mod values;
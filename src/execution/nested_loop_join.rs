@@ -0,0 +1,140 @@
+use crate::{
+    binder::bound_expression::BoundExpression, sql::ast::JoinType, table::table_page::Rid,
+    types::row::Row,
+};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    join::{
+        condition_holds, emits_unmatched_right, joined_row, unmatched_left_row, unmatched_right_row,
+    },
+};
+
+/// Joins every row of the left input with every row of the right input. The right input is
+/// scanned again for each left row, so only one left and one right row are kept in memory. For
+/// right and full joins, the positions of the matched right rows are remembered and the
+/// unmatched right rows are produced by a final scan of the right input.
+pub struct NestedLoopJoinExecutor {
+    left: Box<dyn Executor>,
+    right: Box<dyn Executor>,
+    join_type: JoinType,
+    condition: Option<BoundExpression>,
+    left_width: usize,
+    right_width: usize,
+    current_left: Option<Row>,
+    current_left_matched: bool,
+    right_position: usize,
+    right_matched: Vec<bool>,
+    scanning_unmatched_right: bool,
+}
+
+impl NestedLoopJoinExecutor {
+    pub fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        join_type: JoinType,
+        condition: Option<BoundExpression>,
+        left_width: usize,
+        right_width: usize,
+    ) -> NestedLoopJoinExecutor {
+        NestedLoopJoinExecutor {
+            left,
+            right,
+            join_type,
+            condition,
+            left_width,
+            right_width,
+            current_left: None,
+            current_left_matched: false,
+            right_position: 0,
+            right_matched: Vec::new(),
+            scanning_unmatched_right: false,
+        }
+    }
+
+    fn restart_right(&mut self) -> Result<(), ExecutionError> {
+        self.right_position = 0;
+        self.right.init()
+    }
+
+    /// Returns the next right row and marks its position.
+    fn next_right(&mut self) -> Result<Option<(usize, Row)>, ExecutionError> {
+        let row = match self.right.next()? {
+            Some((row, _)) => row,
+            None => return Ok(None),
+        };
+        let position = self.right_position;
+        self.right_position += 1;
+        if self.right_matched.len() <= position {
+            self.right_matched.resize(position + 1, false);
+        }
+        Ok(Some((position, row)))
+    }
+}
+
+impl Executor for NestedLoopJoinExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.current_left = None;
+        self.right_matched.clear();
+        self.scanning_unmatched_right = false;
+        self.left.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        loop {
+            if self.scanning_unmatched_right {
+                let (position, right) = match self.next_right()? {
+                    Some(right) => right,
+                    None => return Ok(None),
+                };
+                if !self.right_matched[position] {
+                    let row = unmatched_right_row(&right, self.join_type, self.left_width);
+                    return Ok(row.map(|row| (row, None)));
+                }
+                continue;
+            }
+
+            if self.current_left.is_none() {
+                match self.left.next()? {
+                    Some((left, _)) => {
+                        self.current_left = Some(left);
+                        self.current_left_matched = false;
+                        self.restart_right()?;
+                    }
+                    None if emits_unmatched_right(self.join_type) => {
+                        self.scanning_unmatched_right = true;
+                        self.restart_right()?;
+                        continue;
+                    }
+                    None => return Ok(None),
+                }
+            }
+
+            let (position, right) = match self.next_right()? {
+                Some(right) => right,
+                None => {
+                    let left = self.current_left.take().unwrap();
+                    if !self.current_left_matched {
+                        if let Some(row) =
+                            unmatched_left_row(&left, self.join_type, self.right_width)
+                        {
+                            return Ok(Some((row, None)));
+                        }
+                    }
+                    continue;
+                }
+            };
+            let left = self.current_left.as_ref().unwrap();
+            if !condition_holds(self.condition.as_ref(), left, &right)? {
+                continue;
+            }
+            self.current_left_matched = true;
+            self.right_matched[position] = true;
+            match self.join_type {
+                JoinType::Semi => return Ok(self.current_left.take().map(|left| (left, None))),
+                JoinType::Anti => self.current_left = None,
+                _ => return Ok(Some((joined_row(left, &right), None))),
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{binder::bound_expression::BoundExpression, table::table_page::Rid, types::row::Row};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    expression::evaluate,
+};
+
+/// Computes one output value per expression from each row of its child.
+pub struct ProjectionExecutor {
+    child: Box<dyn Executor>,
+    expressions: Vec<BoundExpression>,
+}
+
+impl ProjectionExecutor {
+    pub fn new(child: Box<dyn Executor>, expressions: Vec<BoundExpression>) -> ProjectionExecutor {
+        ProjectionExecutor { child, expressions }
+    }
+}
+
+impl Executor for ProjectionExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        let (row, _) = match self.child.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let values = self
+            .expressions
+            .iter()
+            .map(|expression| evaluate(expression, &row.values))
+            .collect::<Result<_, _>>()?;
+        Ok(Some((Row::new(values), None)))
+    }
+}
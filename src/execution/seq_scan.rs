@@ -0,0 +1,51 @@
+use crate::{
+    table::{table_heap::TableHeap, table_iterator::TableIterator, table_page::Rid},
+    types::{row::Row, schema::Schema},
+};
+
+use super::executor::{ExecutionError, Executor, Visibility};
+
+/// Reads all rows of a table heap that are visible to the transaction, in storage order.
+pub struct SeqScanExecutor {
+    table_heap: TableHeap,
+    schema: Schema,
+    visibility: Visibility,
+    iterator: Option<TableIterator>,
+}
+
+impl SeqScanExecutor {
+    pub fn new(table_heap: TableHeap, schema: Schema, visibility: Visibility) -> SeqScanExecutor {
+        SeqScanExecutor {
+            table_heap,
+            schema,
+            visibility,
+            iterator: None,
+        }
+    }
+}
+
+impl Executor for SeqScanExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.iterator = Some(self.table_heap.iter());
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        let iterator = self
+            .iterator
+            .as_mut()
+            .ok_or(ExecutionError::Storage("The executor is not initialized"))?;
+        for entry in iterator.by_ref() {
+            let (rid, tuple) = entry.map_err(ExecutionError::Storage)?;
+            if !self.visibility.is_visible(tuple.version) {
+                continue;
+            }
+            let row =
+                Row::from_tuple_data(&tuple.data, &self.schema).map_err(ExecutionError::Storage)?;
+            return Ok(Some((row, Some(rid))));
+        }
+        // Release the pinned page of the iterator as soon as the scan is done.
+        self.iterator = None;
+        Ok(None)
+    }
+}
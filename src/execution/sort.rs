@@ -0,0 +1,404 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    disk_management::buffer_pool::{BufferPool, PAGE_SIZE},
+    table::table_page::Rid,
+    types::{row::Row, schema::DataType, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    expression::{compare, evaluate},
+    spill::{row_memory_size, SpillFile, SpillFileIterator},
+};
+
+/// Sorts all rows of its child. Every sort key is paired with a flag that is true for ascending
+/// order. NULL values are larger than all other values, so they come last in ascending and first
+/// in descending order.
+///
+/// Rows are collected in memory until they exceed the memory limit. They are then sorted and
+/// written to a spill file as a sorted run. Once the child is exhausted, the runs are merged,
+/// at most one page per run being held in memory. If there are more runs than fit into memory
+/// that way, groups of runs are merged into longer runs first.
+///
+/// If only the first `limit` rows are needed, the rows are collected in a bounded heap that
+/// drops every row that cannot be among them, so usually nothing is spilled at all.
+pub struct SortExecutor {
+    child: Box<dyn Executor>,
+    order_by: Vec<(BoundExpression, bool)>,
+    limit: Option<u64>,
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    memory_limit: usize,
+    ascending: Arc<[bool]>,
+    /// The types of the spilled rows, which are the sort keys followed by the row values.
+    run_types: Vec<DataType>,
+    output: SortOutput,
+    emitted: u64,
+}
+
+/// A row together with its sort keys, ordered by the sort keys.
+struct SortEntry {
+    keys: Vec<Value>,
+    row: Row,
+    ascending: Arc<[bool]>,
+}
+
+impl SortEntry {
+    fn memory_size(&self) -> usize {
+        row_memory_size(&self.row) + self.keys.len() * std::mem::size_of::<Value>()
+    }
+}
+
+impl PartialEq for SortEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortEntry {}
+
+impl PartialOrd for SortEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SortEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.keys, &other.keys, &self.ascending)
+    }
+}
+
+enum SortOutput {
+    /// All rows fit into memory.
+    Memory(VecDeque<SortEntry>),
+    /// The rows are merged from sorted runs.
+    Merge(RunMerger),
+}
+
+/// Merges sorted runs by repeatedly taking the smallest head row of all runs.
+struct RunMerger {
+    runs: Vec<SpillFileIterator>,
+    /// The head row of every run that is not exhausted, with the position of the run.
+    heads: BinaryHeap<Reverse<(SortEntry, usize)>>,
+    key_count: usize,
+    ascending: Arc<[bool]>,
+}
+
+impl RunMerger {
+    fn new(
+        runs: Vec<SpillFileIterator>,
+        key_count: usize,
+        ascending: Arc<[bool]>,
+    ) -> Result<RunMerger, ExecutionError> {
+        let mut merger = RunMerger {
+            runs,
+            heads: BinaryHeap::new(),
+            key_count,
+            ascending,
+        };
+        for position in 0..merger.runs.len() {
+            merger.advance(position)?;
+        }
+        Ok(merger)
+    }
+
+    /// Reads the next row of a run into the heads.
+    fn advance(&mut self, position: usize) -> Result<(), ExecutionError> {
+        if let Some(row) = self.runs[position].next() {
+            let mut keys = row?.values;
+            let values = keys.split_off(self.key_count);
+            let entry = SortEntry {
+                keys,
+                row: Row::new(values),
+                ascending: self.ascending.clone(),
+            };
+            self.heads.push(Reverse((entry, position)));
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<SortEntry>, ExecutionError> {
+        match self.heads.pop() {
+            Some(Reverse((entry, position))) => {
+                self.advance(position)?;
+                Ok(Some(entry))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl SortExecutor {
+    /// Creates a sort of the child rows, whose columns have the given types. If `limit` is set,
+    /// only the first `limit` sorted rows are produced.
+    pub fn new(
+        child: Box<dyn Executor>,
+        order_by: Vec<(BoundExpression, bool)>,
+        limit: Option<u64>,
+        column_types: Vec<DataType>,
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        memory_limit: usize,
+    ) -> SortExecutor {
+        let ascending = order_by.iter().map(|(_, ascending)| *ascending).collect();
+        let run_types = order_by
+            .iter()
+            .map(|(expression, _)| expression.data_type())
+            .chain(column_types)
+            .collect();
+        SortExecutor {
+            child,
+            order_by,
+            limit,
+            buffer_pool,
+            memory_limit,
+            ascending,
+            run_types,
+            output: SortOutput::Memory(VecDeque::new()),
+            emitted: 0,
+        }
+    }
+
+    /// The number of runs that are merged at once. Every run being merged holds one page in
+    /// memory.
+    fn merge_fan_in(&self) -> usize {
+        (self.memory_limit / PAGE_SIZE).max(2)
+    }
+
+    fn sort_entry(&self, row: Row) -> Result<SortEntry, ExecutionError> {
+        let keys = self
+            .order_by
+            .iter()
+            .map(|(expression, _)| evaluate(expression, &row.values))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SortEntry {
+            keys,
+            row,
+            ascending: self.ascending.clone(),
+        })
+    }
+
+    /// Writes sorted entries to a new run. At most `limit` entries are written, as later ones
+    /// are never produced.
+    fn write_run(
+        &self,
+        entries: impl Iterator<Item = Result<SortEntry, ExecutionError>>,
+    ) -> Result<SpillFile, ExecutionError> {
+        let mut run = SpillFile::new(&self.buffer_pool, &self.run_types);
+        let limit = self.limit.map_or(usize::MAX, |limit| limit as usize);
+        for entry in entries.take(limit) {
+            let mut entry = entry?;
+            entry.keys.append(&mut entry.row.values);
+            run.append(&Row::new(entry.keys))?;
+        }
+        Ok(run)
+    }
+
+    /// Merges the runs until few enough remain to be merged at once.
+    fn merge_runs(&self, mut runs: VecDeque<SpillFile>) -> Result<RunMerger, ExecutionError> {
+        let fan_in = self.merge_fan_in();
+        while runs.len() > fan_in {
+            let group = runs.drain(..fan_in).map(|run| run.iter()).collect();
+            let mut merger = RunMerger::new(group, self.order_by.len(), self.ascending.clone())?;
+            let merged = self.write_run(std::iter::from_fn(|| merger.next().transpose()))?;
+            runs.push_back(merged);
+        }
+        RunMerger::new(
+            runs.iter().map(SpillFile::iter).collect(),
+            self.order_by.len(),
+            self.ascending.clone(),
+        )
+    }
+}
+
+impl Executor for SortExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.output = SortOutput::Memory(VecDeque::new());
+        self.emitted = 0;
+        self.child.init()?;
+
+        let limit = self.limit.map_or(usize::MAX, |limit| limit as usize);
+        // A max-heap, so that the largest entry is dropped once there are more than `limit`.
+        let mut entries: BinaryHeap<SortEntry> = BinaryHeap::new();
+        let mut memory_size = 0;
+        let mut runs = VecDeque::new();
+        while let Some((row, _)) = self.child.next()? {
+            let entry = self.sort_entry(row)?;
+            memory_size += entry.memory_size();
+            entries.push(entry);
+            if entries.len() > limit {
+                let dropped = entries.pop().unwrap();
+                memory_size -= dropped.memory_size();
+            }
+            if memory_size > self.memory_limit {
+                let sorted = std::mem::take(&mut entries).into_sorted_vec();
+                runs.push_back(self.write_run(sorted.into_iter().map(Ok))?);
+                memory_size = 0;
+            }
+        }
+
+        let sorted = entries.into_sorted_vec();
+        self.output = if runs.is_empty() {
+            SortOutput::Memory(sorted.into())
+        } else {
+            if !sorted.is_empty() {
+                runs.push_back(self.write_run(sorted.into_iter().map(Ok))?);
+            }
+            SortOutput::Merge(self.merge_runs(runs)?)
+        };
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        if self.limit.is_some_and(|limit| self.emitted >= limit) {
+            return Ok(None);
+        }
+        let entry = match &mut self.output {
+            SortOutput::Memory(entries) => entries.pop_front(),
+            SortOutput::Merge(merger) => merger.next()?,
+        };
+        if entry.is_some() {
+            self.emitted += 1;
+        }
+        Ok(entry.map(|entry| (entry.row, None)))
+    }
+}
+
+/// Compares two sort keys. `ascending` holds the direction of every key column. NULL values are
+/// larger than all other values.
+pub fn compare_keys(left: &[Value], right: &[Value], ascending: &[bool]) -> Ordering {
+    for ((left, right), ascending) in left.iter().zip(right).zip(ascending) {
+        let ordering = match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare(left, right),
+        };
+        let ordering = if *ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod sort_tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        binder::bound_expression::BoundExpression,
+        disk_management::{
+            buffer_pool::{BufferPool, PAGE_SIZE},
+            test_utils::new_test_buffer_pool,
+        },
+        execution::{executor::Executor, values::ValuesExecutor},
+        types::{schema::DataType, value::Value},
+    };
+
+    use super::{SortExecutor, SortOutput};
+
+    fn constant(value: Value, data_type: DataType) -> BoundExpression {
+        BoundExpression::Constant { value, data_type }
+    }
+
+    fn column(index: usize, data_type: DataType) -> BoundExpression {
+        BoundExpression::Column { index, data_type }
+    }
+
+    /// 5000 rows of a key that repeats every 1000 rows, in a scrambled order, and a text column.
+    fn scrambled_rows() -> Vec<Vec<BoundExpression>> {
+        (0..5000)
+            .map(|i| {
+                let key = match (i * 7919) % 1000 {
+                    0 => Value::Null,
+                    key => Value::Integer(key),
+                };
+                vec![
+                    constant(key, DataType::Integer),
+                    constant(Value::String(format!("row {}", i)), DataType::Text),
+                ]
+            })
+            .collect()
+    }
+
+    fn sort(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        limit: Option<u64>,
+        memory_limit: usize,
+    ) -> (SortExecutor, Vec<Value>) {
+        let mut executor = SortExecutor::new(
+            Box::new(ValuesExecutor::new(scrambled_rows())),
+            vec![(column(0, DataType::Integer), false)],
+            limit,
+            vec![DataType::Integer, DataType::Text],
+            buffer_pool,
+            memory_limit,
+        );
+        executor.init().unwrap();
+        let mut keys = Vec::new();
+        while let Some((row, _)) = executor.next().unwrap() {
+            keys.push(row.values[0].clone());
+        }
+        (executor, keys)
+    }
+
+    #[test]
+    fn external_sort_merges_runs() {
+        let (executor, keys) = sort(new_test_buffer_pool("sort_external"), None, 4 * PAGE_SIZE);
+        // With four pages of memory, runs are merged four at a time.
+        assert!(matches!(executor.output, SortOutput::Merge(ref merger) if merger.runs.len() <= 4));
+        assert_eq!(keys.len(), 5000);
+        assert!(keys[..5].iter().all(Value::is_null));
+        assert_eq!(keys[5], Value::Integer(999));
+        assert_eq!(keys[4999], Value::Integer(1));
+        assert!(keys[5..].windows(2).all(|pair| pair[0] >= pair[1]));
+
+        let (executor, in_memory_keys) =
+            sort(new_test_buffer_pool("sort_in_memory"), None, usize::MAX);
+        assert!(matches!(executor.output, SortOutput::Memory(_)));
+        assert_eq!(in_memory_keys, keys);
+    }
+
+    #[test]
+    fn top_n_keeps_only_the_first_rows() {
+        let (executor, keys) = sort(new_test_buffer_pool("sort_top_n"), Some(7), 4 * PAGE_SIZE);
+        assert!(matches!(executor.output, SortOutput::Memory(_)));
+        assert_eq!(keys.len(), 7);
+        assert!(keys[..5].iter().all(Value::is_null));
+        assert_eq!(keys[5..], [Value::Integer(999), Value::Integer(999)]);
+
+        let (executor, spilled_keys) = sort(
+            new_test_buffer_pool("sort_top_n_spilled"),
+            Some(3000),
+            4 * PAGE_SIZE,
+        );
+        assert!(matches!(executor.output, SortOutput::Merge(_)));
+        assert_eq!(spilled_keys.len(), 3000);
+        assert_eq!(spilled_keys[5], Value::Integer(999));
+    }
+
+    #[test]
+    fn runs_are_freed_after_the_sort() {
+        let buffer_pool = new_test_buffer_pool("sort_frees_runs");
+        let (executor, keys) = sort(buffer_pool.clone(), None, 4 * PAGE_SIZE);
+        drop(executor);
+        let page_count = buffer_pool.lock().unwrap().get_page_count().unwrap();
+        for _ in 0..3 {
+            assert_eq!(sort(buffer_pool.clone(), None, 4 * PAGE_SIZE).1, keys);
+        }
+        assert_eq!(
+            buffer_pool.lock().unwrap().get_page_count().unwrap(),
+            page_count
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    sql::ast::JoinType,
+    table::table_page::Rid,
+    types::{row::Row, value::Value},
+};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    join::{
+        condition_holds, emits_unmatched_right, evaluate_join_key, joined_row, unmatched_left_row,
+        unmatched_right_row,
+    },
+    sort::compare_keys,
+};
+
+/// A row together with its join key, which is None if the key contains NULL.
+type KeyedRow = (Option<Vec<Value>>, Row);
+
+/// Joins two inputs that are sorted ascending by their keys, with NULLs last. The right rows
+/// sharing a key are kept in memory as a group while the left rows with that key are joined with
+/// them.
+pub struct SortMergeJoinExecutor {
+    left: Box<dyn Executor>,
+    right: Box<dyn Executor>,
+    join_type: JoinType,
+    left_keys: Vec<BoundExpression>,
+    right_keys: Vec<BoundExpression>,
+    condition: Option<BoundExpression>,
+    left_width: usize,
+    right_width: usize,
+    /// The next right row that is not part of the group yet.
+    right_lookahead: Option<KeyedRow>,
+    group_key: Option<Vec<Value>>,
+    group: Vec<Row>,
+    group_matched: Vec<bool>,
+    output: VecDeque<Row>,
+    done: bool,
+}
+
+impl SortMergeJoinExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        left: Box<dyn Executor>,
+        right: Box<dyn Executor>,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        right_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+        left_width: usize,
+        right_width: usize,
+    ) -> SortMergeJoinExecutor {
+        SortMergeJoinExecutor {
+            left,
+            right,
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+            left_width,
+            right_width,
+            right_lookahead: None,
+            group_key: None,
+            group: Vec::new(),
+            group_matched: Vec::new(),
+            output: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn read_right(&mut self) -> Result<Option<KeyedRow>, ExecutionError> {
+        match self.right.next()? {
+            Some((row, _)) => Ok(Some((evaluate_join_key(&self.right_keys, &row)?, row))),
+            None => Ok(None),
+        }
+    }
+
+    fn compare(&self, left: &[Value], right: &[Value]) -> Ordering {
+        compare_keys(left, right, &vec![true; left.len()])
+    }
+
+    /// Queues the unmatched rows of the current group and clears it.
+    fn finish_group(&mut self) {
+        for (right, matched) in self.group.drain(..).zip(self.group_matched.drain(..)) {
+            if !matched {
+                self.output
+                    .extend(unmatched_right_row(&right, self.join_type, self.left_width));
+            }
+        }
+        self.group_key = None;
+    }
+
+    /// Moves the group forward until its key is not smaller than the key. The group is empty
+    /// if the right input has no more rows with a non-NULL key.
+    fn advance_group(&mut self, key: &[Value]) -> Result<(), ExecutionError> {
+        loop {
+            if let Some(group_key) = &self.group_key {
+                if self.compare(group_key, key) != Ordering::Less {
+                    return Ok(());
+                }
+            }
+            self.finish_group();
+            let (right_key, right) = match self.right_lookahead.take() {
+                Some(lookahead) => lookahead,
+                None => return Ok(()),
+            };
+            self.right_lookahead = self.read_right()?;
+            let right_key = match right_key {
+                Some(right_key) => right_key,
+                None => {
+                    // Rows with a NULL key never match.
+                    self.output.extend(unmatched_right_row(
+                        &right,
+                        self.join_type,
+                        self.left_width,
+                    ));
+                    continue;
+                }
+            };
+            self.group.push(right);
+            self.group_matched.push(false);
+            while let Some((Some(next_key), _)) = &self.right_lookahead {
+                if self.compare(next_key, &right_key) != Ordering::Equal {
+                    break;
+                }
+                let (_, row) = self.right_lookahead.take().unwrap();
+                self.group.push(row);
+                self.group_matched.push(false);
+                self.right_lookahead = self.read_right()?;
+            }
+            self.group_key = Some(right_key);
+        }
+    }
+
+    /// Queues the output rows of a left row.
+    fn merge(&mut self, left: Row) -> Result<(), ExecutionError> {
+        let mut matched = false;
+        if let Some(key) = evaluate_join_key(&self.left_keys, &left)? {
+            self.advance_group(&key)?;
+            let group_matches = self
+                .group_key
+                .as_ref()
+                .is_some_and(|group_key| self.compare(group_key, &key) == Ordering::Equal);
+            if group_matches {
+                for (right, right_matched) in self.group.iter().zip(&mut self.group_matched) {
+                    if !condition_holds(self.condition.as_ref(), &left, right)? {
+                        continue;
+                    }
+                    matched = true;
+                    *right_matched = true;
+                    match self.join_type {
+                        JoinType::Semi | JoinType::Anti => break,
+                        _ => self.output.push_back(joined_row(&left, right)),
+                    }
+                }
+            }
+        }
+        if matched && self.join_type == JoinType::Semi {
+            self.output.push_back(left);
+        } else if !matched {
+            self.output
+                .extend(unmatched_left_row(&left, self.join_type, self.right_width));
+        }
+        Ok(())
+    }
+
+    /// Queues the remaining unmatched right rows after the left input ended.
+    fn finish(&mut self) -> Result<(), ExecutionError> {
+        self.finish_group();
+        if !emits_unmatched_right(self.join_type) {
+            return Ok(());
+        }
+        while let Some((_, right)) = self.right_lookahead.take() {
+            self.output
+                .extend(unmatched_right_row(&right, self.join_type, self.left_width));
+            self.right_lookahead = self.read_right()?;
+        }
+        Ok(())
+    }
+}
+
+impl Executor for SortMergeJoinExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.group_key = None;
+        self.group.clear();
+        self.group_matched.clear();
+        self.output.clear();
+        self.done = false;
+        self.left.init()?;
+        self.right.init()?;
+        self.right_lookahead = self.read_right()?;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        loop {
+            if let Some(row) = self.output.pop_front() {
+                return Ok(Some((row, None)));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            match self.left.next()? {
+                Some((left, _)) => self.merge(left)?,
+                None => {
+                    self.finish()?;
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    disk_management::buffer_pool::{BufferPool, RawPage, PAGE_SIZE},
+    types::{
+        row::Row,
+        schema::{Column, DataType, Schema},
+        value::Value,
+    },
+};
+
+use super::executor::ExecutionError;
+
+// Rows are encoded with the row codec and written as a stream of records:
+// | ROW_LENGTH [u32] | ROW ... |
+// Records span page boundaries. The pages have no header, their ids are kept in memory.
+
+/// Rows that an executor moved out of memory, e.g. the partitions of a hash join. The rows are
+/// written to temporary pages of the buffer pool, which are not logged and reach the database
+/// file only if the buffer pool evicts them. The pages are freed once the spill file and all
+/// iterators over it are dropped.
+pub struct SpillFile {
+    schema: Schema,
+    pages: Arc<SpillPages>,
+    /// The unwritten end of the stream, always shorter than a page.
+    buffer: Vec<u8>,
+    row_count: usize,
+}
+
+impl SpillFile {
+    pub fn new(buffer_pool: &Arc<Mutex<BufferPool>>, column_types: &[DataType]) -> SpillFile {
+        let schema = Schema::new(
+            column_types
+                .iter()
+                .enumerate()
+                .map(|(i, data_type)| Column::new(&i.to_string(), *data_type, true))
+                .collect(),
+        );
+        SpillFile {
+            schema,
+            pages: Arc::new(SpillPages {
+                buffer_pool: buffer_pool.clone(),
+                page_ids: Mutex::new(Vec::new()),
+            }),
+            buffer: Vec::with_capacity(PAGE_SIZE),
+            row_count: 0,
+        }
+    }
+
+    pub fn append(&mut self, row: &Row) -> Result<(), ExecutionError> {
+        let data = row
+            .to_tuple_data(&self.schema)
+            .map_err(ExecutionError::Storage)?;
+        let length = u32::try_from(data.len())
+            .map_err(|_| ExecutionError::Storage("The row is too large to be spilled"))?;
+        self.write(&length.to_le_bytes())?;
+        self.write(&data)?;
+        self.row_count += 1;
+        Ok(())
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) -> Result<(), ExecutionError> {
+        while !bytes.is_empty() {
+            let length = bytes.len().min(PAGE_SIZE - self.buffer.len());
+            self.buffer.extend_from_slice(&bytes[..length]);
+            bytes = &bytes[length..];
+            if self.buffer.len() == PAGE_SIZE {
+                self.write_page()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_page(&mut self) -> Result<(), ExecutionError> {
+        let mut buffer_pool_lock = self
+            .pages
+            .buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool");
+        let (page_id, _) = buffer_pool_lock
+            .load_new_temp_page()
+            .map_err(|_| ExecutionError::Storage("Could not load a new page"))?;
+        self.pages.page_ids.lock().unwrap().push(page_id as u32);
+        let mut data = [0; PAGE_SIZE];
+        data[..self.buffer.len()].copy_from_slice(&self.buffer);
+        buffer_pool_lock
+            .set_temp_page_data(page_id, RawPage::new(data))
+            .map_err(|_| ExecutionError::Storage("Could not write the spilled page"))?;
+        buffer_pool_lock
+            .unload_page_id(page_id)
+            .map_err(|_| ExecutionError::Storage("Could not unload the spilled page"))?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.row_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.row_count == 0
+    }
+
+    /// Returns the rows in the order they were appended. The iterator does not borrow the spill
+    /// file, rows appended after its creation are not returned.
+    pub fn iter(&self) -> SpillFileIterator {
+        SpillFileIterator {
+            schema: self.schema.clone(),
+            page_ids: self.pages.page_ids.lock().unwrap().clone(),
+            pages: self.pages.clone(),
+            tail: self.buffer.clone(),
+            page: Vec::new(),
+            next_page: 0,
+            position: 0,
+            remaining_rows: self.row_count,
+        }
+    }
+}
+
+/// The pages of a spill file, shared with its iterators.
+struct SpillPages {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    page_ids: Mutex<Vec<u32>>,
+}
+
+impl Drop for SpillPages {
+    fn drop(&mut self) {
+        if let Ok(mut buffer_pool_lock) = self.buffer_pool.lock() {
+            for page_id in self.page_ids.get_mut().unwrap().drain(..) {
+                let _ = buffer_pool_lock.free_temp_page(page_id as usize);
+            }
+        }
+    }
+}
+
+pub struct SpillFileIterator {
+    schema: Schema,
+    /// The pages written when the iterator was created. Holding the pages keeps them from being
+    /// freed while they are read.
+    page_ids: Vec<u32>,
+    pages: Arc<SpillPages>,
+    tail: Vec<u8>,
+    page: Vec<u8>,
+    next_page: usize,
+    position: usize,
+    remaining_rows: usize,
+}
+
+impl SpillFileIterator {
+    fn read(&mut self, length: usize) -> Result<Vec<u8>, ExecutionError> {
+        let mut bytes = Vec::with_capacity(length);
+        while bytes.len() < length {
+            if self.position == self.page.len() {
+                self.load_next_page()?;
+            }
+            let count = (length - bytes.len()).min(self.page.len() - self.position);
+            bytes.extend_from_slice(&self.page[self.position..self.position + count]);
+            self.position += count;
+        }
+        Ok(bytes)
+    }
+
+    fn load_next_page(&mut self) -> Result<(), ExecutionError> {
+        self.position = 0;
+        match self.page_ids.get(self.next_page) {
+            Some(page_id) => {
+                let page_id = *page_id as usize;
+                let mut buffer_pool_lock = self
+                    .pages
+                    .buffer_pool
+                    .lock()
+                    .expect("Could not lock buffer pool");
+                let frame_id = buffer_pool_lock
+                    .load_page(page_id)
+                    .map_err(|_| ExecutionError::Storage("Could not load the spilled page"))?;
+                self.page = buffer_pool_lock
+                    .get_raw_page(frame_id)
+                    .ok_or(ExecutionError::Storage("Could not load the spilled page"))?
+                    .data
+                    .read()
+                    .unwrap()
+                    .to_vec();
+                buffer_pool_lock
+                    .unload_page_id(page_id)
+                    .map_err(|_| ExecutionError::Storage("Could not unload the spilled page"))?;
+            }
+            None if self.next_page == self.page_ids.len() => {
+                self.page = std::mem::take(&mut self.tail);
+            }
+            None => return Err(ExecutionError::Storage("The spilled row is truncated")),
+        }
+        self.next_page += 1;
+        Ok(())
+    }
+
+    fn read_row(&mut self) -> Result<Row, ExecutionError> {
+        let length = u32::from_le_bytes(self.read(4)?.try_into().unwrap()) as usize;
+        let data = self.read(length)?;
+        Row::from_tuple_data(&data, &self.schema).map_err(ExecutionError::Storage)
+    }
+}
+
+impl Iterator for SpillFileIterator {
+    type Item = Result<Row, ExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining_rows == 0 {
+            return None;
+        }
+        self.remaining_rows -= 1;
+        Some(self.read_row())
+    }
+}
+
+/// Returns an estimate of the memory used by a row, used to decide when executors spill.
+pub fn row_memory_size(row: &Row) -> usize {
+    let heap_size = |value: &Value| match value {
+        Value::String(string) => string.len(),
+        Value::Blob(blob) => blob.len(),
+        _ => 0,
+    };
+    std::mem::size_of::<Row>()
+        + row
+            .values
+            .iter()
+            .map(|value| std::mem::size_of::<Value>() + heap_size(value))
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod spill_tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        disk_management::{
+            buffer_pool::BufferPool,
+            disk_manager::DiskManager,
+            test_utils::{new_test_buffer_pool, temp_db_path},
+        },
+        recovery::log_manager::LogManager,
+        types::{row::Row, schema::DataType, value::Value},
+    };
+
+    use super::SpillFile;
+
+    fn spill(buffer_pool: &Arc<Mutex<BufferPool>>, rows: &[Row]) -> SpillFile {
+        let mut spill_file = SpillFile::new(buffer_pool, &[DataType::Text]);
+        for row in rows {
+            spill_file.append(row).unwrap();
+        }
+        spill_file
+    }
+
+    #[test]
+    fn rows_are_read_in_append_order() {
+        let buffer_pool = new_test_buffer_pool("spill_append_order");
+        let mut spill_file = SpillFile::new(&buffer_pool, &[DataType::Integer, DataType::Text]);
+        let rows: Vec<Row> = (0..500)
+            .map(|i| {
+                Row::new(vec![
+                    Value::Integer(i),
+                    if i % 7 == 0 {
+                        Value::Null
+                    } else {
+                        Value::String("x".repeat(i as usize * 3))
+                    },
+                ])
+            })
+            .collect();
+        for row in &rows {
+            spill_file.append(row).unwrap();
+        }
+        assert_eq!(spill_file.len(), 500);
+        let read_rows: Vec<Row> = spill_file.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(read_rows, rows);
+        // The file can be read more than once.
+        assert_eq!(spill_file.iter().count(), 500);
+    }
+
+    #[test]
+    fn pages_are_freed_when_no_longer_read() {
+        let buffer_pool = new_test_buffer_pool("spill_frees_pages");
+        let rows: Vec<Row> = (0..200)
+            .map(|i| Row::new(vec![Value::String(i.to_string().repeat(100))]))
+            .collect();
+        drop(spill(&buffer_pool, &rows));
+        let page_count = buffer_pool.lock().unwrap().get_page_count().unwrap();
+        assert!(page_count > 10);
+
+        // The pages of dropped spill files are reused, but not while an iterator reads them.
+        let spill_file = spill(&buffer_pool, &rows);
+        let iterator = spill_file.iter();
+        drop(spill_file);
+        let other_file = spill(&buffer_pool, &rows);
+        let grown_page_count = buffer_pool.lock().unwrap().get_page_count().unwrap();
+        assert_eq!(grown_page_count, 2 * page_count);
+        assert_eq!(iterator.collect::<Result<Vec<_>, _>>().unwrap(), rows);
+        drop(other_file);
+        for _ in 0..3 {
+            let _spill_files = (spill(&buffer_pool, &rows), spill(&buffer_pool, &rows));
+        }
+        assert_eq!(
+            buffer_pool.lock().unwrap().get_page_count().unwrap(),
+            grown_page_count
+        );
+    }
+
+    #[test]
+    fn spilled_pages_are_not_logged() {
+        let path = temp_db_path("spill_not_logged");
+        let log_manager = Arc::new(Mutex::new(
+            LogManager::open(&format!("{}.wal", path)).unwrap(),
+        ));
+        let buffer_pool = Arc::new(Mutex::new(BufferPool::with_log_manager(
+            Arc::new(Mutex::new(DiskManager::new(path).unwrap())),
+            log_manager.clone(),
+        )));
+        let next_lsn = log_manager.lock().unwrap().next_lsn();
+        let rows: Vec<Row> = (0..200)
+            .map(|i| Row::new(vec![Value::String(i.to_string().repeat(100))]))
+            .collect();
+        let spill_file = spill(&buffer_pool, &rows);
+        assert_eq!(
+            spill_file.iter().collect::<Result<Vec<_>, _>>().unwrap(),
+            rows
+        );
+        assert_eq!(log_manager.lock().unwrap().next_lsn(), next_lsn);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{binder::bound_expression::BoundExpression, table::table_page::Rid, types::row::Row};
+
+use super::{
+    executor::{count_row, ExecutionError, Executor, TableModifier},
+    expression::evaluate,
+};
+
+/// Updates the rows produced by its child, which must read them from the table, and produces a
+/// single row with the number of updated rows. The rows are collected before the first update, so
+/// that updated rows are not seen by the scan again.
+pub struct UpdateExecutor {
+    child: Box<dyn Executor>,
+    table: TableModifier,
+    assignments: Vec<(usize, BoundExpression)>,
+    done: bool,
+}
+
+impl UpdateExecutor {
+    pub fn new(
+        child: Box<dyn Executor>,
+        table: TableModifier,
+        assignments: Vec<(usize, BoundExpression)>,
+    ) -> UpdateExecutor {
+        UpdateExecutor {
+            child,
+            table,
+            assignments,
+            done: false,
+        }
+    }
+}
+
+impl Executor for UpdateExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.done = false;
+        self.child.init()
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        let mut rows = Vec::new();
+        while let Some((row, rid)) = self.child.next()? {
+            let rid = rid.ok_or(ExecutionError::Storage("The updated row has no Rid"))?;
+            rows.push((row, rid));
+        }
+
+        for (old_row, rid) in &rows {
+            let mut new_row = old_row.clone();
+            for (position, expression) in &self.assignments {
+                new_row.values[*position] = evaluate(expression, &old_row.values)?;
+            }
+            self.table.update(*rid, old_row, &new_row)?;
+        }
+        Ok(Some((count_row(rows.len() as u64), None)))
+    }
+}
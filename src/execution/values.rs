@@ -0,0 +1,39 @@
+use crate::{binder::bound_expression::BoundExpression, table::table_page::Rid, types::row::Row};
+
+use super::{
+    executor::{ExecutionError, Executor},
+    expression::evaluate,
+};
+
+/// Produces a fixed list of rows, e.g. the rows of an INSERT statement. The expressions are
+/// evaluated without an input row.
+pub struct ValuesExecutor {
+    rows: Vec<Vec<BoundExpression>>,
+    position: usize,
+}
+
+impl ValuesExecutor {
+    pub fn new(rows: Vec<Vec<BoundExpression>>) -> ValuesExecutor {
+        ValuesExecutor { rows, position: 0 }
+    }
+}
+
+impl Executor for ValuesExecutor {
+    fn init(&mut self) -> Result<(), ExecutionError> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<(Row, Option<Rid>)>, ExecutionError> {
+        let expressions = match self.rows.get(self.position) {
+            Some(expressions) => expressions,
+            None => return Ok(None),
+        };
+        self.position += 1;
+        let values = expressions
+            .iter()
+            .map(|expression| evaluate(expression, &[]))
+            .collect::<Result<_, _>>()?;
+        Ok(Some((Row::new(values), None)))
+    }
+}
@@ -1,15 +1,20 @@
 use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
+    hash::Hash,
     marker::PhantomData,
     sync::{Arc, Mutex, MutexGuard},
 };
 
 use bincode::{Decode, Encode};
 
-use crate::disk_management::buffer_pool::{BufferPool, RawPage};
+use crate::{
+    disk_management::buffer_pool::{BufferPool, RawPage},
+    recovery::log_record::TransactionId,
+};
 
-use super::{hash_bucket_page::HashBucketPage, hash_directory_page::HashDirectoryPage};
+use super::{
+    fnv_hasher::stable_hash, hash_bucket_page::HashBucketPage,
+    hash_directory_page::HashDirectoryPage,
+};
 use std::fmt::Debug;
 
 pub struct ExtendibleHashing<
@@ -37,23 +42,26 @@ impl<
     }
     pub fn setup_new_hashmap(
         buffer_pool: Arc<Mutex<BufferPool>>,
-        log_id: u32,
+        transaction: Option<TransactionId>,
     ) -> Result<ExtendibleHashing<K, V>, &'static str> {
         let mut buffer_pool_lock = buffer_pool.lock().expect("could not lock buffer_pool");
-        let (directory_page_id, directory_frame_id) = buffer_pool_lock
+        let (directory_page_id, _) = buffer_pool_lock
             .load_new_page()
             .expect("Could not load a new page");
 
-        let bucket1_pid = buffer_pool_lock.allocate_new_page();
-        let bucket2_pid = buffer_pool_lock.allocate_new_page();
+        let bucket1_pid = buffer_pool_lock
+            .allocate_new_page()
+            .or(Err("Could not allocate a bucket page"))?;
+        let bucket2_pid = buffer_pool_lock
+            .allocate_new_page()
+            .or(Err("Could not allocate a bucket page"))?;
         let directory_page = HashDirectoryPage::new_empty(
             directory_page_id as u32,
             bucket1_pid as u32,
             bucket2_pid as u32,
-            log_id,
         );
         buffer_pool_lock
-            .update_page(directory_page_id, directory_page.to_raw_page())
+            .set_page_data(directory_page_id, directory_page.to_raw_page(), transaction)
             .expect("Could not update directory page");
 
         buffer_pool_lock.unload_page_id(directory_page_id).unwrap();
@@ -66,101 +74,163 @@ impl<
     }
 
     fn bucket_index_of_key(key: &K, directory_page: &HashDirectoryPage) -> u64 {
-        let hash = get_hash(key);
-        let bucket = hash % (1 << directory_page.get_global_depth());
-        bucket
+        get_hash(key) % (1 << directory_page.get_global_depth())
     }
 
-    pub fn insert(&self, key: K, value: V) {
+    /// Inserts the key value pair. Entries with the same hash that do not fit into a bucket are
+    /// stored in overflow pages of the bucket.
+    pub fn insert(
+        &self,
+        key: K,
+        value: V,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
         let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
 
-        self.insert_with_lock(&mut buffer_pool_lock, key, value);
+        self.insert_with_lock(&mut buffer_pool_lock, key, value, transaction)
     }
 
-    fn insert_with_lock(&self, buffer_pool_lock: &mut MutexGuard<BufferPool>, key: K, value: V) {
-        let directory_frame_id = buffer_pool_lock
-            .load_page(self.directory_page_id as usize)
-            .expect("Could not load the directory page");
-        let mut directory_page = HashDirectoryPage::from_raw_page(
-            buffer_pool_lock.get_raw_page(directory_frame_id).unwrap(),
-        )
-        .expect("Could not create a directory page from the raw page.");
-        let bucket_index = ExtendibleHashing::<K, V>::bucket_index_of_key(&key, &directory_page);
-        let bucket_page_id = *directory_page
-            .get_bucket_page_id(bucket_index as usize)
-            .unwrap() as usize;
-        let bucket_frame_id = buffer_pool_lock
-            .load_page(bucket_page_id)
-            .expect("Could not load the bucket page");
-        let mut bucket_page = HashBucketPage::<K, V>::from_raw_page(
-            buffer_pool_lock.get_raw_page(bucket_frame_id).unwrap(),
-        );
+    fn insert_with_lock(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        key: K,
+        value: V,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let hash = get_hash(&key);
+        loop {
+            let mut directory_page = self.load_directory_page(buffer_pool_lock);
+            let bucket_index =
+                ExtendibleHashing::<K, V>::bucket_index_of_key(&key, &directory_page) as usize;
+            let bucket_page_id = *directory_page.get_bucket_page_id(bucket_index).unwrap() as usize;
+
+            // Look for a free slot in the pages of the bucket.
+            let mut page_id = bucket_page_id;
+            let mut shares_hash = true;
+            let mut bucket_page = loop {
+                let mut bucket_page = load_bucket_page::<K, V>(buffer_pool_lock, page_id);
+                if !bucket_page.is_full() {
+                    bucket_page.insert(key, value).expect(
+                        "Could not insert into the bucket page that wasn't supposed to be full.",
+                    );
+                    store_page(
+                        buffer_pool_lock,
+                        page_id,
+                        bucket_page.to_raw_page(),
+                        transaction,
+                    );
+                    buffer_pool_lock
+                        .unload_page_id(self.directory_page_id as usize)
+                        .expect("Could not unload the directory page");
+                    return Ok(());
+                }
+                shares_hash &= bucket_page
+                    .readable_key_values()
+                    .all(|(entry_key, _)| get_hash(entry_key) == hash);
+                match bucket_page.overflow_page_id {
+                    Some(overflow_page_id) => {
+                        buffer_pool_lock
+                            .unload_page_id(page_id)
+                            .expect("Could not unload the bucket page");
+                        page_id = overflow_page_id as usize;
+                    }
+                    None => break bucket_page,
+                }
+            };
+
+            // Splitting the bucket cannot make room if all of its entries share the hash.
+            if shares_hash || *directory_page.get_local_depth(bucket_index).unwrap() >= MAX_DEPTH {
+                let (overflow_page_id, overflow_frame_id) = buffer_pool_lock
+                    .load_new_page()
+                    .or(Err("Could not allocate an overflow page"))?;
+                let mut overflow_page = HashBucketPage::<K, V>::from_raw_page(
+                    buffer_pool_lock.get_raw_page(overflow_frame_id).unwrap(),
+                );
+                overflow_page
+                    .insert(key, value)
+                    .expect("Could not insert into the new overflow page.");
+                store_page(
+                    buffer_pool_lock,
+                    overflow_page_id,
+                    overflow_page.to_raw_page(),
+                    transaction,
+                );
+                bucket_page.overflow_page_id = Some(overflow_page_id as u32);
+                store_page(
+                    buffer_pool_lock,
+                    page_id,
+                    bucket_page.to_raw_page(),
+                    transaction,
+                );
+                buffer_pool_lock
+                    .unload_page_id(self.directory_page_id as usize)
+                    .expect("Could not unload the directory page");
+                return Ok(());
+            }
+            buffer_pool_lock
+                .unload_page_id(page_id)
+                .expect("Could not unload the bucket page");
 
-        if bucket_page.is_full() {
             self.split_bucket(
-                bucket_index as usize,
-                &mut bucket_page,
+                bucket_index,
                 &mut directory_page,
                 buffer_pool_lock,
-            )
-            .expect("Could not split bucket");
-            self.update_directory_and_bucket(
+                transaction,
+            )?;
+            store_page(
                 buffer_pool_lock,
+                self.directory_page_id as usize,
                 directory_page.to_raw_page(),
-                bucket_page_id,
-                bucket_page.to_raw_page(),
+                transaction,
             );
-            self.insert_with_lock(buffer_pool_lock, key, value);
-        } else {
-            bucket_page
-                .insert(key, value)
-                .expect("Could not insert into the bucket page that wasn't supposed to be full.");
         }
-        self.update_directory_and_bucket(
-            buffer_pool_lock,
-            directory_page.to_raw_page(),
-            bucket_page_id,
-            bucket_page.to_raw_page(),
-        );
     }
 
-    fn update_directory_and_bucket(
-        &self,
-        buffer_pool_lock: &mut MutexGuard<BufferPool>,
-        directory_page: RawPage,
-        bucket_page_id: usize,
-        bucket_page: RawPage,
-    ) {
-        buffer_pool_lock
-            .update_page(self.directory_page_id as usize, directory_page)
-            .unwrap();
+    /// Returns the values of all entries with the given key.
+    pub fn get_values(&self, key: &K) -> Vec<V> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let directory_page = self.load_directory_page(&mut buffer_pool_lock);
+        let bucket_index = ExtendibleHashing::<K, V>::bucket_index_of_key(key, &directory_page);
+        let bucket_page_id = *directory_page
+            .get_bucket_page_id(bucket_index as usize)
+            .unwrap() as usize;
         buffer_pool_lock
             .unload_page_id(self.directory_page_id as usize)
-            .expect("Could not unload");
-        buffer_pool_lock
-            .update_page(bucket_page_id, bucket_page)
-            .expect("Could not update bucket page.");
-        buffer_pool_lock
-            .unload_page_id(bucket_page_id)
-            .expect("Could not unload");
+            .expect("Could not unload the directory page");
+
+        read_bucket::<K, V>(&mut buffer_pool_lock, bucket_page_id)
+            .0
+            .into_iter()
+            .filter(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+            .collect()
     }
 
+    fn load_directory_page(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    ) -> HashDirectoryPage {
+        let directory_frame_id = buffer_pool_lock
+            .load_page(self.directory_page_id as usize)
+            .expect("Could not load the directory page");
+        HashDirectoryPage::from_raw_page(buffer_pool_lock.get_raw_page(directory_frame_id).unwrap())
+            .expect("Could not create a directory page from the raw page.")
+    }
+
+    /// Splits the bucket at the index of the directory and distributes its entries between the
+    /// old and the new bucket. The overflow pages of the old bucket are reused for the entries
+    /// that do not fit into the first page of a bucket.
     fn split_bucket(
         &self,
         bucket_index: usize,
-        bucket_page: &mut HashBucketPage<K, V>,
         directory_page: &mut HashDirectoryPage,
         buffer_pool_lock: &mut MutexGuard<BufferPool>,
-    ) -> Result<(), &str> {
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
         let new_local_depth = directory_page.increment_local_depth(bucket_index).unwrap();
-        let (new_bucket_page_id, new_bucket_page_frame_id) =
-            buffer_pool_lock.load_new_page().unwrap();
-
-        let mut new_bucket_page = HashBucketPage::<K, V>::from_raw_page(
-            buffer_pool_lock
-                .get_raw_page(new_bucket_page_frame_id)
-                .unwrap(),
-        );
+        let new_bucket_page_id = buffer_pool_lock
+            .allocate_new_page()
+            .or(Err("Could not allocate a bucket page"))?;
         let old_bucket_page_id = *directory_page.get_bucket_page_id(bucket_index).unwrap();
 
         // Let old bucket be with 1 in front, new with 0.
@@ -176,61 +246,178 @@ impl<
             );
         }
 
-        for i in 0..bucket_page.key_values.len() {
-            let key = bucket_page.key_at(i).unwrap();
-            if (get_hash(key) >> (new_local_depth - 1)) & 1 == 0 {
-                let key_value = bucket_page.remove_index(i).unwrap();
-                new_bucket_page
-                    .insert(key_value.0, key_value.1)
-                    .expect("Could not insert the value into the new bucket.");
-            }
+        let (key_values, mut spare_page_ids) =
+            read_bucket::<K, V>(buffer_pool_lock, old_bucket_page_id as usize);
+        let (new_key_values, old_key_values): (Vec<_>, Vec<_>) = key_values
+            .into_iter()
+            .partition(|(key, _)| (get_hash(key) >> (new_local_depth - 1)) & 1 == 0);
+        for (bucket_page_id, key_values) in [
+            (old_bucket_page_id as usize, old_key_values),
+            (new_bucket_page_id, new_key_values),
+        ] {
+            write_bucket(
+                buffer_pool_lock,
+                bucket_page_id,
+                key_values,
+                &mut spare_page_ids,
+                transaction,
+            )?;
         }
-
-        buffer_pool_lock
-            .update_page(new_bucket_page_id, new_bucket_page.to_raw_page())
-            .expect("Could not update new page");
-        buffer_pool_lock
-            .unload_page_id(new_bucket_page_id)
-            .expect("Could not unload new bucket page");
-
         Ok(())
     }
 
-    fn remove(&self, key: K) -> Option<(K, V)> {
-        let mut lock = self
+    /// Removes the entry with the given key and value. Returns false if there is no such entry.
+    pub fn remove(&self, key: &K, value: &V, transaction: Option<TransactionId>) -> bool
+    where
+        V: PartialEq,
+    {
+        let mut buffer_pool_lock = self
             .buffer_pool
             .lock()
             .expect("Could not lock the buffer pool.");
 
-        let directory_frame = lock
-            .load_page(self.directory_page_id as usize)
-            .expect("Could not load directory page");
+        let directory_page = self.load_directory_page(&mut buffer_pool_lock);
+        let index = ExtendibleHashing::<K, V>::bucket_index_of_key(key, &directory_page);
+        let mut page_id = (*directory_page.get_bucket_page_id(index as usize).unwrap()) as usize;
+        buffer_pool_lock
+            .unload_page_id(self.directory_page_id as usize)
+            .expect("Could not unload the directory page");
 
-        let directory_page = HashDirectoryPage::from_raw_page(
-            lock.get_raw_page(directory_frame)
-                .expect("Could not load previously loaded frame"),
-        )
-        .expect("Could not parse directory page from raw page");
-
-        let index = ExtendibleHashing::<K, V>::bucket_index_of_key(&key, &directory_page);
-
-        let bucket_pid = (*directory_page.get_bucket_page_id(index as usize).unwrap()) as usize;
-        let bucket_frame = lock.load_page(bucket_pid)?;
-
-        let mut bucket_page =
-            HashBucketPage::<K, V>::from_raw_page(lock.get_raw_page(bucket_frame).unwrap());
-        let result = bucket_page.remove(&key).ok();
-        let raw_page = bucket_page.to_raw_page();
-        self.update_directory_and_bucket(
-            &mut lock,
-            directory_page.to_raw_page(),
-            bucket_pid,
-            raw_page,
+        loop {
+            let mut bucket_page = load_bucket_page::<K, V>(&mut buffer_pool_lock, page_id);
+            let position = (0..bucket_page.key_values.len()).find(|i| {
+                *bucket_page.is_readable(*i).unwrap()
+                    && bucket_page.key_values[*i] == (key.clone(), value.clone())
+            });
+            if let Some(position) = position {
+                bucket_page.remove_index(position).unwrap();
+                store_page(
+                    &mut buffer_pool_lock,
+                    page_id,
+                    bucket_page.to_raw_page(),
+                    transaction,
+                );
+                return true;
+            }
+            buffer_pool_lock
+                .unload_page_id(page_id)
+                .expect("Could not unload the bucket page");
+            match bucket_page.overflow_page_id {
+                Some(overflow_page_id) => page_id = overflow_page_id as usize,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// The directory page can hold 2^9 bucket page ids.
+const MAX_DEPTH: u8 = 9;
+
+fn load_bucket_page<
+    K: Clone + Eq + Debug + Encode + Decode + Default,
+    V: Clone + Debug + Encode + Decode + Default,
+>(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    bucket_page_id: usize,
+) -> HashBucketPage<K, V> {
+    let bucket_frame_id = buffer_pool_lock
+        .load_page(bucket_page_id)
+        .expect("Could not load the bucket page");
+    HashBucketPage::<K, V>::from_raw_page(buffer_pool_lock.get_raw_page(bucket_frame_id).unwrap())
+}
+
+/// Returns the entries of the bucket, including the ones in its overflow pages, and the ids of
+/// its overflow pages.
+fn read_bucket<
+    K: Clone + Eq + Debug + Encode + Decode + Default,
+    V: Clone + Debug + Encode + Decode + Default,
+>(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    bucket_page_id: usize,
+) -> (Vec<(K, V)>, Vec<usize>) {
+    let mut key_values = vec![];
+    let mut overflow_page_ids = vec![];
+    let mut page_id = bucket_page_id;
+    loop {
+        let bucket_page = load_bucket_page::<K, V>(buffer_pool_lock, page_id);
+        buffer_pool_lock
+            .unload_page_id(page_id)
+            .expect("Could not unload the bucket page");
+        key_values.extend(bucket_page.readable_key_values().cloned());
+        match bucket_page.overflow_page_id {
+            Some(overflow_page_id) => {
+                page_id = overflow_page_id as usize;
+                overflow_page_ids.push(page_id);
+            }
+            None => return (key_values, overflow_page_ids),
+        }
+    }
+}
+
+/// Replaces the content of the bucket with the entries. Entries that do not fit into the bucket
+/// page go into overflow pages, which are taken from the spare pages or allocated.
+fn write_bucket<
+    K: Clone + Eq + Debug + Encode + Decode + Default,
+    V: Clone + Debug + Encode + Decode + Default,
+>(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    bucket_page_id: usize,
+    key_values: Vec<(K, V)>,
+    spare_page_ids: &mut Vec<usize>,
+    transaction: Option<TransactionId>,
+) -> Result<(), &'static str> {
+    let mut chunks = key_values
+        .chunks(HashBucketPage::<K, V>::capacity())
+        .peekable();
+    let mut page_id = bucket_page_id;
+    loop {
+        let mut bucket_page = HashBucketPage::<K, V>::new_empty();
+        for (key, value) in chunks.next().unwrap_or_default() {
+            bucket_page
+                .insert(key.clone(), value.clone())
+                .expect("Could not insert into an empty bucket page");
+        }
+        let overflow_page_id = match chunks.peek() {
+            Some(_) => Some(match spare_page_ids.pop() {
+                Some(spare_page_id) => spare_page_id,
+                None => buffer_pool_lock
+                    .allocate_new_page()
+                    .or(Err("Could not allocate an overflow page"))?,
+            }),
+            None => None,
+        };
+        bucket_page.overflow_page_id = overflow_page_id.map(|page_id| page_id as u32);
+        buffer_pool_lock
+            .load_page(page_id)
+            .or(Err("Could not load the bucket page"))?;
+        store_page(
+            buffer_pool_lock,
+            page_id,
+            bucket_page.to_raw_page(),
+            transaction,
         );
-        result
+        match overflow_page_id {
+            Some(overflow_page_id) => page_id = overflow_page_id,
+            None => return Ok(()),
+        }
     }
 }
 
+/// Replaces the content of a pinned page and unpins it.
+fn store_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: usize,
+    raw_page: RawPage,
+    transaction: Option<TransactionId>,
+) {
+    buffer_pool_lock
+        .set_page_data(page_id, raw_page, transaction)
+        .expect("Could not update the page");
+    buffer_pool_lock
+        .unload_page_id(page_id)
+        .expect("Could not unload the page");
+}
+
 fn local_split_bucket(
     directory_page: &mut HashDirectoryPage,
     new_local_depth: u8,
@@ -279,8 +466,68 @@ fn global_split_bucket(
         .expect("Could not set the bucket page id");
 }
 
-fn get_hash<K: Hash>(key: K) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    key.hash(&mut hasher);
-    hasher.finish()
+fn get_hash<K: Hash>(key: &K) -> u64 {
+    stable_hash(key)
+}
+
+#[cfg(test)]
+mod extendible_hashing_tests {
+    use crate::disk_management::test_utils::new_test_buffer_pool;
+
+    use super::ExtendibleHashing;
+
+    #[test]
+    fn insert_get_and_remove() {
+        let hashing = ExtendibleHashing::<u64, u64>::setup_new_hashmap(
+            new_test_buffer_pool("hashing_insert_get_remove"),
+            None,
+        )
+        .unwrap();
+        // Enough entries to need more buckets than there are frames in the buffer pool.
+        for i in 0..15_000 {
+            hashing.insert(i, i * 2, None).unwrap();
+        }
+        hashing.insert(7, 1, None).unwrap();
+
+        for i in (0..15_000).step_by(97) {
+            assert_eq!(hashing.get_values(&i).len(), if i == 7 { 2 } else { 1 });
+        }
+        let mut values = hashing.get_values(&7);
+        values.sort();
+        assert_eq!(values, vec![1, 14]);
+        assert!(hashing.get_values(&15_000).is_empty());
+
+        assert!(hashing.remove(&7, &14, None));
+        assert!(!hashing.remove(&7, &14, None));
+        assert_eq!(hashing.get_values(&7), vec![1]);
+    }
+
+    #[test]
+    fn duplicates_overflow_into_chained_pages() {
+        let hashing = ExtendibleHashing::<u64, u64>::setup_new_hashmap(
+            new_test_buffer_pool("hashing_duplicates_overflow"),
+            None,
+        )
+        .unwrap();
+        for i in 0..1_000 {
+            hashing.insert(1, i, None).unwrap();
+        }
+        // Splits the bucket of the duplicates until the maximum depth.
+        for i in 2..3_000 {
+            hashing.insert(i, i, None).unwrap();
+        }
+
+        let mut values = hashing.get_values(&1);
+        values.sort();
+        assert_eq!(values, (0..1_000).collect::<Vec<_>>());
+        assert_eq!(hashing.get_values(&2_999), vec![2_999]);
+        for i in (0..1_000).step_by(2) {
+            assert!(hashing.remove(&1, &i, None));
+        }
+        assert_eq!(hashing.get_values(&1).len(), 500);
+        for i in 1_000..1_500 {
+            hashing.insert(1, i, None).unwrap();
+        }
+        assert_eq!(hashing.get_values(&1).len(), 1_000);
+    }
 }
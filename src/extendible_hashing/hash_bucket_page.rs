@@ -2,6 +2,15 @@ use bincode::{Decode, Encode};
 
 use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
 use std::fmt::Debug;
+
+/// The page id stored in place of the overflow page id of the last page of a bucket. Page 0 is
+/// never a bucket page, and fresh pages contain zeros.
+const NO_OVERFLOW_PAGE_ID: u32 = 0;
+
+/// A page of a bucket of the extendible hash table. A bucket whose entries do not fit into one
+/// page, because they share a hash, continues in a chain of overflow pages.
+///
+/// | READABLE (1 each) | HAS_BEEN_OCCUPIED (1 each) | KEY_VALUES | ... | OVERFLOW_PAGE_ID (4) |
 #[derive(Debug)]
 pub struct HashBucketPage<
     K: Clone + Debug + Encode + Decode + Default,
@@ -10,12 +19,23 @@ pub struct HashBucketPage<
     readable: Vec<bool>,
     has_been_occupied: Vec<bool>,
     pub key_values: Vec<(K, V)>,
+    pub overflow_page_id: Option<u32>,
 }
 impl<
         K: Clone + Eq + Debug + Encode + Decode + Default,
         V: Clone + Debug + Encode + Decode + Default,
     > HashBucketPage<K, V>
 {
+    /// The number of entries a page fits.
+    pub fn capacity() -> usize {
+        let length_of_single_entry = 1 + 1 + std::mem::size_of::<K>() + std::mem::size_of::<V>();
+        (PAGE_SIZE - 4) / length_of_single_entry
+    }
+
+    pub fn new_empty() -> HashBucketPage<K, V> {
+        HashBucketPage::from_raw_page(&RawPage::new([0; PAGE_SIZE]))
+    }
+
     pub fn from_raw_page(raw_page: &RawPage) -> HashBucketPage<K, V> {
         let data = raw_page
             .data
@@ -23,8 +43,7 @@ impl<
             .expect("Could not read the raw_page data");
         let key_length = std::mem::size_of::<K>();
         let value_length = std::mem::size_of::<V>();
-        let length_of_single_entry = 1 + 1 + key_length as usize + value_length as usize;
-        let number_of_entries = (PAGE_SIZE) / length_of_single_entry;
+        let number_of_entries = HashBucketPage::<K, V>::capacity();
 
         let mut readable = Vec::with_capacity(number_of_entries);
         let mut has_been_occupied = Vec::with_capacity(number_of_entries);
@@ -44,10 +63,16 @@ impl<
             key_values.push(key_value);
         }
 
+        let overflow_page_id = match u32::from_be_bytes(data[PAGE_SIZE - 4..].try_into().unwrap()) {
+            NO_OVERFLOW_PAGE_ID => None,
+            page_id => Some(page_id),
+        };
+
         HashBucketPage {
             readable,
             has_been_occupied,
             key_values,
+            overflow_page_id,
         }
     }
     pub fn toggle_readable(&mut self, index: usize) -> Result<(), &str> {
@@ -64,20 +89,6 @@ impl<
         self.readable.get(index)
     }
 
-    pub fn toggle_occupied(&mut self, index: usize) -> Result<(), &str> {
-        let element = self.has_been_occupied.get_mut(index);
-        if let Some(some_elem) = element {
-            // Toggle the bit
-            *some_elem ^= true;
-            return Ok(());
-        }
-        Err("The index is out of bounds.")
-    }
-
-    pub fn has_been_occupied(&self, index: usize) -> Option<&bool> {
-        self.has_been_occupied.get(index)
-    }
-
     pub fn set_has_been_occupied(
         &mut self,
         index: usize,
@@ -93,22 +104,11 @@ impl<
     }
 
     pub fn is_full(&self) -> bool {
-        self.readable.iter().all(|is_readable| *is_readable == true)
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.readable
-            .iter()
-            .all(|is_readable| *is_readable == false)
+        self.readable.iter().all(|is_readable| *is_readable)
     }
 
     fn first_free_index(&self) -> Option<usize> {
-        for i in 0..self.readable.len() {
-            if !self.readable[i] {
-                return Some(i);
-            }
-        }
-        None
+        self.readable.iter().position(|is_readable| !is_readable)
     }
 
     pub fn insert(&mut self, key: K, value: V) -> Result<(), &str> {
@@ -136,35 +136,13 @@ impl<
             .ok_or("Could not replace the old value with defaults")
     }
 
-    pub fn remove(&mut self, key_to_remove: &K) -> Result<(K, V), &str> {
-        let index = self
-            .key_values
+    /// Returns the entries of the readable slots.
+    pub fn readable_key_values(&self) -> impl Iterator<Item = &(K, V)> {
+        self.key_values
             .iter()
-            .enumerate()
-            .filter(|(i, (key, _))| {
-                key == key_to_remove && *self.is_readable(*i).expect("Unreachable")
-            })
-            .next()
-            .and_then(|(i, _)| Some(i));
-
-        if let Some(index_to_remove) = index {
-            self.toggle_readable(index_to_remove).expect("Unreachable");
-            return self
-                .key_values
-                .splice(index_to_remove..index_to_remove + 1, [Default::default()])
-                .next()
-                .ok_or("Could not replace the old value with defaults");
-        }
-
-        Err("The requested key does not exist.")
-    }
-
-    pub fn key_at(&self, index: usize) -> Option<&K> {
-        self.key_values.get(index).and_then(|key| Some(&key.0))
-    }
-
-    pub fn value_at(&self, index: usize) -> Option<&V> {
-        self.key_values.get(index).and_then(|key| Some(&key.1))
+            .zip(&self.readable)
+            .filter(|(_, readable)| **readable)
+            .map(|(key_value, _)| key_value)
     }
 
     pub fn to_raw_page(&self) -> RawPage {
@@ -188,14 +166,20 @@ impl<
         for key_value in &self.key_values {
             data.append(
                 &mut bincode::encode_to_vec(
-                    &key_value,
+                    key_value,
                     bincode::config::standard().with_fixed_int_encoding(),
                 )
                 .expect("Could not encode value to binary"),
             );
         }
 
-        data.append(&mut vec![0; PAGE_SIZE - data.len()]);
+        data.append(&mut vec![0; PAGE_SIZE - 4 - data.len()]);
+        data.extend_from_slice(
+            &self
+                .overflow_page_id
+                .unwrap_or(NO_OVERFLOW_PAGE_ID)
+                .to_be_bytes(),
+        );
         RawPage::new(data.try_into().expect(""))
     }
 }
@@ -2,16 +2,14 @@ use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
 
 /// Hash directory page layout:
 /// First four bytes: own page id
-/// Second four bytes: log id
 /// Next byte: global_depth
 /// Next 817 bytes: u8 values of local depths
 /// Next 817 * 4 bytes: u32 page_id values for the buckets
-
+///
 /// 512 page_ids can be stored as a result of the page_size, since the buckets must follow 2^n
 #[derive(Debug)]
 pub struct HashDirectoryPage {
     page_id: u32,
-    log_id: u32,
     global_depth: u8,
     local_depths: [u8; 512],
     pub bucket_page_ids: [u32; 512],
@@ -23,30 +21,23 @@ impl HashDirectoryPage {
             .with_fixed_int_encoding()
             .skip_fixed_array_length();
         let page_id: u32 = bincode::decode_from_slice(&bytes[0..4], config).unwrap().0;
-        let log_id: u32 = bincode::decode_from_slice(&bytes[4..8], config).unwrap().0;
-        let global_depth: u8 = bytes[8];
+        let global_depth: u8 = bytes[4];
 
         // 64 bytes of u8 values
-        let local_depths: [u8; 512] = bytes[9..521].try_into().unwrap();
+        let local_depths: [u8; 512] = bytes[5..517].try_into().unwrap();
 
-        let bucket_page_ids: [u32; 512] = bincode::decode_from_slice(&bytes[521..2569], config)
+        let bucket_page_ids: [u32; 512] = bincode::decode_from_slice(&bytes[517..2565], config)
             .unwrap()
             .0;
 
         Ok(HashDirectoryPage {
             page_id,
-            log_id,
             global_depth,
             local_depths,
             bucket_page_ids,
         })
     }
-    pub fn new_empty(
-        own_pid: u32,
-        bucket1_pid: u32,
-        bucket2_pid: u32,
-        log_id: u32,
-    ) -> HashDirectoryPage {
+    pub fn new_empty(own_pid: u32, bucket1_pid: u32, bucket2_pid: u32) -> HashDirectoryPage {
         let mut local_depths = [0; 512];
         local_depths[0] = 1;
         local_depths[1] = 1;
@@ -56,7 +47,6 @@ impl HashDirectoryPage {
         bucket_pids[1] = bucket2_pid;
         HashDirectoryPage {
             page_id: own_pid,
-            log_id,
             global_depth: 1,
             local_depths,
             bucket_page_ids: bucket_pids,
@@ -73,10 +63,6 @@ impl HashDirectoryPage {
                 .expect("Could not encode the page_id into slice of u8s"),
         );
 
-        vec.append(
-            &mut bincode::encode_to_vec(self.log_id, bincode_config)
-                .expect("Could not encode the page_id into slice of u8s"),
-        );
         vec.push(self.global_depth);
         vec.extend(self.local_depths);
         vec.append(
@@ -1,3 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod extendible_hashing;
+pub mod fnv_hasher;
 pub mod hash_bucket_page;
 pub mod hash_directory_page;
@@ -0,0 +1,2 @@
+pub mod ordered_key;
+pub mod table_index;
@@ -0,0 +1,209 @@
+use bincode::{
+    de::{read::Reader, Decoder},
+    enc::{write::Writer, Encoder},
+    error::{DecodeError, EncodeError},
+    Decode, Encode,
+};
+use chrono::Datelike;
+
+use crate::types::value::Value;
+
+/// The number of bytes of an ordered key. Longer encodings are truncated.
+pub const ORDERED_KEY_SIZE: usize = 32;
+
+// KEY FORMAT:
+// | TYPE [u8] | VALUE ... | for every key column, truncated or padded to ORDERED_KEY_SIZE bytes.
+//
+// The type is the rank of the value in the order of [Value], so NULL sorts first. Integers are
+// stored big-endian with a flipped sign bit and floats with all bits flipped if negative, so
+// their bytes sort like their values. Strings and blobs end with 0x00 0x00 and escape 0x00 as
+// 0x00 0xFF, so that no encoding is a prefix of another one.
+
+/// The key of an entry of a B+ tree index. The keys of two rows compare like the values of their
+/// key columns, except that values whose encodings only differ after the first
+/// [ORDERED_KEY_SIZE] bytes get the same key, and so do positive and negative zero. The rows of
+/// an index entry therefore have to be checked for the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OrderedKey([u8; ORDERED_KEY_SIZE]);
+
+impl OrderedKey {
+    pub fn new(key: &[Value]) -> OrderedKey {
+        OrderedKey::padded(key, 0)
+    }
+
+    /// Returns the smallest key of the rows whose first key column is at least the value.
+    pub fn lower_bound(value: &Value) -> OrderedKey {
+        OrderedKey::padded(std::slice::from_ref(value), 0)
+    }
+
+    /// Returns the largest key of the rows whose first key column is at most the value.
+    pub fn upper_bound(value: &Value) -> OrderedKey {
+        OrderedKey::padded(std::slice::from_ref(value), u8::MAX)
+    }
+
+    /// Returns the smallest key of the rows whose first key column is not NULL.
+    pub fn min_not_null() -> OrderedKey {
+        let mut bytes = [0; ORDERED_KEY_SIZE];
+        bytes[0] = Value::Null.type_rank() + 1;
+        OrderedKey(bytes)
+    }
+
+    pub fn max() -> OrderedKey {
+        OrderedKey([u8::MAX; ORDERED_KEY_SIZE])
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<OrderedKey, &'static str> {
+        Ok(OrderedKey(
+            bytes.try_into().or(Err("Malformed ordered index key"))?,
+        ))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn padded(key: &[Value], padding: u8) -> OrderedKey {
+        let mut encoded = Vec::with_capacity(ORDERED_KEY_SIZE);
+        for value in key {
+            encode_value(value, &mut encoded);
+        }
+        encoded.resize(ORDERED_KEY_SIZE.max(encoded.len()), padding);
+        OrderedKey(encoded[..ORDERED_KEY_SIZE].try_into().unwrap())
+    }
+}
+
+fn encode_value(value: &Value, bytes: &mut Vec<u8>) {
+    bytes.push(value.type_rank());
+    match value {
+        Value::Null => {}
+        Value::Integer(value) => bytes.extend_from_slice(&flip_sign_32(*value)),
+        Value::BigInt(value) => bytes.extend_from_slice(&flip_sign_64(*value)),
+        Value::Float(value) => {
+            // Adding zero turns negative into positive zero.
+            let bits = (value + 0.0).to_bits();
+            let bits = match bits >> 63 {
+                0 => bits | 1 << 63,
+                _ => !bits,
+            };
+            bytes.extend_from_slice(&bits.to_be_bytes());
+        }
+        Value::Boolean(value) => bytes.push(*value as u8),
+        Value::String(value) => encode_bytes(value.as_bytes(), bytes),
+        Value::Blob(value) => encode_bytes(value, bytes),
+        Value::Date(value) => bytes.extend_from_slice(&flip_sign_32(value.num_days_from_ce())),
+        Value::Timestamp(value) => {
+            bytes.extend_from_slice(&flip_sign_64(value.and_utc().timestamp_micros()))
+        }
+    }
+}
+
+fn flip_sign_32(value: i32) -> [u8; 4] {
+    (value as u32 ^ 1 << 31).to_be_bytes()
+}
+
+fn flip_sign_64(value: i64) -> [u8; 8] {
+    (value as u64 ^ 1 << 63).to_be_bytes()
+}
+
+fn encode_bytes(value: &[u8], bytes: &mut Vec<u8>) {
+    for byte in value {
+        bytes.push(*byte);
+        if *byte == 0 {
+            bytes.push(u8::MAX);
+        }
+    }
+    bytes.extend_from_slice(&[0, 0]);
+}
+
+// The bytes are stored as they are, without the length that bincode writes in front of arrays,
+// so that a key takes exactly ORDERED_KEY_SIZE bytes in a B+ tree page.
+
+impl Encode for OrderedKey {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encoder.writer().write(&self.0)
+    }
+}
+
+impl Decode for OrderedKey {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        decoder.claim_bytes_read(ORDERED_KEY_SIZE)?;
+        let mut bytes = [0; ORDERED_KEY_SIZE];
+        decoder.reader().read(&mut bytes)?;
+        Ok(OrderedKey(bytes))
+    }
+}
+
+#[cfg(test)]
+mod ordered_key_tests {
+    use chrono::NaiveDate;
+
+    use crate::types::value::Value;
+
+    use super::OrderedKey;
+
+    #[test]
+    fn keys_sort_like_their_values() {
+        let date = |day| Value::Date(NaiveDate::from_ymd_opt(2024, 1, day).unwrap());
+        let values = vec![
+            Value::Null,
+            Value::Integer(i32::MIN),
+            Value::Integer(-1),
+            Value::Integer(0),
+            Value::Integer(7),
+            Value::BigInt(-5_000_000_000),
+            Value::BigInt(3),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Float(-2.5),
+            Value::Float(0.0),
+            Value::Float(1e-300),
+            Value::Float(2.5),
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::String("".to_string()),
+            Value::String("a".to_string()),
+            Value::String("a\0".to_string()),
+            Value::String("a\0b".to_string()),
+            Value::String("ab".to_string()),
+            Value::String("b".to_string()),
+            Value::Blob(vec![0, 0]),
+            Value::Blob(vec![1]),
+            date(1),
+            date(2),
+        ];
+        for pair in values.windows(2) {
+            assert!(
+                OrderedKey::new(&pair[..1]) < OrderedKey::new(&pair[1..]),
+                "{:?}",
+                pair
+            );
+        }
+        assert_eq!(
+            OrderedKey::new(&[Value::Float(-0.0)]),
+            OrderedKey::new(&[Value::Float(0.0)])
+        );
+        assert!(OrderedKey::new(&[Value::Null]) < OrderedKey::min_not_null());
+        assert!(OrderedKey::min_not_null() <= OrderedKey::new(&[Value::Integer(i32::MIN)]));
+    }
+
+    #[test]
+    fn bounds_contain_all_keys_with_the_first_value() {
+        let key = |first: &str, second: i32| {
+            OrderedKey::new(&[Value::String(first.to_string()), Value::Integer(second)])
+        };
+        let value = Value::String("b".to_string());
+        let (lower, upper) = (
+            OrderedKey::lower_bound(&value),
+            OrderedKey::upper_bound(&value),
+        );
+        assert!(key("a", i32::MAX) < lower);
+        assert!(lower <= key("b", i32::MIN));
+        assert!(key("b", i32::MAX) <= upper);
+        assert!(upper < key("b\0", i32::MIN));
+
+        // Truncated keys keep their order, but long values with a common prefix collide.
+        let long = |suffix: &str| Value::String("x".repeat(40) + suffix);
+        assert_eq!(OrderedKey::new(&[long("a")]), OrderedKey::new(&[long("b")]));
+        assert!(OrderedKey::lower_bound(&long("b")) <= OrderedKey::new(&[long("a")]));
+        assert!(OrderedKey::new(&[long("c")]) <= OrderedKey::upper_bound(&long("b")));
+    }
+}
@@ -0,0 +1,326 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{
+    b_plus_tree::b_plus_tree::BPlusTree,
+    catalog::system_catalog::{IndexInfo, IndexType},
+    disk_management::buffer_pool::BufferPool,
+    extendible_hashing::{extendible_hashing::ExtendibleHashing, fnv_hasher::stable_hash},
+    index::ordered_key::OrderedKey,
+    recovery::log_record::{LogicalChange, TransactionId},
+    table::table_page::Rid,
+    types::value::Value,
+};
+
+/// An index of a table, mapping the values of the key columns of each row to its Rid.
+///
+/// Hash indexes store the hash of the key values, which is stable across builds as it is stored
+/// on disk. B+ tree indexes store an [OrderedKey], which keeps the order of the keys, so they can
+/// also be scanned for a range of keys with [Index::scan_range]. Different keys may share an
+/// entry in both kinds of indexes, so the rows of all Rids returned by a scan have to be checked
+/// for the key. Any number of rows may share a key.
+///
+/// The changes of entries made for a transaction are logged as logical changes, so that recovery
+/// undoes them through the index rather than by restoring its pages.
+pub struct Index {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    index_type: IndexType,
+    root_page_id: u32,
+    entries: IndexEntries,
+}
+
+enum IndexEntries {
+    Hash(ExtendibleHashing<u64, u64>),
+    BPlusTree(BPlusTree<OrderedKey, u64>),
+}
+
+impl Index {
+    pub fn open(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        index_info: &IndexInfo,
+    ) -> Result<Index, &'static str> {
+        Ok(Index::open_root(
+            buffer_pool,
+            index_info.index_type,
+            index_info.root_page_id,
+        ))
+    }
+
+    /// Opens the index of the given type with the given directory or header page.
+    pub fn open_root(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        index_type: IndexType,
+        root_page_id: u32,
+    ) -> Index {
+        let entries = match index_type {
+            IndexType::Hash => {
+                IndexEntries::Hash(ExtendibleHashing::new(buffer_pool.clone(), root_page_id))
+            }
+            IndexType::BPlusTree => {
+                IndexEntries::BPlusTree(BPlusTree::new(buffer_pool.clone(), root_page_id))
+            }
+        };
+        Index {
+            buffer_pool,
+            index_type,
+            root_page_id,
+            entries,
+        }
+    }
+
+    /// Adds the entry of the row with the given key and Rid. The change is logged for the
+    /// transaction.
+    pub fn insert_entry(
+        &self,
+        key: &[Value],
+        rid: Rid,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let undo_next_lsn = self.lock_buffer_pool().last_lsn(transaction);
+        let key = self.stored_key(key);
+        self.insert_stored(&key, rid, transaction)?;
+        self.lock_buffer_pool().log_logical_change(
+            transaction,
+            LogicalChange::IndexEntryInserted {
+                index_type: self.index_type,
+                root_page_id: self.root_page_id,
+                key,
+                rid,
+            },
+            undo_next_lsn,
+        );
+        Ok(())
+    }
+
+    /// Removes the entry of the row with the given key and Rid. Returns false if there is no such
+    /// entry.
+    pub fn delete_entry(
+        &self,
+        key: &[Value],
+        rid: Rid,
+        transaction: Option<TransactionId>,
+    ) -> bool {
+        let undo_next_lsn = self.lock_buffer_pool().last_lsn(transaction);
+        let key = self.stored_key(key);
+        let deleted = self
+            .delete_stored(&key, rid, transaction)
+            .expect("The stored key of the index is malformed");
+        if deleted {
+            self.lock_buffer_pool().log_logical_change(
+                transaction,
+                LogicalChange::IndexEntryDeleted {
+                    index_type: self.index_type,
+                    root_page_id: self.root_page_id,
+                    key,
+                    rid,
+                },
+                undo_next_lsn,
+            );
+        }
+        deleted
+    }
+
+    /// Removes the entry with the key as logged in a logical change. The removal is not logged
+    /// for a transaction. Returns false if there is no such entry.
+    pub fn delete_logged_entry(&self, key: &[u8], rid: Rid) -> Result<bool, &'static str> {
+        self.delete_stored(key, rid, None)
+    }
+
+    /// Adds the entry with the key as logged in a logical change, unless the index contains it.
+    /// The change is not logged for a transaction.
+    pub fn restore_logged_entry(&self, key: &[u8], rid: Rid) -> Result<(), &'static str> {
+        if self.get_stored(key)?.contains(&rid) {
+            return Ok(());
+        }
+        self.insert_stored(key, rid, None)
+    }
+
+    /// Returns the Rids of all rows that may have the given key.
+    pub fn scan_key(&self, key: &[Value]) -> Vec<Rid> {
+        self.get_stored(&self.stored_key(key))
+            .expect("The stored key of the index is malformed")
+    }
+
+    /// Returns the Rids of all rows whose first key column may lie between the bounds, which are
+    /// both inclusive. A missing bound leaves the range open, but the rows whose first key
+    /// column is NULL are never returned. Only B+ tree indexes can be scanned for a range.
+    pub fn scan_range(
+        &self,
+        lower: Option<&Value>,
+        upper: Option<&Value>,
+    ) -> Result<Vec<Rid>, &'static str> {
+        let IndexEntries::BPlusTree(tree) = &self.entries else {
+            return Err("Only B+ tree indexes can be scanned for a range of keys");
+        };
+        let lower = lower.map_or_else(OrderedKey::min_not_null, OrderedKey::lower_bound);
+        let upper = upper.map_or_else(OrderedKey::max, OrderedKey::upper_bound);
+        Ok(tree
+            .get_range(&lower, &upper)
+            .into_iter()
+            .map(u64_to_rid)
+            .collect())
+    }
+
+    /// Returns the key as it is stored in the index and logged in logical changes: the hash of
+    /// the key values in hash indexes and their [OrderedKey] in B+ tree indexes.
+    fn stored_key(&self, key: &[Value]) -> Vec<u8> {
+        match self.index_type {
+            IndexType::Hash => stable_hash(key).to_le_bytes().to_vec(),
+            IndexType::BPlusTree => OrderedKey::new(key).as_bytes().to_vec(),
+        }
+    }
+
+    fn insert_stored(
+        &self,
+        key: &[u8],
+        rid: Rid,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        match &self.entries {
+            IndexEntries::Hash(hashing) => {
+                hashing.insert(hash_from_bytes(key)?, rid_to_u64(rid), transaction)
+            }
+            IndexEntries::BPlusTree(tree) => {
+                tree.insert(OrderedKey::from_bytes(key)?, rid_to_u64(rid), transaction)
+            }
+        }
+    }
+
+    fn delete_stored(
+        &self,
+        key: &[u8],
+        rid: Rid,
+        transaction: Option<TransactionId>,
+    ) -> Result<bool, &'static str> {
+        Ok(match &self.entries {
+            IndexEntries::Hash(hashing) => {
+                hashing.remove(&hash_from_bytes(key)?, &rid_to_u64(rid), transaction)
+            }
+            IndexEntries::BPlusTree(tree) => {
+                tree.remove(&OrderedKey::from_bytes(key)?, &rid_to_u64(rid), transaction)
+            }
+        })
+    }
+
+    fn get_stored(&self, key: &[u8]) -> Result<Vec<Rid>, &'static str> {
+        let values = match &self.entries {
+            IndexEntries::Hash(hashing) => hashing.get_values(&hash_from_bytes(key)?),
+            IndexEntries::BPlusTree(tree) => tree.get_values(&OrderedKey::from_bytes(key)?),
+        };
+        Ok(values.into_iter().map(u64_to_rid).collect())
+    }
+
+    fn lock_buffer_pool(&self) -> MutexGuard<'_, BufferPool> {
+        self.buffer_pool.lock().expect("Could not lock buffer pool")
+    }
+}
+
+/// Returns the values of the key columns of the index in a row of its table.
+pub fn index_key(index_info: &IndexInfo, values: &[Value]) -> Vec<Value> {
+    index_info
+        .key_columns
+        .iter()
+        .map(|position| values[*position].clone())
+        .collect()
+}
+
+fn hash_from_bytes(key: &[u8]) -> Result<u64, &'static str> {
+    Ok(u64::from_le_bytes(
+        key.try_into().or(Err("Malformed hashed index key"))?,
+    ))
+}
+
+fn rid_to_u64(rid: Rid) -> u64 {
+    ((rid.page_id as u64) << 32) | rid.slot_id as u64
+}
+
+fn u64_to_rid(value: u64) -> Rid {
+    Rid::new((value >> 32) as u32, value as u32)
+}
+
+#[cfg(test)]
+mod index_tests {
+    use crate::{
+        b_plus_tree::b_plus_tree::BPlusTree,
+        catalog::system_catalog::{IndexInfo, IndexType},
+        disk_management::test_utils::new_test_buffer_pool,
+        extendible_hashing::extendible_hashing::ExtendibleHashing,
+        index::ordered_key::OrderedKey,
+        table::table_page::Rid,
+        types::value::Value,
+    };
+
+    use super::Index;
+
+    fn check_index_entries(index_type: IndexType, name: &str) {
+        let buffer_pool = new_test_buffer_pool(name);
+        let root_page_id = match index_type {
+            IndexType::Hash => {
+                ExtendibleHashing::<u64, u64>::setup_new_hashmap(buffer_pool.clone(), None)
+                    .unwrap()
+                    .directory_page_id
+            }
+            IndexType::BPlusTree => {
+                BPlusTree::<OrderedKey, u64>::setup_new_tree(buffer_pool.clone(), None)
+                    .unwrap()
+                    .header_page_id
+            }
+        };
+        let index_info = IndexInfo {
+            index_id: 1,
+            name: "index".to_string(),
+            table_id: 1,
+            index_type,
+            key_columns: vec![0],
+            unique: false,
+            root_page_id,
+        };
+        let index = Index::open(buffer_pool, &index_info).unwrap();
+
+        let key = vec![Value::String("a".to_string())];
+        index.insert_entry(&key, Rid::new(5, 1), None).unwrap();
+        index
+            .insert_entry(&key, Rid::new(u32::MAX, 7), None)
+            .unwrap();
+        index
+            .insert_entry(&[Value::String("b".to_string())], Rid::new(5, 2), None)
+            .unwrap();
+
+        let mut rids = index.scan_key(&key);
+        rids.sort_by_key(|rid| (rid.page_id, rid.slot_id));
+        assert_eq!(rids, vec![Rid::new(5, 1), Rid::new(u32::MAX, 7)]);
+        assert!(index.delete_entry(&key, Rid::new(5, 1), None));
+        assert_eq!(index.scan_key(&key), vec![Rid::new(u32::MAX, 7)]);
+
+        // More rows with the same key than fit into a page.
+        let duplicate = vec![Value::Integer(3)];
+        for slot_id in 0..1_000 {
+            index
+                .insert_entry(&duplicate, Rid::new(9, slot_id), None)
+                .unwrap();
+        }
+        assert_eq!(index.scan_key(&duplicate).len(), 1_000);
+
+        let lower = Value::String("b".to_string());
+        match index_type {
+            IndexType::Hash => assert!(index.scan_range(Some(&lower), None).is_err()),
+            IndexType::BPlusTree => {
+                // Integers sort before strings.
+                assert_eq!(
+                    index.scan_range(Some(&lower), None).unwrap(),
+                    vec![Rid::new(5, 2)]
+                );
+                assert_eq!(index.scan_range(None, Some(&lower)).unwrap().len(), 1_002);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_index_entries() {
+        check_index_entries(IndexType::Hash, "hash_index_entries");
+    }
+
+    #[test]
+    fn b_plus_tree_index_entries() {
+        check_index_entries(IndexType::BPlusTree, "b_plus_tree_index_entries");
+    }
+}
@@ -0,0 +1,17 @@
+//! A simple relational database management system. The `dbms` binary runs SQL statements
+//! against a database file with [database::engine::Database].
+
+pub mod b_plus_tree;
+pub mod binder;
+pub mod catalog;
+pub mod database;
+pub mod disk_management;
+pub mod execution;
+pub mod extendible_hashing;
+pub mod index;
+pub mod planner;
+pub mod recovery;
+pub mod sql;
+pub mod table;
+pub mod transaction;
+pub mod types;
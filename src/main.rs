@@ -1,45 +1,55 @@
-use std::sync::{Arc, Mutex};
+use std::io::{self, BufRead};
 
-use rand::Rng;
-
-use crate::{
-    disk_management::{buffer_pool::BufferPool, disk_manager::DiskManager},
-    extendible_hashing::extendible_hashing::ExtendibleHashing,
+use dbms::{
+    database::engine::{Database, QueryResult},
+    types::value::Value,
 };
 
-mod b_plus_tree;
-pub mod disk_management;
-mod extendible_hashing;
-mod table;
+/// The database file used if none is given.
+const DEFAULT_DATABASE_PATH: &str = "database.mdb";
 
+/// Runs the SQL statements read from the standard input against the database file given as the
+/// first argument. A statement runs once a line ends with a semicolon, and its results are
+/// written to the standard output.
 fn main() {
-    let file_manager = Arc::new(Mutex::new(DiskManager::new(
-        "resources/db_save_files/test.mdb".to_string(),
-    )));
-    let buffer_pool = BufferPool::new(file_manager);
-    let buffer_pool_mutex = Arc::new(Mutex::new(buffer_pool));
-
-    let extendible_hashing =
-        ExtendibleHashing::<u32, u32>::setup_new_hashmap(buffer_pool_mutex.clone(), 2)
-            .expect("Could not create hashmap");
-    println!(
-        "directory page id: {:?}",
-        extendible_hashing.directory_page_id
-    );
-    // let directory_page = HashDirectoryPage::from_raw_page(lock.get_raw_page(frame).unwrap());
-    // println!("{:?}", directory_page);
-
-    // let frame2 = lock.load_page(2).unwrap();
-    // let bucket_page = HashBucketPage::<u32, u32>::from_raw_page(lock.get_raw_page(frame2).unwrap());
-    // println!("{:?}", bucket_page);
-    // .expect("Could not create new hashmap");
-    let mut rng = rand::thread_rng();
-    for _ in 0..10_000 {
-        extendible_hashing.insert(rng.gen(), rng.gen());
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_DATABASE_PATH.to_string());
+    let mut database = Database::open(&path).expect("Could not open the database file");
+    let mut statements = String::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Could not read the standard input");
+        statements.push_str(&line);
+        statements.push('\n');
+        if !line.trim_end().ends_with(';') {
+            continue;
+        }
+        match database.execute(&statements) {
+            Ok(results) => results.iter().for_each(print_result),
+            Err(error) => eprintln!("error: {}", error),
+        }
+        statements.clear();
     }
+    database
+        .flush()
+        .expect("Could not write the pages to the database file");
+}
 
-    buffer_pool_mutex
-        .lock()
-        .unwrap()
-        .unload_all_pages_and_write_to_file();
+fn print_result(result: &QueryResult) {
+    match result {
+        QueryResult::Rows { schema, rows } => {
+            let names: Vec<&str> = schema
+                .columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect();
+            println!("{}", names.join(" | "));
+            for row in rows {
+                let values: Vec<String> = row.values.iter().map(Value::to_string).collect();
+                println!("{}", values.join(" | "));
+            }
+        }
+        QueryResult::Count(count) => println!("{} rows", count),
+        QueryResult::Ok => println!("OK"),
+    }
 }
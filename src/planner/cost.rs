@@ -0,0 +1,751 @@
+use chrono::Datelike;
+
+use crate::{
+    binder::bound_expression::BoundExpression,
+    catalog::{
+        statistics::ColumnStatistics,
+        system_catalog::{Catalog, IndexInfo, TableInfo},
+    },
+    disk_management::buffer_pool::PAGE_SIZE,
+    sql::ast::{BinaryOperator, JoinType, UnaryOperator},
+    types::{
+        schema::{DataType, Schema},
+        value::Value,
+    },
+};
+
+use super::{
+    logical_plan::LogicalPlan,
+    physical_planner::join_key_condition,
+    plan::{index_range_conditions, PlanNode},
+};
+
+// Costs are measured in units of sequentially read pages.
+
+/// The cost of reading a page of a table scan.
+pub const SEQUENTIAL_PAGE_COST: f64 = 1.0;
+/// The cost of reading a page at an arbitrary position, e.g. an index bucket or the page of a
+/// row found through an index.
+pub const RANDOM_PAGE_COST: f64 = 4.0;
+/// The cost of processing a row, e.g. evaluating a predicate or inserting it into a hash table.
+pub const ROW_COST: f64 = 0.01;
+
+/// The selectivity of an equality if the number of distinct values is unknown.
+const DEFAULT_EQUALITY_SELECTIVITY: f64 = 0.005;
+/// The selectivity of a range comparison such as `<`.
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+/// The selectivity of IS NULL.
+const DEFAULT_NULL_SELECTIVITY: f64 = 0.01;
+/// The selectivity of predicates the estimator knows nothing about.
+const DEFAULT_SELECTIVITY: f64 = 0.25;
+/// The number of groups per input row of an aggregation if the number of distinct values of the
+/// group values is unknown.
+const DEFAULT_GROUP_FRACTION: f64 = 0.1;
+/// The fraction of a page that is assumed to be filled with rows.
+const PAGE_FILL_FACTOR: f64 = 0.9;
+/// The assumed average length of variable-length values in bytes.
+const VARIABLE_LENGTH_WIDTH: usize = 32;
+/// The bytes a row occupies in a page besides its values, i.e. its slot and null bitmap.
+const ROW_OVERHEAD: usize = 12;
+
+/// What the planner knows about the rows of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableEstimate {
+    pub row_count: f64,
+    pub page_count: f64,
+    pub columns: Vec<ColumnEstimate>,
+}
+
+/// What the planner knows about the values of a column.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnEstimate {
+    /// The number of distinct values, if known.
+    pub distinct_count: Option<f64>,
+    /// The distribution of the values in the table the column comes from, if it was analyzed.
+    /// Predicates are assumed to be independent, so the distribution also describes the values
+    /// of filtered rows.
+    pub statistics: Option<ColumnStatistics>,
+}
+
+/// The estimated result of a plan.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub rows: f64,
+    /// What is known about every output column.
+    pub columns: Vec<ColumnEstimate>,
+}
+
+impl Estimate {
+    /// Returns the estimate with at least one row. Estimates never reach zero, so that they
+    /// remain comparable after being multiplied.
+    fn new(rows: f64, columns: Vec<ColumnEstimate>) -> Estimate {
+        let rows = rows.max(1.0);
+        let columns = columns
+            .into_iter()
+            .map(|column| ColumnEstimate {
+                distinct_count: column.distinct_count.map(|count| count.min(rows)),
+                ..column
+            })
+            .collect();
+        Estimate { rows, columns }
+    }
+}
+
+/// The estimated result of a physical plan and the estimated cost of executing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanEstimate {
+    pub estimate: Estimate,
+    pub cost: f64,
+}
+
+/// Estimates the number of rows produced by logical plans and the cost of the algorithms
+/// executing them, as well as the rows and cost of physical plans for EXPLAIN.
+///
+/// If a table was analyzed, its number of rows is scaled from the analyzed rows per page to its
+/// current number of pages, and the distribution of the values of its columns is known.
+/// Otherwise the number of rows is derived from the estimated width of its rows, and the number
+/// of distinct values of a column is only known if the column is the key of a unique index.
+pub struct Estimator<'a> {
+    catalog: &'a Catalog,
+}
+
+impl<'a> Estimator<'a> {
+    pub fn new(catalog: &'a Catalog) -> Estimator<'a> {
+        Estimator { catalog }
+    }
+
+    pub fn table_estimate(&self, table: &TableInfo) -> TableEstimate {
+        let page_count = self
+            .catalog
+            .get_table_heap(table)
+            .get_page_ids()
+            .len()
+            .max(1) as f64;
+        let statistics = self.catalog.get_table_statistics(table.table_id);
+        let rows_per_page = match statistics {
+            Some(statistics) => statistics.row_count as f64 / statistics.page_count.max(1) as f64,
+            None => (PAGE_SIZE as f64 * PAGE_FILL_FACTOR / row_width(&table.schema) as f64)
+                .floor()
+                .max(1.0),
+        };
+        let row_count = page_count * rows_per_page;
+        let indexes = self.catalog.get_table_indexes(table.table_id);
+        let columns = (0..table.schema.columns.len())
+            .map(|column| {
+                let unique = indexes
+                    .iter()
+                    .any(|index| index.unique && index.key_columns == [column]);
+                let statistics = statistics.map(|statistics| statistics.columns[column].clone());
+                let distinct_count = match &statistics {
+                    _ if unique => Some(row_count),
+                    Some(statistics) => Some(statistics.distinct_count.max(1.0)),
+                    None => None,
+                };
+                ColumnEstimate {
+                    distinct_count,
+                    statistics,
+                }
+            })
+            .collect();
+        TableEstimate {
+            row_count,
+            page_count,
+            columns,
+        }
+    }
+
+    pub fn estimate(&self, plan: &LogicalPlan) -> Estimate {
+        match plan {
+            LogicalPlan::Scan { table } => {
+                let table = self.table_estimate(table);
+                Estimate::new(table.row_count, table.columns)
+            }
+            LogicalPlan::Values { rows } => Estimate::new(
+                rows.len() as f64,
+                vec![ColumnEstimate::default(); rows.first().map_or(0, Vec::len)],
+            ),
+            LogicalPlan::Filter { input, predicate } => {
+                let input = self.estimate(input);
+                let rows = input.rows * self.selectivity(predicate, &input);
+                Estimate::new(rows, input.columns)
+            }
+            LogicalPlan::Projection { input, expressions } => {
+                let input = self.estimate(input);
+                let columns = expressions
+                    .iter()
+                    .map(|expression| column_estimate(expression, &input))
+                    .collect();
+                Estimate::new(input.rows, columns)
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                join_type,
+                condition,
+            } => self.estimate_join(
+                &self.estimate(left),
+                &self.estimate(right),
+                *join_type,
+                condition.as_ref(),
+            ),
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => aggregate_estimate(&self.estimate(input), group_by, aggregates.len()),
+            LogicalPlan::Sort { input, .. } => self.estimate(input),
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => limit_estimate(self.estimate(input), *limit, *offset),
+        }
+    }
+
+    /// Estimates the result and the cost of a physical plan node from the estimates of its
+    /// inputs, which are given in the order of [PlanNode::inputs]. The cost includes the cost of
+    /// the inputs.
+    pub fn estimate_node(&self, plan: &PlanNode, inputs: &[PlanEstimate]) -> PlanEstimate {
+        let input_cost: f64 = inputs.iter().map(|input| input.cost).sum();
+        let input_rows = inputs.first().map_or(0.0, |input| input.estimate.rows);
+        let (estimate, cost) = match plan {
+            PlanNode::SeqScan { table } => {
+                let table = self.table_estimate(table);
+                let cost = self.sequential_scan_cost(&table);
+                (Estimate::new(table.row_count, table.columns), cost)
+            }
+            PlanNode::IndexScan { table, index, .. } => {
+                let table = self.table_estimate(table);
+                let rows = self.index_key_rows(&table, index);
+                (
+                    Estimate::new(rows, table.columns),
+                    self.index_lookup_cost(rows),
+                )
+            }
+            PlanNode::IndexRangeScan {
+                table,
+                index,
+                lower,
+                upper,
+            } => {
+                let conditions =
+                    index_range_conditions(table, index, lower.as_ref(), upper.as_ref());
+                let table = self.table_estimate(table);
+                let rows = self.index_range_rows(&table, index, &conditions);
+                (
+                    Estimate::new(rows, table.columns),
+                    self.index_lookup_cost(rows),
+                )
+            }
+            PlanNode::Values { rows } => (
+                self.estimate(&LogicalPlan::Values { rows: rows.clone() }),
+                rows.len() as f64 * ROW_COST,
+            ),
+            PlanNode::Filter { predicate, .. } => {
+                let input = &inputs[0].estimate;
+                let rows = input.rows * self.selectivity(predicate, input);
+                (
+                    Estimate::new(rows, input.columns.clone()),
+                    input_rows * ROW_COST,
+                )
+            }
+            PlanNode::Projection { expressions, .. } => {
+                let input = &inputs[0].estimate;
+                let columns = expressions
+                    .iter()
+                    .map(|expression| column_estimate(expression, input))
+                    .collect();
+                (Estimate::new(input.rows, columns), input_rows * ROW_COST)
+            }
+            PlanNode::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => (
+                aggregate_estimate(&inputs[0].estimate, group_by, aggregates.len()),
+                input_rows * ROW_COST,
+            ),
+            PlanNode::Sort { limit, .. } => {
+                let input = inputs[0].estimate.clone();
+                // A bounded sort only keeps the first `limit` rows in a heap.
+                let kept_rows = limit.map_or(input.rows, |limit| input.rows.min(limit as f64));
+                let cost = input.rows * kept_rows.max(2.0).log2() * ROW_COST;
+                (limit_estimate(input, *limit, 0), cost)
+            }
+            PlanNode::Limit { limit, offset, .. } => (
+                limit_estimate(inputs[0].estimate.clone(), *limit, *offset),
+                0.0,
+            ),
+            PlanNode::NestedLoopJoin {
+                join_type,
+                condition,
+                ..
+            } => {
+                let (left, right) = (&inputs[0], &inputs[1]);
+                let estimate = self.estimate_join(
+                    &left.estimate,
+                    &right.estimate,
+                    *join_type,
+                    condition.as_ref(),
+                );
+                let cost =
+                    self.nested_loop_join_cost(left.estimate.rows, right.estimate.rows, right.cost);
+                (estimate, cost)
+            }
+            PlanNode::IndexNestedLoopJoin {
+                table,
+                index,
+                join_type,
+                left_keys,
+                condition,
+                ..
+            } => {
+                let left = &inputs[0].estimate;
+                let table_estimate = self.table_estimate(table);
+                let right = Estimate::new(table_estimate.row_count, table_estimate.columns.clone());
+                let right_keys: Vec<BoundExpression> = index
+                    .key_columns
+                    .iter()
+                    .map(|column| BoundExpression::Column {
+                        index: *column,
+                        data_type: table.schema.columns[*column].data_type,
+                    })
+                    .collect();
+                let condition = join_key_condition(
+                    left_keys,
+                    &right_keys,
+                    left.columns.len(),
+                    condition.as_ref(),
+                );
+                let estimate = self.estimate_join(left, &right, *join_type, condition.as_ref());
+                let lookup_cost =
+                    self.index_lookup_cost(self.index_key_rows(&table_estimate, index));
+                (estimate, left.rows * lookup_cost)
+            }
+            PlanNode::HashJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                condition,
+                ..
+            }
+            | PlanNode::SortMergeJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                condition,
+                ..
+            } => {
+                let (left, right) = (&inputs[0].estimate, &inputs[1].estimate);
+                let condition = join_key_condition(
+                    left_keys,
+                    right_keys,
+                    left.columns.len(),
+                    condition.as_ref(),
+                );
+                let estimate = self.estimate_join(left, right, *join_type, condition.as_ref());
+                let cost = match plan {
+                    PlanNode::HashJoin { .. } => self.hash_join_cost(left.rows, right.rows),
+                    _ => (left.rows + right.rows) * ROW_COST,
+                };
+                (estimate, cost)
+            }
+            PlanNode::Insert { .. } | PlanNode::Update { .. } | PlanNode::Delete { .. } => (
+                Estimate::new(1.0, vec![ColumnEstimate::default()]),
+                input_rows * ROW_COST,
+            ),
+        };
+        PlanEstimate {
+            estimate,
+            cost: input_cost + cost,
+        }
+    }
+
+    /// Estimates the result of joining two inputs. The condition is evaluated over the left row
+    /// followed by the right row.
+    pub fn estimate_join(
+        &self,
+        left: &Estimate,
+        right: &Estimate,
+        join_type: JoinType,
+        condition: Option<&BoundExpression>,
+    ) -> Estimate {
+        let joined = Estimate {
+            rows: left.rows * right.rows,
+            columns: left.columns.iter().chain(&right.columns).cloned().collect(),
+        };
+        let selectivity = condition.map_or(1.0, |condition| self.selectivity(condition, &joined));
+        let matches = joined.rows * selectivity;
+        // The fraction of left rows that have at least one matching right row.
+        let matched_fraction = (selectivity * right.rows).min(1.0);
+        let rows = match join_type {
+            JoinType::Inner | JoinType::Cross => matches,
+            JoinType::Left => matches.max(left.rows),
+            JoinType::Right => matches.max(right.rows),
+            JoinType::Full => matches.max(left.rows + right.rows),
+            JoinType::Semi => left.rows * matched_fraction,
+            JoinType::Anti => left.rows * (1.0 - matched_fraction),
+        };
+        let columns = match join_type {
+            JoinType::Semi | JoinType::Anti => left.columns.clone(),
+            _ => joined.columns,
+        };
+        Estimate::new(rows, columns)
+    }
+
+    /// Estimates the fraction of the input rows that satisfy the predicate.
+    pub fn selectivity(&self, predicate: &BoundExpression, input: &Estimate) -> f64 {
+        let selectivity = match predicate {
+            BoundExpression::Constant {
+                value: Value::Boolean(true),
+                ..
+            } => 1.0,
+            BoundExpression::Constant { .. } => 0.0,
+            BoundExpression::Unary {
+                operator: UnaryOperator::Not,
+                operand,
+                ..
+            } => 1.0 - self.selectivity(operand, input),
+            BoundExpression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => match operator {
+                BinaryOperator::And => {
+                    self.selectivity(left, input) * self.selectivity(right, input)
+                }
+                BinaryOperator::Or => {
+                    let left = self.selectivity(left, input);
+                    let right = self.selectivity(right, input);
+                    left + right - left * right
+                }
+                BinaryOperator::Equal => equality_selectivity(left, right, input),
+                BinaryOperator::NotEqual => 1.0 - equality_selectivity(left, right, input),
+                BinaryOperator::Less
+                | BinaryOperator::LessEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterEqual => range_selectivity(left, *operator, right, input),
+                _ => DEFAULT_SELECTIVITY,
+            },
+            BoundExpression::IsNull { operand, negated } => {
+                let null_fraction = column_statistics(operand, input)
+                    .map_or(DEFAULT_NULL_SELECTIVITY, |statistics| {
+                        statistics.null_fraction
+                    });
+                if *negated {
+                    1.0 - null_fraction
+                } else {
+                    null_fraction
+                }
+            }
+            _ => DEFAULT_SELECTIVITY,
+        };
+        selectivity.clamp(0.0, 1.0)
+    }
+
+    /// Estimates the cost of executing the plan, assuming that tables are scanned sequentially
+    /// and that every node processes each of its output rows once.
+    pub fn cost(&self, plan: &LogicalPlan) -> f64 {
+        match plan {
+            LogicalPlan::Scan { table } => self.sequential_scan_cost(&self.table_estimate(table)),
+            plan => {
+                plan.inputs()
+                    .into_iter()
+                    .map(|input| self.cost(input))
+                    .sum::<f64>()
+                    + self.estimate(plan).rows * ROW_COST
+            }
+        }
+    }
+
+    /// Returns the cost of an index nested loop join that looks up `left_rows` keys in the
+    /// cheapest hash index of the table whose key columns are all among the given columns, or
+    /// None if there is no such index.
+    pub fn index_join_cost(
+        &self,
+        table: &TableInfo,
+        key_columns: &[usize],
+        left_rows: f64,
+    ) -> Option<f64> {
+        let table_estimate = self.table_estimate(table);
+        self.catalog
+            .get_table_indexes(table.table_id)
+            .into_iter()
+            .filter(|index| {
+                index
+                    .key_columns
+                    .iter()
+                    .all(|column| key_columns.contains(column))
+            })
+            .map(|index| {
+                left_rows * self.index_lookup_cost(self.index_key_rows(&table_estimate, index))
+            })
+            .min_by(f64::total_cmp)
+    }
+
+    /// Estimates the number of rows of the table with the same key in the index.
+    pub fn index_key_rows(&self, table: &TableEstimate, index: &IndexInfo) -> f64 {
+        if index.unique {
+            return 1.0;
+        }
+        let selectivity: f64 = index
+            .key_columns
+            .iter()
+            .map(|column| {
+                table.columns[*column]
+                    .distinct_count
+                    .map_or(DEFAULT_EQUALITY_SELECTIVITY, |count| 1.0 / count.max(1.0))
+            })
+            .product();
+        (table.row_count * selectivity).max(1.0)
+    }
+
+    /// Estimates the number of rows of the table whose first key column of the index satisfies
+    /// the conditions of a range scan. If the column was analyzed, the rows between a lower and
+    /// an upper bound are those above the lower bound minus those above the upper bound, as the
+    /// bounds are not independent.
+    pub fn index_range_rows(
+        &self,
+        table: &TableEstimate,
+        index: &IndexInfo,
+        conditions: &[BoundExpression],
+    ) -> f64 {
+        let input = Estimate::new(table.row_count, table.columns.clone());
+        let selectivities = conditions
+            .iter()
+            .map(|condition| self.selectivity(condition, &input));
+        let selectivity = match &table.columns[index.key_columns[0]].statistics {
+            Some(statistics) if conditions.len() == 2 => {
+                selectivities.sum::<f64>() - (1.0 - statistics.null_fraction)
+            }
+            _ => selectivities.product(),
+        };
+        (table.row_count * selectivity).max(1.0)
+    }
+
+    /// The cost of reading all rows of a table.
+    pub fn sequential_scan_cost(&self, table: &TableEstimate) -> f64 {
+        table.page_count * SEQUENTIAL_PAGE_COST + table.row_count * ROW_COST
+    }
+
+    /// The cost of looking up a key in an index and reading the matching rows, every one of them
+    /// from a different page.
+    pub fn index_lookup_cost(&self, matching_rows: f64) -> f64 {
+        RANDOM_PAGE_COST + matching_rows * (RANDOM_PAGE_COST + ROW_COST)
+    }
+
+    /// The cost of a hash join besides reading its inputs. Building the hash table over the
+    /// right rows is more expensive than probing it with the left rows.
+    pub fn hash_join_cost(&self, left_rows: f64, right_rows: f64) -> f64 {
+        (left_rows + 2.0 * right_rows) * ROW_COST
+    }
+
+    /// The cost of a nested loop join besides reading its inputs once. The right input, which
+    /// costs `right_cost` to produce, is produced again for every left row.
+    pub fn nested_loop_join_cost(&self, left_rows: f64, right_rows: f64, right_cost: f64) -> f64 {
+        (left_rows - 1.0).max(0.0) * right_cost + left_rows * right_rows * ROW_COST
+    }
+}
+
+fn aggregate_estimate(
+    input: &Estimate,
+    group_by: &[BoundExpression],
+    aggregate_count: usize,
+) -> Estimate {
+    let groups = if group_by.is_empty() {
+        1.0
+    } else {
+        group_by
+            .iter()
+            .map(|expression| {
+                distinct_count(expression, input).unwrap_or(input.rows * DEFAULT_GROUP_FRACTION)
+            })
+            .product::<f64>()
+            .min(input.rows)
+    };
+    let columns = group_by
+        .iter()
+        .map(|expression| column_estimate(expression, input))
+        .chain(std::iter::repeat_n(
+            ColumnEstimate::default(),
+            aggregate_count,
+        ))
+        .collect();
+    Estimate::new(groups, columns)
+}
+
+fn limit_estimate(input: Estimate, limit: Option<u64>, offset: u64) -> Estimate {
+    let rows = (input.rows - offset as f64).min(limit.map_or(f64::INFINITY, |limit| limit as f64));
+    Estimate::new(rows, input.columns)
+}
+
+/// Returns what is known about the values of an expression, which is only something if it reads
+/// a single column.
+fn column_estimate(expression: &BoundExpression, input: &Estimate) -> ColumnEstimate {
+    match expression {
+        BoundExpression::Column { index, .. } => input.columns[*index].clone(),
+        _ => ColumnEstimate::default(),
+    }
+}
+
+fn distinct_count(expression: &BoundExpression, input: &Estimate) -> Option<f64> {
+    match expression {
+        BoundExpression::Column { index, .. } => input.columns[*index].distinct_count,
+        _ => None,
+    }
+}
+
+fn column_statistics<'e>(
+    expression: &BoundExpression,
+    input: &'e Estimate,
+) -> Option<&'e ColumnStatistics> {
+    match expression {
+        BoundExpression::Column { index, .. } => input.columns[*index].statistics.as_ref(),
+        _ => None,
+    }
+}
+
+/// Returns the statistics of the column and the constant if one operand is an analyzed column
+/// and the other one a constant. The flag is true if the constant is the left operand.
+fn column_and_constant<'e, 'p>(
+    left: &'p BoundExpression,
+    right: &'p BoundExpression,
+    input: &'e Estimate,
+) -> Option<(&'e ColumnStatistics, &'p Value, bool)> {
+    match (left, right) {
+        (column, BoundExpression::Constant { value, .. }) => {
+            Some((column_statistics(column, input)?, value, false))
+        }
+        (BoundExpression::Constant { value, .. }, column) => {
+            Some((column_statistics(column, input)?, value, true))
+        }
+        _ => None,
+    }
+}
+
+/// Estimates the selectivity of `left = right`. A constant compared with an analyzed column
+/// matches its frequency if it is a most common value, and otherwise the average frequency of
+/// the other values. Otherwise each value of the side with fewer distinct values is assumed to
+/// match a value of the other side.
+fn equality_selectivity(left: &BoundExpression, right: &BoundExpression, input: &Estimate) -> f64 {
+    if let Some((statistics, value, _)) = column_and_constant(left, right, input) {
+        if value.is_null() {
+            return 0.0;
+        }
+        if let Some((_, frequency)) = statistics
+            .most_common_values
+            .iter()
+            .find(|(common_value, _)| common_value == value)
+        {
+            return *frequency;
+        }
+        let other_values =
+            (statistics.distinct_count - statistics.most_common_values.len() as f64).max(1.0);
+        return statistics.histogram_fraction() / other_values;
+    }
+    match (distinct_count(left, input), distinct_count(right, input)) {
+        (Some(left), Some(right)) => 1.0 / left.max(right).max(1.0),
+        (Some(count), None) | (None, Some(count)) => 1.0 / count.max(1.0),
+        (None, None) => DEFAULT_EQUALITY_SELECTIVITY,
+    }
+}
+
+/// Estimates the selectivity of a comparison such as `left < right` from the most common values
+/// and the histogram of an analyzed column compared with a constant.
+fn range_selectivity(
+    left: &BoundExpression,
+    operator: BinaryOperator,
+    right: &BoundExpression,
+    input: &Estimate,
+) -> f64 {
+    let (statistics, value, flipped) = match column_and_constant(left, right, input) {
+        Some((_, value, _)) if value.is_null() => return 0.0,
+        Some(column_and_constant) => column_and_constant,
+        None => return DEFAULT_RANGE_SELECTIVITY,
+    };
+    // Turn `constant < column` into `column > constant`.
+    let operator = match (operator, flipped) {
+        (BinaryOperator::Less, true) => BinaryOperator::Greater,
+        (BinaryOperator::LessEqual, true) => BinaryOperator::GreaterEqual,
+        (BinaryOperator::Greater, true) => BinaryOperator::Less,
+        (BinaryOperator::GreaterEqual, true) => BinaryOperator::LessEqual,
+        (operator, _) => operator,
+    };
+    // The fraction of rows whose value is below the constant, or equal to it if inclusive.
+    let below = |inclusive: bool| {
+        let most_common: f64 = statistics
+            .most_common_values
+            .iter()
+            .filter(|(common_value, _)| {
+                common_value < value || (inclusive && common_value == value)
+            })
+            .map(|(_, frequency)| frequency)
+            .sum();
+        most_common
+            + statistics.histogram_fraction()
+                * histogram_position(&statistics.histogram_bounds, value)
+    };
+    let not_null = 1.0 - statistics.null_fraction;
+    match operator {
+        BinaryOperator::Less => below(false),
+        BinaryOperator::LessEqual => below(true),
+        BinaryOperator::Greater => not_null - below(true),
+        _ => not_null - below(false),
+    }
+}
+
+/// Estimates the fraction of the values described by the histogram that are below the value.
+/// Within a bucket, numeric and temporal values are assumed to be distributed uniformly.
+fn histogram_position(bounds: &[Value], value: &Value) -> f64 {
+    if bounds.len() < 2 {
+        return 0.5;
+    }
+    let buckets = (bounds.len() - 1) as f64;
+    let above = bounds.partition_point(|bound| bound < value);
+    if above == 0 {
+        return 0.0;
+    }
+    if above == bounds.len() {
+        return 1.0;
+    }
+    let (low, high) = (&bounds[above - 1], &bounds[above]);
+    let within = match (
+        numeric_position(low),
+        numeric_position(high),
+        numeric_position(value),
+    ) {
+        (Some(low), Some(high), Some(value)) if high > low => (value - low) / (high - low),
+        _ => 0.5,
+    };
+    ((above - 1) as f64 + within.clamp(0.0, 1.0)) / buckets
+}
+
+/// Maps values whose distance can be measured to numbers.
+fn numeric_position(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(value) => Some(*value as f64),
+        Value::BigInt(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        Value::Date(value) => Some(value.num_days_from_ce() as f64),
+        Value::Timestamp(value) => Some(value.and_utc().timestamp_micros() as f64),
+        _ => None,
+    }
+}
+
+/// Estimates the number of bytes a row of the schema occupies in a page.
+fn row_width(schema: &Schema) -> usize {
+    ROW_OVERHEAD
+        + schema
+            .columns
+            .iter()
+            .map(|column| {
+                let variable_width = match column.data_type {
+                    DataType::Varchar(length) => (length as usize).min(VARIABLE_LENGTH_WIDTH),
+                    DataType::Text | DataType::Blob => VARIABLE_LENGTH_WIDTH,
+                    _ => 0,
+                };
+                column.data_type.fixed_size() + variable_width
+            })
+            .sum::<usize>()
+}
@@ -0,0 +1,227 @@
+use crate::{
+    binder::bound_expression::BoundExpression, catalog::system_catalog::Catalog,
+    execution::instrument::OperatorMetrics, sql::ast::JoinType,
+};
+
+use super::{
+    cost::{Estimator, PlanEstimate},
+    physical_planner::join_key_condition,
+    plan::{index_range_conditions, PlanNode},
+};
+
+/// Returns the lines of the EXPLAIN output of a physical plan: one line per node with its
+/// estimated rows and cost, and the inputs of a node indented below it. Columns are written as
+/// their position in the input row of the node, e.g. `#0`.
+///
+/// If the plan was executed, `metrics` holds the metrics of its nodes in post-order, as returned
+/// by [crate::execution::instrument::create_instrumented_executor], and every line also shows
+/// the actual rows and time, and the buffer pool requests the node made itself in this session.
+pub fn explain_plan(
+    catalog: &Catalog,
+    plan: &PlanNode,
+    metrics: Option<&[OperatorMetrics]>,
+) -> Vec<String> {
+    let mut metrics = metrics.map(|metrics| metrics.iter());
+    explain_node(&Estimator::new(catalog), plan, &mut metrics).1
+}
+
+fn explain_node<'m>(
+    estimator: &Estimator,
+    plan: &PlanNode,
+    metrics: &mut Option<impl Iterator<Item = &'m OperatorMetrics>>,
+) -> (PlanEstimate, Vec<String>) {
+    let mut input_estimates = Vec::new();
+    let mut input_lines = Vec::new();
+    for input in plan.inputs() {
+        let (estimate, lines) = explain_node(estimator, input, metrics);
+        input_estimates.push(estimate);
+        for (i, line) in lines.into_iter().enumerate() {
+            let prefix = if i == 0 { "  -> " } else { "     " };
+            input_lines.push(format!("{}{}", prefix, line));
+        }
+    }
+    let estimate = estimator.estimate_node(plan, &input_estimates);
+
+    let mut line = format!(
+        "{}  (rows={:.0} cost={:.2})",
+        describe(plan),
+        estimate.estimate.rows,
+        estimate.cost
+    );
+    if let Some(metrics) = metrics {
+        let metrics = metrics
+            .next()
+            .expect("There are fewer metrics than plan nodes");
+        if metrics.loops == 0 {
+            line.push_str(" (never executed)");
+        } else {
+            line.push_str(&format!(
+                " (actual rows={} loops={} time={:.3} ms) (own buffers hits={} misses={})",
+                metrics.rows,
+                metrics.loops,
+                metrics.elapsed.as_secs_f64() * 1000.0,
+                metrics.buffer_hits,
+                metrics.buffer_misses
+            ));
+        }
+    }
+    let mut lines = vec![line];
+    lines.extend(input_lines);
+    (estimate, lines)
+}
+
+/// Returns the name of the node followed by what it computes.
+fn describe(plan: &PlanNode) -> String {
+    let list = |expressions: &[BoundExpression]| {
+        expressions
+            .iter()
+            .map(BoundExpression::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let left_width = || plan.inputs()[0].output_types().len();
+    match plan {
+        PlanNode::SeqScan { table } => format!("SeqScan on {}", table.name),
+        PlanNode::IndexScan { table, index, key } => format!(
+            "IndexScan on {} using {} key ({})",
+            table.name,
+            index.name,
+            list(key)
+        ),
+        PlanNode::IndexRangeScan {
+            table,
+            index,
+            lower,
+            upper,
+        } => format!(
+            "IndexRangeScan on {} using {} range {}",
+            table.name,
+            index.name,
+            list(&index_range_conditions(
+                table,
+                index,
+                lower.as_ref(),
+                upper.as_ref()
+            ))
+        ),
+        PlanNode::Values { rows } => format!("Values ({} rows)", rows.len()),
+        PlanNode::Filter { predicate, .. } => format!("Filter {}", predicate),
+        PlanNode::Projection { expressions, .. } => format!("Projection {}", list(expressions)),
+        PlanNode::Aggregate {
+            group_by,
+            aggregates,
+            ..
+        } => {
+            let mut description = "HashAggregate".to_string();
+            if !aggregates.is_empty() {
+                let aggregates: Vec<String> = aggregates
+                    .iter()
+                    .map(|aggregate| aggregate.to_string())
+                    .collect();
+                description.push_str(&format!(" {}", aggregates.join(", ")));
+            }
+            if !group_by.is_empty() {
+                description.push_str(&format!(" group by {}", list(group_by)));
+            }
+            description
+        }
+        PlanNode::Sort {
+            order_by, limit, ..
+        } => {
+            let order_by = order_by
+                .iter()
+                .map(|(expression, ascending)| {
+                    format!("{} {}", expression, if *ascending { "ASC" } else { "DESC" })
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            match limit {
+                Some(limit) => format!("Sort {} limit {}", order_by, limit),
+                None => format!("Sort {}", order_by),
+            }
+        }
+        PlanNode::Limit { limit, offset, .. } => match limit {
+            Some(limit) => format!("Limit {} offset {}", limit, offset),
+            None => format!("Limit ALL offset {}", offset),
+        },
+        PlanNode::NestedLoopJoin {
+            join_type,
+            condition,
+            ..
+        } => join_description("NestedLoopJoin", *join_type, condition.clone()),
+        PlanNode::IndexNestedLoopJoin {
+            table,
+            index,
+            join_type,
+            left_keys,
+            condition,
+            ..
+        } => {
+            let right_keys: Vec<BoundExpression> = index
+                .key_columns
+                .iter()
+                .map(|column| BoundExpression::Column {
+                    index: *column,
+                    data_type: table.schema.columns[*column].data_type,
+                })
+                .collect();
+            let condition =
+                join_key_condition(left_keys, &right_keys, left_width(), condition.as_ref());
+            let name = format!(
+                "IndexNestedLoopJoin with {} using {}",
+                table.name, index.name
+            );
+            join_description(&name, *join_type, condition)
+        }
+        PlanNode::HashJoin {
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+            ..
+        } => join_description(
+            "HashJoin",
+            *join_type,
+            join_key_condition(left_keys, right_keys, left_width(), condition.as_ref()),
+        ),
+        PlanNode::SortMergeJoin {
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+            ..
+        } => join_description(
+            "SortMergeJoin",
+            *join_type,
+            join_key_condition(left_keys, right_keys, left_width(), condition.as_ref()),
+        ),
+        PlanNode::Insert { table, .. } => format!("Insert into {}", table.name),
+        PlanNode::Update {
+            table, assignments, ..
+        } => {
+            let assignments = assignments
+                .iter()
+                .map(|(column, expression)| format!("#{} = {}", column, expression))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("Update {} set {}", table.name, assignments)
+        }
+        PlanNode::Delete { table, .. } => format!("Delete from {}", table.name),
+    }
+}
+
+fn join_description(name: &str, join_type: JoinType, condition: Option<BoundExpression>) -> String {
+    let join_type = match join_type {
+        JoinType::Cross => "cross",
+        JoinType::Inner => "inner",
+        JoinType::Left => "left",
+        JoinType::Right => "right",
+        JoinType::Full => "full",
+        JoinType::Semi => "semi",
+        JoinType::Anti => "anti",
+    };
+    match condition {
+        Some(condition) => format!("{} {} on {}", name, join_type, condition),
+        None => format!("{} {}", name, join_type),
+    }
+}
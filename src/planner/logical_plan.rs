@@ -0,0 +1,261 @@
+use crate::{
+    binder::{
+        bound_expression::{BoundAggregate, BoundExpression},
+        bound_statement::{BoundSelect, BoundTableReference},
+    },
+    catalog::system_catalog::{Catalog, CatalogError, TableInfo},
+    execution::executor::ExecutionError,
+    sql::ast::JoinType,
+    types::schema::DataType,
+};
+
+/// A node of a logical query plan. Logical plans describe what a query computes, but not which
+/// algorithms compute it. The optimizer rewrites them before they are turned into physical plans.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogicalPlan {
+    /// Reads all columns of a table.
+    Scan {
+        table: TableInfo,
+    },
+    Values {
+        rows: Vec<Vec<BoundExpression>>,
+    },
+    Filter {
+        input: Box<LogicalPlan>,
+        predicate: BoundExpression,
+    },
+    Projection {
+        input: Box<LogicalPlan>,
+        expressions: Vec<BoundExpression>,
+    },
+    /// Produces the columns of the left input followed by the columns of the right input, or
+    /// only the columns of the left input for semi and anti joins. The condition is evaluated
+    /// over the left row followed by the right row.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        join_type: JoinType,
+        condition: Option<BoundExpression>,
+    },
+    /// Produces the group values followed by the aggregate values for every group.
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<BoundExpression>,
+        aggregates: Vec<BoundAggregate>,
+    },
+    Sort {
+        input: Box<LogicalPlan>,
+        order_by: Vec<(BoundExpression, bool)>,
+    },
+    Limit {
+        input: Box<LogicalPlan>,
+        limit: Option<u64>,
+        offset: u64,
+    },
+}
+
+impl LogicalPlan {
+    /// Returns the logical plan of a SELECT statement: the FROM clause, then WHERE, GROUP BY,
+    /// HAVING, ORDER BY, the select list and LIMIT.
+    pub fn from_select(
+        catalog: &Catalog,
+        select: &BoundSelect,
+    ) -> Result<LogicalPlan, ExecutionError> {
+        let mut plan = match &select.from {
+            Some(table_reference) => LogicalPlan::from_table_reference(catalog, table_reference)?,
+            None => LogicalPlan::Values {
+                rows: vec![Vec::new()],
+            },
+        };
+        plan = plan.with_filter(select.filter.clone());
+        if select.is_aggregate() {
+            plan = LogicalPlan::Aggregate {
+                input: Box::new(plan),
+                group_by: select.group_by.clone(),
+                aggregates: select.aggregates.clone(),
+            }
+            .with_filter(select.having.clone());
+        }
+        if !select.order_by.is_empty() {
+            plan = LogicalPlan::Sort {
+                input: Box::new(plan),
+                order_by: select.order_by.clone(),
+            };
+        }
+        plan = LogicalPlan::Projection {
+            input: Box::new(plan),
+            expressions: select.projection.clone(),
+        };
+        if select.limit.is_some() || select.offset.is_some() {
+            plan = LogicalPlan::Limit {
+                input: Box::new(plan),
+                limit: select.limit,
+                offset: select.offset.unwrap_or(0),
+            };
+        }
+        Ok(plan)
+    }
+
+    fn from_table_reference(
+        catalog: &Catalog,
+        table_reference: &BoundTableReference,
+    ) -> Result<LogicalPlan, ExecutionError> {
+        match table_reference {
+            BoundTableReference::Table { table_id, .. } => {
+                let table = catalog.get_table_by_id(*table_id).ok_or_else(|| {
+                    ExecutionError::Catalog(CatalogError::TableNotFound(table_id.to_string()))
+                })?;
+                Ok(LogicalPlan::Scan {
+                    table: table.clone(),
+                })
+            }
+            BoundTableReference::Join {
+                left,
+                right,
+                join_type,
+                condition,
+            } => Ok(LogicalPlan::Join {
+                left: Box::new(LogicalPlan::from_table_reference(catalog, left)?),
+                right: Box::new(LogicalPlan::from_table_reference(catalog, right)?),
+                join_type: *join_type,
+                condition: condition.clone(),
+            }),
+        }
+    }
+
+    /// Wraps the plan in a filter, unless the predicate is None.
+    pub fn with_filter(self, predicate: Option<BoundExpression>) -> LogicalPlan {
+        match predicate {
+            Some(predicate) => LogicalPlan::Filter {
+                input: Box::new(self),
+                predicate,
+            },
+            None => self,
+        }
+    }
+
+    /// Returns the table read by the plan if it is a scan of the table, possibly filtered.
+    pub fn table(&self) -> Option<&TableInfo> {
+        match self {
+            LogicalPlan::Scan { table } => Some(table),
+            LogicalPlan::Filter { input, .. } => match input.as_ref() {
+                LogicalPlan::Scan { table } => Some(table),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the inputs of the node.
+    pub fn inputs(&self) -> Vec<&LogicalPlan> {
+        match self {
+            LogicalPlan::Scan { .. } | LogicalPlan::Values { .. } => Vec::new(),
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Projection { input, .. }
+            | LogicalPlan::Aggregate { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => vec![input],
+            LogicalPlan::Join { left, right, .. } => vec![left, right],
+        }
+    }
+
+    /// Returns the node with every input replaced by the result of `map`.
+    pub fn map_inputs(self, mut map: impl FnMut(LogicalPlan) -> LogicalPlan) -> LogicalPlan {
+        let mut map = |input: Box<LogicalPlan>| Box::new(map(*input));
+        match self {
+            LogicalPlan::Scan { .. } | LogicalPlan::Values { .. } => self,
+            LogicalPlan::Filter { input, predicate } => LogicalPlan::Filter {
+                input: map(input),
+                predicate,
+            },
+            LogicalPlan::Projection { input, expressions } => LogicalPlan::Projection {
+                input: map(input),
+                expressions,
+            },
+            LogicalPlan::Join {
+                left,
+                right,
+                join_type,
+                condition,
+            } => LogicalPlan::Join {
+                left: map(left),
+                right: map(right),
+                join_type,
+                condition,
+            },
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => LogicalPlan::Aggregate {
+                input: map(input),
+                group_by,
+                aggregates,
+            },
+            LogicalPlan::Sort { input, order_by } => LogicalPlan::Sort {
+                input: map(input),
+                order_by,
+            },
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => LogicalPlan::Limit {
+                input: map(input),
+                limit,
+                offset,
+            },
+        }
+    }
+
+    /// Returns the types of the columns of the rows produced by the node.
+    pub fn output_types(&self) -> Vec<DataType> {
+        let expression_types = |expressions: &[BoundExpression]| {
+            expressions
+                .iter()
+                .map(BoundExpression::data_type)
+                .collect::<Vec<_>>()
+        };
+        match self {
+            LogicalPlan::Scan { table } => table
+                .schema
+                .columns
+                .iter()
+                .map(|column| column.data_type)
+                .collect(),
+            LogicalPlan::Values { rows } => rows
+                .first()
+                .map(|row| expression_types(row))
+                .unwrap_or_default(),
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Sort { input, .. }
+            | LogicalPlan::Limit { input, .. } => input.output_types(),
+            LogicalPlan::Projection { expressions, .. } => expression_types(expressions),
+            LogicalPlan::Join {
+                left,
+                right,
+                join_type,
+                ..
+            } => {
+                let mut types = left.output_types();
+                if !matches!(join_type, JoinType::Semi | JoinType::Anti) {
+                    types.extend(right.output_types());
+                }
+                types
+            }
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => expression_types(group_by)
+                .into_iter()
+                .chain(aggregates.iter().map(|aggregate| aggregate.data_type))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of columns of the rows produced by the node.
+    pub fn width(&self) -> usize {
+        self.output_types().len()
+    }
+}
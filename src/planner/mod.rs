@@ -0,0 +1,6 @@
+pub mod cost;
+pub mod explain;
+pub mod logical_plan;
+pub mod optimizer;
+pub mod physical_planner;
+pub mod plan;
@@ -0,0 +1,910 @@
+use crate::{
+    binder::bound_expression::BoundExpression,
+    catalog::system_catalog::Catalog,
+    sql::ast::{BinaryOperator, JoinType},
+};
+
+use super::{
+    cost::{Estimate, Estimator, ROW_COST},
+    logical_plan::LogicalPlan,
+    physical_planner::{combine_conjunction, split_conjunction},
+};
+
+/// The largest number of inner-joined inputs whose join order is found by dynamic programming
+/// over all subsets of the inputs. The inputs of larger joins are ordered greedily.
+const MAX_EXHAUSTIVE_JOIN_INPUTS: usize = 10;
+
+/// Rewrites logical plans into equivalent plans that are cheaper to execute:
+///
+/// - Predicates are pushed down as far as possible, into join conditions and below joins,
+///   aggregations and projections, so that rows are discarded early.
+/// - Inputs connected by inner and cross joins are reordered to minimize the estimated cost of
+///   the joins, considering hash joins, index nested loop joins and, for inputs without a join
+///   condition, nested loop joins.
+/// - Columns that are not needed by later nodes are projected away directly above the tables.
+pub struct Optimizer<'a> {
+    estimator: Estimator<'a>,
+}
+
+impl<'a> Optimizer<'a> {
+    pub fn new(catalog: &'a Catalog) -> Optimizer<'a> {
+        Optimizer {
+            estimator: Estimator::new(catalog),
+        }
+    }
+
+    pub fn optimize(&self, plan: LogicalPlan) -> LogicalPlan {
+        let plan = push_down_predicates(plan, Vec::new());
+        let plan = self.reorder_joins(plan);
+        let required = vec![true; plan.width()];
+        prune_columns(plan, &required).0
+    }
+
+    fn reorder_joins(&self, plan: LogicalPlan) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Join {
+                join_type: JoinType::Inner | JoinType::Cross,
+                ..
+            } => {
+                let mut inputs = Vec::new();
+                let mut conjuncts = Vec::new();
+                self.collect_join_inputs(plan, 0, &mut inputs, &mut conjuncts);
+                self.order_join_inputs(inputs, conjuncts)
+            }
+            plan => plan.map_inputs(|input| self.reorder_joins(input)),
+        }
+    }
+
+    /// Collects the inputs of a tree of inner and cross joins and the conjuncts of their
+    /// conditions. The conjuncts are expressions over the concatenated rows of all inputs.
+    fn collect_join_inputs(
+        &self,
+        plan: LogicalPlan,
+        offset: usize,
+        inputs: &mut Vec<LogicalPlan>,
+        conjuncts: &mut Vec<BoundExpression>,
+    ) {
+        match plan {
+            LogicalPlan::Join {
+                left,
+                right,
+                join_type: JoinType::Inner | JoinType::Cross,
+                condition,
+            } => {
+                let left_width = left.width();
+                for conjunct in condition.iter().flat_map(split_conjunction) {
+                    conjuncts.push(conjunct.map_columns(&|column| column + offset));
+                }
+                self.collect_join_inputs(*left, offset, inputs, conjuncts);
+                self.collect_join_inputs(*right, offset + left_width, inputs, conjuncts);
+            }
+            plan => inputs.push(self.reorder_joins(plan)),
+        }
+    }
+
+    /// Joins the inputs in the cheapest order found. Each conjunct becomes part of the condition
+    /// of the first join after which all of its columns are available. If the order differs from
+    /// the order of the inputs, a projection restores the original order of the columns.
+    fn order_join_inputs(
+        &self,
+        inputs: Vec<LogicalPlan>,
+        conjuncts: Vec<BoundExpression>,
+    ) -> LogicalPlan {
+        let graph = JoinGraph::new(&self.estimator, &inputs, &conjuncts);
+        let order = if inputs.len() <= MAX_EXHAUSTIVE_JOIN_INPUTS {
+            graph.exhaustive_order()
+        } else {
+            graph.greedy_order()
+        };
+        let JoinGraph {
+            widths,
+            offsets,
+            conjunct_inputs,
+            ..
+        } = graph;
+
+        let types: Vec<_> = inputs.iter().flat_map(LogicalPlan::output_types).collect();
+        let mut positions = vec![0; types.len()];
+        let mut position = 0;
+        for input in &order {
+            for column in 0..widths[*input] {
+                positions[offsets[*input] + column] = position;
+                position += 1;
+            }
+        }
+
+        let mut inputs: Vec<Option<LogicalPlan>> = inputs.into_iter().map(Some).collect();
+        let mut joined = vec![false; inputs.len()];
+        let mut used = vec![false; conjuncts.len()];
+        joined[order[0]] = true;
+        let mut plan = inputs[order[0]].take().unwrap();
+        for input in &order[1..] {
+            joined[*input] = true;
+            let mut condition = Vec::new();
+            for (i, conjunct) in conjuncts.iter().enumerate() {
+                if !used[i] && conjunct_inputs[i].iter().all(|input| joined[*input]) {
+                    used[i] = true;
+                    condition.push(conjunct.map_columns(&|column| positions[column]));
+                }
+            }
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(inputs[*input].take().unwrap()),
+                join_type: if condition.is_empty() {
+                    JoinType::Cross
+                } else {
+                    JoinType::Inner
+                },
+                condition: combine_conjunction(condition),
+            };
+        }
+
+        if order.windows(2).all(|pair| pair[0] < pair[1]) {
+            return plan;
+        }
+        LogicalPlan::Projection {
+            input: Box::new(plan),
+            expressions: types
+                .into_iter()
+                .enumerate()
+                .map(|(column, data_type)| BoundExpression::Column {
+                    index: positions[column],
+                    data_type,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The inputs of a tree of inner joins and the conjuncts connecting them.
+struct JoinGraph<'a, 'e> {
+    estimator: &'e Estimator<'a>,
+    inputs: &'e [LogicalPlan],
+    widths: Vec<usize>,
+    /// The position of the first column of every input in the concatenated row.
+    offsets: Vec<usize>,
+    estimates: Vec<Estimate>,
+    /// The cost of producing the rows of every input once.
+    costs: Vec<f64>,
+    /// The inputs whose columns every conjunct reads.
+    conjunct_inputs: Vec<Vec<usize>>,
+    conjunct_selectivities: Vec<f64>,
+    conjuncts: &'e [BoundExpression],
+}
+
+/// A join order of some of the inputs, with its estimated cost and number of rows.
+#[derive(Clone)]
+struct PartialOrder {
+    order: Vec<usize>,
+    cost: f64,
+    rows: f64,
+}
+
+impl<'a, 'e> JoinGraph<'a, 'e> {
+    fn new(
+        estimator: &'e Estimator<'a>,
+        inputs: &'e [LogicalPlan],
+        conjuncts: &'e [BoundExpression],
+    ) -> JoinGraph<'a, 'e> {
+        let widths: Vec<usize> = inputs.iter().map(LogicalPlan::width).collect();
+        let offsets: Vec<usize> = widths
+            .iter()
+            .scan(0, |offset, width| {
+                let start = *offset;
+                *offset += width;
+                Some(start)
+            })
+            .collect();
+        let estimates: Vec<Estimate> = inputs
+            .iter()
+            .map(|input| estimator.estimate(input))
+            .collect();
+        let joined = Estimate {
+            rows: estimates.iter().map(|estimate| estimate.rows).product(),
+            columns: estimates
+                .iter()
+                .flat_map(|estimate| estimate.columns.iter().cloned())
+                .collect(),
+        };
+        let input_of = |column: usize| offsets.partition_point(|offset| *offset <= column) - 1;
+        let conjunct_inputs = conjuncts
+            .iter()
+            .map(|conjunct| {
+                let mut inputs: Vec<usize> = conjunct
+                    .referenced_columns()
+                    .into_iter()
+                    .map(input_of)
+                    .collect();
+                inputs.sort_unstable();
+                inputs.dedup();
+                inputs
+            })
+            .collect();
+        JoinGraph {
+            estimator,
+            inputs,
+            costs: inputs.iter().map(|input| estimator.cost(input)).collect(),
+            widths,
+            offsets,
+            estimates,
+            conjunct_inputs,
+            conjunct_selectivities: conjuncts
+                .iter()
+                .map(|conjunct| estimator.selectivity(conjunct, &joined))
+                .collect(),
+            conjuncts,
+        }
+    }
+
+    fn start(&self, input: usize) -> PartialOrder {
+        PartialOrder {
+            order: vec![input],
+            cost: self.costs[input],
+            rows: self.estimates[input].rows,
+        }
+    }
+
+    /// Returns the order extended by joining the input as the right input of a join.
+    fn extend(&self, partial: &PartialOrder, input: usize) -> PartialOrder {
+        let joined = |other: usize| other == input || partial.order.contains(&other);
+        let mut selectivity = 1.0;
+        let mut connected = false;
+        let mut key_columns = Vec::new();
+        for (i, inputs) in self.conjunct_inputs.iter().enumerate() {
+            if !inputs.contains(&input) || !inputs.iter().all(|other| joined(*other)) {
+                continue;
+            }
+            selectivity *= self.conjunct_selectivities[i];
+            if inputs.len() > 1 {
+                connected = true;
+                key_columns.extend(self.key_column(&self.conjuncts[i], input));
+            }
+        }
+
+        let right_rows = self.estimates[input].rows;
+        let rows = (partial.rows * right_rows * selectivity).max(1.0);
+        let right_cost = self.costs[input];
+        let mut join_cost = if connected {
+            right_cost + self.estimator.hash_join_cost(partial.rows, right_rows)
+        } else {
+            right_cost
+                + self
+                    .estimator
+                    .nested_loop_join_cost(partial.rows, right_rows, right_cost)
+        };
+        if let Some(table) = self.inputs[input].table() {
+            if let Some(index_join_cost) =
+                self.estimator
+                    .index_join_cost(table, &key_columns, partial.rows)
+            {
+                join_cost = join_cost.min(index_join_cost);
+            }
+        }
+
+        let mut order = partial.order.clone();
+        order.push(input);
+        PartialOrder {
+            order,
+            cost: partial.cost + join_cost + rows * ROW_COST,
+            rows,
+        }
+    }
+
+    /// Returns the column of the input that the conjunct compares for equality with the other
+    /// inputs, as a position in the rows of the input.
+    fn key_column(&self, conjunct: &BoundExpression, input: usize) -> Option<usize> {
+        let (left, right) = match conjunct {
+            BoundExpression::Binary {
+                left,
+                operator: BinaryOperator::Equal,
+                right,
+                ..
+            } => (left.as_ref(), right.as_ref()),
+            _ => return None,
+        };
+        let range = self.offsets[input]..self.offsets[input] + self.widths[input];
+        let column = |expression: &BoundExpression| match expression {
+            BoundExpression::Column { index, .. } if range.contains(index) => Some(*index),
+            _ => None,
+        };
+        let outside = |expression: &BoundExpression| {
+            expression
+                .referenced_columns()
+                .iter()
+                .all(|column| !range.contains(column))
+        };
+        match (column(left), column(right)) {
+            (Some(column), None) if outside(right) => Some(column - range.start),
+            (None, Some(column)) if outside(left) => Some(column - range.start),
+            _ => None,
+        }
+    }
+
+    /// Finds the cheapest left-deep join order by dynamic programming over the subsets of the
+    /// inputs.
+    fn exhaustive_order(&self) -> Vec<usize> {
+        let count = self.inputs.len();
+        let mut best: Vec<Option<PartialOrder>> = vec![None; 1 << count];
+        for input in 0..count {
+            best[1 << input] = Some(self.start(input));
+        }
+        for subset in 1..(1usize << count) {
+            let partial = match best[subset].take() {
+                Some(partial) => partial,
+                None => continue,
+            };
+            for input in (0..count).filter(|input| subset & (1 << input) == 0) {
+                let extended = self.extend(&partial, input);
+                let entry = &mut best[subset | (1 << input)];
+                if entry
+                    .as_ref()
+                    .is_none_or(|other| extended.cost < other.cost)
+                {
+                    *entry = Some(extended);
+                }
+            }
+            best[subset] = Some(partial);
+        }
+        best[(1 << count) - 1].take().unwrap().order
+    }
+
+    /// Starts with the input with the fewest rows and repeatedly joins the input that is the
+    /// cheapest to join next.
+    fn greedy_order(&self) -> Vec<usize> {
+        let count = self.inputs.len();
+        let first = (0..count)
+            .min_by(|left, right| {
+                self.estimates[*left]
+                    .rows
+                    .total_cmp(&self.estimates[*right].rows)
+            })
+            .unwrap();
+        let mut partial = self.start(first);
+        while partial.order.len() < count {
+            partial = (0..count)
+                .filter(|input| !partial.order.contains(input))
+                .map(|input| self.extend(&partial, input))
+                .min_by(|left, right| left.cost.total_cmp(&right.cost))
+                .unwrap();
+        }
+        partial.order
+    }
+}
+
+/// Moves the predicates, which are conditions over the rows produced by the plan, as far down
+/// into the plan as possible. The predicates that cannot be moved further are evaluated by a
+/// filter.
+fn push_down_predicates(plan: LogicalPlan, mut predicates: Vec<BoundExpression>) -> LogicalPlan {
+    match plan {
+        LogicalPlan::Filter { input, predicate } => {
+            predicates.extend(split_conjunction(&predicate));
+            push_down_predicates(*input, predicates)
+        }
+        LogicalPlan::Projection { input, expressions } => {
+            let predicates = predicates
+                .iter()
+                .map(|predicate| predicate.replace_columns(&|column| expressions[column].clone()))
+                .collect();
+            LogicalPlan::Projection {
+                input: Box::new(push_down_predicates(*input, predicates)),
+                expressions,
+            }
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            condition,
+        } => push_down_into_join(*left, *right, join_type, condition, predicates),
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+        } => {
+            // Predicates that only read group values can be evaluated before the aggregation.
+            // Predicates without columns cannot, as an aggregation without GROUP BY produces a
+            // row even without input rows.
+            let (pushed, remaining): (Vec<_>, Vec<_>) =
+                predicates.into_iter().partition(|predicate| {
+                    let columns = predicate.referenced_columns();
+                    !columns.is_empty() && columns.iter().all(|column| *column < group_by.len())
+                });
+            let pushed = pushed
+                .iter()
+                .map(|predicate| predicate.replace_columns(&|column| group_by[column].clone()))
+                .collect();
+            LogicalPlan::Aggregate {
+                input: Box::new(push_down_predicates(*input, pushed)),
+                group_by,
+                aggregates,
+            }
+            .with_filter(combine_conjunction(remaining))
+        }
+        LogicalPlan::Sort { input, order_by } => LogicalPlan::Sort {
+            input: Box::new(push_down_predicates(*input, predicates)),
+            order_by,
+        },
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => LogicalPlan::Limit {
+            input: Box::new(push_down_predicates(*input, Vec::new())),
+            limit,
+            offset,
+        }
+        .with_filter(combine_conjunction(predicates)),
+        plan @ (LogicalPlan::Scan { .. } | LogicalPlan::Values { .. }) => {
+            plan.with_filter(combine_conjunction(predicates))
+        }
+    }
+}
+
+/// Distributes the predicates over the join and the conjuncts of its condition to the inputs
+/// of the join, as far as this preserves the rows the join produces for the preserved side of
+/// outer joins.
+fn push_down_into_join(
+    left: LogicalPlan,
+    right: LogicalPlan,
+    join_type: JoinType,
+    condition: Option<BoundExpression>,
+    predicates: Vec<BoundExpression>,
+) -> LogicalPlan {
+    let left_width = left.width();
+    let sides = |expression: &BoundExpression| {
+        let columns = expression.referenced_columns();
+        (
+            columns.iter().any(|column| *column < left_width),
+            columns.iter().any(|column| *column >= left_width),
+        )
+    };
+    let rebase =
+        |expression: &BoundExpression| expression.map_columns(&|column| column - left_width);
+    let inner = matches!(join_type, JoinType::Inner | JoinType::Cross);
+
+    let mut left_predicates = Vec::new();
+    let mut right_predicates = Vec::new();
+    let mut conjuncts = Vec::new();
+    let mut remaining = Vec::new();
+    // Predicates over the joined rows may only filter the inputs whose unmatched rows are not
+    // padded with NULLs.
+    for predicate in predicates {
+        let (uses_left, uses_right) = sides(&predicate);
+        if !uses_right
+            && (inner || matches!(join_type, JoinType::Left | JoinType::Semi | JoinType::Anti))
+        {
+            left_predicates.push(predicate);
+        } else if !uses_left && (inner || join_type == JoinType::Right) {
+            right_predicates.push(rebase(&predicate));
+        } else if inner {
+            conjuncts.push(predicate);
+        } else {
+            remaining.push(predicate);
+        }
+    }
+    // Conjuncts of the condition may only filter the inputs whose rows are dropped if they have
+    // no match.
+    for conjunct in condition.iter().flat_map(split_conjunction) {
+        let (uses_left, uses_right) = sides(&conjunct);
+        if !uses_right && (inner || matches!(join_type, JoinType::Right | JoinType::Semi)) {
+            left_predicates.push(conjunct);
+        } else if !uses_left
+            && (inner || matches!(join_type, JoinType::Left | JoinType::Semi | JoinType::Anti))
+        {
+            right_predicates.push(rebase(&conjunct));
+        } else {
+            conjuncts.push(conjunct);
+        }
+    }
+
+    let join_type = match join_type {
+        JoinType::Cross if !conjuncts.is_empty() => JoinType::Inner,
+        join_type => join_type,
+    };
+    LogicalPlan::Join {
+        left: Box::new(push_down_predicates(left, left_predicates)),
+        right: Box::new(push_down_predicates(right, right_predicates)),
+        join_type,
+        condition: combine_conjunction(conjuncts),
+    }
+    .with_filter(combine_conjunction(remaining))
+}
+
+/// Removes the columns that are not required from the rows produced by the plan, as far as
+/// possible. Returns the new plan and the new position of every column of the original rows,
+/// which is None for removed columns.
+fn prune_columns(plan: LogicalPlan, required: &[bool]) -> (LogicalPlan, Vec<Option<usize>>) {
+    match plan {
+        LogicalPlan::Scan { .. } => project_columns(plan, required),
+        LogicalPlan::Filter { input, predicate } if matches!(*input, LogicalPlan::Scan { .. }) => {
+            // The columns are removed after the filter, so that the filter can still use an
+            // index of the table.
+            project_columns(LogicalPlan::Filter { input, predicate }, required)
+        }
+        LogicalPlan::Filter { input, predicate } => {
+            let mut needed = required.to_vec();
+            mark_columns(&predicate, &mut needed);
+            let (input, positions) = prune_columns(*input, &needed);
+            let predicate = move_columns(&predicate, &positions);
+            (
+                LogicalPlan::Filter {
+                    input: Box::new(input),
+                    predicate,
+                },
+                positions,
+            )
+        }
+        LogicalPlan::Projection { input, expressions } => {
+            let kept: Vec<usize> = match (0..expressions.len()).filter(|i| required[*i]).collect() {
+                kept if Vec::is_empty(&kept) => (0..expressions.len()).collect(),
+                kept => kept,
+            };
+            let mut needed = vec![false; input.width()];
+            for i in &kept {
+                mark_columns(&expressions[*i], &mut needed);
+            }
+            let (input, input_positions) = prune_columns(*input, &needed);
+            let mut positions = vec![None; expressions.len()];
+            let mut kept_expressions = Vec::with_capacity(kept.len());
+            for i in kept {
+                positions[i] = Some(kept_expressions.len());
+                kept_expressions.push(move_columns(&expressions[i], &input_positions));
+            }
+            // Consecutive projections are merged into one.
+            let (input, expressions) = match input {
+                LogicalPlan::Projection {
+                    input,
+                    expressions: input_expressions,
+                } => (
+                    *input,
+                    kept_expressions
+                        .iter()
+                        .map(|expression| {
+                            expression.replace_columns(&|column| input_expressions[column].clone())
+                        })
+                        .collect(),
+                ),
+                input => (input, kept_expressions),
+            };
+            (
+                LogicalPlan::Projection {
+                    input: Box::new(input),
+                    expressions,
+                },
+                positions,
+            )
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            condition,
+        } => {
+            let left_width = left.width();
+            let mut needed = vec![false; left_width + right.width()];
+            for (needed, required) in needed.iter_mut().zip(required) {
+                *needed |= *required;
+            }
+            if let Some(condition) = &condition {
+                mark_columns(condition, &mut needed);
+            }
+            let (left, left_positions) = prune_columns(*left, &needed[..left_width]);
+            let (right, right_positions) = prune_columns(*right, &needed[left_width..]);
+            let new_left_width = left.width();
+            let joined_positions: Vec<Option<usize>> = left_positions
+                .iter()
+                .cloned()
+                .chain(
+                    right_positions
+                        .iter()
+                        .map(|position| position.map(|position| position + new_left_width)),
+                )
+                .collect();
+            let condition = condition
+                .as_ref()
+                .map(|condition| move_columns(condition, &joined_positions));
+            let positions = match join_type {
+                JoinType::Semi | JoinType::Anti => left_positions,
+                _ => joined_positions,
+            };
+            (
+                LogicalPlan::Join {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    join_type,
+                    condition,
+                },
+                positions,
+            )
+        }
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            mut aggregates,
+        } => {
+            let mut needed = vec![false; input.width()];
+            for expression in group_by.iter().chain(
+                aggregates
+                    .iter()
+                    .filter_map(|aggregate| aggregate.argument.as_ref()),
+            ) {
+                mark_columns(expression, &mut needed);
+            }
+            let (input, input_positions) = prune_columns(*input, &needed);
+            let group_by = group_by
+                .iter()
+                .map(|expression| move_columns(expression, &input_positions))
+                .collect();
+            for aggregate in &mut aggregates {
+                if let Some(argument) = &aggregate.argument {
+                    aggregate.argument = Some(move_columns(argument, &input_positions));
+                }
+            }
+            (
+                LogicalPlan::Aggregate {
+                    input: Box::new(input),
+                    group_by,
+                    aggregates,
+                },
+                (0..required.len()).map(Some).collect(),
+            )
+        }
+        LogicalPlan::Sort { input, order_by } => {
+            let mut needed = required.to_vec();
+            for (expression, _) in &order_by {
+                mark_columns(expression, &mut needed);
+            }
+            let (input, positions) = prune_columns(*input, &needed);
+            let order_by = order_by
+                .iter()
+                .map(|(expression, ascending)| (move_columns(expression, &positions), *ascending))
+                .collect();
+            (
+                LogicalPlan::Sort {
+                    input: Box::new(input),
+                    order_by,
+                },
+                positions,
+            )
+        }
+        LogicalPlan::Limit {
+            input,
+            limit,
+            offset,
+        } => {
+            let (input, positions) = prune_columns(*input, required);
+            (
+                LogicalPlan::Limit {
+                    input: Box::new(input),
+                    limit,
+                    offset,
+                },
+                positions,
+            )
+        }
+        plan @ LogicalPlan::Values { .. } => {
+            let width = plan.width();
+            (plan, (0..width).map(Some).collect())
+        }
+    }
+}
+
+/// Projects the rows of the plan to the required columns. The plan is kept as is if all or none
+/// of its columns are required.
+fn project_columns(plan: LogicalPlan, required: &[bool]) -> (LogicalPlan, Vec<Option<usize>>) {
+    if required.iter().all(|required| *required) || !required.contains(&true) {
+        return (plan, (0..required.len()).map(Some).collect());
+    }
+    let types = plan.output_types();
+    let mut positions = vec![None; required.len()];
+    let mut expressions = Vec::new();
+    for (column, data_type) in types.into_iter().enumerate() {
+        if required[column] {
+            positions[column] = Some(expressions.len());
+            expressions.push(BoundExpression::Column {
+                index: column,
+                data_type,
+            });
+        }
+    }
+    (
+        LogicalPlan::Projection {
+            input: Box::new(plan),
+            expressions,
+        },
+        positions,
+    )
+}
+
+fn mark_columns(expression: &BoundExpression, needed: &mut [bool]) {
+    for column in expression.referenced_columns() {
+        needed[column] = true;
+    }
+}
+
+/// Replaces the columns of the expression by their new positions.
+fn move_columns(expression: &BoundExpression, positions: &[Option<usize>]) -> BoundExpression {
+    expression.map_columns(&|column| {
+        positions[column].expect("A column used by an expression has been removed")
+    })
+}
+
+#[cfg(test)]
+mod optimizer_tests {
+    use crate::{
+        binder::{bound_statement::BoundStatement, statement_binder::Binder},
+        database::engine::{Database, QueryResult},
+        disk_management::test_utils::new_test_buffer_pool,
+        sql::{ast::JoinType, parser::parse_statement},
+        types::value::Value,
+    };
+
+    use super::{LogicalPlan, Optimizer};
+
+    fn optimize(database: &Database, sql: &str) -> LogicalPlan {
+        let catalog = &database.catalog();
+        let select = match Binder::new(catalog)
+            .bind(&parse_statement(sql).unwrap())
+            .unwrap()
+        {
+            BoundStatement::Select(select) => select,
+            statement => panic!("expected a SELECT, got {:?}", statement),
+        };
+        let plan = LogicalPlan::from_select(catalog, &select).unwrap();
+        Optimizer::new(catalog).optimize(plan)
+    }
+
+    /// Returns the nodes of the plan in pre-order.
+    fn all_nodes(plan: &LogicalPlan) -> Vec<&LogicalPlan> {
+        let mut nodes = vec![plan];
+        for input in plan.inputs() {
+            nodes.extend(all_nodes(input));
+        }
+        nodes
+    }
+
+    fn is_filtered_scan(plan: &LogicalPlan, table: &str) -> bool {
+        match plan {
+            LogicalPlan::Filter { input, .. } => {
+                matches!(input.as_ref(), LogicalPlan::Scan { table: info } if info.name == table)
+            }
+            _ => false,
+        }
+    }
+
+    fn new_database(name: &str) -> Database {
+        let mut database = Database::new(new_test_buffer_pool(name)).unwrap();
+        let values: Vec<String> = (0..2000)
+            .map(|i| format!("({}, {}, 'item {}')", i, i % 50, i))
+            .collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE small (id INTEGER, x INTEGER, name TEXT);
+                 CREATE TABLE big (id INTEGER, x INTEGER, label TEXT);
+                 CREATE TABLE tiny (x INTEGER, note TEXT);
+                 INSERT INTO small VALUES (1, 1, 'one'), (2, 2, 'two'), (3, 3, 'three');
+                 INSERT INTO tiny VALUES (1, 'a'), (2, 'b');
+                 INSERT INTO big VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+        database
+    }
+
+    #[test]
+    fn push_down_predicates() {
+        let database = new_database("optimizer_push_down");
+        let plan = optimize(
+            &database,
+            "SELECT s.name FROM small s JOIN tiny t ON s.x = t.x \
+             WHERE s.id > 1 AND t.note = 'b' AND s.id + t.x > 2",
+        );
+        let nodes = all_nodes(&plan);
+        assert!(nodes.iter().any(|node| is_filtered_scan(node, "small")));
+        assert!(nodes.iter().any(|node| is_filtered_scan(node, "tiny")));
+        // The predicate over both inputs becomes part of the join condition.
+        assert!(!nodes.iter().any(|node| matches!(
+            node,
+            LogicalPlan::Filter { input, .. } if matches!(input.as_ref(), LogicalPlan::Join { .. })
+        )));
+
+        // A predicate over the NULL-padded side of an outer join must stay above the join.
+        let plan = optimize(
+            &database,
+            "SELECT s.name FROM small s LEFT JOIN tiny t ON s.x = t.x WHERE t.note IS NULL",
+        );
+        let nodes = all_nodes(&plan);
+        assert!(!nodes.iter().any(|node| is_filtered_scan(node, "tiny")));
+        assert!(nodes.iter().any(|node| matches!(
+            node,
+            LogicalPlan::Filter { input, .. }
+                if matches!(input.as_ref(), LogicalPlan::Join { join_type: JoinType::Left, .. })
+        )));
+
+        // A HAVING predicate on the group values is evaluated before the aggregation.
+        let plan = optimize(
+            &database,
+            "SELECT x, COUNT(*) FROM big GROUP BY x HAVING x = 3 AND COUNT(*) > 1",
+        );
+        let nodes = all_nodes(&plan);
+        assert!(nodes.iter().any(|node| is_filtered_scan(node, "big")));
+        assert!(nodes.iter().any(|node| matches!(
+            node,
+            LogicalPlan::Filter { input, .. }
+                if matches!(input.as_ref(), LogicalPlan::Aggregate { .. })
+        )));
+    }
+
+    #[test]
+    fn reorder_joins_by_estimated_rows() {
+        let mut database = new_database("optimizer_reorder");
+        let sql = "SELECT * FROM tiny t JOIN small s ON t.x = s.x JOIN big b ON s.x = b.x \
+                   WHERE s.id = 1";
+        let plan = optimize(&database, sql);
+        // Hash joins build their hash tables over the right input, so the large table is the
+        // left input that probes the hash tables over the small inputs.
+        let (input, expressions) = match &plan {
+            LogicalPlan::Projection { input, expressions } => (input.as_ref(), expressions),
+            plan => panic!(
+                "expected a projection restoring the column order, got {:?}",
+                plan
+            ),
+        };
+        assert_eq!(expressions.len(), 8);
+        let mut leftmost = input;
+        let mut joins = 0;
+        while let LogicalPlan::Join {
+            left, join_type, ..
+        } = leftmost
+        {
+            assert_eq!(*join_type, JoinType::Inner);
+            leftmost = left;
+            joins += 1;
+        }
+        assert_eq!(joins, 2);
+        assert_eq!(leftmost.table().unwrap().name, "big");
+
+        // The columns are produced in the order of the FROM clause.
+        let rows = match database.execute(sql).unwrap().remove(0) {
+            QueryResult::Rows { rows, .. } => rows,
+            result => panic!("expected rows, got {:?}", result),
+        };
+        assert_eq!(rows.len(), 40);
+        for row in &rows {
+            assert_eq!(
+                row.values[..5],
+                [
+                    Value::Integer(1),
+                    Value::String("a".to_string()),
+                    Value::Integer(1),
+                    Value::Integer(1),
+                    Value::String("one".to_string()),
+                ]
+            );
+            assert_eq!(row.values[6], Value::Integer(1));
+        }
+    }
+
+    #[test]
+    fn prune_unused_columns() {
+        let database = new_database("optimizer_prune");
+        let plan = optimize(
+            &database,
+            "SELECT b.label FROM big b JOIN small s ON b.id = s.id WHERE s.name = 'two'",
+        );
+        let nodes = all_nodes(&plan);
+        // Only the join key is read from the filtered small table, and only the key and the
+        // label from the big table.
+        assert!(nodes.iter().any(|node| matches!(
+            node,
+            LogicalPlan::Projection { input, expressions }
+                if expressions.len() == 1 && is_filtered_scan(input, "small")
+        )));
+        assert!(nodes.iter().any(|node| matches!(
+            node,
+            LogicalPlan::Projection { input, expressions }
+                if expressions.len() == 2
+                    && matches!(input.as_ref(), LogicalPlan::Scan { table } if table.name == "big")
+        )));
+    }
+}
@@ -0,0 +1,957 @@
+use crate::{
+    binder::{
+        bound_expression::BoundExpression,
+        bound_statement::{BoundDelete, BoundInsert, BoundSelect, BoundStatement, BoundUpdate},
+    },
+    catalog::system_catalog::{Catalog, CatalogError, IndexInfo, IndexType, TableInfo},
+    execution::executor::ExecutionError,
+    sql::ast::{BinaryOperator, JoinType},
+    types::schema::DataType,
+};
+
+use super::{
+    cost::Estimator,
+    logical_plan::LogicalPlan,
+    optimizer::Optimizer,
+    plan::{index_range_conditions, IndexBound, PlanNode},
+};
+
+/// The join algorithms the planner can choose from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinStrategy {
+    NestedLoop,
+    IndexNestedLoop,
+    Hash,
+    SortMerge,
+}
+
+/// Creates the physical plans of bound SELECT, INSERT, UPDATE and DELETE statements. The
+/// logical plan of a SELECT is rewritten by the optimizer first.
+///
+/// A table is read with an index scan if the filter compares all key columns of an index with
+/// constants and the estimated cost of the lookup is lower than that of a sequential scan. B+
+/// tree indexes keep their keys in order, so a table is also read with an index range scan if
+/// the filter compares the first key column of one with constants using `<`, `<=`, `>` or `>=`
+/// and the range is estimated to be cheaper to read.
+///
+/// Joins use an index nested loop join if the right input is a table with an index on
+/// columns compared for equality with the left input and looking up the left rows is estimated
+/// to be cheaper than a hash join, and a hash join if there is any such equality. Other joins
+/// are nested loop joins.
+pub struct Planner<'a> {
+    catalog: &'a Catalog,
+    join_strategy: Option<JoinStrategy>,
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(catalog: &'a Catalog) -> Planner<'a> {
+        Planner {
+            catalog,
+            join_strategy: None,
+        }
+    }
+
+    /// Uses the join algorithm for all joins it can execute.
+    pub fn with_join_strategy(mut self, join_strategy: JoinStrategy) -> Planner<'a> {
+        self.join_strategy = Some(join_strategy);
+        self
+    }
+
+    pub fn plan(&self, statement: &BoundStatement) -> Result<PlanNode, ExecutionError> {
+        match statement {
+            BoundStatement::Select(select) => self.plan_select(select),
+            BoundStatement::Insert(insert) => self.plan_insert(insert),
+            BoundStatement::Update(update) => self.plan_update(update),
+            BoundStatement::Delete(delete) => self.plan_delete(delete),
+            BoundStatement::CreateTable { .. }
+            | BoundStatement::DropTable { .. }
+            | BoundStatement::CreateIndex { .. }
+            | BoundStatement::Analyze { .. }
+            | BoundStatement::Vacuum { .. } => Err(ExecutionError::Unsupported(
+                "DDL statements have no query plan".to_string(),
+            )),
+            BoundStatement::Explain { .. } => Err(ExecutionError::Unsupported(
+                "EXPLAIN has no query plan".to_string(),
+            )),
+            BoundStatement::Begin { .. } | BoundStatement::Commit | BoundStatement::Rollback => {
+                Err(ExecutionError::Unsupported(
+                    "Transaction statements have no query plan".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn plan_select(&self, select: &BoundSelect) -> Result<PlanNode, ExecutionError> {
+        let plan = LogicalPlan::from_select(self.catalog, select)?;
+        let plan = Optimizer::new(self.catalog).optimize(plan);
+        self.plan_logical(&plan)
+    }
+
+    /// Chooses the algorithms that execute an optimized logical plan.
+    fn plan_logical(&self, plan: &LogicalPlan) -> Result<PlanNode, ExecutionError> {
+        let plan_input = |input: &LogicalPlan| self.plan_logical(input).map(Box::new);
+        Ok(match plan {
+            LogicalPlan::Scan { table } => self.plan_table_access(table, None),
+            LogicalPlan::Values { rows } => PlanNode::Values { rows: rows.clone() },
+            LogicalPlan::Filter { input, predicate } => match input.as_ref() {
+                LogicalPlan::Scan { table } => self.plan_table_access(table, Some(predicate)),
+                input => PlanNode::Filter {
+                    input: plan_input(input)?,
+                    predicate: predicate.clone(),
+                },
+            },
+            LogicalPlan::Projection { input, expressions } => PlanNode::Projection {
+                input: plan_input(input)?,
+                expressions: expressions.clone(),
+            },
+            LogicalPlan::Join {
+                left,
+                right,
+                join_type,
+                condition,
+            } => self.plan_join(left, right, *join_type, condition.as_ref())?,
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => PlanNode::Aggregate {
+                input: plan_input(input)?,
+                group_by: group_by.clone(),
+                aggregates: aggregates.clone(),
+            },
+            LogicalPlan::Sort { input, order_by } => PlanNode::Sort {
+                input: plan_input(input)?,
+                order_by: order_by.clone(),
+                limit: None,
+            },
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let sort_limit = limit.map(|limit| limit.saturating_add(*offset));
+                PlanNode::Limit {
+                    input: Box::new(limit_sort(self.plan_logical(input)?, sort_limit)),
+                    limit: *limit,
+                    offset: *offset,
+                }
+            }
+        })
+    }
+
+    fn plan_insert(&self, insert: &BoundInsert) -> Result<PlanNode, ExecutionError> {
+        let table = self.get_table(insert.table_id)?;
+        Ok(PlanNode::Insert {
+            table: table.clone(),
+            indexes: self.get_indexes(table),
+            input: Box::new(PlanNode::Values {
+                rows: insert.rows.clone(),
+            }),
+        })
+    }
+
+    fn plan_update(&self, update: &BoundUpdate) -> Result<PlanNode, ExecutionError> {
+        let table = self.get_table(update.table_id)?;
+        Ok(PlanNode::Update {
+            table: table.clone(),
+            indexes: self.get_indexes(table),
+            input: Box::new(self.plan_table_access(table, update.filter.as_ref())),
+            assignments: update.assignments.clone(),
+        })
+    }
+
+    fn plan_delete(&self, delete: &BoundDelete) -> Result<PlanNode, ExecutionError> {
+        let table = self.get_table(delete.table_id)?;
+        Ok(PlanNode::Delete {
+            table: table.clone(),
+            indexes: self.get_indexes(table),
+            input: Box::new(self.plan_table_access(table, delete.filter.as_ref())),
+        })
+    }
+
+    fn plan_join(
+        &self,
+        left: &LogicalPlan,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        condition: Option<&BoundExpression>,
+    ) -> Result<PlanNode, ExecutionError> {
+        let left_plan = self.plan_logical(left)?;
+        let left_width = left.width();
+        let conjuncts = condition.map(split_conjunction).unwrap_or_default();
+        let mut equalities = Vec::new();
+        let mut residual = Vec::new();
+        for conjunct in conjuncts {
+            match join_equality(&conjunct, left_width) {
+                Some(equality) => equalities.push(equality),
+                None => residual.push(conjunct),
+            }
+        }
+
+        let strategy = self.join_strategy;
+        if strategy.is_none() || strategy == Some(JoinStrategy::IndexNestedLoop) {
+            if let Some(access) = TableAccess::new(right) {
+                if let Some((plan, cost)) = self.plan_index_join(
+                    &left_plan,
+                    left,
+                    &access,
+                    join_type,
+                    &equalities,
+                    &residual,
+                ) {
+                    let estimator = Estimator::new(self.catalog);
+                    let hash_join_cost = estimator.cost(right)
+                        + estimator.hash_join_cost(
+                            estimator.estimate(left).rows,
+                            estimator.estimate(right).rows,
+                        );
+                    if strategy.is_some() || cost < hash_join_cost {
+                        return Ok(plan);
+                    }
+                }
+            }
+        }
+
+        let right_plan = self.plan_logical(right)?;
+        if equalities.is_empty() || strategy == Some(JoinStrategy::NestedLoop) {
+            return Ok(PlanNode::NestedLoopJoin {
+                left: Box::new(left_plan),
+                right: Box::new(right_plan),
+                join_type,
+                condition: condition.cloned(),
+            });
+        }
+        let (left_keys, right_keys): (Vec<_>, Vec<_>) = equalities
+            .into_iter()
+            .map(|equality| (equality.left, equality.right))
+            .unzip();
+        let condition = combine_conjunction(residual);
+        if strategy == Some(JoinStrategy::SortMerge) {
+            let sorted = |input: PlanNode, keys: &[BoundExpression]| PlanNode::Sort {
+                input: Box::new(input),
+                order_by: keys.iter().map(|key| (key.clone(), true)).collect(),
+                limit: None,
+            };
+            return Ok(PlanNode::SortMergeJoin {
+                left: Box::new(sorted(left_plan, &left_keys)),
+                right: Box::new(sorted(right_plan, &right_keys)),
+                join_type,
+                left_keys,
+                right_keys,
+                condition,
+            });
+        }
+        Ok(PlanNode::HashJoin {
+            left: Box::new(left_plan),
+            right: Box::new(right_plan),
+            join_type,
+            left_keys,
+            right_keys,
+            condition,
+        })
+    }
+
+    /// Returns the cheapest index nested loop join over a hash index of the right table whose
+    /// key columns are all compared with expressions over the left input, and its estimated
+    /// cost. The filter of the right table becomes part of the join condition, and its
+    /// projection is applied to the joined rows.
+    fn plan_index_join(
+        &self,
+        left_plan: &PlanNode,
+        left: &LogicalPlan,
+        right: &TableAccess,
+        join_type: JoinType,
+        equalities: &[JoinEquality],
+        residual: &[BoundExpression],
+    ) -> Option<(PlanNode, f64)> {
+        if matches!(join_type, JoinType::Right | JoinType::Full) {
+            return None;
+        }
+        let table = right.table;
+        let left_types = left.output_types();
+        let left_width = left_types.len();
+        // The join condition over the left row followed by the row of the table.
+        let table_column = |column: usize| right.column(column);
+        let equalities: Vec<JoinEquality> = equalities
+            .iter()
+            .map(|equality| JoinEquality {
+                left: equality.left.clone(),
+                right: equality.right.replace_columns(&table_column),
+            })
+            .collect();
+        let residual: Vec<BoundExpression> = residual
+            .iter()
+            .map(|conjunct| {
+                conjunct.replace_columns(&|column| {
+                    if column < left_width {
+                        BoundExpression::Column {
+                            index: column,
+                            data_type: left_types[column],
+                        }
+                    } else {
+                        right
+                            .column(column - left_width)
+                            .map_columns(&|column| column + left_width)
+                    }
+                })
+            })
+            .chain(
+                right
+                    .filter
+                    .map(|filter| filter.map_columns(&|column| column + left_width)),
+            )
+            .collect();
+
+        let estimator = Estimator::new(self.catalog);
+        let statistics = estimator.table_estimate(table);
+        let left_rows = estimator.estimate(left).rows;
+        let mut best: Option<(PlanNode, f64)> = None;
+        for index in self.catalog.get_table_indexes(table.table_id) {
+            let mut used = vec![false; equalities.len()];
+            let mut left_keys = Vec::with_capacity(index.key_columns.len());
+            for key_column in &index.key_columns {
+                let column_type = table.schema.columns[*key_column].data_type;
+                let position = equalities.iter().enumerate().position(|(i, equality)| {
+                    !used[i]
+                        && matches!(equality.right, BoundExpression::Column { index, .. } if index == *key_column)
+                        && same_key_type(equality.left.data_type(), column_type)
+                });
+                match position {
+                    Some(position) => {
+                        used[position] = true;
+                        left_keys.push(equalities[position].left.clone());
+                    }
+                    None => break,
+                }
+            }
+            if left_keys.len() != index.key_columns.len() {
+                continue;
+            }
+            let cost = left_rows
+                * estimator.index_lookup_cost(estimator.index_key_rows(&statistics, index));
+            if best
+                .as_ref()
+                .is_some_and(|(_, best_cost)| *best_cost <= cost)
+            {
+                continue;
+            }
+            let condition = equalities
+                .iter()
+                .zip(&used)
+                .filter(|(_, used)| !**used)
+                .map(|(equality, _)| equality.to_condition(left_width))
+                .chain(residual.iter().cloned())
+                .collect();
+            let join = PlanNode::IndexNestedLoopJoin {
+                left: Box::new(left_plan.clone()),
+                table: table.clone(),
+                index: index.clone(),
+                join_type,
+                left_keys,
+                condition: combine_conjunction(condition),
+            };
+            best = Some((join, cost));
+        }
+
+        let (join, cost) = best?;
+        let join = match right.projection {
+            Some(projection) if !matches!(join_type, JoinType::Semi | JoinType::Anti) => {
+                let expressions =
+                    left_types
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, data_type)| BoundExpression::Column { index, data_type })
+                        .chain(projection.iter().map(|expression| {
+                            expression.map_columns(&|column| column + left_width)
+                        }))
+                        .collect();
+                PlanNode::Projection {
+                    input: Box::new(join),
+                    expressions,
+                }
+            }
+            _ => join,
+        };
+        Some((join, cost))
+    }
+
+    /// Returns the cheapest plan reading the rows of the table that satisfy the filter, which
+    /// is either a sequential scan or an index scan.
+    fn plan_table_access(&self, table: &TableInfo, filter: Option<&BoundExpression>) -> PlanNode {
+        let estimator = Estimator::new(self.catalog);
+        let statistics = estimator.table_estimate(table);
+        let mut best_cost = estimator.sequential_scan_cost(&statistics);
+        let mut best = with_filter(
+            PlanNode::SeqScan {
+                table: table.clone(),
+            },
+            filter.cloned(),
+        );
+        let conjuncts = filter.map(split_conjunction).unwrap_or_default();
+        for index in self.catalog.get_table_indexes(table.table_id) {
+            if let Some((key, remaining)) = match_index_key(index, &conjuncts) {
+                let cost =
+                    estimator.index_lookup_cost(estimator.index_key_rows(&statistics, index));
+                if cost < best_cost {
+                    let index_scan = PlanNode::IndexScan {
+                        table: table.clone(),
+                        index: index.clone(),
+                        key,
+                    };
+                    best_cost = cost;
+                    best = with_filter(index_scan, combine_conjunction(remaining));
+                }
+            }
+            if let Some((lower, upper, remaining)) = match_index_range(index, &conjuncts) {
+                let conditions =
+                    index_range_conditions(table, index, lower.as_ref(), upper.as_ref());
+                let cost = estimator.index_lookup_cost(estimator.index_range_rows(
+                    &statistics,
+                    index,
+                    &conditions,
+                ));
+                if cost < best_cost {
+                    let index_range_scan = PlanNode::IndexRangeScan {
+                        table: table.clone(),
+                        index: index.clone(),
+                        lower,
+                        upper,
+                    };
+                    best_cost = cost;
+                    best = with_filter(index_range_scan, combine_conjunction(remaining));
+                }
+            }
+        }
+        best
+    }
+
+    fn get_table(&self, table_id: u32) -> Result<&TableInfo, ExecutionError> {
+        self.catalog.get_table_by_id(table_id).ok_or_else(|| {
+            ExecutionError::Catalog(CatalogError::TableNotFound(table_id.to_string()))
+        })
+    }
+
+    fn get_indexes(&self, table: &TableInfo) -> Vec<IndexInfo> {
+        self.catalog
+            .get_table_indexes(table.table_id)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Limits the sort below a chain of projections to the first rows, as only those are produced.
+fn limit_sort(plan: PlanNode, sort_limit: Option<u64>) -> PlanNode {
+    match plan {
+        PlanNode::Sort {
+            input, order_by, ..
+        } => PlanNode::Sort {
+            input,
+            order_by,
+            limit: sort_limit,
+        },
+        PlanNode::Projection { input, expressions } => PlanNode::Projection {
+            input: Box::new(limit_sort(*input, sort_limit)),
+            expressions,
+        },
+        plan => plan,
+    }
+}
+
+/// The right input of a join if it reads a single table: the table, a filter over the rows of
+/// the table and a projection of the filtered rows.
+struct TableAccess<'p> {
+    table: &'p TableInfo,
+    filter: Option<&'p BoundExpression>,
+    projection: Option<&'p [BoundExpression]>,
+}
+
+impl<'p> TableAccess<'p> {
+    fn new(plan: &'p LogicalPlan) -> Option<TableAccess<'p>> {
+        let (plan, projection) = match plan {
+            LogicalPlan::Projection { input, expressions } => {
+                (input.as_ref(), Some(&expressions[..]))
+            }
+            plan => (plan, None),
+        };
+        let (plan, filter) = match plan {
+            LogicalPlan::Filter { input, predicate } => (input.as_ref(), Some(predicate)),
+            plan => (plan, None),
+        };
+        match plan {
+            LogicalPlan::Scan { table } => Some(TableAccess {
+                table,
+                filter,
+                projection,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns a column of the accessed rows as an expression over the row of the table.
+    fn column(&self, column: usize) -> BoundExpression {
+        match self.projection {
+            Some(projection) => projection[column].clone(),
+            None => BoundExpression::Column {
+                index: column,
+                data_type: self.table.schema.columns[column].data_type,
+            },
+        }
+    }
+}
+
+fn with_filter(plan: PlanNode, predicate: Option<BoundExpression>) -> PlanNode {
+    match predicate {
+        Some(predicate) => PlanNode::Filter {
+            input: Box::new(plan),
+            predicate,
+        },
+        None => plan,
+    }
+}
+
+/// An equality of a join condition between an expression over the left input and an expression
+/// over the right input. The right expression is evaluated over the rows of the right input.
+struct JoinEquality {
+    left: BoundExpression,
+    right: BoundExpression,
+}
+
+impl JoinEquality {
+    /// Returns the equality as a condition over the joined row.
+    fn to_condition(&self, left_width: usize) -> BoundExpression {
+        BoundExpression::Binary {
+            left: Box::new(self.left.clone()),
+            operator: BinaryOperator::Equal,
+            right: Box::new(self.right.map_columns(&|column| column + left_width)),
+            data_type: DataType::Boolean,
+        }
+    }
+}
+
+fn join_equality(expression: &BoundExpression, left_width: usize) -> Option<JoinEquality> {
+    let (left, right) = match expression {
+        BoundExpression::Binary {
+            left,
+            operator: BinaryOperator::Equal,
+            right,
+            ..
+        } => (left.as_ref(), right.as_ref()),
+        _ => return None,
+    };
+    let side = |expression: &BoundExpression| {
+        let columns = expression.referenced_columns();
+        if columns.is_empty() {
+            None
+        } else if columns.iter().all(|column| *column < left_width) {
+            Some(true)
+        } else if columns.iter().all(|column| *column >= left_width) {
+            Some(false)
+        } else {
+            None
+        }
+    };
+    let (left, right) = match (side(left)?, side(right)?) {
+        (true, false) => (left, right),
+        (false, true) => (right, left),
+        _ => return None,
+    };
+    Some(JoinEquality {
+        left: left.clone(),
+        right: right.map_columns(&|column| column - left_width),
+    })
+}
+
+/// Returns true if values of the types can be compared as index or join keys.
+fn same_key_type(left: DataType, right: DataType) -> bool {
+    left == right || (left.is_string() && right.is_string())
+}
+
+/// Splits a condition into the operands of its top-level ANDs.
+pub fn split_conjunction(expression: &BoundExpression) -> Vec<BoundExpression> {
+    match expression {
+        BoundExpression::Binary {
+            left,
+            operator: BinaryOperator::And,
+            right,
+            ..
+        } => {
+            let mut conjuncts = split_conjunction(left);
+            conjuncts.extend(split_conjunction(right));
+            conjuncts
+        }
+        expression => vec![expression.clone()],
+    }
+}
+
+/// Joins the conditions with AND. Returns None if there are no conditions.
+pub fn combine_conjunction(conjuncts: Vec<BoundExpression>) -> Option<BoundExpression> {
+    conjuncts
+        .into_iter()
+        .reduce(|left, right| BoundExpression::Binary {
+            left: Box::new(left),
+            operator: BinaryOperator::And,
+            right: Box::new(right),
+            data_type: DataType::Boolean,
+        })
+}
+
+/// Returns the full condition of a join with key equalities, e.g. a hash join, over the left
+/// row followed by the right row: the equalities of the keys and the remaining condition. The
+/// right keys are expressions over the right row.
+pub fn join_key_condition(
+    left_keys: &[BoundExpression],
+    right_keys: &[BoundExpression],
+    left_width: usize,
+    condition: Option<&BoundExpression>,
+) -> Option<BoundExpression> {
+    let equalities = left_keys
+        .iter()
+        .zip(right_keys)
+        .map(|(left_key, right_key)| BoundExpression::Binary {
+            left: Box::new(left_key.clone()),
+            operator: BinaryOperator::Equal,
+            right: Box::new(right_key.map_columns(&|column| column + left_width)),
+            data_type: DataType::Boolean,
+        });
+    combine_conjunction(equalities.chain(condition.cloned()).collect())
+}
+
+/// Finds an equality with a constant for every key column of the index. Returns the key
+/// expressions in key column order and the conditions that are not covered by the key.
+fn match_index_key(
+    index: &IndexInfo,
+    conjuncts: &[BoundExpression],
+) -> Option<(Vec<BoundExpression>, Vec<BoundExpression>)> {
+    let mut key = Vec::with_capacity(index.key_columns.len());
+    let mut used = vec![false; conjuncts.len()];
+    for key_column in &index.key_columns {
+        let (position, constant) = conjuncts
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| !used[*position])
+            .find_map(|(position, conjunct)| {
+                column_equals_constant(conjunct, *key_column).map(|constant| (position, constant))
+            })?;
+        used[position] = true;
+        key.push(constant.clone());
+    }
+    let remaining = conjuncts
+        .iter()
+        .zip(used)
+        .filter(|(_, used)| !used)
+        .map(|(conjunct, _)| conjunct.clone())
+        .collect();
+    Some((key, remaining))
+}
+
+/// Finds the comparisons of the first key column of a B+ tree index with constants that bound
+/// it from below and above. Returns the bounds, at least one of which is set, and the conditions
+/// that are not covered by them.
+fn match_index_range(
+    index: &IndexInfo,
+    conjuncts: &[BoundExpression],
+) -> Option<(Option<IndexBound>, Option<IndexBound>, Vec<BoundExpression>)> {
+    if index.index_type != IndexType::BPlusTree {
+        return None;
+    }
+    let (mut lower, mut upper) = (None, None);
+    let mut remaining = Vec::new();
+    for conjunct in conjuncts {
+        match column_compared_with_constant(conjunct, index.key_columns[0]) {
+            Some((operator, value)) if is_lower_bound(operator) && lower.is_none() => {
+                lower = Some(IndexBound {
+                    value: value.clone(),
+                    inclusive: operator == BinaryOperator::GreaterEqual,
+                });
+            }
+            Some((operator, value)) if !is_lower_bound(operator) && upper.is_none() => {
+                upper = Some(IndexBound {
+                    value: value.clone(),
+                    inclusive: operator == BinaryOperator::LessEqual,
+                });
+            }
+            _ => remaining.push(conjunct.clone()),
+        }
+    }
+    (lower.is_some() || upper.is_some()).then_some((lower, upper, remaining))
+}
+
+fn is_lower_bound(operator: BinaryOperator) -> bool {
+    matches!(
+        operator,
+        BinaryOperator::Greater | BinaryOperator::GreaterEqual
+    )
+}
+
+/// Returns the operator and the constant if the expression compares the column with a constant
+/// using `<`, `<=`, `>` or `>=`, turning `constant < column` into `column > constant`.
+fn column_compared_with_constant(
+    expression: &BoundExpression,
+    column: usize,
+) -> Option<(BinaryOperator, &BoundExpression)> {
+    let BoundExpression::Binary {
+        left,
+        operator,
+        right,
+        ..
+    } = expression
+    else {
+        return None;
+    };
+    if !matches!(
+        operator,
+        BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual
+    ) {
+        return None;
+    }
+    match (left.as_ref(), right.as_ref()) {
+        (BoundExpression::Column { index, .. }, constant @ BoundExpression::Constant { .. })
+            if *index == column =>
+        {
+            Some((*operator, constant))
+        }
+        (constant @ BoundExpression::Constant { .. }, BoundExpression::Column { index, .. })
+            if *index == column =>
+        {
+            let operator = match operator {
+                BinaryOperator::Less => BinaryOperator::Greater,
+                BinaryOperator::LessEqual => BinaryOperator::GreaterEqual,
+                BinaryOperator::Greater => BinaryOperator::Less,
+                _ => BinaryOperator::LessEqual,
+            };
+            Some((operator, constant))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the constant if the expression is `column = constant` or `constant = column`. Both
+/// sides have the same type after binding, so the constant can be used as an index key. Floats
+/// are excluded because their index keys distinguish positive and negative zero.
+fn column_equals_constant(expression: &BoundExpression, column: usize) -> Option<&BoundExpression> {
+    let (left, right) = match expression {
+        BoundExpression::Binary {
+            left,
+            operator: BinaryOperator::Equal,
+            right,
+            ..
+        } => (left.as_ref(), right.as_ref()),
+        _ => return None,
+    };
+    let constant = match (left, right) {
+        (BoundExpression::Column { index, .. }, constant @ BoundExpression::Constant { .. })
+        | (constant @ BoundExpression::Constant { .. }, BoundExpression::Column { index, .. })
+            if *index == column =>
+        {
+            constant
+        }
+        _ => return None,
+    };
+    (constant.data_type() != DataType::Float).then_some(constant)
+}
+
+#[cfg(test)]
+mod planner_tests {
+    use crate::{
+        binder::{bound_statement::BoundStatement, statement_binder::Binder},
+        catalog::system_catalog::Catalog,
+        database::engine::Database,
+        disk_management::test_utils::new_test_buffer_pool,
+        planner::{cost::Estimator, logical_plan::LogicalPlan},
+        sql::parser::parse_statement,
+    };
+
+    use super::{PlanNode, Planner};
+
+    fn plan(catalog: &Catalog, sql: &str) -> PlanNode {
+        let statement = Binder::new(catalog)
+            .bind(&parse_statement(sql).unwrap())
+            .unwrap();
+        Planner::new(catalog).plan(&statement).unwrap()
+    }
+
+    /// Returns the scan at the bottom of a chain of single-input plan nodes.
+    fn access_path(plan: &PlanNode) -> &PlanNode {
+        match plan {
+            PlanNode::Filter { input, .. }
+            | PlanNode::Projection { input, .. }
+            | PlanNode::Sort { input, .. }
+            | PlanNode::Limit { input, .. }
+            | PlanNode::Update { input, .. }
+            | PlanNode::Delete { input, .. } => access_path(input),
+            plan => plan,
+        }
+    }
+
+    #[test]
+    fn use_hash_index_for_equality_on_all_key_columns() {
+        let mut database = Database::new(new_test_buffer_pool("planner_index")).unwrap();
+        let values: Vec<String> = (0..2000)
+            .map(|i| format!("({}, 'product {}', {})", i % 100, i, i))
+            .collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE orders (user_id INTEGER NOT NULL, product TEXT NOT NULL, price FLOAT);
+                 CREATE INDEX orders_key ON orders USING HASH (user_id, product);
+                 INSERT INTO orders VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+        let catalog = &database.catalog();
+
+        let plan_with_index = plan(
+            catalog,
+            "SELECT price FROM orders WHERE product = 'tea' AND 3 = user_id AND price > 2",
+        );
+        match &plan_with_index {
+            PlanNode::Projection { input, .. } => match input.as_ref() {
+                PlanNode::Filter { input, .. } => {
+                    assert!(
+                        matches!(input.as_ref(), PlanNode::IndexScan { key, .. } if key.len() == 2)
+                    )
+                }
+                plan => panic!("expected a filter, got {:?}", plan),
+            },
+            plan => panic!("expected a projection, got {:?}", plan),
+        }
+        assert!(matches!(
+            access_path(&plan(
+                catalog,
+                "DELETE FROM orders WHERE user_id = 3 AND product = 'tea'"
+            )),
+            PlanNode::IndexScan { .. }
+        ));
+        assert!(matches!(
+            access_path(&plan(catalog, "SELECT * FROM orders WHERE user_id = 3")),
+            PlanNode::SeqScan { .. }
+        ));
+        assert!(matches!(
+            access_path(&plan(
+                catalog,
+                "UPDATE orders SET price = 1 WHERE user_id = 3 OR product = 'tea'"
+            )),
+            PlanNode::SeqScan { .. }
+        ));
+    }
+
+    #[test]
+    fn use_b_plus_tree_index_for_ranges_on_the_first_key_column() {
+        let mut database = Database::new(new_test_buffer_pool("planner_index_range")).unwrap();
+        let values: Vec<String> = (0..2000)
+            .map(|i| format!("({}, {}, 'product {}')", i, i % 10, i))
+            .collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE orders (amount INTEGER NOT NULL, user_id INTEGER, product TEXT);
+                 CREATE INDEX orders_amount ON orders USING BTREE (amount, user_id);
+                 CREATE INDEX orders_user ON orders USING HASH (user_id);
+                 INSERT INTO orders VALUES {};
+                 ANALYZE orders;",
+                values.join(", ")
+            ))
+            .unwrap();
+        let catalog = &database.catalog();
+
+        match plan(
+            catalog,
+            "SELECT * FROM orders WHERE 10 > amount AND amount >= 5 AND product <> 'tea'",
+        ) {
+            PlanNode::Projection { input, .. } => match input.as_ref() {
+                PlanNode::Filter { input, .. } => match input.as_ref() {
+                    PlanNode::IndexRangeScan {
+                        index,
+                        lower: Some(lower),
+                        upper: Some(upper),
+                        ..
+                    } => {
+                        assert_eq!(index.name, "orders_amount");
+                        assert!(lower.inclusive && !upper.inclusive);
+                    }
+                    plan => panic!("expected an index range scan, got {:?}", plan),
+                },
+                plan => panic!("expected a filter, got {:?}", plan),
+            },
+            plan => panic!("expected a projection, got {:?}", plan),
+        }
+        assert!(matches!(
+            access_path(&plan(catalog, "DELETE FROM orders WHERE amount > 1990")),
+            PlanNode::IndexRangeScan {
+                lower: Some(_),
+                upper: None,
+                ..
+            }
+        ));
+        // Most rows are in the range, the hash index does not serve ranges and the second key
+        // column cannot be scanned for a range.
+        for filter in ["amount > 10", "user_id < 1", "user_id > 8"] {
+            assert!(matches!(
+                access_path(&plan(
+                    catalog,
+                    &format!("SELECT * FROM orders WHERE {}", filter)
+                )),
+                PlanNode::SeqScan { .. }
+            ));
+        }
+    }
+
+    fn estimated_rows(catalog: &Catalog, sql: &str) -> f64 {
+        let select = match Binder::new(catalog)
+            .bind(&parse_statement(sql).unwrap())
+            .unwrap()
+        {
+            BoundStatement::Select(select) => select,
+            statement => panic!("expected a SELECT, got {:?}", statement),
+        };
+        let plan = LogicalPlan::from_select(catalog, &select).unwrap();
+        Estimator::new(catalog).estimate(&plan).rows
+    }
+
+    #[test]
+    fn estimate_rows_from_statistics() {
+        let mut database = Database::new(new_test_buffer_pool("planner_statistics")).unwrap();
+        // 90% of the orders are shipped, and the amounts are spread evenly over 0..2000.
+        let values: Vec<String> = (0..2000)
+            .map(|i| {
+                let status = if i % 10 == 0 { "open" } else { "shipped" };
+                let note = if i % 4 == 0 { "NULL" } else { "'gift'" };
+                format!("({}, '{}', {})", i, status, note)
+            })
+            .collect();
+        database
+            .execute(&format!(
+                "CREATE TABLE orders (amount INTEGER NOT NULL, status TEXT NOT NULL, note TEXT);
+                 INSERT INTO orders VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+        assert!(database.execute("ANALYZE missing").is_err());
+        database.execute("ANALYZE orders").unwrap();
+        let catalog = &database.catalog();
+
+        let close = |sql: &str, expected: f64| {
+            let rows = estimated_rows(catalog, sql);
+            assert!(
+                (rows - expected).abs() <= 0.1 * expected,
+                "{}: estimated {} rows instead of {}",
+                sql,
+                rows,
+                expected
+            );
+        };
+        close("SELECT * FROM orders", 2000.0);
+        close("SELECT * FROM orders WHERE status = 'shipped'", 1800.0);
+        close("SELECT * FROM orders WHERE 'open' = status", 200.0);
+        close("SELECT * FROM orders WHERE amount < 500", 500.0);
+        close("SELECT * FROM orders WHERE 1500 <= amount", 500.0);
+        close("SELECT * FROM orders WHERE note IS NULL", 500.0);
+        close(
+            "SELECT * FROM orders WHERE amount >= 1000 AND status = 'shipped'",
+            900.0,
+        );
+        assert!(estimated_rows(catalog, "SELECT * FROM orders WHERE status = 'lost'") < 10.0);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    binder::bound_expression::{BoundAggregate, BoundExpression},
+    catalog::system_catalog::{IndexInfo, TableInfo},
+    sql::ast::{BinaryOperator, JoinType},
+    types::schema::DataType,
+};
+
+/// A node of a physical query plan. Every node is executed by the executor of the same name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlanNode {
+    SeqScan {
+        table: TableInfo,
+    },
+    /// Reads the rows whose key columns of the index are equal to the key expressions.
+    IndexScan {
+        table: TableInfo,
+        index: IndexInfo,
+        key: Vec<BoundExpression>,
+    },
+    /// Reads the rows whose first key column of the B+ tree index lies between the bounds. A
+    /// missing bound leaves the range open on that side.
+    IndexRangeScan {
+        table: TableInfo,
+        index: IndexInfo,
+        lower: Option<IndexBound>,
+        upper: Option<IndexBound>,
+    },
+    Values {
+        rows: Vec<Vec<BoundExpression>>,
+    },
+    Filter {
+        input: Box<PlanNode>,
+        predicate: BoundExpression,
+    },
+    Projection {
+        input: Box<PlanNode>,
+        expressions: Vec<BoundExpression>,
+    },
+    /// Groups the rows of the input by the values of the group-by expressions. Produces a row of
+    /// the group values followed by the aggregate values for every group, or a single row if there
+    /// are no group-by expressions.
+    Aggregate {
+        input: Box<PlanNode>,
+        group_by: Vec<BoundExpression>,
+        aggregates: Vec<BoundAggregate>,
+    },
+    /// Sorts the rows of the input. If `limit` is set, only the first `limit` sorted rows are
+    /// produced.
+    Sort {
+        input: Box<PlanNode>,
+        order_by: Vec<(BoundExpression, bool)>,
+        limit: Option<u64>,
+    },
+    Limit {
+        input: Box<PlanNode>,
+        limit: Option<u64>,
+        offset: u64,
+    },
+    /// Compares every row of the left input with every row of the right input. The condition is
+    /// evaluated over the left row followed by the right row; None matches all pairs.
+    NestedLoopJoin {
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+        join_type: JoinType,
+        condition: Option<BoundExpression>,
+    },
+    /// Looks up the rows of the table matching each left row in an index. The left keys are
+    /// evaluated over the left row, one for each key column of the index. The condition holds
+    /// the rest of the join condition.
+    IndexNestedLoopJoin {
+        left: Box<PlanNode>,
+        table: TableInfo,
+        index: IndexInfo,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+    },
+    /// Builds a hash table of the right input on the right keys and probes it with the left
+    /// keys of every left row. The condition holds the rest of the join condition.
+    HashJoin {
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        right_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+    },
+    /// Merges both inputs, which must be sorted ascending by their keys with NULLs last. The
+    /// condition holds the rest of the join condition.
+    SortMergeJoin {
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+        join_type: JoinType,
+        left_keys: Vec<BoundExpression>,
+        right_keys: Vec<BoundExpression>,
+        condition: Option<BoundExpression>,
+    },
+    /// Inserts the rows of the input, which have the schema of the table.
+    Insert {
+        table: TableInfo,
+        indexes: Vec<IndexInfo>,
+        input: Box<PlanNode>,
+    },
+    /// Updates the rows of the input, which must be read from the table.
+    Update {
+        table: TableInfo,
+        indexes: Vec<IndexInfo>,
+        input: Box<PlanNode>,
+        assignments: Vec<(usize, BoundExpression)>,
+    },
+    /// Deletes the rows of the input, which must be read from the table.
+    Delete {
+        table: TableInfo,
+        indexes: Vec<IndexInfo>,
+        input: Box<PlanNode>,
+    },
+}
+
+/// A bound of an index range scan. The value is a constant expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexBound {
+    pub value: BoundExpression,
+    pub inclusive: bool,
+}
+
+/// Returns the comparisons of the first key column of the index with the bounds of a range
+/// scan, which hold for exactly the rows the scan reads.
+pub fn index_range_conditions(
+    table: &TableInfo,
+    index: &IndexInfo,
+    lower: Option<&IndexBound>,
+    upper: Option<&IndexBound>,
+) -> Vec<BoundExpression> {
+    let column = index.key_columns[0];
+    let condition = |bound: &IndexBound, operator| BoundExpression::Binary {
+        left: Box::new(BoundExpression::Column {
+            index: column,
+            data_type: table.schema.columns[column].data_type,
+        }),
+        operator,
+        right: Box::new(bound.value.clone()),
+        data_type: DataType::Boolean,
+    };
+    let lower = lower.map(|bound| match bound.inclusive {
+        true => condition(bound, BinaryOperator::GreaterEqual),
+        false => condition(bound, BinaryOperator::Greater),
+    });
+    let upper = upper.map(|bound| match bound.inclusive {
+        true => condition(bound, BinaryOperator::LessEqual),
+        false => condition(bound, BinaryOperator::Less),
+    });
+    lower.into_iter().chain(upper).collect()
+}
+
+impl PlanNode {
+    /// Returns the inputs of the node, in the order in which their executors are created.
+    pub fn inputs(&self) -> Vec<&PlanNode> {
+        match self {
+            PlanNode::SeqScan { .. }
+            | PlanNode::IndexScan { .. }
+            | PlanNode::IndexRangeScan { .. }
+            | PlanNode::Values { .. } => Vec::new(),
+            PlanNode::Filter { input, .. }
+            | PlanNode::Projection { input, .. }
+            | PlanNode::Aggregate { input, .. }
+            | PlanNode::Sort { input, .. }
+            | PlanNode::Limit { input, .. }
+            | PlanNode::Insert { input, .. }
+            | PlanNode::Update { input, .. }
+            | PlanNode::Delete { input, .. } => vec![input],
+            PlanNode::IndexNestedLoopJoin { left, .. } => vec![left],
+            PlanNode::NestedLoopJoin { left, right, .. }
+            | PlanNode::HashJoin { left, right, .. }
+            | PlanNode::SortMergeJoin { left, right, .. } => vec![left, right],
+        }
+    }
+
+    /// Returns the types of the columns of the rows produced by the node.
+    pub fn output_types(&self) -> Vec<DataType> {
+        let column_types = |table: &TableInfo| {
+            table
+                .schema
+                .columns
+                .iter()
+                .map(|column| column.data_type)
+                .collect::<Vec<_>>()
+        };
+        let expression_types = |expressions: &[BoundExpression]| {
+            expressions
+                .iter()
+                .map(BoundExpression::data_type)
+                .collect::<Vec<_>>()
+        };
+        match self {
+            PlanNode::SeqScan { table }
+            | PlanNode::IndexScan { table, .. }
+            | PlanNode::IndexRangeScan { table, .. } => column_types(table),
+            PlanNode::Values { rows } => rows
+                .first()
+                .map(|row| expression_types(row))
+                .unwrap_or_default(),
+            PlanNode::Filter { input, .. }
+            | PlanNode::Sort { input, .. }
+            | PlanNode::Limit { input, .. } => input.output_types(),
+            PlanNode::Projection { expressions, .. } => expression_types(expressions),
+            PlanNode::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => expression_types(group_by)
+                .into_iter()
+                .chain(aggregates.iter().map(|aggregate| aggregate.data_type))
+                .collect(),
+            PlanNode::NestedLoopJoin {
+                left,
+                right,
+                join_type,
+                ..
+            }
+            | PlanNode::HashJoin {
+                left,
+                right,
+                join_type,
+                ..
+            }
+            | PlanNode::SortMergeJoin {
+                left,
+                right,
+                join_type,
+                ..
+            } => join_types(left.output_types(), right.output_types(), *join_type),
+            PlanNode::IndexNestedLoopJoin {
+                left,
+                table,
+                join_type,
+                ..
+            } => join_types(left.output_types(), column_types(table), *join_type),
+            PlanNode::Insert { .. } | PlanNode::Update { .. } | PlanNode::Delete { .. } => {
+                vec![DataType::BigInt]
+            }
+        }
+    }
+}
+
+fn join_types(mut left: Vec<DataType>, right: Vec<DataType>, join_type: JoinType) -> Vec<DataType> {
+    if !matches!(join_type, JoinType::Semi | JoinType::Anti) {
+        left.extend(right);
+    }
+    left
+}
@@ -0,0 +1,54 @@
+use crate::disk_management::buffer_pool::BufferPool;
+
+use super::log_record::{LogRecordBody, Lsn};
+
+/// Takes a fuzzy checkpoint: the transactions that have not ended and the dirty pages of the
+/// buffer pool are logged, without writing any page to disk. The master record then points at
+/// the checkpoint, so that recovery starts reading the log there.
+///
+/// Afterwards, the log segments before the oldest record recovery may still need are removed:
+/// the first change of a dirty page, the first record of an active transaction and the
+/// checkpoint itself. Does nothing if the buffer pool has no log.
+pub fn checkpoint(buffer_pool: &BufferPool) -> Result<(), &'static str> {
+    let Some(log_manager) = buffer_pool.log_manager() else {
+        return Ok(());
+    };
+    let begin_lsn = log_manager
+        .lock()
+        .unwrap()
+        .append(None, LogRecordBody::CheckpointBegin);
+    let dirty_pages = buffer_pool.dirty_pages();
+
+    let mut log_manager = log_manager.lock().unwrap();
+    let transactions: Vec<_> = log_manager
+        .active_transactions()
+        .iter()
+        .map(|(transaction, lsns)| (*transaction, *lsns))
+        .collect();
+    let oldest_lsn = dirty_pages
+        .iter()
+        .map(|(_, recovery_lsn)| *recovery_lsn)
+        .chain(transactions.iter().map(|(_, lsns)| lsns.first_lsn))
+        .fold(begin_lsn, Lsn::min);
+    let end = LogRecordBody::CheckpointEnd {
+        next_transaction_id: log_manager.next_transaction_id(),
+        transactions,
+        dirty_pages,
+    };
+    log_manager.append(None, end);
+    log_manager.flush_all()?;
+    log_manager.set_checkpoint(begin_lsn)?;
+    log_manager.remove_segments_before(oldest_lsn)
+}
+
+/// Takes a checkpoint if the log grew enough since the last one.
+pub fn checkpoint_if_due(buffer_pool: &BufferPool) -> Result<(), &'static str> {
+    let due = match buffer_pool.log_manager() {
+        Some(log_manager) => log_manager.lock().unwrap().checkpoint_due(),
+        None => false,
+    };
+    if due {
+        checkpoint(buffer_pool)?;
+    }
+    Ok(())
+}
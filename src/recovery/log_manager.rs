@@ -0,0 +1,655 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::disk_management::disk_manager::EncryptionKey;
+
+use super::log_record::{crc32, ActiveTransaction, LogRecord, LogRecordBody, Lsn, TransactionId};
+
+// LOG DIRECTORY:
+// Every segment of the log is a file named after the LSN of its first record, as 16 hexadecimal
+// digits with the extension `.log`. Removed segments are renamed to `.recycled` and reused for
+// new segments. The file `master` holds the master record.
+//
+// MASTER RECORD FORMAT:
+// | CHECKPOINT_LSN [u64] | CHECKSUM [u32] (CRC-32 of the LSN) |
+
+/// The size after which the log continues in a new segment.
+pub const SEGMENT_SIZE: u64 = 1 << 20;
+/// A checkpoint is due once the log grew by this many segments since the last one.
+const CHECKPOINT_INTERVAL: u64 = 4;
+/// The number of files of removed segments that are kept to be reused.
+const RECYCLED_SEGMENTS: usize = 2;
+const MASTER_RECORD: &str = "master";
+
+/// Appends records to the write-ahead log. The LSN of a record is its byte offset in the log,
+/// which is split into segment files. Segments before the last checkpoint that are not needed
+/// for recovery anymore are removed.
+///
+/// Appended records are buffered in memory until they are flushed. Before a modified page is
+/// written to the database file, all records up to the LSN of its last change have to be
+/// flushed, which the buffer pool ensures.
+///
+/// The log manager also keeps track of the transactions that have not ended yet, to link the
+/// records of a transaction by their previous LSN.
+///
+/// A log opened with a key encrypts its records, as they hold the changed bytes of pages.
+pub struct LogManager {
+    directory: PathBuf,
+    segment_size: u64,
+    /// The first LSN of every segment, ascending. Records are appended to the last segment.
+    segments: Vec<Lsn>,
+    /// The file of the last segment.
+    file: File,
+    /// Files of removed segments that are reused for new segments.
+    recycled: Vec<PathBuf>,
+    /// Records that have not been written to the file yet.
+    buffer: Vec<u8>,
+    next_lsn: Lsn,
+    /// All records with a lower LSN are stored durably in the log.
+    flushed_lsn: Lsn,
+    /// The transactions that have not ended.
+    transactions: HashMap<TransactionId, ActiveTransaction>,
+    next_transaction_id: TransactionId,
+    /// The LSN of the begin record of the last complete checkpoint, as stored in the master
+    /// record.
+    checkpoint_lsn: Option<Lsn>,
+    /// The key the records are encrypted with, if the log is encrypted.
+    key: Option<EncryptionKey>,
+}
+
+impl LogManager {
+    /// Opens the log in the directory at the path, creating it if it does not exist. A record at
+    /// the end of the log that was only partially written before a crash is removed.
+    pub fn open(log_directory_path: &str) -> Result<LogManager, &'static str> {
+        LogManager::open_with_segment_size(log_directory_path, SEGMENT_SIZE)
+    }
+
+    /// Opens the log like [LogManager::open], but encrypts the records with the key. The key is
+    /// not checked: records that cannot be decrypted are treated like a torn end of the log, so
+    /// the key must have been checked with the database file before.
+    pub fn open_encrypted(
+        log_directory_path: &str,
+        key: &EncryptionKey,
+    ) -> Result<LogManager, &'static str> {
+        LogManager::open_with(log_directory_path, SEGMENT_SIZE, Some(key.clone()))
+    }
+
+    pub fn open_with_segment_size(
+        log_directory_path: &str,
+        segment_size: u64,
+    ) -> Result<LogManager, &'static str> {
+        LogManager::open_with(log_directory_path, segment_size, None)
+    }
+
+    fn open_with(
+        log_directory_path: &str,
+        segment_size: u64,
+        key: Option<EncryptionKey>,
+    ) -> Result<LogManager, &'static str> {
+        let directory = PathBuf::from(log_directory_path);
+        fs::create_dir_all(&directory).or(Err("Could not create the log directory"))?;
+        let mut segments = Vec::new();
+        let mut recycled = Vec::new();
+        for entry in fs::read_dir(&directory).or(Err("Could not read the log directory"))? {
+            let path = entry.or(Err("Could not read the log directory"))?.path();
+            let first_lsn = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Lsn::from_str_radix(stem, 16).ok());
+            match (
+                path.extension().and_then(|extension| extension.to_str()),
+                first_lsn,
+            ) {
+                (Some("log"), Some(first_lsn)) => segments.push(first_lsn),
+                (Some("recycled"), _) => recycled.push(path),
+                _ => {}
+            }
+        }
+        segments.sort();
+        if segments.is_empty() {
+            segments.push(0);
+        }
+
+        let last_segment = *segments.last().unwrap();
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(segment_path(&directory, last_segment))
+            .or(Err("Could not open the log file"))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .or(Err("Could not read the log file"))?;
+        let valid_length: usize = read_records(&bytes, last_segment, key.as_ref())
+            .iter()
+            .map(|(_, length)| length)
+            .sum();
+        if valid_length < bytes.len() {
+            file.set_len(valid_length as u64)
+                .or(Err("Could not remove the torn end of the log"))?;
+        }
+        let end = last_segment + valid_length as u64;
+        let checkpoint_lsn =
+            read_master_record(&directory).filter(|lsn| (segments[0]..end).contains(lsn));
+        let mut log_manager = LogManager {
+            directory,
+            segment_size,
+            segments,
+            file,
+            recycled,
+            buffer: Vec::new(),
+            next_lsn: end,
+            flushed_lsn: end,
+            transactions: HashMap::new(),
+            next_transaction_id: 1,
+            checkpoint_lsn,
+            key,
+        };
+        let start = checkpoint_lsn.unwrap_or(0);
+        for record in log_manager.read_from(start)? {
+            log_manager.track(&record);
+        }
+        Ok(log_manager)
+    }
+
+    /// Updates the transactions with a record that was read or appended.
+    fn track(&mut self, record: &LogRecord) {
+        if let LogRecordBody::CheckpointEnd {
+            next_transaction_id,
+            transactions,
+            ..
+        } = &record.body
+        {
+            // Transactions that wrote records since the checkpoint began may already be known,
+            // but not with their first LSN.
+            self.next_transaction_id = self.next_transaction_id.max(*next_transaction_id);
+            for (transaction, lsns) in transactions {
+                self.transactions
+                    .entry(*transaction)
+                    .and_modify(|known| known.first_lsn = known.first_lsn.min(lsns.first_lsn))
+                    .or_insert(*lsns);
+            }
+        }
+        if let Some(transaction) = record.transaction {
+            self.next_transaction_id = self.next_transaction_id.max(transaction + 1);
+            if record.body == LogRecordBody::End {
+                self.transactions.remove(&transaction);
+            } else {
+                self.transactions
+                    .entry(transaction)
+                    .and_modify(|known| known.last_lsn = record.lsn)
+                    .or_insert(ActiveTransaction {
+                        first_lsn: record.lsn,
+                        last_lsn: record.lsn,
+                    });
+            }
+        }
+    }
+
+    /// Commits the transaction: its commit record is flushed, so that its changes survive a crash.
+    pub fn commit(&mut self, transaction: TransactionId) -> Result<(), &'static str> {
+        let lsn = self.append(Some(transaction), LogRecordBody::Commit);
+        self.flush(lsn)?;
+        self.append(Some(transaction), LogRecordBody::End);
+        Ok(())
+    }
+
+    /// Appends a record of the transaction to the log and returns its LSN.
+    pub fn append(&mut self, transaction: Option<TransactionId>, body: LogRecordBody) -> Lsn {
+        let lsn = self.next_lsn;
+        let record = LogRecord {
+            lsn,
+            transaction,
+            prev_lsn: transaction
+                .and_then(|transaction| self.transactions.get(&transaction))
+                .map(|lsns| lsns.last_lsn),
+            body,
+        };
+        let bytes = match &self.key {
+            Some(key) => record.to_encrypted_bytes(key),
+            None => record.to_bytes(),
+        };
+        self.next_lsn += bytes.len() as u64;
+        self.buffer.extend(bytes);
+        self.track(&record);
+        lsn
+    }
+
+    /// Returns the transactions that have not ended.
+    pub fn active_transactions(&self) -> &HashMap<TransactionId, ActiveTransaction> {
+        &self.transactions
+    }
+
+    /// Returns an id that is higher than the id of every transaction in the log.
+    pub fn next_transaction_id(&self) -> TransactionId {
+        self.next_transaction_id
+    }
+
+    /// Writes all records up to and including the one at the LSN to the log, unless they are
+    /// already stored.
+    pub fn flush(&mut self, lsn: Lsn) -> Result<(), &'static str> {
+        if lsn < self.flushed_lsn || self.buffer.is_empty() {
+            return Ok(());
+        }
+        let segment = *self.segments.last().unwrap();
+        self.file
+            .seek(SeekFrom::Start(self.flushed_lsn - segment))
+            .or(Err("Could not write the log"))?;
+        self.file
+            .write_all(&self.buffer)
+            .or(Err("Could not write the log"))?;
+        self.file.sync_data().or(Err("Could not write the log"))?;
+        self.buffer.clear();
+        self.flushed_lsn = self.next_lsn;
+        if self.flushed_lsn - segment >= self.segment_size {
+            self.start_segment()?;
+        }
+        Ok(())
+    }
+
+    /// Writes all appended records to the log.
+    pub fn flush_all(&mut self) -> Result<(), &'static str> {
+        self.flush(self.next_lsn)
+    }
+
+    /// Continues the log in a new segment, reusing the file of a removed segment if there is one.
+    /// A reused file is not cleared: its old records have lower LSNs than their position in the
+    /// new segment, so they are never read.
+    fn start_segment(&mut self) -> Result<(), &'static str> {
+        let path = segment_path(&self.directory, self.flushed_lsn);
+        if let Some(recycled) = self.recycled.pop() {
+            fs::rename(recycled, &path).or(Err("Could not reuse a log segment"))?;
+        }
+        self.file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .or(Err("Could not create a log segment"))?;
+        self.segments.push(self.flushed_lsn);
+        Ok(())
+    }
+
+    /// Returns the LSN up to which, exclusively, all records are stored in the log.
+    pub fn flushed_lsn(&self) -> Lsn {
+        self.flushed_lsn
+    }
+
+    /// Returns the LSN the next appended record will get.
+    pub fn next_lsn(&self) -> Lsn {
+        self.next_lsn
+    }
+
+    /// Returns the LSN of the first record that is still stored in the log.
+    pub fn first_lsn(&self) -> Lsn {
+        self.segments[0]
+    }
+
+    /// Returns the LSN of the begin record of the last complete checkpoint.
+    pub fn checkpoint_lsn(&self) -> Option<Lsn> {
+        self.checkpoint_lsn
+    }
+
+    /// Returns whether the log grew enough since the last checkpoint to take a new one.
+    pub fn checkpoint_due(&self) -> bool {
+        let since = self.checkpoint_lsn.unwrap_or(self.segments[0]);
+        self.next_lsn - since >= CHECKPOINT_INTERVAL * self.segment_size
+    }
+
+    /// Stores the LSN of the begin record of a checkpoint in the master record. All records of
+    /// the checkpoint must be flushed.
+    pub fn set_checkpoint(&mut self, checkpoint_lsn: Lsn) -> Result<(), &'static str> {
+        let mut bytes = checkpoint_lsn.to_le_bytes().to_vec();
+        bytes.extend(crc32(&bytes).to_le_bytes());
+        // The master record is replaced at once, so a crash leaves either the old or the new one.
+        let path = self.directory.join(MASTER_RECORD);
+        let temporary_path = path.with_extension("new");
+        let mut file =
+            File::create(&temporary_path).or(Err("Could not write the master record"))?;
+        file.write_all(&bytes)
+            .or(Err("Could not write the master record"))?;
+        file.sync_all()
+            .or(Err("Could not write the master record"))?;
+        fs::rename(temporary_path, path).or(Err("Could not write the master record"))?;
+        self.checkpoint_lsn = Some(checkpoint_lsn);
+        Ok(())
+    }
+
+    /// Removes the segments that only hold records before the LSN. The files of up to
+    /// [RECYCLED_SEGMENTS] removed segments are kept to be reused.
+    pub fn remove_segments_before(&mut self, lsn: Lsn) -> Result<(), &'static str> {
+        while self.segments.len() > 1 && self.segments[1] <= lsn {
+            let path = segment_path(&self.directory, self.segments.remove(0));
+            if self.recycled.len() < RECYCLED_SEGMENTS {
+                let recycled = path.with_extension("recycled");
+                fs::rename(&path, &recycled).or(Err("Could not remove a log segment"))?;
+                self.recycled.push(recycled);
+            } else {
+                fs::remove_file(path).or(Err("Could not remove a log segment"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the stored bytes of the log from the LSN up to the end LSN, which must lie in the
+    /// same segment.
+    fn read_stored(&self, lsn: Lsn, end: Lsn) -> Result<Vec<u8>, &'static str> {
+        let segment = self.segment_of(lsn)?;
+        let mut file =
+            File::open(segment_path(&self.directory, segment)).or(Err("Could not read the log"))?;
+        file.seek(SeekFrom::Start(lsn - segment))
+            .or(Err("Could not read the log"))?;
+        let mut bytes = vec![0; (end - lsn) as usize];
+        file.read_exact(&mut bytes)
+            .or(Err("Could not read the log"))?;
+        Ok(bytes)
+    }
+
+    /// Returns the first LSN of the segment holding the LSN.
+    fn segment_of(&self, lsn: Lsn) -> Result<Lsn, &'static str> {
+        if lsn < self.segments[0] {
+            return Err("The log record was removed");
+        }
+        Ok(*self
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| **segment <= lsn)
+            .unwrap())
+    }
+
+    /// Returns the LSN up to which the segment starting at the LSN holds records.
+    fn segment_end(&self, segment: Lsn) -> Lsn {
+        self.segments
+            .iter()
+            .find(|other| **other > segment)
+            .copied()
+            .unwrap_or(self.flushed_lsn)
+    }
+
+    /// Returns the record at the LSN.
+    pub fn read_record(&mut self, lsn: Lsn) -> Result<LogRecord, &'static str> {
+        let bytes = if lsn >= self.flushed_lsn {
+            self.buffer
+                .get((lsn - self.flushed_lsn) as usize..)
+                .ok_or("There is no log record at the LSN")?
+                .to_vec()
+        } else {
+            let segment_end = self.segment_end(self.segment_of(lsn)?);
+            let length = self.read_stored(lsn, (lsn + 4).min(segment_end))?;
+            let length = u32::from_le_bytes(
+                length
+                    .try_into()
+                    .or(Err("There is no log record at the LSN"))?,
+            ) as u64;
+            self.read_stored(lsn, (lsn + length).min(segment_end))?
+        };
+        match decode(&bytes, self.key.as_ref()) {
+            Some((record, _)) if record.lsn == lsn => Ok(record),
+            _ => Err("There is no log record at the LSN"),
+        }
+    }
+
+    /// Returns all records of the log starting at the LSN, including those that have not been
+    /// flushed yet. Starts at the first stored record if the LSN lies before it.
+    pub fn read_from(&mut self, lsn: Lsn) -> Result<Vec<LogRecord>, &'static str> {
+        let start = lsn.max(self.segments[0]).min(self.next_lsn);
+        let mut bytes = Vec::new();
+        if start < self.flushed_lsn {
+            for segment in self.segments.clone() {
+                let segment_end = self.segment_end(segment);
+                if segment_end > start {
+                    bytes.extend(self.read_stored(start.max(segment), segment_end)?);
+                }
+            }
+            bytes.extend(&self.buffer);
+        } else {
+            bytes.extend(&self.buffer[(start - self.flushed_lsn) as usize..]);
+        }
+        Ok(read_records(&bytes, start, self.key.as_ref())
+            .into_iter()
+            .map(|(record, _)| record)
+            .collect())
+    }
+}
+
+fn segment_path(directory: &Path, first_lsn: Lsn) -> PathBuf {
+    directory.join(format!("{:016x}.log", first_lsn))
+}
+
+/// Returns the checkpoint LSN of the master record in the directory, or None if there is no
+/// intact master record.
+fn read_master_record(directory: &Path) -> Option<Lsn> {
+    let bytes = fs::read(directory.join(MASTER_RECORD)).ok()?;
+    if bytes.len() != 12 || crc32(&bytes[..8]).to_le_bytes() != bytes[8..] {
+        return None;
+    }
+    Some(Lsn::from_le_bytes(bytes[..8].try_into().unwrap()))
+}
+
+/// Decodes the record at the start of the bytes, decrypting it with the key if there is one.
+fn decode(bytes: &[u8], key: Option<&EncryptionKey>) -> Option<(LogRecord, usize)> {
+    match key {
+        Some(key) => LogRecord::from_encrypted_bytes(bytes, key),
+        None => LogRecord::from_bytes(bytes),
+    }
+}
+
+/// Decodes the records in the bytes of the log starting at the LSN, up to the first one that is
+/// not intact.
+fn read_records(bytes: &[u8], lsn: Lsn, key: Option<&EncryptionKey>) -> Vec<(LogRecord, usize)> {
+    let mut records = Vec::new();
+    let mut position = 0;
+    while let Some((record, length)) = decode(&bytes[position..], key) {
+        if record.lsn != lsn + position as u64 {
+            break;
+        }
+        position += length;
+        records.push((record, length));
+    }
+    records
+}
+
+#[cfg(test)]
+mod log_manager_tests {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        disk_management::{
+            buffer_pool::{BufferPool, RawPage, PAGE_SIZE},
+            disk_manager::DiskManager,
+            test_utils::temp_db_path,
+        },
+        recovery::log_record::LogRecordBody,
+    };
+
+    use super::{segment_path, LogManager};
+
+    fn page_write(page_id: u32, value: u8) -> LogRecordBody {
+        LogRecordBody::PageWrite {
+            page_id,
+            offset: 0,
+            before: vec![0],
+            after: vec![value],
+        }
+    }
+
+    #[test]
+    fn append_flush_and_reopen() {
+        let path = temp_db_path("log_append");
+        let mut log_manager = LogManager::open(&path).unwrap();
+        let first = log_manager.append(None, page_write(1, 1));
+        let second = log_manager.append(None, page_write(2, 2));
+        assert!(first < second);
+        assert_eq!(log_manager.flushed_lsn(), 0);
+        assert_eq!(log_manager.read_from(0).unwrap().len(), 2);
+
+        log_manager.flush(first).unwrap();
+        assert_eq!(log_manager.flushed_lsn(), log_manager.next_lsn());
+        let third = log_manager.append(None, page_write(3, 3));
+        let records = log_manager.read_from(second).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].lsn, second);
+        assert_eq!(records[1].body, page_write(3, 3));
+        drop(log_manager);
+
+        // The unflushed record is lost.
+        let mut log_manager = LogManager::open(&path).unwrap();
+        assert_eq!(log_manager.next_lsn(), third);
+        let lsns: Vec<_> = log_manager
+            .read_from(0)
+            .unwrap()
+            .iter()
+            .map(|record| record.lsn)
+            .collect();
+        assert_eq!(lsns, vec![first, second]);
+        assert_eq!(log_manager.append(None, page_write(4, 4)), third);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn transactions_link_their_records() {
+        let path = temp_db_path("log_transactions");
+        let mut log_manager = LogManager::open(&path).unwrap();
+        let (first, second) = (1, 2);
+        log_manager.append(Some(first), LogRecordBody::Begin);
+        log_manager.append(Some(second), LogRecordBody::Begin);
+        let write = log_manager.append(Some(first), page_write(1, 1));
+        log_manager.append(Some(second), page_write(2, 2));
+        log_manager.commit(first).unwrap();
+        assert_eq!(log_manager.active_transactions().len(), 1);
+
+        let record = log_manager.read_record(write).unwrap();
+        assert_eq!(record.transaction, Some(first));
+        let begin = log_manager.read_record(record.prev_lsn.unwrap()).unwrap();
+        assert_eq!(begin.body, LogRecordBody::Begin);
+        assert_eq!(begin.prev_lsn, None);
+        log_manager.flush_all().unwrap();
+        drop(log_manager);
+
+        // The transaction that did not end is still active after reopening the log, and ids of
+        // new transactions do not collide with those in the log.
+        let log_manager = LogManager::open(&path).unwrap();
+        assert_eq!(
+            log_manager.active_transactions().keys().collect::<Vec<_>>(),
+            vec![&second]
+        );
+        assert_eq!(log_manager.next_transaction_id(), 3);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn segments_are_removed_and_reused() {
+        let path = temp_db_path("log_segments");
+        let mut log_manager = LogManager::open_with_segment_size(&path, 100).unwrap();
+        let mut lsns = Vec::new();
+        for i in 0..20 {
+            lsns.push(log_manager.append(None, page_write(i, 1)));
+            log_manager.flush_all().unwrap();
+        }
+        let segment_count = log_manager.segments.len();
+        assert!(segment_count > 3);
+        assert_eq!(log_manager.read_from(0).unwrap().len(), 20);
+        assert_eq!(
+            log_manager.read_record(lsns[7]).unwrap().body,
+            page_write(7, 1)
+        );
+
+        log_manager.set_checkpoint(lsns[15]).unwrap();
+        log_manager.remove_segments_before(lsns[15]).unwrap();
+        assert!(log_manager.first_lsn() <= lsns[15]);
+        assert!(log_manager.read_record(lsns[0]).is_err());
+        assert_eq!(log_manager.recycled.len(), 2);
+        let records = log_manager.read_from(0).unwrap();
+        assert_eq!(records[0].lsn, log_manager.first_lsn());
+        assert_eq!(records.last().unwrap().body, page_write(19, 1));
+
+        // New segments reuse the removed files, whose old records are never read.
+        for i in 20..40 {
+            log_manager.append(None, page_write(i, 2));
+            log_manager.flush_all().unwrap();
+        }
+        assert!(log_manager.recycled.is_empty());
+        drop(log_manager);
+        let mut log_manager = LogManager::open_with_segment_size(&path, 100).unwrap();
+        assert_eq!(log_manager.checkpoint_lsn(), Some(lsns[15]));
+        let records = log_manager.read_from(lsns[15]).unwrap();
+        assert_eq!(records.len(), 25);
+        assert_eq!(records.last().unwrap().body, page_write(39, 2));
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn torn_record_is_removed() {
+        let path = temp_db_path("log_torn");
+        let mut log_manager = LogManager::open(&path).unwrap();
+        log_manager.append(None, page_write(1, 1));
+        let second = log_manager.append(None, page_write(2, 2));
+        log_manager.flush_all().unwrap();
+        drop(log_manager);
+
+        let segment = segment_path(Path::new(&path), 0);
+        let length = std::fs::metadata(&segment).unwrap().len();
+        let file = std::fs::File::options().write(true).open(&segment).unwrap();
+        file.set_len(length - 3).unwrap();
+        let mut log_manager = LogManager::open(&path).unwrap();
+        assert_eq!(log_manager.next_lsn(), second);
+        assert_eq!(std::fs::metadata(&segment).unwrap().len(), second);
+        assert_eq!(log_manager.read_from(0).unwrap().len(), 1);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn pages_are_written_after_their_log_records() {
+        let log_path = temp_db_path("wal_rule_log");
+        let log_manager = Arc::new(Mutex::new(LogManager::open(&log_path).unwrap()));
+        let disk_manager = DiskManager::new(temp_db_path("wal_rule")).unwrap();
+        let mut buffer_pool =
+            BufferPool::with_log_manager(Arc::new(Mutex::new(disk_manager)), log_manager.clone());
+
+        let (page_id, _) = buffer_pool.load_new_page().unwrap();
+        let mut data = [0; PAGE_SIZE];
+        data[100] = 7;
+        buffer_pool
+            .set_page_data(page_id, RawPage::new(data), None)
+            .unwrap();
+        // Setting the same content again does not change the page.
+        buffer_pool
+            .set_page_data(page_id, RawPage::new(data), None)
+            .unwrap();
+        buffer_pool.unload_page_id(page_id).unwrap();
+        let records = log_manager.lock().unwrap().read_from(0).unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| &record.body)
+                .collect::<Vec<_>>(),
+            vec![&LogRecordBody::PageWrite {
+                page_id: page_id as u32,
+                offset: 100,
+                before: vec![0],
+                after: vec![7],
+            }]
+        );
+        assert_eq!(log_manager.lock().unwrap().flushed_lsn(), 0);
+
+        // Evicting the dirty page flushes its log record first.
+        for _ in 0..200 {
+            let (page_id, _) = buffer_pool.load_new_page().unwrap();
+            buffer_pool.unload_page_id(page_id).unwrap();
+        }
+        assert!(!buffer_pool.page_table.contains_key(&page_id));
+        let log_manager = log_manager.lock().unwrap();
+        assert_eq!(log_manager.flushed_lsn(), log_manager.next_lsn());
+        std::fs::remove_dir_all(log_path).unwrap();
+    }
+}
@@ -0,0 +1,879 @@
+// RECORD FORMAT:
+// | LENGTH [u32] | CHECKSUM [u32] | LSN [u64] | TRANSACTION_ID [u64] | PREV_LSN [u64] | TYPE [u8] |
+// | BODY |
+//
+// LENGTH is the length of the whole record in bytes, CHECKSUM the CRC-32 of all bytes after it.
+// A record whose length or checksum does not match was torn by a crash while it was written.
+// TRANSACTION_ID is 0 for records outside of a transaction, PREV_LSN is u64::MAX for the first
+// record of a transaction.
+//
+// BEGIN (TYPE 2), COMMIT (TYPE 3), ABORT (TYPE 4), END (TYPE 5) AND CHECKPOINT BEGIN (TYPE 7)
+// HAVE NO BODY.
+//
+// PAGE WRITE BODY (TYPE 1):
+// | PAGE_ID [u32] | OFFSET [u16] | LENGTH [u16] | BEFORE [LENGTH bytes] | AFTER [LENGTH bytes] |
+//
+// COMPENSATION BODY (TYPE 6):
+// | UNDO_NEXT_LSN [u64] | PAGE_ID [u32] | OFFSET [u16] | LENGTH [u16] | AFTER [LENGTH bytes] |
+//
+// CHECKPOINT END BODY (TYPE 8):
+// | NEXT_TRANSACTION_ID [u64] | TRANSACTION_COUNT [u32] | TRANSACTIONS | PAGE_COUNT [u32] |
+// | DIRTY PAGES |
+// with every transaction: | TRANSACTION_ID [u64] | FIRST_LSN [u64] | LAST_LSN [u64] |
+// and every dirty page: | PAGE_ID [u32] | RECOVERY_LSN [u64] |
+//
+// LOGICAL CHANGE BODY (TYPE 9):
+// | UNDO_NEXT_LSN [u64] | CHANGE_TYPE [u8] | CHANGE |
+// TUPLE INSERTED (CHANGE_TYPE 1): | FIRST_DIRECTORY_PAGE_ID [u32] | RID | VERSION |
+// TUPLE REMOVED (CHANGE_TYPE 2):
+// | FIRST_DIRECTORY_PAGE_ID [u32] | RID | VERSION | DATA_LENGTH [u32] | DATA |
+// TUPLE VERSION CHANGED (CHANGE_TYPE 3):
+// | FIRST_DIRECTORY_PAGE_ID [u32] | RID | BEFORE VERSION | AFTER VERSION |
+// TUPLE UPDATED (CHANGE_TYPE 4):
+// | FIRST_DIRECTORY_PAGE_ID [u32] | RID | BEFORE_LENGTH [u32] | BEFORE | AFTER_LENGTH [u32] |
+// | AFTER |
+// INDEX ENTRY INSERTED (CHANGE_TYPE 5) AND DELETED (CHANGE_TYPE 6):
+// | INDEX_TYPE [u8] | ROOT_PAGE_ID [u32] | RID | KEY_LENGTH [u16] | KEY |
+// with RID: | PAGE_ID [u32] | SLOT_ID [u32] |
+// and VERSION: | CREATED_BY [u64] | DELETED_BY [u64] | PREVIOUS_PAGE_ID [u32] | PREVIOUS_SLOT_ID [u32] |
+// like in the tuple header of a table page. INDEX_TYPE is 0 for hash and 1 for B+ tree indexes.
+//
+// LOGICAL COMPENSATION BODY (TYPE 10):
+// | UNDO_NEXT_LSN [u64] |
+//
+// ENCRYPTED RECORD FORMAT:
+// | LENGTH [u32] | CHECKSUM [u32] | LSN [u64] | NONCE [12 bytes] | CIPHERTEXT | TAG [16 bytes] |
+//
+// The ciphertext holds all fields of the record after the LSN. The LSN is passed as associated
+// data, so a record that was copied to another position fails authentication.
+
+use crate::{
+    catalog::system_catalog::IndexType,
+    disk_management::{buffer_pool::PAGE_SIZE, disk_manager::EncryptionKey},
+    table::table_page::{Rid, TupleVersion},
+};
+
+/// A log sequence number: the position of a log record in the log. LSNs increase with every
+/// appended record.
+pub type Lsn = u64;
+
+/// Identifies a transaction. Ids start at 1 and are never reused.
+pub type TransactionId = u64;
+
+const HEADER_SIZE: usize = 33;
+const NO_TRANSACTION: u64 = 0;
+const NO_LSN: u64 = u64::MAX;
+
+const PAGE_WRITE: u8 = 1;
+const BEGIN: u8 = 2;
+const COMMIT: u8 = 3;
+const ABORT: u8 = 4;
+const END: u8 = 5;
+const COMPENSATION: u8 = 6;
+const CHECKPOINT_BEGIN: u8 = 7;
+const CHECKPOINT_END: u8 = 8;
+const LOGICAL_CHANGE: u8 = 9;
+const LOGICAL_COMPENSATION: u8 = 10;
+
+const TUPLE_INSERTED: u8 = 1;
+const TUPLE_REMOVED: u8 = 2;
+const TUPLE_VERSION_CHANGED: u8 = 3;
+const TUPLE_UPDATED: u8 = 4;
+const INDEX_ENTRY_INSERTED: u8 = 5;
+const INDEX_ENTRY_DELETED: u8 = 6;
+const NO_PREVIOUS_PAGE_ID: u32 = u32::MAX;
+
+/// The records of a transaction that has not ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActiveTransaction {
+    pub first_lsn: Lsn,
+    pub last_lsn: Lsn,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub lsn: Lsn,
+    /// The transaction the record belongs to. Changes outside of a transaction are redone, but
+    /// never undone.
+    pub transaction: Option<TransactionId>,
+    /// The LSN of the previous record of the same transaction.
+    pub prev_lsn: Option<Lsn>,
+    pub body: LogRecordBody,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogRecordBody {
+    /// The bytes of a page starting at `offset` changed from `before` to `after`. Redoing the
+    /// change writes `after`, undoing it writes `before`; both can be repeated any number of
+    /// times.
+    PageWrite {
+        page_id: u32,
+        offset: u16,
+        before: Vec<u8>,
+        after: Vec<u8>,
+    },
+    Begin,
+    /// The transaction committed. Its changes survive a crash once this record is flushed.
+    Commit,
+    /// The transaction is being rolled back.
+    Abort,
+    /// The transaction is finished: it committed, or all its changes were undone.
+    End,
+    /// A page write of the transaction was undone by writing `after`. Compensation records are
+    /// redone but never undone themselves; `undo_next_lsn` is the next record of the transaction
+    /// to undo, so that a rollback interrupted by a crash continues where it stopped.
+    Compensation {
+        page_id: u32,
+        offset: u16,
+        after: Vec<u8>,
+        undo_next_lsn: Option<Lsn>,
+    },
+    /// A checkpoint started. Recovery starts reading the log here.
+    CheckpointBegin,
+    /// The state of the log and the buffer pool during the checkpoint: the transactions that
+    /// have not ended, and the dirty pages with the LSN of the first change that is not on disk.
+    CheckpointEnd {
+        next_transaction_id: TransactionId,
+        transactions: Vec<(TransactionId, ActiveTransaction)>,
+        dirty_pages: Vec<(u32, Lsn)>,
+    },
+    /// A change of a row or of an index entry, logged after the page writes that made it. Other
+    /// transactions may change the same pages afterwards, so undoing it applies the inverse
+    /// change through the table heap or the index instead of restoring the pages.
+    /// `undo_next_lsn` is the record of the transaction before the page writes, which undo
+    /// continues at.
+    LogicalChange {
+        change: LogicalChange,
+        undo_next_lsn: Option<Lsn>,
+    },
+    /// A logical change of the transaction was undone by the page writes before this record,
+    /// which are logged outside of any transaction. Like a compensation record, it is never
+    /// undone itself.
+    LogicalCompensation {
+        undo_next_lsn: Option<Lsn>,
+    },
+}
+
+/// A change of a tuple in the table heap starting at `first_directory_page_id`, or of an entry of
+/// the index with the given root page. Undoing a change checks that it is still in place, so
+/// that undoing it again, e.g. after the transaction already undid it while running, does
+/// nothing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LogicalChange {
+    /// Undone by removing the tuple, if it still has the version it was inserted with.
+    TupleInserted {
+        first_directory_page_id: u32,
+        rid: Rid,
+        version: TupleVersion,
+    },
+    /// Undone by putting the tuple back into its slot, if the slot is still free.
+    TupleRemoved {
+        first_directory_page_id: u32,
+        rid: Rid,
+        version: TupleVersion,
+        data: Vec<u8>,
+    },
+    /// Undone by setting the version `before`, if the tuple still has the version `after`.
+    TupleVersionChanged {
+        first_directory_page_id: u32,
+        rid: Rid,
+        before: TupleVersion,
+        after: TupleVersion,
+    },
+    /// Undone by writing the data `before`, if the tuple still has the data `after`.
+    TupleUpdated {
+        first_directory_page_id: u32,
+        rid: Rid,
+        before: Vec<u8>,
+        after: Vec<u8>,
+    },
+    /// Undone by removing the entry. `key` is the key as the index stores it.
+    IndexEntryInserted {
+        index_type: IndexType,
+        root_page_id: u32,
+        key: Vec<u8>,
+        rid: Rid,
+    },
+    /// Undone by adding the entry again, unless the index contains it.
+    IndexEntryDeleted {
+        index_type: IndexType,
+        root_page_id: u32,
+        key: Vec<u8>,
+        rid: Rid,
+    },
+}
+
+impl LogRecordBody {
+    /// Returns the record of a page changing from `old` to `new`, covering the bytes from the
+    /// first to the last changed one, or None if the page did not change.
+    pub fn page_write(
+        page_id: u32,
+        old: &[u8; PAGE_SIZE],
+        new: &[u8; PAGE_SIZE],
+    ) -> Option<LogRecordBody> {
+        let first = old.iter().zip(new).position(|(old, new)| old != new)?;
+        let last = PAGE_SIZE
+            - 1
+            - old
+                .iter()
+                .zip(new)
+                .rev()
+                .position(|(old, new)| old != new)?;
+        Some(LogRecordBody::PageWrite {
+            page_id,
+            offset: first as u16,
+            before: old[first..=last].to_vec(),
+            after: new[first..=last].to_vec(),
+        })
+    }
+
+    /// Returns the page, offset and new bytes of a record that changes a page.
+    pub fn page_change(&self) -> Option<(u32, u16, &[u8])> {
+        match self {
+            LogRecordBody::PageWrite {
+                page_id,
+                offset,
+                after,
+                ..
+            }
+            | LogRecordBody::Compensation {
+                page_id,
+                offset,
+                after,
+                ..
+            } => Some((*page_id, *offset, after)),
+            _ => None,
+        }
+    }
+}
+
+impl LogRecord {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; 8];
+        bytes.extend(self.lsn.to_le_bytes());
+        bytes.extend(self.transaction.unwrap_or(NO_TRANSACTION).to_le_bytes());
+        bytes.extend(self.prev_lsn.unwrap_or(NO_LSN).to_le_bytes());
+        match &self.body {
+            LogRecordBody::PageWrite {
+                page_id,
+                offset,
+                before,
+                after,
+            } => {
+                bytes.push(PAGE_WRITE);
+                bytes.extend(page_id.to_le_bytes());
+                bytes.extend(offset.to_le_bytes());
+                bytes.extend((before.len() as u16).to_le_bytes());
+                bytes.extend(before);
+                bytes.extend(after);
+            }
+            LogRecordBody::Begin => bytes.push(BEGIN),
+            LogRecordBody::Commit => bytes.push(COMMIT),
+            LogRecordBody::Abort => bytes.push(ABORT),
+            LogRecordBody::End => bytes.push(END),
+            LogRecordBody::Compensation {
+                page_id,
+                offset,
+                after,
+                undo_next_lsn,
+            } => {
+                bytes.push(COMPENSATION);
+                bytes.extend(undo_next_lsn.unwrap_or(NO_LSN).to_le_bytes());
+                bytes.extend(page_id.to_le_bytes());
+                bytes.extend(offset.to_le_bytes());
+                bytes.extend((after.len() as u16).to_le_bytes());
+                bytes.extend(after);
+            }
+            LogRecordBody::CheckpointBegin => bytes.push(CHECKPOINT_BEGIN),
+            LogRecordBody::CheckpointEnd {
+                next_transaction_id,
+                transactions,
+                dirty_pages,
+            } => {
+                bytes.push(CHECKPOINT_END);
+                bytes.extend(next_transaction_id.to_le_bytes());
+                bytes.extend((transactions.len() as u32).to_le_bytes());
+                for (transaction, lsns) in transactions {
+                    bytes.extend(transaction.to_le_bytes());
+                    bytes.extend(lsns.first_lsn.to_le_bytes());
+                    bytes.extend(lsns.last_lsn.to_le_bytes());
+                }
+                bytes.extend((dirty_pages.len() as u32).to_le_bytes());
+                for (page_id, recovery_lsn) in dirty_pages {
+                    bytes.extend(page_id.to_le_bytes());
+                    bytes.extend(recovery_lsn.to_le_bytes());
+                }
+            }
+            LogRecordBody::LogicalChange {
+                change,
+                undo_next_lsn,
+            } => {
+                bytes.push(LOGICAL_CHANGE);
+                bytes.extend(undo_next_lsn.unwrap_or(NO_LSN).to_le_bytes());
+                encode_logical_change(&mut bytes, change);
+            }
+            LogRecordBody::LogicalCompensation { undo_next_lsn } => {
+                bytes.push(LOGICAL_COMPENSATION);
+                bytes.extend(undo_next_lsn.unwrap_or(NO_LSN).to_le_bytes());
+            }
+        }
+        seal(&mut bytes);
+        bytes
+    }
+
+    /// Encodes the record like [LogRecord::to_bytes], but encrypts everything after its LSN.
+    pub fn to_encrypted_bytes(&self, key: &EncryptionKey) -> Vec<u8> {
+        let plain = self.to_bytes();
+        let mut bytes = plain[..16].to_vec();
+        bytes.extend(key.encrypt(&plain[16..], &plain[8..16]));
+        seal(&mut bytes);
+        bytes
+    }
+
+    /// Decodes a record encoded by [LogRecord::to_encrypted_bytes] like
+    /// [LogRecord::from_bytes]. Returns None as well if the record cannot be decrypted.
+    pub fn from_encrypted_bytes(bytes: &[u8], key: &EncryptionKey) -> Option<(LogRecord, usize)> {
+        if bytes.len() < 16 {
+            return None;
+        }
+        let length = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+        if length < 16
+            || length > bytes.len()
+            || crc32(&bytes[8..length]).to_le_bytes() != bytes[4..8]
+        {
+            return None;
+        }
+        let mut plain = bytes[..16].to_vec();
+        plain.extend(key.decrypt(&bytes[16..length], &bytes[8..16])?);
+        seal(&mut plain);
+        let (record, _) = LogRecord::from_bytes(&plain)?;
+        Some((record, length))
+    }
+
+    /// Decodes the record at the start of the bytes and returns it with its length. Returns
+    /// None if the bytes do not start with a complete and intact record.
+    pub fn from_bytes(bytes: &[u8]) -> Option<(LogRecord, usize)> {
+        let read_u16 =
+            |offset: usize| u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap());
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        let read_lsn = |offset: usize| Some(read_u64(offset)).filter(|lsn| *lsn != NO_LSN);
+        if bytes.len() < HEADER_SIZE {
+            return None;
+        }
+        let length = read_u32(0) as usize;
+        if length < HEADER_SIZE || length > bytes.len() || crc32(&bytes[8..length]) != read_u32(4) {
+            return None;
+        }
+        let lsn = read_u64(8);
+        let transaction = Some(read_u64(16)).filter(|id| *id != NO_TRANSACTION);
+        let prev_lsn = read_lsn(24);
+        let body = match bytes[32] {
+            PAGE_WRITE if length >= HEADER_SIZE + 8 => {
+                let changed = read_u16(HEADER_SIZE + 6) as usize;
+                let start = HEADER_SIZE + 8;
+                if length != start + 2 * changed {
+                    return None;
+                }
+                LogRecordBody::PageWrite {
+                    page_id: read_u32(HEADER_SIZE),
+                    offset: read_u16(HEADER_SIZE + 4),
+                    before: bytes[start..start + changed].to_vec(),
+                    after: bytes[start + changed..length].to_vec(),
+                }
+            }
+            BEGIN if length == HEADER_SIZE => LogRecordBody::Begin,
+            COMMIT if length == HEADER_SIZE => LogRecordBody::Commit,
+            ABORT if length == HEADER_SIZE => LogRecordBody::Abort,
+            END if length == HEADER_SIZE => LogRecordBody::End,
+            COMPENSATION if length >= HEADER_SIZE + 16 => {
+                let changed = read_u16(HEADER_SIZE + 14) as usize;
+                let start = HEADER_SIZE + 16;
+                if length != start + changed {
+                    return None;
+                }
+                LogRecordBody::Compensation {
+                    page_id: read_u32(HEADER_SIZE + 8),
+                    offset: read_u16(HEADER_SIZE + 12),
+                    after: bytes[start..length].to_vec(),
+                    undo_next_lsn: read_lsn(HEADER_SIZE),
+                }
+            }
+            CHECKPOINT_BEGIN if length == HEADER_SIZE => LogRecordBody::CheckpointBegin,
+            CHECKPOINT_END if length >= HEADER_SIZE + 12 => {
+                let transaction_count = read_u32(HEADER_SIZE + 8) as usize;
+                let pages_start = HEADER_SIZE + 12 + transaction_count * 24;
+                if length < pages_start + 4 {
+                    return None;
+                }
+                let page_count = read_u32(pages_start) as usize;
+                if length != pages_start + 4 + page_count * 12 {
+                    return None;
+                }
+                LogRecordBody::CheckpointEnd {
+                    next_transaction_id: read_u64(HEADER_SIZE),
+                    transactions: (0..transaction_count)
+                        .map(|i| {
+                            let start = HEADER_SIZE + 12 + i * 24;
+                            let lsns = ActiveTransaction {
+                                first_lsn: read_u64(start + 8),
+                                last_lsn: read_u64(start + 16),
+                            };
+                            (read_u64(start), lsns)
+                        })
+                        .collect(),
+                    dirty_pages: (0..page_count)
+                        .map(|i| {
+                            let start = pages_start + 4 + i * 12;
+                            (read_u32(start), read_u64(start + 4))
+                        })
+                        .collect(),
+                }
+            }
+            LOGICAL_CHANGE if length >= HEADER_SIZE + 8 => {
+                let mut reader = Reader {
+                    bytes: &bytes[HEADER_SIZE + 8..length],
+                };
+                let change = decode_logical_change(&mut reader)?;
+                if !reader.bytes.is_empty() {
+                    return None;
+                }
+                LogRecordBody::LogicalChange {
+                    change,
+                    undo_next_lsn: read_lsn(HEADER_SIZE),
+                }
+            }
+            LOGICAL_COMPENSATION if length == HEADER_SIZE + 8 => {
+                LogRecordBody::LogicalCompensation {
+                    undo_next_lsn: read_lsn(HEADER_SIZE),
+                }
+            }
+            _ => return None,
+        };
+        Some((
+            LogRecord {
+                lsn,
+                transaction,
+                prev_lsn,
+                body,
+            },
+            length,
+        ))
+    }
+}
+
+fn encode_logical_change(bytes: &mut Vec<u8>, change: &LogicalChange) {
+    match change {
+        LogicalChange::TupleInserted {
+            first_directory_page_id,
+            rid,
+            version,
+        } => {
+            bytes.push(TUPLE_INSERTED);
+            bytes.extend(first_directory_page_id.to_le_bytes());
+            encode_rid(bytes, *rid);
+            encode_version(bytes, version);
+        }
+        LogicalChange::TupleRemoved {
+            first_directory_page_id,
+            rid,
+            version,
+            data,
+        } => {
+            bytes.push(TUPLE_REMOVED);
+            bytes.extend(first_directory_page_id.to_le_bytes());
+            encode_rid(bytes, *rid);
+            encode_version(bytes, version);
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend(data);
+        }
+        LogicalChange::TupleVersionChanged {
+            first_directory_page_id,
+            rid,
+            before,
+            after,
+        } => {
+            bytes.push(TUPLE_VERSION_CHANGED);
+            bytes.extend(first_directory_page_id.to_le_bytes());
+            encode_rid(bytes, *rid);
+            encode_version(bytes, before);
+            encode_version(bytes, after);
+        }
+        LogicalChange::TupleUpdated {
+            first_directory_page_id,
+            rid,
+            before,
+            after,
+        } => {
+            bytes.push(TUPLE_UPDATED);
+            bytes.extend(first_directory_page_id.to_le_bytes());
+            encode_rid(bytes, *rid);
+            bytes.extend((before.len() as u32).to_le_bytes());
+            bytes.extend(before);
+            bytes.extend((after.len() as u32).to_le_bytes());
+            bytes.extend(after);
+        }
+        LogicalChange::IndexEntryInserted {
+            index_type,
+            root_page_id,
+            key,
+            rid,
+        }
+        | LogicalChange::IndexEntryDeleted {
+            index_type,
+            root_page_id,
+            key,
+            rid,
+        } => {
+            bytes.push(match change {
+                LogicalChange::IndexEntryInserted { .. } => INDEX_ENTRY_INSERTED,
+                _ => INDEX_ENTRY_DELETED,
+            });
+            bytes.push(match index_type {
+                IndexType::Hash => 0,
+                IndexType::BPlusTree => 1,
+            });
+            bytes.extend(root_page_id.to_le_bytes());
+            encode_rid(bytes, *rid);
+            bytes.extend((key.len() as u16).to_le_bytes());
+            bytes.extend(key);
+        }
+    }
+}
+
+fn encode_rid(bytes: &mut Vec<u8>, rid: Rid) {
+    bytes.extend(rid.page_id.to_le_bytes());
+    bytes.extend(rid.slot_id.to_le_bytes());
+}
+
+fn encode_version(bytes: &mut Vec<u8>, version: &TupleVersion) {
+    bytes.extend(version.created_by.unwrap_or(NO_TRANSACTION).to_le_bytes());
+    bytes.extend(version.deleted_by.unwrap_or(NO_TRANSACTION).to_le_bytes());
+    encode_rid(
+        bytes,
+        version.previous.unwrap_or(Rid::new(NO_PREVIOUS_PAGE_ID, 0)),
+    );
+}
+
+/// Reads the fields of a body of variable length. Every read returns None once the bytes are
+/// exhausted.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if length > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn rid(&mut self) -> Option<Rid> {
+        Some(Rid::new(self.u32()?, self.u32()?))
+    }
+
+    fn version(&mut self) -> Option<TupleVersion> {
+        let transaction = |id| Some(id).filter(|id| *id != NO_TRANSACTION);
+        Some(TupleVersion {
+            created_by: transaction(self.u64()?),
+            deleted_by: transaction(self.u64()?),
+            previous: Some(self.rid()?).filter(|rid| rid.page_id != NO_PREVIOUS_PAGE_ID),
+        })
+    }
+
+    fn data(&mut self) -> Option<Vec<u8>> {
+        let length = self.u32()? as usize;
+        Some(self.take(length)?.to_vec())
+    }
+}
+
+fn decode_logical_change(reader: &mut Reader) -> Option<LogicalChange> {
+    let change = match reader.u8()? {
+        TUPLE_INSERTED => LogicalChange::TupleInserted {
+            first_directory_page_id: reader.u32()?,
+            rid: reader.rid()?,
+            version: reader.version()?,
+        },
+        TUPLE_REMOVED => LogicalChange::TupleRemoved {
+            first_directory_page_id: reader.u32()?,
+            rid: reader.rid()?,
+            version: reader.version()?,
+            data: reader.data()?,
+        },
+        TUPLE_VERSION_CHANGED => LogicalChange::TupleVersionChanged {
+            first_directory_page_id: reader.u32()?,
+            rid: reader.rid()?,
+            before: reader.version()?,
+            after: reader.version()?,
+        },
+        TUPLE_UPDATED => LogicalChange::TupleUpdated {
+            first_directory_page_id: reader.u32()?,
+            rid: reader.rid()?,
+            before: reader.data()?,
+            after: reader.data()?,
+        },
+        change_type @ (INDEX_ENTRY_INSERTED | INDEX_ENTRY_DELETED) => {
+            let index_type = match reader.u8()? {
+                0 => IndexType::Hash,
+                1 => IndexType::BPlusTree,
+                _ => return None,
+            };
+            let root_page_id = reader.u32()?;
+            let rid = reader.rid()?;
+            let key_length = reader.u16()? as usize;
+            let key = reader.take(key_length)?.to_vec();
+            match change_type {
+                INDEX_ENTRY_INSERTED => LogicalChange::IndexEntryInserted {
+                    index_type,
+                    root_page_id,
+                    key,
+                    rid,
+                },
+                _ => LogicalChange::IndexEntryDeleted {
+                    index_type,
+                    root_page_id,
+                    key,
+                    rid,
+                },
+            }
+        }
+        _ => return None,
+    };
+    Some(change)
+}
+
+/// Writes the length and the checksum into the header of the encoded record.
+fn seal(bytes: &mut [u8]) {
+    let length = bytes.len() as u32;
+    let checksum = crc32(&bytes[8..]);
+    bytes[0..4].copy_from_slice(&length.to_le_bytes());
+    bytes[4..8].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// The CRC-32 (IEEE) checksum of the bytes.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod log_record_tests {
+    use crate::{
+        catalog::system_catalog::IndexType,
+        disk_management::{buffer_pool::PAGE_SIZE, disk_manager::EncryptionKey},
+        table::table_page::{Rid, TupleVersion},
+    };
+
+    use super::{crc32, ActiveTransaction, LogRecord, LogRecordBody, LogicalChange};
+
+    #[test]
+    fn page_write_covers_changed_bytes() {
+        let old = [0; PAGE_SIZE];
+        let mut new = [0; PAGE_SIZE];
+        assert_eq!(LogRecordBody::page_write(3, &old, &new), None);
+        new[10] = 1;
+        new[12] = 2;
+        assert_eq!(
+            LogRecordBody::page_write(3, &old, &new),
+            Some(LogRecordBody::PageWrite {
+                page_id: 3,
+                offset: 10,
+                before: vec![0, 0, 0],
+                after: vec![1, 0, 2],
+            })
+        );
+    }
+
+    #[test]
+    fn encode_and_decode_records() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let record = LogRecord {
+            lsn: 42,
+            transaction: None,
+            prev_lsn: None,
+            body: LogRecordBody::PageWrite {
+                page_id: 7,
+                offset: 4090,
+                before: vec![1, 2, 3],
+                after: vec![4, 5, 6],
+            },
+        };
+        let mut bytes = record.to_bytes();
+        let length = bytes.len();
+        bytes.extend([9; 5]);
+        assert_eq!(LogRecord::from_bytes(&bytes), Some((record, length)));
+
+        for body in [
+            LogRecordBody::Begin,
+            LogRecordBody::Commit,
+            LogRecordBody::Abort,
+            LogRecordBody::End,
+            LogRecordBody::Compensation {
+                page_id: 3,
+                offset: 8,
+                after: vec![1, 2],
+                undo_next_lsn: Some(17),
+            },
+            LogRecordBody::Compensation {
+                page_id: 3,
+                offset: 8,
+                after: vec![1, 2],
+                undo_next_lsn: None,
+            },
+            LogRecordBody::CheckpointBegin,
+            LogRecordBody::CheckpointEnd {
+                next_transaction_id: 9,
+                transactions: vec![(
+                    5,
+                    ActiveTransaction {
+                        first_lsn: 10,
+                        last_lsn: 60,
+                    },
+                )],
+                dirty_pages: vec![(1, 20), (4, 10)],
+            },
+            LogRecordBody::LogicalCompensation {
+                undo_next_lsn: Some(17),
+            },
+        ]
+        .into_iter()
+        .chain(
+            logical_changes()
+                .into_iter()
+                .map(|change| LogRecordBody::LogicalChange {
+                    change,
+                    undo_next_lsn: None,
+                }),
+        ) {
+            let record = LogRecord {
+                lsn: 100,
+                transaction: Some(5),
+                prev_lsn: Some(60),
+                body,
+            };
+            let record_bytes = record.to_bytes();
+            assert_eq!(
+                LogRecord::from_bytes(&record_bytes),
+                Some((record, record_bytes.len()))
+            );
+        }
+
+        assert_eq!(LogRecord::from_bytes(&bytes[..length - 1]), None);
+        bytes[40] ^= 1;
+        assert_eq!(LogRecord::from_bytes(&bytes), None);
+    }
+
+    fn logical_changes() -> Vec<LogicalChange> {
+        let rid = Rid::new(4, 2);
+        let version = TupleVersion {
+            created_by: Some(3),
+            deleted_by: None,
+            previous: Some(Rid::new(4, 1)),
+        };
+        vec![
+            LogicalChange::TupleInserted {
+                first_directory_page_id: 1,
+                rid,
+                version,
+            },
+            LogicalChange::TupleRemoved {
+                first_directory_page_id: 1,
+                rid,
+                version: TupleVersion::default(),
+                data: vec![1; 5000],
+            },
+            LogicalChange::TupleVersionChanged {
+                first_directory_page_id: 1,
+                rid,
+                before: version,
+                after: TupleVersion {
+                    deleted_by: Some(5),
+                    ..version
+                },
+            },
+            LogicalChange::TupleUpdated {
+                first_directory_page_id: 1,
+                rid,
+                before: vec![1, 2],
+                after: vec![],
+            },
+            LogicalChange::IndexEntryInserted {
+                index_type: IndexType::Hash,
+                root_page_id: 9,
+                key: vec![7; 8],
+                rid,
+            },
+            LogicalChange::IndexEntryDeleted {
+                index_type: IndexType::BPlusTree,
+                root_page_id: 9,
+                key: vec![7; 8],
+                rid,
+            },
+        ]
+    }
+
+    #[test]
+    fn encrypted_records() {
+        let key = EncryptionKey::new([7; 32]);
+        let record = LogRecord {
+            lsn: 100,
+            transaction: Some(5),
+            prev_lsn: None,
+            body: LogRecordBody::PageWrite {
+                page_id: 3,
+                offset: 8,
+                before: vec![0; 16],
+                after: vec![0xAB; 16],
+            },
+        };
+        let mut bytes = record.to_encrypted_bytes(&key);
+        let length = bytes.len();
+        assert!(!bytes.windows(16).any(|window| window == [0xAB; 16]));
+        assert_eq!(
+            LogRecord::from_encrypted_bytes(&bytes, &key),
+            Some((record, length))
+        );
+        assert_eq!(
+            LogRecord::from_encrypted_bytes(&bytes, &EncryptionKey::new([8; 32])),
+            None
+        );
+        assert_eq!(LogRecord::from_bytes(&bytes), None);
+
+        // A record moved to another LSN fails authentication, even with a valid checksum.
+        bytes[8] ^= 1;
+        let checksum = crc32(&bytes[8..]);
+        bytes[4..8].copy_from_slice(&checksum.to_le_bytes());
+        assert_eq!(LogRecord::from_encrypted_bytes(&bytes, &key), None);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod checkpoint;
+pub mod log_manager;
+pub mod log_record;
+pub mod recovery_manager;
@@ -0,0 +1,550 @@
+use std::{
+    collections::{BinaryHeap, HashMap},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    disk_management::buffer_pool::BufferPool, index::table_index::Index,
+    table::table_heap::TableHeap,
+};
+
+use super::{
+    checkpoint::checkpoint,
+    log_manager::LogManager,
+    log_record::{LogRecordBody, LogicalChange, Lsn, TransactionId},
+};
+
+/// Brings the pages back into the state of the committed transactions after a crash, following
+/// ARIES:
+///
+/// 1. Analysis reads the log from the last checkpoint to find the transactions that did not end
+///    and the pages that may have been changed without being written to disk.
+/// 2. Redo repeats history: every change of such a page since its first change that may not be
+///    on disk is applied again, including those of unfinished transactions and compensation
+///    records.
+/// 3. Undo rolls back the transactions that did not commit, newest change first, and logs a
+///    compensation record for every undone change. A crash during undo therefore never undoes
+///    a change twice. Changes of rows and index entries are undone logically through the table
+///    heaps and indexes, skipping the page writes that made them, since other transactions may
+///    have changed the same pages since. Only page writes without a logical change, e.g. those
+///    of an operation interrupted by the crash or of new pages, are undone by restoring the
+///    bytes they overwrote.
+///
+/// Recovery ends with a checkpoint, so that the next recovery does not repeat its work.
+///
+/// Every page stores the LSN of the last change it contains when it is written to disk, so redo
+/// skips the changes that already reached the disk.
+///
+/// Must run before anything reads the pages, e.g. before the catalog is opened. Does nothing if
+/// the buffer pool has no log.
+pub fn recover(buffer_pool: &Arc<Mutex<BufferPool>>) -> Result<(), &'static str> {
+    let Some(log_manager) = lock(buffer_pool).log_manager() else {
+        return Ok(());
+    };
+    let checkpoint_lsn = log_manager.lock().unwrap().checkpoint_lsn();
+    let records = log_manager
+        .lock()
+        .unwrap()
+        .read_from(checkpoint_lsn.unwrap_or(0))?;
+
+    // Analysis
+    let mut committed = Vec::new();
+    let mut dirty_pages: HashMap<u32, Lsn> = HashMap::new();
+    for record in &records {
+        match &record.body {
+            LogRecordBody::Commit => committed.extend(record.transaction),
+            LogRecordBody::End => committed.retain(|id| Some(*id) != record.transaction),
+            LogRecordBody::CheckpointEnd {
+                dirty_pages: checkpoint_dirty_pages,
+                ..
+            } => {
+                for (page_id, recovery_lsn) in checkpoint_dirty_pages {
+                    let lsn = dirty_pages.entry(*page_id).or_insert(*recovery_lsn);
+                    *lsn = (*lsn).min(*recovery_lsn);
+                }
+            }
+            _ => {}
+        }
+        if let Some((page_id, _, _)) = record.body.page_change() {
+            dirty_pages.entry(page_id).or_insert(record.lsn);
+        }
+    }
+
+    // Redo
+    if let Some(redo_lsn) = dirty_pages.values().min().copied() {
+        let records = log_manager.lock().unwrap().read_from(redo_lsn)?;
+        let mut buffer_pool = lock(buffer_pool);
+        for record in &records {
+            let Some((page_id, offset, after)) = record.body.page_change() else {
+                continue;
+            };
+            if dirty_pages
+                .get(&page_id)
+                .is_some_and(|recovery_lsn| *recovery_lsn <= record.lsn)
+            {
+                buffer_pool.apply_logged_change(
+                    page_id as usize,
+                    offset as usize,
+                    after,
+                    record.lsn,
+                )?;
+            }
+        }
+    }
+
+    // Undo
+    let mut losers = Vec::new();
+    {
+        let mut log_manager = log_manager.lock().unwrap();
+        let active: Vec<TransactionId> =
+            log_manager.active_transactions().keys().copied().collect();
+        for transaction in active {
+            if committed.contains(&transaction) {
+                log_manager.append(Some(transaction), LogRecordBody::End);
+            } else {
+                losers.push((
+                    log_manager.append(Some(transaction), LogRecordBody::Abort),
+                    transaction,
+                ));
+            }
+        }
+    }
+    undo(buffer_pool, &log_manager, losers)?;
+    checkpoint(&lock(buffer_pool))
+}
+
+fn lock(buffer_pool: &Mutex<BufferPool>) -> MutexGuard<'_, BufferPool> {
+    buffer_pool.lock().expect("Could not lock buffer pool")
+}
+
+/// Undoes the changes of the transactions, each given with the LSN to start undoing at. All
+/// transactions are undone together, the newest change first, and ended afterwards.
+fn undo(
+    buffer_pool: &Arc<Mutex<BufferPool>>,
+    log_manager: &Mutex<LogManager>,
+    transactions: Vec<(Lsn, TransactionId)>,
+) -> Result<(), &'static str> {
+    let mut to_undo = BinaryHeap::from(transactions);
+    while let Some((lsn, transaction)) = to_undo.pop() {
+        let record = log_manager.lock().unwrap().read_record(lsn)?;
+        let undo_next_lsn = match record.body {
+            LogRecordBody::PageWrite {
+                page_id,
+                offset,
+                before,
+                ..
+            } => {
+                let compensation_lsn = log_manager.lock().unwrap().append(
+                    Some(transaction),
+                    LogRecordBody::Compensation {
+                        page_id,
+                        offset,
+                        after: before.clone(),
+                        undo_next_lsn: record.prev_lsn,
+                    },
+                );
+                lock(buffer_pool).apply_logged_change(
+                    page_id as usize,
+                    offset as usize,
+                    &before,
+                    compensation_lsn,
+                )?;
+                record.prev_lsn
+            }
+            LogRecordBody::LogicalChange {
+                change,
+                undo_next_lsn,
+            } => {
+                undo_logical_change(buffer_pool, change)?;
+                log_manager.lock().unwrap().append(
+                    Some(transaction),
+                    LogRecordBody::LogicalCompensation { undo_next_lsn },
+                );
+                undo_next_lsn
+            }
+            LogRecordBody::Compensation { undo_next_lsn, .. }
+            | LogRecordBody::LogicalCompensation { undo_next_lsn } => undo_next_lsn,
+            _ => record.prev_lsn,
+        };
+        match undo_next_lsn {
+            Some(lsn) => to_undo.push((lsn, transaction)),
+            None => {
+                log_manager
+                    .lock()
+                    .unwrap()
+                    .append(Some(transaction), LogRecordBody::End);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Applies the inverse of a logical change, unless it is no longer in place. The page writes are
+/// logged outside of any transaction.
+fn undo_logical_change(
+    buffer_pool: &Arc<Mutex<BufferPool>>,
+    change: LogicalChange,
+) -> Result<(), &'static str> {
+    match change {
+        LogicalChange::TupleInserted {
+            first_directory_page_id,
+            rid,
+            version,
+        } => {
+            let table_heap = TableHeap::new(buffer_pool.clone(), first_directory_page_id);
+            if table_heap
+                .get(rid)?
+                .is_some_and(|tuple| tuple.version == version)
+            {
+                table_heap.delete(rid, None);
+            }
+        }
+        LogicalChange::TupleRemoved {
+            first_directory_page_id,
+            rid,
+            version,
+            data,
+        } => {
+            let table_heap = TableHeap::new(buffer_pool.clone(), first_directory_page_id);
+            if table_heap.get(rid)?.is_none() {
+                table_heap.restore(rid, data, version, None)?;
+            }
+        }
+        LogicalChange::TupleVersionChanged {
+            first_directory_page_id,
+            rid,
+            before,
+            after,
+        } => {
+            let table_heap = TableHeap::new(buffer_pool.clone(), first_directory_page_id);
+            if table_heap
+                .get(rid)?
+                .is_some_and(|tuple| tuple.version == after)
+            {
+                table_heap.set_version(rid, before, None)?;
+            }
+        }
+        LogicalChange::TupleUpdated {
+            first_directory_page_id,
+            rid,
+            before,
+            after,
+        } => {
+            let table_heap = TableHeap::new(buffer_pool.clone(), first_directory_page_id);
+            if table_heap
+                .get(rid)?
+                .is_some_and(|tuple| tuple.data == after)
+            {
+                table_heap.update(rid, before, None)?;
+            }
+        }
+        LogicalChange::IndexEntryInserted {
+            index_type,
+            root_page_id,
+            key,
+            rid,
+        } => {
+            Index::open_root(buffer_pool.clone(), index_type, root_page_id)
+                .delete_logged_entry(&key, rid)?;
+        }
+        LogicalChange::IndexEntryDeleted {
+            index_type,
+            root_page_id,
+            key,
+            rid,
+        } => {
+            Index::open_root(buffer_pool.clone(), index_type, root_page_id)
+                .restore_logged_entry(&key, rid)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod recovery_tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        disk_management::{
+            buffer_pool::{BufferPool, RawPage, PAGE_SIZE},
+            disk_manager::DiskManager,
+            test_utils::temp_db_path,
+        },
+        recovery::{
+            log_manager::LogManager,
+            log_record::{LogRecordBody, TransactionId},
+        },
+        table::{table_heap::TableHeap, table_page::TupleVersion},
+    };
+
+    use super::{checkpoint, recover};
+
+    fn open(path: &str) -> (BufferPool, Arc<Mutex<LogManager>>) {
+        let log_manager = Arc::new(Mutex::new(
+            LogManager::open_with_segment_size(&format!("{}.wal", path), 256).unwrap(),
+        ));
+        let disk_manager = Arc::new(Mutex::new(DiskManager::new(path.to_string()).unwrap()));
+        (
+            BufferPool::with_log_manager(disk_manager, log_manager.clone()),
+            log_manager,
+        )
+    }
+
+    fn recovered(buffer_pool: BufferPool) -> BufferPool {
+        let buffer_pool = Arc::new(Mutex::new(buffer_pool));
+        recover(&buffer_pool).unwrap();
+        Arc::into_inner(buffer_pool).unwrap().into_inner().unwrap()
+    }
+
+    fn first_byte(buffer_pool: &mut BufferPool, page_id: usize) -> u8 {
+        let frame_id = buffer_pool.load_page(page_id).unwrap();
+        let byte = buffer_pool
+            .get_raw_page(frame_id)
+            .unwrap()
+            .data
+            .read()
+            .unwrap()[0];
+        buffer_pool.unload_page_id(page_id).unwrap();
+        byte
+    }
+
+    fn set_first_byte(
+        buffer_pool: &mut BufferPool,
+        page_id: usize,
+        byte: u8,
+        transaction: TransactionId,
+    ) {
+        let mut data = [0; PAGE_SIZE];
+        data[0] = byte;
+        buffer_pool
+            .update_page(page_id, RawPage::new(data), Some(transaction))
+            .unwrap();
+        buffer_pool.unload_page_id(page_id).unwrap();
+    }
+
+    fn remove_files(path: &str) {
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_dir_all(format!("{}.wal", path)).unwrap();
+    }
+
+    #[test]
+    fn redo_committed_and_undo_uncommitted_changes() {
+        let path = temp_db_path("recovery_redo_undo");
+        let (mut buffer_pool, _) = open(&path);
+        let committed_page = buffer_pool.allocate_new_page().unwrap();
+        let uncommitted_page = buffer_pool.allocate_new_page().unwrap();
+
+        // The uncommitted change reaches the disk, the committed one does not.
+        buffer_pool.begin_transaction(1);
+        set_first_byte(&mut buffer_pool, uncommitted_page, 2, 1);
+        buffer_pool.unload_all_pages_and_write_to_file().unwrap();
+        buffer_pool.commit_transaction(1).unwrap();
+        drop(buffer_pool);
+
+        let (mut buffer_pool, _) = open(&path);
+        buffer_pool.begin_transaction(2);
+        set_first_byte(&mut buffer_pool, committed_page, 1, 2);
+        buffer_pool.commit_transaction(2).unwrap();
+        buffer_pool.begin_transaction(3);
+        set_first_byte(&mut buffer_pool, uncommitted_page, 3, 3);
+        buffer_pool.unload_all_pages_and_write_to_file().unwrap();
+        drop(buffer_pool);
+
+        let (buffer_pool, log_manager) = open(&path);
+        let mut buffer_pool = recovered(buffer_pool);
+        assert_eq!(first_byte(&mut buffer_pool, committed_page), 1);
+        assert_eq!(first_byte(&mut buffer_pool, uncommitted_page), 2);
+        assert!(log_manager.lock().unwrap().active_transactions().is_empty());
+        remove_files(&path);
+    }
+
+    #[test]
+    fn changes_on_disk_are_not_redone() {
+        let path = temp_db_path("recovery_page_lsn");
+        let (mut buffer_pool, log_manager) = open(&path);
+        let page_id = buffer_pool.allocate_new_page().unwrap();
+        buffer_pool.begin_transaction(1);
+        let lsn = log_manager.lock().unwrap().next_lsn();
+        set_first_byte(&mut buffer_pool, page_id, 1, 1);
+        buffer_pool.commit_transaction(1).unwrap();
+        buffer_pool.unload_all_pages_and_write_to_file().unwrap();
+        drop(buffer_pool);
+
+        // The page on disk contains the change, so redoing it must not overwrite the data.
+        // Behind the format marker of the file.
+        let page_offset = 8 + page_id * (8 + PAGE_SIZE);
+        let mut content = std::fs::read(&path).unwrap();
+        let page_lsn =
+            u64::from_le_bytes(content[page_offset..page_offset + 8].try_into().unwrap());
+        assert_eq!(page_lsn, lsn);
+        content[page_offset + 8] = 7;
+        std::fs::write(&path, &content).unwrap();
+
+        let (buffer_pool, _) = open(&path);
+        let mut buffer_pool = recovered(buffer_pool);
+        assert_eq!(first_byte(&mut buffer_pool, page_id), 7);
+        remove_files(&path);
+    }
+
+    #[test]
+    fn interrupted_undo_continues() {
+        let path = temp_db_path("recovery_interrupted_undo");
+        let (mut buffer_pool, log_manager) = open(&path);
+        let page_id = buffer_pool.allocate_new_page().unwrap();
+        let page_write = |before: u8, after: u8| LogRecordBody::PageWrite {
+            page_id: page_id as u32,
+            offset: 0,
+            before: vec![before],
+            after: vec![after],
+        };
+        let begin = log_manager.lock().unwrap().next_lsn();
+        {
+            // The crash happened after the second write was undone.
+            let mut log_manager = log_manager.lock().unwrap();
+            let transaction = 1;
+            log_manager.append(Some(transaction), LogRecordBody::Begin);
+            let first = log_manager.append(Some(transaction), page_write(0, 1));
+            log_manager.append(Some(transaction), page_write(1, 2));
+            log_manager.append(Some(transaction), LogRecordBody::Abort);
+            log_manager.append(
+                Some(transaction),
+                LogRecordBody::Compensation {
+                    page_id: page_id as u32,
+                    offset: 0,
+                    after: vec![1],
+                    undo_next_lsn: Some(first),
+                },
+            );
+            log_manager.flush_all().unwrap();
+        }
+        drop(buffer_pool);
+
+        let (buffer_pool, log_manager) = open(&path);
+        let end = log_manager.lock().unwrap().next_lsn();
+        let mut buffer_pool = recovered(buffer_pool);
+        assert_eq!(first_byte(&mut buffer_pool, page_id), 0);
+        let bodies: Vec<LogRecordBody> = log_manager
+            .lock()
+            .unwrap()
+            .read_from(end)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.body)
+            .collect();
+        assert_eq!(
+            bodies[..3],
+            [
+                LogRecordBody::Abort,
+                LogRecordBody::Compensation {
+                    page_id: page_id as u32,
+                    offset: 0,
+                    after: vec![0],
+                    undo_next_lsn: Some(begin),
+                },
+                LogRecordBody::End,
+            ]
+        );
+        remove_files(&path);
+    }
+
+    #[test]
+    fn recover_from_checkpoint() {
+        let path = temp_db_path("recovery_checkpoint");
+        let (mut buffer_pool, log_manager) = open(&path);
+        let page_ids: Vec<usize> = (0..3)
+            .map(|_| buffer_pool.allocate_new_page().unwrap())
+            .collect();
+        for byte in 1..=20 {
+            buffer_pool.begin_transaction(byte as u64);
+            set_first_byte(&mut buffer_pool, page_ids[0], byte, byte as u64);
+            buffer_pool.commit_transaction(byte as u64).unwrap();
+        }
+        buffer_pool.unload_all_pages_and_write_to_file().unwrap();
+        let old_segments_end = log_manager.lock().unwrap().next_lsn();
+
+        // A transaction that is active during the checkpoint keeps its records in the log.
+        buffer_pool.begin_transaction(21);
+        let active_first_lsn = log_manager.lock().unwrap().next_lsn();
+        set_first_byte(&mut buffer_pool, page_ids[1], 1, 21);
+        buffer_pool.unload_all_pages_and_write_to_file().unwrap();
+        for _ in 0..10 {
+            set_first_byte(&mut buffer_pool, page_ids[2], 1, 21);
+            set_first_byte(&mut buffer_pool, page_ids[2], 0, 21);
+        }
+        checkpoint(&buffer_pool).unwrap();
+        {
+            let log_manager = log_manager.lock().unwrap();
+            assert!(log_manager.first_lsn() > 0);
+            assert!(log_manager.first_lsn() < old_segments_end);
+            assert!(log_manager.first_lsn() <= active_first_lsn);
+        }
+        set_first_byte(&mut buffer_pool, page_ids[2], 3, 21);
+        drop(buffer_pool);
+
+        let (buffer_pool, log_manager) = open(&path);
+        let crash_lsn = log_manager.lock().unwrap().next_lsn();
+        let mut buffer_pool = recovered(buffer_pool);
+        assert_eq!(first_byte(&mut buffer_pool, page_ids[0]), 20);
+        assert_eq!(first_byte(&mut buffer_pool, page_ids[1]), 0);
+        assert_eq!(first_byte(&mut buffer_pool, page_ids[2]), 0);
+        let log_manager = log_manager.lock().unwrap();
+        assert!(log_manager.checkpoint_lsn().unwrap() > crash_lsn);
+        assert!(log_manager.active_transactions().is_empty());
+        remove_files(&path);
+    }
+
+    #[test]
+    fn undo_keeps_the_changes_of_other_transactions() {
+        let path = temp_db_path("recovery_logical_undo");
+        let (buffer_pool, _) = open(&path);
+        let buffer_pool = Arc::new(Mutex::new(buffer_pool));
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let deleted = table_heap.insert(vec![1], None).unwrap();
+        let removed = table_heap.insert(vec![2; 3000], None).unwrap();
+        let created_by = |transaction| TupleVersion {
+            created_by: Some(transaction),
+            ..TupleVersion::default()
+        };
+
+        buffer_pool.lock().unwrap().begin_transaction(1);
+        let deleted_version = TupleVersion {
+            deleted_by: Some(1),
+            ..TupleVersion::default()
+        };
+        table_heap
+            .set_version(deleted, deleted_version, Some(1))
+            .unwrap();
+        let inserted = table_heap
+            .insert_version(vec![3], created_by(1), Some(1))
+            .unwrap();
+        // The insert was already undone while the transaction ran, and another transaction
+        // reused its slot.
+        table_heap.delete(inserted, None).unwrap();
+        buffer_pool.lock().unwrap().begin_transaction(2);
+        let reused = table_heap
+            .insert_version(vec![4], created_by(2), Some(2))
+            .unwrap();
+        assert_eq!(reused, inserted);
+        buffer_pool.lock().unwrap().commit_transaction(2).unwrap();
+        table_heap.delete(removed, Some(1)).unwrap();
+        drop(table_heap);
+        drop(buffer_pool);
+
+        let (buffer_pool, log_manager) = open(&path);
+        let buffer_pool = Arc::new(Mutex::new(recovered(buffer_pool)));
+        let table_heap = TableHeap::new(buffer_pool.clone(), 0);
+        let tuple = table_heap.get(deleted).unwrap().unwrap();
+        assert_eq!(tuple.version, TupleVersion::default());
+        assert_eq!(
+            table_heap.get(removed).unwrap().unwrap().data,
+            vec![2; 3000]
+        );
+        let tuple = table_heap.get(reused).unwrap().unwrap();
+        assert_eq!((tuple.data, tuple.version), (vec![4], created_by(2)));
+        assert_eq!(table_heap.iter().count(), 3);
+        assert!(log_manager.lock().unwrap().active_transactions().is_empty());
+        drop(table_heap);
+        drop(buffer_pool);
+        remove_files(&path);
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt::Display;
+
+use crate::{
+    catalog::system_catalog::IndexType, transaction::transaction_state::IsolationLevel,
+    types::schema::DataType,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    CreateTable(CreateTable),
+    DropTable {
+        name: String,
+        if_exists: bool,
+    },
+    CreateIndex(CreateIndex),
+    Insert(Insert),
+    Select(Box<Select>),
+    Update(Update),
+    Delete(Delete),
+    /// Gathers the statistics of a table, or of all tables if no table is given.
+    Analyze {
+        table: Option<String>,
+    },
+    /// Removes the versions of rows no transaction sees anymore from a table, or from all tables
+    /// if no table is given.
+    Vacuum {
+        table: Option<String>,
+    },
+    /// Shows the plan of a statement. With ANALYZE, the statement is executed and the plan shows
+    /// what happened.
+    Explain {
+        statement: Box<Statement>,
+        analyze: bool,
+    },
+    /// Starts a transaction that the following statements run in, until COMMIT or ROLLBACK.
+    Begin {
+        isolation_level: Option<IsolationLevel>,
+    },
+    Commit,
+    Rollback,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTable {
+    pub name: String,
+    pub columns: Vec<ColumnDefinition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub index_type: IndexType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Insert {
+    pub table: String,
+    /// The target columns, or None if the values are given for all columns in schema order.
+    pub columns: Option<Vec<String>>,
+    pub rows: Vec<Vec<Expression>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    pub projection: Vec<SelectItem>,
+    /// The tables of the FROM clause. Multiple entries are combined with a cross join.
+    pub from: Vec<TableReference>,
+    pub where_clause: Option<Expression>,
+    pub group_by: Vec<Expression>,
+    pub having: Option<Expression>,
+    pub order_by: Vec<OrderByItem>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectItem {
+    /// `*`
+    Wildcard,
+    /// `table.*`
+    QualifiedWildcard(String),
+    Expression {
+        expression: Expression,
+        alias: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableReference {
+    Table {
+        name: String,
+        alias: Option<String>,
+    },
+    Join {
+        left: Box<TableReference>,
+        right: Box<TableReference>,
+        join_type: JoinType,
+        /// The ON condition. It is None for cross joins.
+        condition: Option<Expression>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinType {
+    Cross,
+    Inner,
+    Left,
+    Right,
+    Full,
+    /// `LEFT SEMI JOIN`: the rows of the left input with at least one matching right row. Only
+    /// the columns of the left input are produced.
+    Semi,
+    /// `LEFT ANTI JOIN`: the rows of the left input without a matching right row. Only the
+    /// columns of the left input are produced.
+    Anti,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderByItem {
+    pub expression: Expression,
+    pub ascending: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Update {
+    pub table: String,
+    pub assignments: Vec<(String, Expression)>,
+    pub where_clause: Option<Expression>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delete {
+    pub table: String,
+    pub where_clause: Option<Expression>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Literal(Literal),
+    Column {
+        table: Option<String>,
+        name: String,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    Binary {
+        left: Box<Expression>,
+        operator: BinaryOperator,
+        right: Box<Expression>,
+    },
+    IsNull {
+        operand: Box<Expression>,
+        negated: bool,
+    },
+    Cast {
+        operand: Box<Expression>,
+        data_type: DataType,
+    },
+    Function {
+        name: String,
+        arguments: Vec<Expression>,
+        distinct: bool,
+    },
+    /// The `*` argument of `COUNT(*)`.
+    Wildcard,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnaryOperator::Not => write!(f, "NOT "),
+            UnaryOperator::Minus => write!(f, "-"),
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Or => "OR",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Equal => "=",
+            BinaryOperator::NotEqual => "<>",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
@@ -0,0 +1,297 @@
+use super::parser::ParseError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// An unquoted identifier or keyword. Keywords are recognized case insensitively by the
+    /// parser.
+    Word(String),
+    /// An identifier in double quotes. It is never treated as a keyword.
+    QuotedIdentifier(String),
+    Integer(String),
+    Float(String),
+    String(String),
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+    Semicolon,
+    Dot,
+    Asterisk,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Eof,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The line of the first character of the token, starting at 1.
+    pub line: usize,
+    /// The column of the first character of the token, starting at 1.
+    pub column: usize,
+}
+
+/// Splits the SQL text into tokens. The last token is always [TokenKind::Eof].
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        chars: sql.chars().collect(),
+        position: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    loop {
+        lexer.skip_whitespace_and_comments();
+        let (line, column) = (lexer.line, lexer.column);
+        let kind = match lexer.peek() {
+            None => {
+                tokens.push(Token {
+                    kind: TokenKind::Eof,
+                    line,
+                    column,
+                });
+                return Ok(tokens);
+            }
+            Some(c) => lexer.next_token(c)?,
+        };
+        tokens.push(Token { kind, line, column });
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.chars.get(self.position + 1).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(message, self.line, self.column)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.advance();
+                }
+                (Some('-'), Some('-')) => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.advance();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn next_token(&mut self, c: char) -> Result<TokenKind, ParseError> {
+        if c.is_ascii_alphabetic() || c == '_' {
+            return Ok(TokenKind::Word(
+                self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'),
+            ));
+        }
+        if c.is_ascii_digit() {
+            return self.number();
+        }
+
+        let (line, column) = (self.line, self.column);
+        self.advance();
+        let kind = match c {
+            '\'' => {
+                TokenKind::String(self.quoted('\'', "Unterminated string literal", line, column)?)
+            }
+            '"' => TokenKind::QuotedIdentifier(self.quoted(
+                '"',
+                "Unterminated quoted identifier",
+                line,
+                column,
+            )?),
+            '(' => TokenKind::LeftParenthesis,
+            ')' => TokenKind::RightParenthesis,
+            ',' => TokenKind::Comma,
+            ';' => TokenKind::Semicolon,
+            '.' => TokenKind::Dot,
+            '*' => TokenKind::Asterisk,
+            '+' => TokenKind::Plus,
+            '-' => TokenKind::Minus,
+            '/' => TokenKind::Slash,
+            '%' => TokenKind::Percent,
+            '=' => TokenKind::Equal,
+            '!' if self.peek() == Some('=') => {
+                self.advance();
+                TokenKind::NotEqual
+            }
+            '<' => match self.peek() {
+                Some('=') => {
+                    self.advance();
+                    TokenKind::LessEqual
+                }
+                Some('>') => {
+                    self.advance();
+                    TokenKind::NotEqual
+                }
+                _ => TokenKind::Less,
+            },
+            '>' => match self.peek() {
+                Some('=') => {
+                    self.advance();
+                    TokenKind::GreaterEqual
+                }
+                _ => TokenKind::Greater,
+            },
+            _ => {
+                return Err(ParseError::new(
+                    &format!("Unexpected character '{}'", c),
+                    line,
+                    column,
+                ))
+            }
+        };
+        Ok(kind)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            text.push(c);
+            self.advance();
+        }
+        text
+    }
+
+    fn number(&mut self) -> Result<TokenKind, ParseError> {
+        let mut text = self.take_while(|c| c.is_ascii_digit());
+        let mut is_float = false;
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            text.push('.');
+            text.push_str(&self.take_while(|c| c.is_ascii_digit()));
+            is_float = true;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let exponent_is_valid = match self.peek_next() {
+                Some('+' | '-') => self
+                    .chars
+                    .get(self.position + 2)
+                    .is_some_and(|c| c.is_ascii_digit()),
+                Some(c) => c.is_ascii_digit(),
+                None => false,
+            };
+            if exponent_is_valid {
+                text.push(self.advance().unwrap());
+                if matches!(self.peek(), Some('+' | '-')) {
+                    text.push(self.advance().unwrap());
+                }
+                text.push_str(&self.take_while(|c| c.is_ascii_digit()));
+                is_float = true;
+            }
+        }
+        if self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return Err(self.error("Unexpected character after a number"));
+        }
+        Ok(match is_float {
+            true => TokenKind::Float(text),
+            false => TokenKind::Integer(text),
+        })
+    }
+
+    /// Reads the rest of a quoted string. A doubled quote character stands for the quote itself.
+    fn quoted(
+        &mut self,
+        quote: char,
+        message: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<String, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.advance() {
+                None => return Err(ParseError::new(message, line, column)),
+                Some(c) if c == quote => {
+                    if self.peek() != Some(quote) {
+                        return Ok(text);
+                    }
+                    self.advance();
+                    text.push(quote);
+                }
+                Some(c) => text.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::{tokenize, TokenKind};
+
+    #[test]
+    fn tokenize_statement() {
+        let tokens =
+            tokenize("SELECT a, \"b c\" -- comment\nFROM t WHERE x <> 'it''s' AND y >= 1.5e3;")
+                .unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Word("SELECT".to_string()),
+                TokenKind::Word("a".to_string()),
+                TokenKind::Comma,
+                TokenKind::QuotedIdentifier("b c".to_string()),
+                TokenKind::Word("FROM".to_string()),
+                TokenKind::Word("t".to_string()),
+                TokenKind::Word("WHERE".to_string()),
+                TokenKind::Word("x".to_string()),
+                TokenKind::NotEqual,
+                TokenKind::String("it's".to_string()),
+                TokenKind::Word("AND".to_string()),
+                TokenKind::Word("y".to_string()),
+                TokenKind::GreaterEqual,
+                TokenKind::Float("1.5e3".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!((tokens[4].line, tokens[4].column), (2, 1));
+        assert_eq!((tokens[9].line, tokens[9].column), (2, 19));
+    }
+
+    #[test]
+    fn lexer_errors_point_to_position() {
+        let error = tokenize("SELECT 1\n  FROM t WHERE a = 'abc").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 20));
+        let error = tokenize("SELECT #").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 8));
+    }
+}
@@ -0,0 +1,1145 @@
+use std::fmt::Display;
+
+use crate::{
+    catalog::system_catalog::IndexType, transaction::transaction_state::IsolationLevel,
+    types::schema::DataType,
+};
+
+use super::{
+    ast::{
+        BinaryOperator, ColumnDefinition, CreateIndex, CreateTable, Delete, Expression, Insert,
+        JoinType, Literal, OrderByItem, Select, SelectItem, Statement, TableReference,
+        UnaryOperator, Update,
+    },
+    lexer::{tokenize, Token, TokenKind},
+};
+
+/// Words that cannot be used as unquoted identifiers.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "AND", "ANTI", "AS", "ASC", "BY", "CAST", "CREATE", "CROSS", "DELETE", "DESC", "DISTINCT",
+    "DROP", "FALSE", "FROM", "FULL", "GROUP", "HAVING", "INDEX", "INNER", "INSERT", "INTO", "IS",
+    "JOIN", "LEFT", "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER", "RIGHT",
+    "SELECT", "SEMI", "SET", "TABLE", "TRUE", "UNIQUE", "UPDATE", "USING", "VALUES", "WHERE",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// The line of the offending token, starting at 1.
+    pub line: usize,
+    /// The column of the offending token, starting at 1.
+    pub column: usize,
+}
+
+impl ParseError {
+    pub fn new(message: &str, line: usize, column: usize) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+/// Parses a sequence of statements separated by semicolons.
+pub fn parse(sql: &str) -> Result<Vec<Statement>, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        position: 0,
+    };
+    let mut statements = Vec::new();
+    loop {
+        while parser.consume(&TokenKind::Semicolon) {}
+        if parser.peek().kind == TokenKind::Eof {
+            return Ok(statements);
+        }
+        statements.push(parser.statement()?);
+        if !parser.consume(&TokenKind::Semicolon) && parser.peek().kind != TokenKind::Eof {
+            return Err(parser.unexpected("';' or the end of the input"));
+        }
+    }
+}
+
+/// Parses exactly one statement, optionally followed by a semicolon.
+pub fn parse_statement(sql: &str) -> Result<Statement, ParseError> {
+    let mut statements = parse(sql)?;
+    match statements.len() {
+        1 => Ok(statements.remove(0)),
+        0 => Err(ParseError::new("Expected a statement", 1, 1)),
+        _ => Err(ParseError::new("Expected a single statement", 1, 1)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        let position = (self.position + n).min(self.tokens.len() - 1);
+        &self.tokens[position]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::Eof {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::Word(word) if is_reserved(word) => {
+                format!("keyword {}", word.to_uppercase())
+            }
+            TokenKind::Word(word) | TokenKind::QuotedIdentifier(word) => {
+                format!("identifier {}", word)
+            }
+            TokenKind::Integer(number) | TokenKind::Float(number) => format!("number {}", number),
+            TokenKind::String(string) => format!("string '{}'", string),
+            TokenKind::Eof => "the end of the input".to_string(),
+            kind => format!("'{}'", symbol(kind)),
+        };
+        ParseError::new(
+            &format!("Expected {}, found {}", expected, found),
+            token.line,
+            token.column,
+        )
+    }
+
+    fn consume(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        if self.consume(&kind) {
+            return Ok(());
+        }
+        Err(self.unexpected(&format!("'{}'", symbol(&kind))))
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.consume_keyword(keyword) {
+            return Ok(());
+        }
+        Err(self.unexpected(keyword))
+    }
+
+    fn identifier(&mut self) -> Result<String, ParseError> {
+        match &self.peek().kind {
+            TokenKind::Word(word) if !is_reserved(word) => {
+                let word = word.clone();
+                self.next();
+                Ok(word)
+            }
+            TokenKind::QuotedIdentifier(identifier) => {
+                let identifier = identifier.clone();
+                self.next();
+                Ok(identifier)
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn identifier_list(&mut self) -> Result<Vec<String>, ParseError> {
+        self.expect(TokenKind::LeftParenthesis)?;
+        let mut identifiers = vec![self.identifier()?];
+        while self.consume(&TokenKind::Comma) {
+            identifiers.push(self.identifier()?);
+        }
+        self.expect(TokenKind::RightParenthesis)?;
+        Ok(identifiers)
+    }
+
+    fn unsigned_integer(&mut self) -> Result<u64, ParseError> {
+        if let TokenKind::Integer(number) = &self.peek().kind {
+            if let Ok(number) = number.parse() {
+                self.next();
+                return Ok(number);
+            }
+        }
+        Err(self.unexpected("an unsigned integer"))
+    }
+
+    fn statement(&mut self) -> Result<Statement, ParseError> {
+        if self.consume_keyword("SELECT") {
+            return Ok(Statement::Select(Box::new(self.select()?)));
+        }
+        if self.consume_keyword("INSERT") {
+            return self.insert();
+        }
+        if self.consume_keyword("UPDATE") {
+            return self.update();
+        }
+        if self.consume_keyword("DELETE") {
+            return self.delete();
+        }
+        if self.consume_keyword("CREATE") {
+            if self.consume_keyword("TABLE") {
+                return self.create_table();
+            }
+            let unique = self.consume_keyword("UNIQUE");
+            if self.consume_keyword("INDEX") {
+                return self.create_index(unique);
+            }
+            return Err(self.unexpected(match unique {
+                true => "INDEX",
+                false => "TABLE or INDEX",
+            }));
+        }
+        if self.consume_keyword("DROP") {
+            self.expect_keyword("TABLE")?;
+            let if_exists = self.peek_keyword("IF");
+            if if_exists {
+                self.next();
+                self.expect_keyword("EXISTS")?;
+            }
+            return Ok(Statement::DropTable {
+                name: self.identifier()?,
+                if_exists,
+            });
+        }
+        if self.consume_keyword("EXPLAIN") {
+            let analyze = self.consume_keyword("ANALYZE");
+            return Ok(Statement::Explain {
+                statement: Box::new(self.statement()?),
+                analyze,
+            });
+        }
+        if self.consume_keyword("ANALYZE") {
+            let table = match self.peek().kind {
+                TokenKind::Semicolon | TokenKind::Eof => None,
+                _ => Some(self.identifier()?),
+            };
+            return Ok(Statement::Analyze { table });
+        }
+        if self.consume_keyword("VACUUM") {
+            let table = match self.peek().kind {
+                TokenKind::Semicolon | TokenKind::Eof => None,
+                _ => Some(self.identifier()?),
+            };
+            return Ok(Statement::Vacuum { table });
+        }
+        if self.consume_keyword("BEGIN") {
+            self.transaction_keyword();
+            return self.begin();
+        }
+        if self.consume_keyword("START") {
+            self.expect_keyword("TRANSACTION")?;
+            return self.begin();
+        }
+        if self.consume_keyword("COMMIT") {
+            self.transaction_keyword();
+            return Ok(Statement::Commit);
+        }
+        if self.consume_keyword("ROLLBACK") {
+            self.transaction_keyword();
+            return Ok(Statement::Rollback);
+        }
+        Err(self.unexpected("a statement"))
+    }
+
+    /// Skips the optional TRANSACTION or WORK after BEGIN, COMMIT and ROLLBACK.
+    fn transaction_keyword(&mut self) {
+        if !self.consume_keyword("TRANSACTION") {
+            self.consume_keyword("WORK");
+        }
+    }
+
+    /// Parses the optional isolation level of BEGIN:
+    /// ISOLATION LEVEL {SERIALIZABLE | SNAPSHOT | REPEATABLE READ}. Repeatable read is provided
+    /// by snapshot isolation.
+    fn begin(&mut self) -> Result<Statement, ParseError> {
+        let isolation_level = match self.consume_keyword("ISOLATION") {
+            true => {
+                self.expect_keyword("LEVEL")?;
+                if self.consume_keyword("SERIALIZABLE") {
+                    Some(IsolationLevel::Serializable)
+                } else if self.consume_keyword("SNAPSHOT") {
+                    Some(IsolationLevel::SnapshotIsolation)
+                } else if self.consume_keyword("REPEATABLE") {
+                    self.expect_keyword("READ")?;
+                    Some(IsolationLevel::SnapshotIsolation)
+                } else {
+                    return Err(self.unexpected("an isolation level"));
+                }
+            }
+            false => None,
+        };
+        Ok(Statement::Begin { isolation_level })
+    }
+
+    fn create_table(&mut self) -> Result<Statement, ParseError> {
+        let name = self.identifier()?;
+        self.expect(TokenKind::LeftParenthesis)?;
+        let mut columns = Vec::new();
+        loop {
+            let column_name = self.identifier()?;
+            let data_type = self.data_type()?;
+            let mut nullable = true;
+            loop {
+                if self.consume_keyword("NOT") {
+                    self.expect_keyword("NULL")?;
+                    nullable = false;
+                } else if !self.consume_keyword("NULL") {
+                    break;
+                }
+            }
+            columns.push(ColumnDefinition {
+                name: column_name,
+                data_type,
+                nullable,
+            });
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::RightParenthesis)?;
+        Ok(Statement::CreateTable(CreateTable { name, columns }))
+    }
+
+    fn data_type(&mut self) -> Result<DataType, ParseError> {
+        let word = match &self.peek().kind {
+            TokenKind::Word(word) => word.to_uppercase(),
+            _ => return Err(self.unexpected("a data type")),
+        };
+        let data_type = match word.as_str() {
+            "INTEGER" | "INT" => DataType::Integer,
+            "BIGINT" => DataType::BigInt,
+            "FLOAT" | "DOUBLE" | "REAL" => DataType::Float,
+            "BOOLEAN" | "BOOL" => DataType::Boolean,
+            "VARCHAR" => {
+                self.next();
+                self.expect(TokenKind::LeftParenthesis)?;
+                let length = self.unsigned_integer()?;
+                let length = u32::try_from(length).map_err(|_| {
+                    let token = &self.tokens[self.position - 1];
+                    ParseError::new("The VARCHAR length is too large", token.line, token.column)
+                })?;
+                self.expect(TokenKind::RightParenthesis)?;
+                return Ok(DataType::Varchar(length));
+            }
+            "TEXT" => DataType::Text,
+            "BLOB" => DataType::Blob,
+            "DATE" => DataType::Date,
+            "TIMESTAMP" => DataType::Timestamp,
+            _ => return Err(self.unexpected("a data type")),
+        };
+        self.next();
+        Ok(data_type)
+    }
+
+    fn create_index(&mut self, unique: bool) -> Result<Statement, ParseError> {
+        let name = self.identifier()?;
+        self.expect_keyword("ON")?;
+        let table = self.identifier()?;
+        let mut index_type = IndexType::Hash;
+        if self.consume_keyword("USING") {
+            index_type = match &self.peek().kind {
+                TokenKind::Word(word) if word.eq_ignore_ascii_case("HASH") => IndexType::Hash,
+                TokenKind::Word(word) if word.eq_ignore_ascii_case("BTREE") => IndexType::BPlusTree,
+                _ => return Err(self.unexpected("HASH or BTREE")),
+            };
+            self.next();
+        }
+        let columns = self.identifier_list()?;
+        Ok(Statement::CreateIndex(CreateIndex {
+            name,
+            table,
+            columns,
+            unique,
+            index_type,
+        }))
+    }
+
+    fn insert(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword("INTO")?;
+        let table = self.identifier()?;
+        let columns = match self.peek().kind {
+            TokenKind::LeftParenthesis => Some(self.identifier_list()?),
+            _ => None,
+        };
+        self.expect_keyword("VALUES")?;
+        let mut rows = Vec::new();
+        loop {
+            self.expect(TokenKind::LeftParenthesis)?;
+            let mut row = vec![self.expression()?];
+            while self.consume(&TokenKind::Comma) {
+                row.push(self.expression()?);
+            }
+            self.expect(TokenKind::RightParenthesis)?;
+            rows.push(row);
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        Ok(Statement::Insert(Insert {
+            table,
+            columns,
+            rows,
+        }))
+    }
+
+    fn update(&mut self) -> Result<Statement, ParseError> {
+        let table = self.identifier()?;
+        self.expect_keyword("SET")?;
+        let mut assignments = Vec::new();
+        loop {
+            let column = self.identifier()?;
+            self.expect(TokenKind::Equal)?;
+            assignments.push((column, self.expression()?));
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        let where_clause = self.where_clause()?;
+        Ok(Statement::Update(Update {
+            table,
+            assignments,
+            where_clause,
+        }))
+    }
+
+    fn delete(&mut self) -> Result<Statement, ParseError> {
+        self.expect_keyword("FROM")?;
+        let table = self.identifier()?;
+        let where_clause = self.where_clause()?;
+        Ok(Statement::Delete(Delete {
+            table,
+            where_clause,
+        }))
+    }
+
+    fn where_clause(&mut self) -> Result<Option<Expression>, ParseError> {
+        match self.consume_keyword("WHERE") {
+            true => Ok(Some(self.expression()?)),
+            false => Ok(None),
+        }
+    }
+
+    fn select(&mut self) -> Result<Select, ParseError> {
+        let mut projection = vec![self.select_item()?];
+        while self.consume(&TokenKind::Comma) {
+            projection.push(self.select_item()?);
+        }
+
+        let mut from = Vec::new();
+        if self.consume_keyword("FROM") {
+            from.push(self.table_reference()?);
+            while self.consume(&TokenKind::Comma) {
+                from.push(self.table_reference()?);
+            }
+        }
+        let where_clause = self.where_clause()?;
+
+        let mut group_by = Vec::new();
+        if self.consume_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by.push(self.expression()?);
+            while self.consume(&TokenKind::Comma) {
+                group_by.push(self.expression()?);
+            }
+        }
+        let having = if self.consume_keyword("HAVING") {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        let mut order_by = Vec::new();
+        if self.consume_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expression = self.expression()?;
+                let ascending = !self.consume_keyword("DESC");
+                if ascending {
+                    self.consume_keyword("ASC");
+                }
+                order_by.push(OrderByItem {
+                    expression,
+                    ascending,
+                });
+                if !self.consume(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let mut limit = None;
+        let mut offset = None;
+        if self.consume_keyword("LIMIT") {
+            limit = Some(self.unsigned_integer()?);
+        }
+        if self.consume_keyword("OFFSET") {
+            offset = Some(self.unsigned_integer()?);
+        }
+
+        Ok(Select {
+            projection,
+            from,
+            where_clause,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem, ParseError> {
+        if self.consume(&TokenKind::Asterisk) {
+            return Ok(SelectItem::Wildcard);
+        }
+        if self.peek_nth(1).kind == TokenKind::Dot && self.peek_nth(2).kind == TokenKind::Asterisk {
+            let table = self.identifier()?;
+            self.next();
+            self.next();
+            return Ok(SelectItem::QualifiedWildcard(table));
+        }
+
+        let expression = self.expression()?;
+        Ok(SelectItem::Expression {
+            expression,
+            alias: self.alias()?,
+        })
+    }
+
+    /// Parses an optional alias, which is either introduced by AS or a plain identifier.
+    fn alias(&mut self) -> Result<Option<String>, ParseError> {
+        if self.consume_keyword("AS") {
+            return Ok(Some(self.identifier()?));
+        }
+        match &self.peek().kind {
+            TokenKind::Word(word) if !is_reserved(word) => Ok(Some(self.identifier()?)),
+            TokenKind::QuotedIdentifier(_) => Ok(Some(self.identifier()?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn table_reference(&mut self) -> Result<TableReference, ParseError> {
+        let mut table_reference = self.table_factor()?;
+        loop {
+            let join_type = if self.consume_keyword("CROSS") {
+                JoinType::Cross
+            } else if self.consume_keyword("INNER") {
+                JoinType::Inner
+            } else if self.consume_keyword("LEFT") {
+                if self.consume_keyword("SEMI") {
+                    JoinType::Semi
+                } else if self.consume_keyword("ANTI") {
+                    JoinType::Anti
+                } else {
+                    self.consume_keyword("OUTER");
+                    JoinType::Left
+                }
+            } else if self.consume_keyword("RIGHT") {
+                self.consume_keyword("OUTER");
+                JoinType::Right
+            } else if self.consume_keyword("FULL") {
+                self.consume_keyword("OUTER");
+                JoinType::Full
+            } else if self.peek_keyword("JOIN") {
+                JoinType::Inner
+            } else {
+                return Ok(table_reference);
+            };
+            self.expect_keyword("JOIN")?;
+            let right = self.table_factor()?;
+            let condition = match join_type {
+                JoinType::Cross => None,
+                _ => {
+                    self.expect_keyword("ON")?;
+                    Some(self.expression()?)
+                }
+            };
+            table_reference = TableReference::Join {
+                left: Box::new(table_reference),
+                right: Box::new(right),
+                join_type,
+                condition,
+            };
+        }
+    }
+
+    fn table_factor(&mut self) -> Result<TableReference, ParseError> {
+        if self.consume(&TokenKind::LeftParenthesis) {
+            let table_reference = self.table_reference()?;
+            self.expect(TokenKind::RightParenthesis)?;
+            return Ok(table_reference);
+        }
+        let name = self.identifier()?;
+        Ok(TableReference::Table {
+            name,
+            alias: self.alias()?,
+        })
+    }
+
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        self.or_expression()
+    }
+
+    fn or_expression(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.and_expression()?;
+        while self.consume_keyword("OR") {
+            expression = binary(expression, BinaryOperator::Or, self.and_expression()?);
+        }
+        Ok(expression)
+    }
+
+    fn and_expression(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.not_expression()?;
+        while self.consume_keyword("AND") {
+            expression = binary(expression, BinaryOperator::And, self.not_expression()?);
+        }
+        Ok(expression)
+    }
+
+    fn not_expression(&mut self) -> Result<Expression, ParseError> {
+        if self.consume_keyword("NOT") {
+            return Ok(Expression::Unary {
+                operator: UnaryOperator::Not,
+                operand: Box::new(self.not_expression()?),
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expression, ParseError> {
+        let expression = self.additive()?;
+        if self.consume_keyword("IS") {
+            let negated = self.consume_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expression::IsNull {
+                operand: Box::new(expression),
+                negated,
+            });
+        }
+        let operator = match self.peek().kind {
+            TokenKind::Equal => BinaryOperator::Equal,
+            TokenKind::NotEqual => BinaryOperator::NotEqual,
+            TokenKind::Less => BinaryOperator::Less,
+            TokenKind::LessEqual => BinaryOperator::LessEqual,
+            TokenKind::Greater => BinaryOperator::Greater,
+            TokenKind::GreaterEqual => BinaryOperator::GreaterEqual,
+            _ => return Ok(expression),
+        };
+        self.next();
+        Ok(binary(expression, operator, self.additive()?))
+    }
+
+    fn additive(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.multiplicative()?;
+        loop {
+            let operator = match self.peek().kind {
+                TokenKind::Plus => BinaryOperator::Plus,
+                TokenKind::Minus => BinaryOperator::Minus,
+                _ => return Ok(expression),
+            };
+            self.next();
+            expression = binary(expression, operator, self.multiplicative()?);
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.unary()?;
+        loop {
+            let operator = match self.peek().kind {
+                TokenKind::Asterisk => BinaryOperator::Multiply,
+                TokenKind::Slash => BinaryOperator::Divide,
+                TokenKind::Percent => BinaryOperator::Modulo,
+                _ => return Ok(expression),
+            };
+            self.next();
+            expression = binary(expression, operator, self.unary()?);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, ParseError> {
+        if self.consume(&TokenKind::Minus) {
+            // Fold the sign into numeric literals, so that i64::MIN can be written.
+            if let TokenKind::Integer(number) = &self.peek().kind {
+                if let Ok(number) = format!("-{}", number).parse() {
+                    self.next();
+                    return Ok(Expression::Literal(Literal::Integer(number)));
+                }
+            }
+            return Ok(Expression::Unary {
+                operator: UnaryOperator::Minus,
+                operand: Box::new(self.unary()?),
+            });
+        }
+        if self.consume(&TokenKind::Plus) {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        let token = self.peek().clone();
+        match &token.kind {
+            TokenKind::Integer(number) => {
+                self.next();
+                let number = number.parse().map_err(|_| {
+                    ParseError::new("The integer is too large", token.line, token.column)
+                })?;
+                Ok(Expression::Literal(Literal::Integer(number)))
+            }
+            TokenKind::Float(number) => {
+                self.next();
+                Ok(Expression::Literal(Literal::Float(number.parse().unwrap())))
+            }
+            TokenKind::String(string) => {
+                self.next();
+                Ok(Expression::Literal(Literal::String(string.clone())))
+            }
+            TokenKind::LeftParenthesis => {
+                self.next();
+                let expression = self.expression()?;
+                self.expect(TokenKind::RightParenthesis)?;
+                Ok(expression)
+            }
+            TokenKind::Word(word) => match word.to_uppercase().as_str() {
+                "NULL" => {
+                    self.next();
+                    Ok(Expression::Literal(Literal::Null))
+                }
+                "TRUE" | "FALSE" => {
+                    self.next();
+                    Ok(Expression::Literal(Literal::Boolean(
+                        word.eq_ignore_ascii_case("TRUE"),
+                    )))
+                }
+                "CAST" => {
+                    self.next();
+                    self.expect(TokenKind::LeftParenthesis)?;
+                    let operand = self.expression()?;
+                    self.expect_keyword("AS")?;
+                    let data_type = self.data_type()?;
+                    self.expect(TokenKind::RightParenthesis)?;
+                    Ok(Expression::Cast {
+                        operand: Box::new(operand),
+                        data_type,
+                    })
+                }
+                // Typed literals like DATE '2020-01-01' are casts of string literals.
+                "DATE" | "TIMESTAMP" if matches!(self.peek_nth(1).kind, TokenKind::String(_)) => {
+                    let data_type = self.data_type()?;
+                    let operand = self.primary()?;
+                    Ok(Expression::Cast {
+                        operand: Box::new(operand),
+                        data_type,
+                    })
+                }
+                _ => self.column_or_function(),
+            },
+            TokenKind::QuotedIdentifier(_) => self.column_or_function(),
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    fn column_or_function(&mut self) -> Result<Expression, ParseError> {
+        let name = self.identifier()?;
+        if self.consume(&TokenKind::Dot) {
+            return Ok(Expression::Column {
+                table: Some(name),
+                name: self.identifier()?,
+            });
+        }
+        if !self.consume(&TokenKind::LeftParenthesis) {
+            return Ok(Expression::Column { table: None, name });
+        }
+
+        let distinct = self.consume_keyword("DISTINCT");
+        let mut arguments = Vec::new();
+        if self.consume(&TokenKind::Asterisk) {
+            arguments.push(Expression::Wildcard);
+        } else if self.peek().kind != TokenKind::RightParenthesis {
+            arguments.push(self.expression()?);
+            while self.consume(&TokenKind::Comma) {
+                arguments.push(self.expression()?);
+            }
+        }
+        self.expect(TokenKind::RightParenthesis)?;
+        Ok(Expression::Function {
+            name,
+            arguments,
+            distinct,
+        })
+    }
+}
+
+fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Expression {
+    Expression::Binary {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    }
+}
+
+fn is_reserved(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+fn symbol(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::LeftParenthesis => "(",
+        TokenKind::RightParenthesis => ")",
+        TokenKind::Comma => ",",
+        TokenKind::Semicolon => ";",
+        TokenKind::Dot => ".",
+        TokenKind::Asterisk => "*",
+        TokenKind::Plus => "+",
+        TokenKind::Minus => "-",
+        TokenKind::Slash => "/",
+        TokenKind::Percent => "%",
+        TokenKind::Equal => "=",
+        TokenKind::NotEqual => "<>",
+        TokenKind::Less => "<",
+        TokenKind::LessEqual => "<=",
+        TokenKind::Greater => ">",
+        TokenKind::GreaterEqual => ">=",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::{
+        catalog::system_catalog::IndexType,
+        sql::ast::{
+            BinaryOperator, ColumnDefinition, CreateIndex, CreateTable, Expression, JoinType,
+            Literal, SelectItem, Statement, TableReference, Update,
+        },
+        transaction::transaction_state::IsolationLevel,
+        types::schema::DataType,
+    };
+
+    use super::{parse, parse_statement};
+
+    fn column(name: &str) -> Expression {
+        Expression::Column {
+            table: None,
+            name: name.to_string(),
+        }
+    }
+
+    fn integer(value: i64) -> Expression {
+        Expression::Literal(Literal::Integer(value))
+    }
+
+    fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Expression {
+        super::binary(left, operator, right)
+    }
+
+    #[test]
+    fn parse_ddl() {
+        let statements = parse(
+            "CREATE TABLE users (id INTEGER NOT NULL, name VARCHAR(20), born date);
+             CREATE UNIQUE INDEX users_id ON users USING HASH (id);
+             drop table if exists users",
+        )
+        .unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                Statement::CreateTable(CreateTable {
+                    name: "users".to_string(),
+                    columns: vec![
+                        ColumnDefinition {
+                            name: "id".to_string(),
+                            data_type: DataType::Integer,
+                            nullable: false,
+                        },
+                        ColumnDefinition {
+                            name: "name".to_string(),
+                            data_type: DataType::Varchar(20),
+                            nullable: true,
+                        },
+                        ColumnDefinition {
+                            name: "born".to_string(),
+                            data_type: DataType::Date,
+                            nullable: true,
+                        },
+                    ],
+                }),
+                Statement::CreateIndex(CreateIndex {
+                    name: "users_id".to_string(),
+                    table: "users".to_string(),
+                    columns: vec!["id".to_string()],
+                    unique: true,
+                    index_type: IndexType::Hash,
+                }),
+                Statement::DropTable {
+                    name: "users".to_string(),
+                    if_exists: true,
+                },
+            ]
+        );
+        assert_eq!(
+            parse("ANALYZE; analyze users").unwrap(),
+            vec![
+                Statement::Analyze { table: None },
+                Statement::Analyze {
+                    table: Some("users".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            parse("VACUUM; vacuum users").unwrap(),
+            vec![
+                Statement::Vacuum { table: None },
+                Statement::Vacuum {
+                    table: Some("users".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dml() {
+        let insert = parse_statement("INSERT INTO t (a, b) VALUES (1, 'x'), (-2, NULL)").unwrap();
+        match insert {
+            Statement::Insert(insert) => {
+                assert_eq!(insert.columns.unwrap().len(), 2);
+                assert_eq!(insert.rows[1][0], integer(-2));
+                assert_eq!(insert.rows[1][1], Expression::Literal(Literal::Null));
+            }
+            _ => panic!("Expected an INSERT statement"),
+        }
+
+        assert_eq!(
+            parse_statement("UPDATE t SET a = a + 1 WHERE b IS NOT NULL").unwrap(),
+            Statement::Update(Update {
+                table: "t".to_string(),
+                assignments: vec![(
+                    "a".to_string(),
+                    binary(column("a"), BinaryOperator::Plus, integer(1))
+                )],
+                where_clause: Some(Expression::IsNull {
+                    operand: Box::new(column("b")),
+                    negated: true,
+                }),
+            })
+        );
+        assert!(matches!(
+            parse_statement("DELETE FROM t").unwrap(),
+            Statement::Delete(delete) if delete.where_clause.is_none()
+        ));
+        assert!(matches!(
+            parse_statement("EXPLAIN ANALYZE DELETE FROM t").unwrap(),
+            Statement::Explain { statement, analyze: true }
+                if matches!(*statement, Statement::Delete(_))
+        ));
+        assert!(matches!(
+            parse_statement("explain SELECT 1").unwrap(),
+            Statement::Explain { analyze: false, .. }
+        ));
+    }
+
+    #[test]
+    fn parse_transaction_statements() {
+        assert_eq!(
+            parse(
+                "BEGIN; begin transaction; START TRANSACTION; COMMIT; commit work;
+                 ROLLBACK; ROLLBACK TRANSACTION"
+            )
+            .unwrap(),
+            vec![
+                Statement::Begin {
+                    isolation_level: None
+                },
+                Statement::Begin {
+                    isolation_level: None
+                },
+                Statement::Begin {
+                    isolation_level: None
+                },
+                Statement::Commit,
+                Statement::Commit,
+                Statement::Rollback,
+                Statement::Rollback,
+            ]
+        );
+        assert!(parse_statement("START").is_err());
+        assert!(parse_statement("COMMIT users").is_err());
+    }
+
+    #[test]
+    fn parse_isolation_levels() {
+        let begin = |isolation_level| Statement::Begin {
+            isolation_level: Some(isolation_level),
+        };
+        assert_eq!(
+            parse(
+                "BEGIN ISOLATION LEVEL SERIALIZABLE;
+                 START TRANSACTION ISOLATION LEVEL REPEATABLE READ;
+                 begin transaction isolation level snapshot"
+            )
+            .unwrap(),
+            vec![
+                begin(IsolationLevel::Serializable),
+                begin(IsolationLevel::SnapshotIsolation),
+                begin(IsolationLevel::SnapshotIsolation),
+            ]
+        );
+        assert!(parse_statement("BEGIN ISOLATION LEVEL READ UNCOMMITTED").is_err());
+        assert!(parse_statement("BEGIN ISOLATION SERIALIZABLE").is_err());
+    }
+
+    #[test]
+    fn parse_select() {
+        let statement = parse_statement(
+            "SELECT u.*, count(DISTINCT o.id) AS orders, 2 * 3 + 1 total
+             FROM users u LEFT JOIN orders o ON u.id = o.user_id
+             WHERE NOT u.id = 1 OR u.id < 10 AND u.name <> 'bob'
+             ORDER BY u.name DESC, 2 LIMIT 10 OFFSET 5;",
+        )
+        .unwrap();
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => panic!("Expected a SELECT statement"),
+        };
+
+        assert_eq!(
+            select.projection[0],
+            SelectItem::QualifiedWildcard("u".to_string())
+        );
+        assert!(matches!(
+            &select.projection[1],
+            SelectItem::Expression {
+                expression: Expression::Function { distinct: true, .. },
+                alias: Some(alias),
+            } if alias == "orders"
+        ));
+        assert_eq!(
+            select.projection[2],
+            SelectItem::Expression {
+                expression: binary(
+                    binary(integer(2), BinaryOperator::Multiply, integer(3)),
+                    BinaryOperator::Plus,
+                    integer(1)
+                ),
+                alias: Some("total".to_string()),
+            }
+        );
+        assert!(matches!(
+            &select.from[0],
+            TableReference::Join {
+                join_type: JoinType::Left,
+                condition: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            select.where_clause,
+            Some(Expression::Binary {
+                operator: BinaryOperator::Or,
+                ..
+            })
+        ));
+        assert!(!select.order_by[0].ascending);
+        assert!(select.order_by[1].ascending);
+        assert_eq!((select.limit, select.offset), (Some(10), Some(5)));
+
+        match parse_statement(
+            "SELECT name, count(*) FROM t GROUP BY name, age + 1 HAVING sum(age) > 10",
+        )
+        .unwrap()
+        {
+            Statement::Select(select) => {
+                assert_eq!(select.group_by[0], column("name"));
+                assert_eq!(
+                    select.group_by[1],
+                    binary(column("age"), BinaryOperator::Plus, integer(1))
+                );
+                assert!(matches!(
+                    select.having,
+                    Some(Expression::Binary {
+                        operator: BinaryOperator::Greater,
+                        ..
+                    })
+                ));
+            }
+            _ => panic!("Expected a SELECT statement"),
+        }
+
+        let join_types = |sql| match parse_statement(sql).unwrap() {
+            Statement::Select(select) => match &select.from[0] {
+                TableReference::Join {
+                    left, join_type, ..
+                } => match left.as_ref() {
+                    TableReference::Join {
+                        join_type: inner, ..
+                    } => vec![*inner, *join_type],
+                    _ => vec![*join_type],
+                },
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        assert_eq!(
+            join_types(
+                "SELECT * FROM a LEFT SEMI JOIN b ON a.x = b.x LEFT ANTI JOIN c ON a.x = c.x"
+            ),
+            vec![JoinType::Semi, JoinType::Anti]
+        );
+        assert_eq!(
+            join_types("SELECT * FROM a FULL OUTER JOIN b ON true CROSS JOIN c"),
+            vec![JoinType::Full, JoinType::Cross]
+        );
+    }
+
+    #[test]
+    fn errors_point_to_line_and_column() {
+        let error = parse("SELECT a\nFROM t\nWHERE a = ;").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 11));
+        assert_eq!(error.message, "Expected an expression, found ';'");
+
+        let error = parse_statement("CREATE TABLE t (a INTEGR)").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 19));
+
+        let error = parse_statement("SELECT * FROM select").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expected an identifier, found keyword SELECT at line 1, column 15"
+        );
+
+        let error = parse("SELECT 1 SELECT 2").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 10));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod overflow_page;
 pub mod table_directory_page;
+pub mod table_heap;
+pub mod table_iterator;
 pub mod table_page;
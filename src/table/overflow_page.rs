@@ -0,0 +1,231 @@
+use std::sync::MutexGuard;
+
+use crate::{
+    disk_management::buffer_pool::{BufferPool, RawPage, PAGE_SIZE},
+    recovery::log_record::TransactionId,
+};
+
+// PAGE FORMAT:
+// | OWN_PID [u32] | NEXT_PAGE [u32] | DATA_LENGTH [u16] | DATA ... |
+//
+// Tuples that are too large for a table page are split into a chain of overflow pages. The table
+// page only stores an overflow reference:
+// | TUPLE_LENGTH [u32] | FIRST_OVERFLOW_PAGE [u32] |
+//
+// The overflow pages of removed or updated tuples are linked into a list of free overflow pages
+// through NEXT_PAGE, and are reused by later chains before new pages are allocated.
+
+const OVERFLOW_PAGE_HEADER_SIZE: usize = 10;
+pub const OVERFLOW_PAGE_CAPACITY: usize = PAGE_SIZE - OVERFLOW_PAGE_HEADER_SIZE;
+
+#[derive(Debug, PartialEq)]
+pub struct OverflowPage {
+    own_pid: u32,
+    next_page: u32,
+    data: Vec<u8>,
+}
+
+impl OverflowPage {
+    pub fn new(own_pid: u32, next_page: u32, data: Vec<u8>) -> OverflowPage {
+        OverflowPage {
+            own_pid,
+            next_page,
+            data,
+        }
+    }
+
+    pub fn from_raw_page(raw_page: &RawPage) -> Result<OverflowPage, &str> {
+        let bytes = raw_page.data.read().unwrap();
+        let own_pid = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let next_page = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let data_length = u16::from_le_bytes(bytes[8..10].try_into().unwrap()) as usize;
+        if data_length > OVERFLOW_PAGE_CAPACITY {
+            return Err("Malformed overflow page");
+        }
+        let data =
+            bytes[OVERFLOW_PAGE_HEADER_SIZE..OVERFLOW_PAGE_HEADER_SIZE + data_length].to_vec();
+        Ok(OverflowPage::new(own_pid, next_page, data))
+    }
+
+    pub fn to_raw_page(&self) -> RawPage {
+        let mut bytes = [0; PAGE_SIZE];
+        bytes[0..4].copy_from_slice(&self.own_pid.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.next_page.to_le_bytes());
+        bytes[8..10].copy_from_slice(&(self.data.len() as u16).to_le_bytes());
+        bytes[OVERFLOW_PAGE_HEADER_SIZE..OVERFLOW_PAGE_HEADER_SIZE + self.data.len()]
+            .copy_from_slice(&self.data);
+        RawPage::new(bytes)
+    }
+}
+
+/// Writes the data into a new chain of overflow pages and returns the overflow reference that
+/// has to be stored in the table page. Pages are taken from the list of free overflow pages
+/// starting at `free_pages` first, which is advanced past the taken pages.
+pub fn write_overflow_chain(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    data: &[u8],
+    free_pages: &mut u32,
+    transaction: Option<TransactionId>,
+) -> Result<Vec<u8>, &'static str> {
+    let mut page_ids = Vec::new();
+    for _ in 0..data.len().div_ceil(OVERFLOW_PAGE_CAPACITY) {
+        let page_id = match *free_pages {
+            0 => buffer_pool_lock
+                .allocate_new_page()
+                .or(Err("Could not allocate an overflow page"))? as u32,
+            free_page => {
+                *free_pages = load_overflow_page(buffer_pool_lock, free_page)?.next_page;
+                free_page
+            }
+        };
+        page_ids.push(page_id);
+    }
+
+    for (i, chunk) in data.chunks(OVERFLOW_PAGE_CAPACITY).enumerate() {
+        let next_page = page_ids.get(i + 1).copied().unwrap_or(0);
+        let overflow_page = OverflowPage::new(page_ids[i], next_page, chunk.to_vec());
+        store_overflow_page(buffer_pool_lock, &overflow_page, transaction)?;
+    }
+
+    let mut reference = (data.len() as u32).to_le_bytes().to_vec();
+    reference.extend(page_ids.first().copied().unwrap_or(0).to_le_bytes());
+    Ok(reference)
+}
+
+/// Reassembles the data referenced by an overflow reference.
+pub fn read_overflow_chain(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    reference: &[u8],
+) -> Result<Vec<u8>, &'static str> {
+    let mut data = Vec::new();
+    for overflow_page in read_chain(buffer_pool_lock, reference)? {
+        data.extend(overflow_page.data);
+    }
+    Ok(data)
+}
+
+/// Adds the overflow pages of the chain referenced by an overflow reference to the front of the
+/// list of free overflow pages starting at `free_pages`.
+pub fn free_overflow_chain(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    reference: &[u8],
+    free_pages: &mut u32,
+    transaction: Option<TransactionId>,
+) -> Result<(), &'static str> {
+    let chain = read_chain(buffer_pool_lock, reference)?;
+    let (Some(first_page), Some(last_page)) = (chain.first(), chain.last()) else {
+        return Ok(());
+    };
+    let first_page_id = first_page.own_pid;
+    let last_page = OverflowPage::new(last_page.own_pid, *free_pages, Vec::new());
+    store_overflow_page(buffer_pool_lock, &last_page, transaction)?;
+    *free_pages = first_page_id;
+    Ok(())
+}
+
+/// Reads the pages of the chain referenced by an overflow reference. A chain is never longer
+/// than the number of pages its data needs, so that a corrupted chain cannot cycle forever.
+fn read_chain(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    reference: &[u8],
+) -> Result<Vec<OverflowPage>, &'static str> {
+    let length = u32::from_le_bytes(reference[0..4].try_into().unwrap()) as usize;
+    let mut next_page = u32::from_le_bytes(reference[4..8].try_into().unwrap());
+
+    let page_count = length.div_ceil(OVERFLOW_PAGE_CAPACITY);
+    let mut chain = Vec::with_capacity(page_count);
+    let mut data_length = 0;
+    for _ in 0..page_count {
+        if next_page == 0 {
+            return Err("The overflow chain ends before its data");
+        }
+        let overflow_page = load_overflow_page(buffer_pool_lock, next_page)?;
+        if overflow_page.own_pid != next_page {
+            return Err("The overflow chain references a page that is not part of it");
+        }
+        next_page = overflow_page.next_page;
+        data_length += overflow_page.data.len();
+        chain.push(overflow_page);
+    }
+    if next_page != 0 || data_length != length {
+        return Err("The length of the overflow chain does not match its data");
+    }
+    Ok(chain)
+}
+
+fn load_overflow_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: u32,
+) -> Result<OverflowPage, &'static str> {
+    let frame_id = buffer_pool_lock
+        .load_page(page_id as usize)
+        .or(Err("Could not load the overflow page"))?;
+    let overflow_page =
+        OverflowPage::from_raw_page(buffer_pool_lock.get_raw_page(frame_id).unwrap())
+            .map_err(|_| "Malformed overflow page");
+    buffer_pool_lock
+        .unload_page_id(page_id as usize)
+        .expect("Could not unload the overflow page");
+    overflow_page
+}
+
+fn store_overflow_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    overflow_page: &OverflowPage,
+    transaction: Option<TransactionId>,
+) -> Result<(), &'static str> {
+    let page_id = overflow_page.own_pid as usize;
+    buffer_pool_lock
+        .load_page(page_id)
+        .or(Err("Could not load the overflow page"))?;
+    buffer_pool_lock
+        .set_page_data(page_id, overflow_page.to_raw_page(), transaction)
+        .expect("Could not update the overflow page");
+    buffer_pool_lock
+        .unload_page_id(page_id)
+        .expect("Could not unload the overflow page");
+    Ok(())
+}
+
+#[test]
+fn raw_page_round_trip() {
+    let overflow_page = OverflowPage::new(3, 4, vec![1, 2, 3]);
+    let raw_page = overflow_page.to_raw_page();
+    assert_eq!(
+        raw_page.data.read().unwrap()[0..13],
+        [3, 0, 0, 0, 4, 0, 0, 0, 3, 0, 1, 2, 3]
+    );
+    assert_eq!(
+        OverflowPage::from_raw_page(&raw_page).unwrap(),
+        overflow_page
+    );
+}
+
+#[test]
+fn corrupted_chains_are_detected() {
+    let buffer_pool = crate::disk_management::test_utils::new_test_buffer_pool("overflow_cycle");
+    let mut buffer_pool_lock = buffer_pool.lock().unwrap();
+    // Page 0 is never part of a chain.
+    buffer_pool_lock.allocate_new_page().unwrap();
+    let data = vec![7; 3 * OVERFLOW_PAGE_CAPACITY];
+    let mut free_pages = 0;
+    let reference =
+        write_overflow_chain(&mut buffer_pool_lock, &data, &mut free_pages, None).unwrap();
+    assert_eq!(
+        read_overflow_chain(&mut buffer_pool_lock, &reference).unwrap(),
+        data
+    );
+
+    // The second page points back to the first one.
+    let first_page = u32::from_le_bytes(reference[4..8].try_into().unwrap());
+    let second_page = load_overflow_page(&mut buffer_pool_lock, first_page)
+        .unwrap()
+        .next_page;
+    let cycle = OverflowPage::new(second_page, first_page, vec![7; OVERFLOW_PAGE_CAPACITY]);
+    store_overflow_page(&mut buffer_pool_lock, &cycle, None).unwrap();
+    assert!(read_overflow_chain(&mut buffer_pool_lock, &reference).is_err());
+
+    let truncated = OverflowPage::new(second_page, 0, vec![7; OVERFLOW_PAGE_CAPACITY]);
+    store_overflow_page(&mut buffer_pool_lock, &truncated, None).unwrap();
+    assert!(read_overflow_chain(&mut buffer_pool_lock, &reference).is_err());
+}
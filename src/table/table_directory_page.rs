@@ -7,9 +7,17 @@ use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
 // ------------------------------------------------------------------------------------------------
 //
 // HEADER [16 bytes]:
-// --------------------------------------------------------------------------------
-// | SELF PAGE_ID [u32] | LSN [u32] | PREV_DIRECTORY [u32] | NEXT_DIRECTORY [u32] |
-// --------------------------------------------------------------------------------
+// ------------------------------------------------------------------------------------------
+// | SELF PAGE_ID [u32] | PREV_DIRECTORY [u32] | NEXT_DIRECTORY [u32] | FREE_OVERFLOW [u32] |
+// ------------------------------------------------------------------------------------------
+//
+// CAPACITY is the free space of the table page in units of CAPACITY_UNIT bytes, rounded down.
+// Entries with a PAGE_ID of 0 are unused, as is a PREV_DIRECTORY or NEXT_DIRECTORY of 0.
+// FREE_OVERFLOW is only used in the first directory page of a table heap. It is the first page of
+// the list of overflow pages that were freed and can be reused, or 0 if there is none.
+
+pub const CAPACITY_UNIT: usize = PAGE_SIZE / 256;
+const ENTRY_COUNT: usize = (PAGE_SIZE - 16) / 5;
 
 #[derive(bincode::Encode, bincode::Decode, Copy, Clone, Debug)]
 struct DirectoryEntry {
@@ -20,20 +28,33 @@ struct DirectoryEntry {
 #[derive(bincode::Encode, bincode::Decode, Debug)]
 pub struct TableDirectoryPage {
     own_pid: u32,
-    lsn: u32,
     prev_directory: u32,
     next_directory: u32,
-    entries: [DirectoryEntry; (PAGE_SIZE - 16) / 5],
+    free_overflow_page: u32,
+    entries: [DirectoryEntry; ENTRY_COUNT],
 }
 
 impl TableDirectoryPage {
+    pub fn new_empty(own_pid: u32, prev_directory: u32) -> TableDirectoryPage {
+        TableDirectoryPage {
+            own_pid,
+            prev_directory,
+            next_directory: 0,
+            free_overflow_page: 0,
+            entries: [DirectoryEntry {
+                capacity: 0,
+                page_id: 0,
+            }; ENTRY_COUNT],
+        }
+    }
+
     pub fn from_raw_page(raw_page: &RawPage) -> Result<TableDirectoryPage, &str> {
         let bincode_config = bincode::config::standard()
             .with_fixed_int_encoding()
             .skip_fixed_array_length();
 
         let data = raw_page.data.read().unwrap();
-        let res = bincode::decode_from_slice(&data.as_slice(), bincode_config);
+        let res = bincode::decode_from_slice(data.as_slice(), bincode_config);
         let res = res.expect("Could not build Table Directory").0;
         Ok(res)
     }
@@ -46,19 +67,88 @@ impl TableDirectoryPage {
             .expect("Unexpected error while creating raw page");
         RawPage::new(slice)
     }
+
+    /// Returns the page id of the next directory page, if there is one.
+    pub fn get_next_directory(&self) -> Option<u32> {
+        match self.next_directory {
+            0 => None,
+            next_directory => Some(next_directory),
+        }
+    }
+
+    pub fn set_next_directory(&mut self, next_directory: u32) {
+        self.next_directory = next_directory;
+    }
+
+    /// Returns the first page of the list of free overflow pages, or 0 if the list is empty.
+    pub fn get_free_overflow_page(&self) -> u32 {
+        self.free_overflow_page
+    }
+
+    pub fn set_free_overflow_page(&mut self, free_overflow_page: u32) {
+        self.free_overflow_page = free_overflow_page;
+    }
+
+    /// Returns the page ids of all table pages registered in this directory page.
+    pub fn get_page_ids(&self) -> Vec<u32> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.page_id != 0)
+            .map(|entry| entry.page_id)
+            .collect()
+    }
+
+    /// Returns the id of a table page with at least the given amount of free bytes.
+    pub fn find_page_with_free_space(&self, free_space: usize) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|entry| {
+                entry.page_id != 0 && entry.capacity as usize * CAPACITY_UNIT >= free_space
+            })
+            .map(|entry| entry.page_id)
+    }
+
+    /// Registers a new table page with the given amount of free bytes.
+    pub fn add_page(&mut self, page_id: u32, free_space: usize) -> Result<(), &str> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.page_id == 0)
+            .ok_or("The directory page is full")?;
+        *entry = DirectoryEntry {
+            capacity: to_capacity(free_space),
+            page_id,
+        };
+        Ok(())
+    }
+
+    /// Updates the free space of a registered table page. Returns an error if the page is not
+    /// registered in this directory page.
+    pub fn set_free_space(&mut self, page_id: u32, free_space: usize) -> Result<(), &str> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.page_id == page_id)
+            .ok_or("The page is not part of this directory page")?;
+        entry.capacity = to_capacity(free_space);
+        Ok(())
+    }
+}
+
+fn to_capacity(free_space: usize) -> u8 {
+    (free_space / CAPACITY_UNIT).min(u8::MAX as usize) as u8
 }
 
 #[test]
 fn from_raw_page_test() {
     let mut raw_page_content = [0; 4096];
     // PID: 12
-    [12, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0].swap_with_slice(&mut raw_page_content[0..16]);
+    [12, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0].swap_with_slice(&mut raw_page_content[0..12]);
     raw_page_content[4095] = 34;
 
     let raw_page = RawPage::new(raw_page_content);
     let tuple_page = TableDirectoryPage::from_raw_page(&raw_page).expect("expect to build page");
     assert_eq!(tuple_page.own_pid, 12);
-    assert_eq!(tuple_page.lsn, 1);
     assert_eq!(tuple_page.prev_directory, 0);
 
     assert_eq!(tuple_page.next_directory, 1);
@@ -68,18 +158,34 @@ fn from_raw_page_test() {
 fn to_raw_page() {
     let directory_page = TableDirectoryPage {
         own_pid: 20,
-        lsn: 512,
         prev_directory: 124,
         next_directory: 125,
+        free_overflow_page: 7,
         entries: [DirectoryEntry {
             capacity: 0,
             page_id: 0,
-        }; (PAGE_SIZE - 16) / 5],
+        }; ENTRY_COUNT],
     };
 
     let mut expected = [0_u8; PAGE_SIZE];
-    [20, 0, 0, 0, 0, 2, 0, 0, 124, 0, 0, 0, 125, 0, 0, 0].swap_with_slice(&mut expected[0..16]);
+    [20, 0, 0, 0, 124, 0, 0, 0, 125, 0, 0, 0, 7, 0, 0, 0].swap_with_slice(&mut expected[0..16]);
     let actual = directory_page.to_raw_page();
     let actual_data = actual.data.read().unwrap();
     assert!(actual_data.eq(&expected));
 }
+
+#[test]
+fn find_page_with_free_space() {
+    let mut directory_page = TableDirectoryPage::new_empty(1, 0);
+    directory_page.add_page(2, 100).unwrap();
+    directory_page.add_page(3, 4000).unwrap();
+
+    assert_eq!(directory_page.find_page_with_free_space(96), Some(2));
+    assert_eq!(directory_page.find_page_with_free_space(100), Some(3));
+    assert_eq!(directory_page.find_page_with_free_space(4096), None);
+
+    directory_page.set_free_space(3, 0).unwrap();
+    assert_eq!(directory_page.find_page_with_free_space(100), None);
+    assert!(directory_page.set_free_space(4, 0).is_err());
+    assert_eq!(directory_page.get_page_ids(), vec![2, 3]);
+}
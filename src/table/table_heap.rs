@@ -0,0 +1,1140 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    disk_management::buffer_pool::{BufferPool, RawPage},
+    recovery::log_record::{LogicalChange, TransactionId},
+};
+
+use super::{
+    overflow_page::{free_overflow_chain, read_overflow_chain, write_overflow_chain},
+    table_directory_page::TableDirectoryPage,
+    table_iterator::TableIterator,
+    table_page::{Rid, TablePage, Tuple, TupleVersion, MAX_TUPLE_SIZE, TUPLE_HEADER_SIZE},
+};
+
+/// A table heap stores the tuples of a single table in table pages. All table pages are
+/// registered in a doubly linked list of directory pages, starting at `first_directory_page_id`.
+///
+/// The changes of tuples made for a transaction are logged as logical changes after their page
+/// writes, while the buffer pool stays locked, so that recovery can undo them without undoing
+/// the later changes of other transactions to the same pages.
+pub struct TableHeap {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    pub first_directory_page_id: u32,
+}
+
+impl TableHeap {
+    /// Opens an existing table heap.
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>, first_directory_page_id: u32) -> TableHeap {
+        TableHeap {
+            buffer_pool,
+            first_directory_page_id,
+        }
+    }
+
+    /// Allocates the first directory page of a new, empty table heap.
+    pub fn setup_new_table_heap(
+        buffer_pool: Arc<Mutex<BufferPool>>,
+        transaction: Option<TransactionId>,
+    ) -> Result<TableHeap, &'static str> {
+        let mut buffer_pool_lock = buffer_pool.lock().expect("could not lock buffer_pool");
+        let (directory_page_id, _) = buffer_pool_lock
+            .load_new_page()
+            .or(Err("Could not load a new page"))?;
+        let directory_page = TableDirectoryPage::new_empty(directory_page_id as u32, 0);
+        store_page(
+            &mut buffer_pool_lock,
+            directory_page_id as u32,
+            directory_page.to_raw_page(),
+            transaction,
+        );
+        drop(buffer_pool_lock);
+
+        Ok(TableHeap::new(buffer_pool, directory_page_id as u32))
+    }
+
+    /// Inserts the data into a table page with enough free space and returns the Rid of the new
+    /// tuple. A new table page is allocated if no such page exists. Data that is too large for a
+    /// table page is stored in overflow pages.
+    pub fn insert(
+        &self,
+        tuple_data: Vec<u8>,
+        transaction: Option<TransactionId>,
+    ) -> Result<Rid, &'static str> {
+        self.insert_version(tuple_data, TupleVersion::default(), transaction)
+    }
+
+    /// Inserts the data like [TableHeap::insert] as a tuple with the given version.
+    pub fn insert_version(
+        &self,
+        tuple_data: Vec<u8>,
+        version: TupleVersion,
+        transaction: Option<TransactionId>,
+    ) -> Result<Rid, &'static str> {
+        if tuple_data.len() > u32::MAX as usize {
+            return Err("The tuple is too large to be stored");
+        }
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let undo_next_lsn = buffer_pool_lock.last_lsn(transaction);
+        let stored_tuple = self.to_stored_tuple(&mut buffer_pool_lock, tuple_data, transaction)?;
+        let overflow_reference = stored_tuple.overflow.then(|| stored_tuple.data.clone());
+        let rid =
+            match self.insert_with_lock(&mut buffer_pool_lock, stored_tuple, false, transaction) {
+                Ok(rid) => rid,
+                Err(error) => {
+                    if let Some(reference) = overflow_reference {
+                        self.free_overflow(&mut buffer_pool_lock, &reference, transaction)?;
+                    }
+                    return Err(error);
+                }
+            };
+        if version != TupleVersion::default() {
+            set_version(&mut buffer_pool_lock, rid, version, transaction)?;
+        }
+        buffer_pool_lock.log_logical_change(
+            transaction,
+            LogicalChange::TupleInserted {
+                first_directory_page_id: self.first_directory_page_id,
+                rid,
+                version,
+            },
+            undo_next_lsn,
+        );
+        Ok(rid)
+    }
+
+    fn insert_with_lock(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        stored_tuple: StoredTuple,
+        relocated: bool,
+        transaction: Option<TransactionId>,
+    ) -> Result<Rid, &'static str> {
+        let required_space = stored_tuple.data.len() + TUPLE_HEADER_SIZE as usize;
+        let page_id = match self.find_page_with_free_space(buffer_pool_lock, required_space) {
+            Some(page_id) => page_id,
+            None => self.allocate_table_page(buffer_pool_lock, transaction)?,
+        };
+
+        let mut table_page = load_table_page(buffer_pool_lock, page_id);
+        let rid = table_page
+            .insert(stored_tuple.data)
+            .expect("The table page does not have the free space listed in the directory");
+        if relocated {
+            table_page.set_relocated(rid.slot_id as usize).unwrap();
+        }
+        table_page
+            .set_overflow(rid.slot_id as usize, stored_tuple.overflow)
+            .unwrap();
+        let free_space = table_page.available_space() as usize;
+        store_page(
+            buffer_pool_lock,
+            page_id,
+            table_page.to_raw_page(),
+            transaction,
+        );
+        self.set_free_space(buffer_pool_lock, page_id, free_space, transaction)?;
+        Ok(rid)
+    }
+
+    /// Returns the tuple with the given Rid, if it exists. Fails if the tuple cannot be read.
+    pub fn get(&self, rid: Rid) -> Result<Option<Tuple>, &'static str> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        read_tuple(&mut buffer_pool_lock, rid)
+    }
+
+    /// Sets the version of the tuple with the given Rid.
+    pub fn set_version(
+        &self,
+        rid: Rid,
+        version: TupleVersion,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let undo_next_lsn = buffer_pool_lock.last_lsn(transaction);
+        let before = set_version(&mut buffer_pool_lock, rid, version, transaction)?;
+        buffer_pool_lock.log_logical_change(
+            transaction,
+            LogicalChange::TupleVersionChanged {
+                first_directory_page_id: self.first_directory_page_id,
+                rid,
+                before,
+                after: version,
+            },
+            undo_next_lsn,
+        );
+        Ok(())
+    }
+
+    /// Removes the tuple with the given Rid and returns it. The space of the tuple is reclaimed
+    /// by later inserts into the same page.
+    pub fn delete(&self, rid: Rid, transaction: Option<TransactionId>) -> Option<Tuple> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let undo_next_lsn = buffer_pool_lock.last_lsn(transaction);
+        let tuple = read_tuple(&mut buffer_pool_lock, rid).expect("Could not read the tuple")?;
+        let new_location =
+            load_table_page(&mut buffer_pool_lock, rid.page_id).get_forward(rid.slot_id as usize);
+        buffer_pool_lock
+            .unload_page_id(rid.page_id as usize)
+            .expect("Could not unload the table page");
+
+        self.remove_with_lock(&mut buffer_pool_lock, rid, transaction);
+        if let Some(new_location) = new_location {
+            self.remove_with_lock(&mut buffer_pool_lock, new_location, transaction);
+        }
+        if transaction.is_some() {
+            buffer_pool_lock.log_logical_change(
+                transaction,
+                LogicalChange::TupleRemoved {
+                    first_directory_page_id: self.first_directory_page_id,
+                    rid,
+                    version: tuple.version,
+                    data: tuple.data.clone(),
+                },
+                undo_next_lsn,
+            );
+        }
+        Some(tuple)
+    }
+
+    /// Puts a removed tuple back into its slot with the given version, e.g. when its removal is
+    /// undone. The tuple is relocated if it no longer fits into its page. Fails if the slot
+    /// holds a tuple.
+    pub fn restore(
+        &self,
+        rid: Rid,
+        tuple_data: Vec<u8>,
+        version: TupleVersion,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let slot_id = rid.slot_id as usize;
+        let mut table_page = try_load_table_page(&mut buffer_pool_lock, rid.page_id)?;
+        let occupied = table_page.get_version(slot_id).is_some();
+        buffer_pool_lock
+            .unload_page_id(rid.page_id as usize)
+            .expect("Could not unload the table page");
+        if occupied {
+            return Err("The slot of the tuple is occupied");
+        }
+        let stored_tuple = self.to_stored_tuple(&mut buffer_pool_lock, tuple_data, transaction)?;
+
+        table_page = load_table_page(&mut buffer_pool_lock, rid.page_id);
+        if table_page
+            .insert_at(slot_id, stored_tuple.data.clone())
+            .is_ok()
+        {
+            table_page
+                .set_overflow(slot_id, stored_tuple.overflow)
+                .unwrap();
+        } else {
+            buffer_pool_lock
+                .unload_page_id(rid.page_id as usize)
+                .expect("Could not unload the table page");
+            let new_location =
+                self.insert_with_lock(&mut buffer_pool_lock, stored_tuple, true, transaction)?;
+            table_page = load_table_page(&mut buffer_pool_lock, rid.page_id);
+            let forwarded = table_page.insert_at(slot_id, Vec::new()).is_ok()
+                && table_page.set_forward(slot_id, new_location).is_ok();
+            if !forwarded {
+                buffer_pool_lock
+                    .unload_page_id(rid.page_id as usize)
+                    .expect("Could not unload the table page");
+                self.remove_with_lock(&mut buffer_pool_lock, new_location, transaction);
+                return Err("The page of the tuple has no space for a forwarding pointer");
+            }
+        }
+        table_page.set_version(slot_id, version).unwrap();
+        let free_space = table_page.available_space() as usize;
+        store_page(
+            &mut buffer_pool_lock,
+            rid.page_id,
+            table_page.to_raw_page(),
+            transaction,
+        );
+        self.set_free_space(&mut buffer_pool_lock, rid.page_id, free_space, transaction)
+    }
+
+    fn remove_with_lock(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        rid: Rid,
+        transaction: Option<TransactionId>,
+    ) {
+        let mut table_page = load_table_page(buffer_pool_lock, rid.page_id);
+        let overflow_reference = get_overflow_reference(&table_page, rid.slot_id as usize);
+        table_page.remove(rid.slot_id as usize);
+        let free_space = table_page.available_space() as usize;
+        store_page(
+            buffer_pool_lock,
+            rid.page_id,
+            table_page.to_raw_page(),
+            transaction,
+        );
+        self.set_free_space(buffer_pool_lock, rid.page_id, free_space, transaction)
+            .expect("Could not update the free space of the table page");
+        if let Some(reference) = overflow_reference {
+            self.free_overflow(buffer_pool_lock, &reference, transaction)
+                .expect("Could not free the overflow pages of the tuple");
+        }
+    }
+
+    /// Replaces the data of the tuple with the given Rid. The tuple keeps its Rid: the data is
+    /// updated in place if it fits into the page of the tuple, otherwise the tuple is relocated to
+    /// another page and a forwarding pointer is left behind.
+    pub fn update(
+        &self,
+        rid: Rid,
+        tuple_data: Vec<u8>,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        if tuple_data.len() > u32::MAX as usize {
+            return Err("The tuple is too large to be stored");
+        }
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let undo_next_lsn = buffer_pool_lock.last_lsn(transaction);
+        let before = match transaction {
+            Some(_) => read_tuple(&mut buffer_pool_lock, rid)?,
+            None => None,
+        };
+        let after = before.as_ref().map(|_| tuple_data.clone());
+        self.update_with_lock(&mut buffer_pool_lock, rid, tuple_data, transaction)?;
+        if let (Some(before), Some(after)) = (before, after) {
+            buffer_pool_lock.log_logical_change(
+                transaction,
+                LogicalChange::TupleUpdated {
+                    first_directory_page_id: self.first_directory_page_id,
+                    rid,
+                    before: before.data,
+                    after,
+                },
+                undo_next_lsn,
+            );
+        }
+        Ok(())
+    }
+
+    fn update_with_lock(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        rid: Rid,
+        tuple_data: Vec<u8>,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let slot_id = rid.slot_id as usize;
+        let mut table_page = load_table_page(buffer_pool_lock, rid.page_id);
+        if table_page.get(slot_id).is_none() && table_page.get_forward(slot_id).is_none() {
+            buffer_pool_lock
+                .unload_page_id(rid.page_id as usize)
+                .expect("Could not unload the table page");
+            return Err("The tuple does not exist");
+        }
+        let stored_tuple = self.to_stored_tuple(buffer_pool_lock, tuple_data, transaction)?;
+        let overflow_reference = stored_tuple.overflow.then(|| stored_tuple.data.clone());
+
+        let relocated_to = table_page.get_forward(slot_id);
+        match relocated_to {
+            Some(relocated_to) => {
+                buffer_pool_lock
+                    .unload_page_id(rid.page_id as usize)
+                    .expect("Could not unload the table page");
+                let mut relocated_page = load_table_page(buffer_pool_lock, relocated_to.page_id);
+                let old_reference =
+                    get_overflow_reference(&relocated_page, relocated_to.slot_id as usize);
+                if update_stored_tuple(
+                    &mut relocated_page,
+                    relocated_to.slot_id as usize,
+                    &stored_tuple,
+                ) {
+                    let free_space = relocated_page.available_space() as usize;
+                    store_page(
+                        buffer_pool_lock,
+                        relocated_to.page_id,
+                        relocated_page.to_raw_page(),
+                        transaction,
+                    );
+                    self.set_free_space(
+                        buffer_pool_lock,
+                        relocated_to.page_id,
+                        free_space,
+                        transaction,
+                    )?;
+                    if let Some(old_reference) = old_reference {
+                        self.free_overflow(buffer_pool_lock, &old_reference, transaction)?;
+                    }
+                    return Ok(());
+                }
+                buffer_pool_lock
+                    .unload_page_id(relocated_to.page_id as usize)
+                    .expect("Could not unload the table page");
+            }
+            None => {
+                let old_reference = get_overflow_reference(&table_page, slot_id);
+                if update_stored_tuple(&mut table_page, slot_id, &stored_tuple) {
+                    let free_space = table_page.available_space() as usize;
+                    store_page(
+                        buffer_pool_lock,
+                        rid.page_id,
+                        table_page.to_raw_page(),
+                        transaction,
+                    );
+                    self.set_free_space(buffer_pool_lock, rid.page_id, free_space, transaction)?;
+                    if let Some(old_reference) = old_reference {
+                        self.free_overflow(buffer_pool_lock, &old_reference, transaction)?;
+                    }
+                    return Ok(());
+                }
+                let can_forward = table_page.can_forward(slot_id);
+                buffer_pool_lock
+                    .unload_page_id(rid.page_id as usize)
+                    .expect("Could not unload the table page");
+                if !can_forward {
+                    if let Some(reference) = overflow_reference {
+                        self.free_overflow(buffer_pool_lock, &reference, transaction)?;
+                    }
+                    return Err("The page of the tuple has no space for a forwarding pointer");
+                }
+            }
+        }
+
+        // The tuple is relocated. The new copy is inserted before the forwarding pointer is
+        // written, so the old data stays in place if the insert fails.
+        let new_location =
+            match self.insert_with_lock(buffer_pool_lock, stored_tuple, true, transaction) {
+                Ok(new_location) => new_location,
+                Err(error) => {
+                    if let Some(reference) = overflow_reference {
+                        self.free_overflow(buffer_pool_lock, &reference, transaction)?;
+                    }
+                    return Err(error);
+                }
+            };
+        let mut table_page = load_table_page(buffer_pool_lock, rid.page_id);
+        let old_reference = get_overflow_reference(&table_page, slot_id);
+        table_page
+            .set_forward(slot_id, new_location)
+            .expect("Could not write the forwarding pointer");
+        let free_space = table_page.available_space() as usize;
+        store_page(
+            buffer_pool_lock,
+            rid.page_id,
+            table_page.to_raw_page(),
+            transaction,
+        );
+        self.set_free_space(buffer_pool_lock, rid.page_id, free_space, transaction)?;
+        if let Some(old_reference) = old_reference {
+            self.free_overflow(buffer_pool_lock, &old_reference, transaction)?;
+        }
+        if let Some(relocated_to) = relocated_to {
+            self.remove_with_lock(buffer_pool_lock, relocated_to, transaction);
+        }
+        Ok(())
+    }
+
+    /// Removes the tuples whose versions are dead and returns them. Afterwards, every page is
+    /// compacted and the removed slots at its end are dropped, and the versions that replaced a
+    /// removed version no longer link to it.
+    pub fn vacuum(
+        &self,
+        is_dead: impl Fn(TupleVersion) -> bool,
+        transaction: Option<TransactionId>,
+    ) -> Vec<Tuple> {
+        let mut removed = Vec::new();
+        for page_id in self.get_page_ids() {
+            let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+            let table_page = load_table_page(&mut buffer_pool_lock, page_id);
+            // Relocated tuples are removed together with their forwarding slot.
+            let dead_rids: Vec<Rid> = (0..table_page.get_tuple_count() as usize)
+                .filter(|slot_id| {
+                    !table_page.is_relocated(*slot_id)
+                        && table_page.get_version(*slot_id).is_some_and(&is_dead)
+                })
+                .map(|slot_id| Rid::new(page_id, slot_id as u32))
+                .collect();
+            buffer_pool_lock
+                .unload_page_id(page_id as usize)
+                .expect("Could not unload the table page");
+            drop(buffer_pool_lock);
+            removed.extend(
+                dead_rids
+                    .into_iter()
+                    .filter_map(|rid| self.delete(rid, transaction)),
+            );
+
+            let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+            let mut table_page = load_table_page(&mut buffer_pool_lock, page_id);
+            let tuple_count = table_page.get_tuple_count();
+            table_page.truncate_free_slots();
+            if table_page.get_tuple_count() == tuple_count
+                && table_page.free_space() == table_page.available_space()
+            {
+                buffer_pool_lock
+                    .unload_page_id(page_id as usize)
+                    .expect("Could not unload the table page");
+                continue;
+            }
+            table_page.compact();
+            let free_space = table_page.available_space() as usize;
+            store_page(
+                &mut buffer_pool_lock,
+                page_id,
+                table_page.to_raw_page(),
+                transaction,
+            );
+            self.set_free_space(&mut buffer_pool_lock, page_id, free_space, transaction)
+                .expect("Could not update the free space of the table page");
+        }
+
+        // The slots of the removed versions are reused by later inserts.
+        let removed_rids: HashSet<Rid> = removed.iter().map(|tuple| tuple.own_rid).collect();
+        if !removed_rids.is_empty() {
+            let unlinked: Vec<(Rid, TupleVersion)> = self
+                .iter()
+                .map(|entry| entry.expect("Could not read the table heap"))
+                .filter(|(_, tuple)| {
+                    tuple
+                        .version
+                        .previous
+                        .is_some_and(|previous| removed_rids.contains(&previous))
+                })
+                .map(|(rid, tuple)| {
+                    let version = TupleVersion {
+                        previous: None,
+                        ..tuple.version
+                    };
+                    (rid, version)
+                })
+                .collect();
+            for (rid, version) in unlinked {
+                self.set_version(rid, version, transaction)
+                    .expect("Could not unlink the removed version");
+            }
+        }
+        removed
+    }
+
+    /// Returns an iterator over all tuples of this heap.
+    pub fn iter(&self) -> TableIterator {
+        TableIterator::new(self.buffer_pool.clone(), self.first_directory_page_id)
+    }
+
+    /// Returns the ids of all table pages of this heap in directory order.
+    pub fn get_page_ids(&self) -> Vec<u32> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        let mut page_ids = Vec::new();
+        let mut next_directory = Some(self.first_directory_page_id);
+        while let Some(directory_page_id) = next_directory {
+            let directory_page = load_directory_page(&mut buffer_pool_lock, directory_page_id);
+            page_ids.extend(directory_page.get_page_ids());
+            next_directory = directory_page.get_next_directory();
+            buffer_pool_lock
+                .unload_page_id(directory_page_id as usize)
+                .expect("Could not unload the directory page");
+        }
+        page_ids
+    }
+
+    fn find_page_with_free_space(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        free_space: usize,
+    ) -> Option<u32> {
+        let mut next_directory = Some(self.first_directory_page_id);
+        while let Some(directory_page_id) = next_directory {
+            let directory_page = load_directory_page(buffer_pool_lock, directory_page_id);
+            buffer_pool_lock
+                .unload_page_id(directory_page_id as usize)
+                .expect("Could not unload the directory page");
+
+            let page_id = directory_page.find_page_with_free_space(free_space);
+            if page_id.is_some() {
+                return page_id;
+            }
+            next_directory = directory_page.get_next_directory();
+        }
+        None
+    }
+
+    /// Allocates a new, empty table page and registers it in the last directory page. A new
+    /// directory page is appended to the list if the last one is full.
+    fn allocate_table_page(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        transaction: Option<TransactionId>,
+    ) -> Result<u32, &'static str> {
+        let (page_id, _) = buffer_pool_lock
+            .load_new_page()
+            .or(Err("Could not load a new page"))?;
+        let page_id = page_id as u32;
+        let table_page = TablePage::new_empty(page_id);
+        let free_space = table_page.available_space() as usize;
+        store_page(
+            buffer_pool_lock,
+            page_id,
+            table_page.to_raw_page(),
+            transaction,
+        );
+
+        let mut directory_page_id = self.first_directory_page_id;
+        let mut directory_page = load_directory_page(buffer_pool_lock, directory_page_id);
+        while let Some(next_directory) = directory_page.get_next_directory() {
+            buffer_pool_lock
+                .unload_page_id(directory_page_id as usize)
+                .expect("Could not unload the directory page");
+            directory_page_id = next_directory;
+            directory_page = load_directory_page(buffer_pool_lock, directory_page_id);
+        }
+
+        if directory_page.add_page(page_id, free_space).is_err() {
+            let (new_directory_page_id, _) = buffer_pool_lock
+                .load_new_page()
+                .or(Err("Could not load a new page"))?;
+            let new_directory_page_id = new_directory_page_id as u32;
+            let mut new_directory_page =
+                TableDirectoryPage::new_empty(new_directory_page_id, directory_page_id);
+            new_directory_page
+                .add_page(page_id, free_space)
+                .expect("Could not add the page to an empty directory page");
+            directory_page.set_next_directory(new_directory_page_id);
+            store_page(
+                buffer_pool_lock,
+                new_directory_page_id,
+                new_directory_page.to_raw_page(),
+                transaction,
+            );
+        }
+        store_page(
+            buffer_pool_lock,
+            directory_page_id,
+            directory_page.to_raw_page(),
+            transaction,
+        );
+        Ok(page_id)
+    }
+
+    /// Moves data that does not fit into a table page into overflow pages.
+    fn to_stored_tuple(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        tuple_data: Vec<u8>,
+        transaction: Option<TransactionId>,
+    ) -> Result<StoredTuple, &'static str> {
+        if tuple_data.len() <= MAX_TUPLE_SIZE {
+            return Ok(StoredTuple {
+                data: tuple_data,
+                overflow: false,
+            });
+        }
+        let mut directory_page =
+            load_directory_page(buffer_pool_lock, self.first_directory_page_id);
+        let mut free_pages = directory_page.get_free_overflow_page();
+        let reference =
+            write_overflow_chain(buffer_pool_lock, &tuple_data, &mut free_pages, transaction);
+        directory_page.set_free_overflow_page(free_pages);
+        store_page(
+            buffer_pool_lock,
+            self.first_directory_page_id,
+            directory_page.to_raw_page(),
+            transaction,
+        );
+        Ok(StoredTuple {
+            data: reference?,
+            overflow: true,
+        })
+    }
+
+    /// Adds the overflow pages referenced by the overflow reference to the free overflow pages of
+    /// this heap.
+    fn free_overflow(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        reference: &[u8],
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let mut directory_page =
+            load_directory_page(buffer_pool_lock, self.first_directory_page_id);
+        let mut free_pages = directory_page.get_free_overflow_page();
+        let result = free_overflow_chain(buffer_pool_lock, reference, &mut free_pages, transaction);
+        directory_page.set_free_overflow_page(free_pages);
+        store_page(
+            buffer_pool_lock,
+            self.first_directory_page_id,
+            directory_page.to_raw_page(),
+            transaction,
+        );
+        result
+    }
+
+    /// Updates the free space of a table page in the directory page it is registered in.
+    fn set_free_space(
+        &self,
+        buffer_pool_lock: &mut MutexGuard<BufferPool>,
+        page_id: u32,
+        free_space: usize,
+        transaction: Option<TransactionId>,
+    ) -> Result<(), &'static str> {
+        let mut next_directory = Some(self.first_directory_page_id);
+        while let Some(directory_page_id) = next_directory {
+            let mut directory_page = load_directory_page(buffer_pool_lock, directory_page_id);
+            if directory_page.set_free_space(page_id, free_space).is_ok() {
+                store_page(
+                    buffer_pool_lock,
+                    directory_page_id,
+                    directory_page.to_raw_page(),
+                    transaction,
+                );
+                return Ok(());
+            }
+            buffer_pool_lock
+                .unload_page_id(directory_page_id as usize)
+                .expect("Could not unload the directory page");
+            next_directory = directory_page.get_next_directory();
+        }
+        Err("The table page is not part of the table heap")
+    }
+}
+
+/// The data of a tuple as it is stored in a table page.
+struct StoredTuple {
+    data: Vec<u8>,
+    overflow: bool,
+}
+
+/// Returns the overflow reference stored at the given slot, if its data is in overflow pages.
+fn get_overflow_reference(table_page: &TablePage, slot_id: usize) -> Option<Vec<u8>> {
+    match table_page.is_overflow(slot_id) {
+        true => table_page.get(slot_id).map(|tuple| tuple.data.clone()),
+        false => None,
+    }
+}
+
+fn update_stored_tuple(
+    table_page: &mut TablePage,
+    slot_id: usize,
+    stored_tuple: &StoredTuple,
+) -> bool {
+    if table_page
+        .update(slot_id, stored_tuple.data.clone())
+        .is_err()
+    {
+        return false;
+    }
+    table_page
+        .set_overflow(slot_id, stored_tuple.overflow)
+        .unwrap();
+    true
+}
+
+/// Sets the version of the tuple with the given Rid and returns the version it replaced.
+fn set_version(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    rid: Rid,
+    version: TupleVersion,
+    transaction: Option<TransactionId>,
+) -> Result<TupleVersion, &'static str> {
+    let mut table_page = load_table_page(buffer_pool_lock, rid.page_id);
+    let before = table_page.get_version(rid.slot_id as usize);
+    if table_page
+        .set_version(rid.slot_id as usize, version)
+        .is_err()
+    {
+        buffer_pool_lock
+            .unload_page_id(rid.page_id as usize)
+            .expect("Could not unload the table page");
+        return Err("The tuple does not exist");
+    }
+    store_page(
+        buffer_pool_lock,
+        rid.page_id,
+        table_page.to_raw_page(),
+        transaction,
+    );
+    Ok(before.unwrap_or_default())
+}
+
+/// Reads the tuple with the given Rid. If the tuple has been relocated, its new location is read
+/// instead. Fails if the overflow chain of the tuple cannot be read.
+pub fn read_tuple(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    rid: Rid,
+) -> Result<Option<Tuple>, &'static str> {
+    let slot_id = rid.slot_id as usize;
+    let table_page = try_load_table_page(buffer_pool_lock, rid.page_id)?;
+    let new_location = table_page.get_forward(slot_id);
+    let version = table_page.get_version(slot_id).unwrap_or_default();
+    let data = match table_page.is_relocated(slot_id) {
+        true => Ok(None),
+        false => read_data(buffer_pool_lock, &table_page, slot_id),
+    };
+    buffer_pool_lock
+        .unload_page_id(rid.page_id as usize)
+        .expect("Could not unload the table page");
+
+    let new_location = match new_location {
+        Some(new_location) => new_location,
+        None => {
+            return Ok(data?.map(|data| Tuple {
+                data,
+                own_rid: rid,
+                version,
+            }))
+        }
+    };
+    let table_page = try_load_table_page(buffer_pool_lock, new_location.page_id)?;
+    let data = read_data(buffer_pool_lock, &table_page, new_location.slot_id as usize);
+    buffer_pool_lock
+        .unload_page_id(new_location.page_id as usize)
+        .expect("Could not unload the table page");
+    Ok(data?.map(|data| Tuple {
+        data,
+        own_rid: rid,
+        version,
+    }))
+}
+
+/// Returns the data of the tuple at the given slot and reassembles it from its overflow pages if
+/// necessary. Fails if the overflow chain cannot be read, e.g. because it was truncated.
+pub fn read_data(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    table_page: &TablePage,
+    slot_id: usize,
+) -> Result<Option<Vec<u8>>, &'static str> {
+    let Some(tuple) = table_page.get(slot_id) else {
+        return Ok(None);
+    };
+    match table_page.is_overflow(slot_id) {
+        true => read_overflow_chain(buffer_pool_lock, &tuple.data).map(Some),
+        false => Ok(Some(tuple.data.clone())),
+    }
+}
+
+/// Loads and pins a directory page.
+pub fn load_directory_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: u32,
+) -> TableDirectoryPage {
+    try_load_directory_page(buffer_pool_lock, page_id).expect("Could not load the directory page")
+}
+
+/// Loads and pins a directory page. Fails if the page cannot be read.
+pub fn try_load_directory_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: u32,
+) -> Result<TableDirectoryPage, &'static str> {
+    let frame_id = buffer_pool_lock
+        .load_page(page_id as usize)
+        .or(Err("Could not load the directory page"))?;
+    TableDirectoryPage::from_raw_page(buffer_pool_lock.get_raw_page(frame_id).unwrap())
+        .or(Err("Could not create a directory page from the raw page"))
+}
+
+/// Loads and pins a table page.
+pub fn load_table_page(buffer_pool_lock: &mut MutexGuard<BufferPool>, page_id: u32) -> TablePage {
+    try_load_table_page(buffer_pool_lock, page_id).expect("Could not load the table page")
+}
+
+/// Loads and pins a table page. Fails if the page cannot be read.
+pub fn try_load_table_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: u32,
+) -> Result<TablePage, &'static str> {
+    let frame_id = buffer_pool_lock
+        .load_page(page_id as usize)
+        .or(Err("Could not load the table page"))?;
+    TablePage::from_raw_page(buffer_pool_lock.get_raw_page(frame_id).unwrap())
+        .or(Err("Could not create a table page from the raw page"))
+}
+
+/// Writes the new content of a pinned page and unpins it.
+fn store_page(
+    buffer_pool_lock: &mut MutexGuard<BufferPool>,
+    page_id: u32,
+    raw_page: RawPage,
+    transaction: Option<TransactionId>,
+) {
+    buffer_pool_lock
+        .set_page_data(page_id as usize, raw_page, transaction)
+        .expect("Could not update the page");
+    buffer_pool_lock
+        .unload_page_id(page_id as usize)
+        .expect("Could not unload the page");
+}
+
+#[cfg(test)]
+mod table_heap_tests {
+    use crate::{
+        disk_management::test_utils::new_test_buffer_pool,
+        table::table_page::{TupleVersion, MAX_TUPLE_SIZE},
+    };
+
+    use super::TableHeap;
+
+    #[test]
+    fn insert_and_get() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("heap_insert_and_get"), None)
+                .unwrap();
+        let first = table_heap.insert(vec![1, 2, 3], None).unwrap();
+        let second = table_heap.insert(vec![4, 5], None).unwrap();
+
+        assert_eq!(first.page_id, second.page_id);
+        assert_eq!(table_heap.get(first).unwrap().unwrap().data, vec![1, 2, 3]);
+        assert_eq!(table_heap.get(second).unwrap().unwrap().data, vec![4, 5]);
+    }
+
+    #[test]
+    fn delete_and_update() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("heap_delete_and_update"), None)
+                .unwrap();
+        let first = table_heap.insert(vec![1, 2, 3], None).unwrap();
+        let second = table_heap.insert(vec![4, 5], None).unwrap();
+
+        assert_eq!(table_heap.delete(first, None).unwrap().data, vec![1, 2, 3]);
+        assert!(table_heap.get(first).unwrap().is_none());
+        assert!(table_heap.delete(first, None).is_none());
+
+        table_heap.update(second, vec![6, 7, 8, 9], None).unwrap();
+        assert_eq!(
+            table_heap.get(second).unwrap().unwrap().data,
+            vec![6, 7, 8, 9]
+        );
+        assert!(table_heap.update(first, vec![1], None).is_err());
+    }
+
+    #[test]
+    fn vacuum_removes_dead_tuples() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("heap_vacuum"), None).unwrap();
+        let deleted_by = |transaction| TupleVersion {
+            created_by: Some(1),
+            deleted_by: Some(transaction),
+            previous: None,
+        };
+        let live = table_heap
+            .insert(vec![1; MAX_TUPLE_SIZE / 2], None)
+            .unwrap();
+        let relocated = table_heap.insert(vec![2; 10], None).unwrap();
+        let dead = table_heap
+            .insert_version(vec![3; 100], deleted_by(2), None)
+            .unwrap();
+        let recently_deleted = table_heap
+            .insert_version(vec![4; 100], deleted_by(5), None)
+            .unwrap();
+        table_heap
+            .update(relocated, vec![2; MAX_TUPLE_SIZE / 2], None)
+            .unwrap();
+        table_heap
+            .set_version(relocated, deleted_by(3), None)
+            .unwrap();
+        assert_eq!(table_heap.get_page_ids().len(), 2);
+
+        let removed =
+            table_heap.vacuum(|version| version.deleted_by.is_some_and(|id| id < 5), None);
+        let removed: Vec<_> = removed.into_iter().map(|tuple| tuple.own_rid).collect();
+        assert_eq!(removed, vec![relocated, dead]);
+        assert!(table_heap.get(relocated).unwrap().is_none());
+        assert!(table_heap.get(dead).unwrap().is_none());
+        assert_eq!(
+            table_heap.get(recently_deleted).unwrap().unwrap().version,
+            deleted_by(5)
+        );
+        let rids: Vec<_> = table_heap.iter().map(|entry| entry.unwrap().0).collect();
+        assert_eq!(rids, vec![live, recently_deleted]);
+
+        // The space of the relocated tuple is free again.
+        table_heap
+            .insert(vec![5; MAX_TUPLE_SIZE / 2], None)
+            .unwrap();
+        assert_eq!(table_heap.get_page_ids().len(), 2);
+    }
+
+    #[test]
+    fn update_relocates_tuples() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("heap_update_relocates"), None)
+                .unwrap();
+        let first = table_heap
+            .insert(vec![1; MAX_TUPLE_SIZE / 2], None)
+            .unwrap();
+        let second = table_heap
+            .insert(vec![2; MAX_TUPLE_SIZE / 3], None)
+            .unwrap();
+
+        // Shrinking the second tuple makes enough room for the first one after compaction.
+        table_heap.update(second, vec![2; 10], None).unwrap();
+        table_heap
+            .update(first, vec![1; MAX_TUPLE_SIZE / 2 + 100], None)
+            .unwrap();
+        assert_eq!(table_heap.get_page_ids().len(), 1);
+
+        table_heap
+            .update(second, vec![3; MAX_TUPLE_SIZE / 2], None)
+            .unwrap();
+        assert_eq!(table_heap.get_page_ids().len(), 2);
+        assert_eq!(
+            table_heap.get(second).unwrap().unwrap().data,
+            vec![3; MAX_TUPLE_SIZE / 2]
+        );
+        assert_eq!(table_heap.get(second).unwrap().unwrap().own_rid, second);
+
+        // The relocated tuple is updated at its new location.
+        table_heap
+            .update(second, vec![4; MAX_TUPLE_SIZE], None)
+            .unwrap();
+        assert_eq!(
+            table_heap.get(second).unwrap().unwrap().data,
+            vec![4; MAX_TUPLE_SIZE]
+        );
+        table_heap.update(second, vec![5; 10], None).unwrap();
+        table_heap.insert(vec![6; 100], None).unwrap();
+        assert_eq!(table_heap.get_page_ids().len(), 2);
+
+        let tuples: Vec<_> = table_heap.iter().map(Result::unwrap).collect();
+        assert_eq!(tuples.len(), 3);
+        assert_eq!(tuples[1].0, second);
+        assert_eq!(tuples[1].1.data, vec![5; 10]);
+
+        assert_eq!(table_heap.delete(second, None).unwrap().data, vec![5; 10]);
+        assert!(table_heap.get(second).unwrap().is_none());
+        assert_eq!(table_heap.iter().count(), 2);
+    }
+
+    #[test]
+    fn failed_relocation_keeps_the_tuple() {
+        let buffer_pool = new_test_buffer_pool("heap_failed_relocation");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let rid = table_heap.insert(vec![1; 100], None).unwrap();
+        table_heap.insert(vec![2; 3800], None).unwrap();
+
+        // Pin every frame, so that no page can be allocated for the relocated tuple.
+        let mut pinned_pages = table_heap.get_page_ids();
+        pinned_pages.push(table_heap.first_directory_page_id);
+        let mut buffer_pool_lock = buffer_pool.lock().unwrap();
+        for page_id in &pinned_pages {
+            buffer_pool_lock.load_page(*page_id as usize).unwrap();
+        }
+        while let Ok((page_id, _)) = buffer_pool_lock.load_new_page() {
+            pinned_pages.push(page_id as u32);
+        }
+        drop(buffer_pool_lock);
+
+        assert!(table_heap.update(rid, vec![3; 300], None).is_err());
+        assert_eq!(table_heap.get(rid).unwrap().unwrap().data, vec![1; 100]);
+
+        let mut buffer_pool_lock = buffer_pool.lock().unwrap();
+        for page_id in pinned_pages {
+            buffer_pool_lock.unload_page_id(page_id as usize).unwrap();
+        }
+        drop(buffer_pool_lock);
+        table_heap.update(rid, vec![3; 300], None).unwrap();
+        assert_eq!(table_heap.get(rid).unwrap().unwrap().data, vec![3; 300]);
+    }
+
+    #[test]
+    fn insert_reuses_space_of_deleted_tuples() {
+        let table_heap = TableHeap::setup_new_table_heap(
+            new_test_buffer_pool("heap_reuses_deleted_space"),
+            None,
+        )
+        .unwrap();
+        let rids: Vec<_> = (0..4)
+            .map(|i| table_heap.insert(vec![i; 980], None).unwrap())
+            .collect();
+        assert_eq!(table_heap.get_page_ids().len(), 1);
+
+        table_heap.delete(rids[1], None).unwrap();
+        table_heap.delete(rids[2], None).unwrap();
+        assert_eq!(table_heap.insert(vec![5; 1500], None).unwrap(), rids[1]);
+        assert_eq!(table_heap.insert(vec![6; 400], None).unwrap(), rids[2]);
+        assert_eq!(table_heap.get_page_ids().len(), 1);
+        assert_eq!(table_heap.get(rids[3]).unwrap().unwrap().data, vec![3; 980]);
+    }
+
+    #[test]
+    fn large_tuples_use_overflow_pages() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("heap_overflow_pages"), None)
+                .unwrap();
+        let large_data: Vec<u8> = (0..100_000_u32).map(|i| i as u8).collect();
+        let small = table_heap.insert(vec![1; 10], None).unwrap();
+        let large = table_heap.insert(large_data.clone(), None).unwrap();
+        assert_eq!(small.page_id, large.page_id);
+        assert_eq!(table_heap.get(large).unwrap().unwrap().data, large_data);
+
+        table_heap
+            .update(small, vec![2; MAX_TUPLE_SIZE + 1], None)
+            .unwrap();
+        assert_eq!(
+            table_heap.get(small).unwrap().unwrap().data,
+            vec![2; MAX_TUPLE_SIZE + 1]
+        );
+        table_heap.update(large, vec![3; 5], None).unwrap();
+        assert_eq!(table_heap.get(large).unwrap().unwrap().data, vec![3; 5]);
+
+        let tuples: Vec<_> = table_heap
+            .iter()
+            .map(|entry| entry.unwrap().1.data)
+            .collect();
+        assert_eq!(tuples, vec![vec![2; MAX_TUPLE_SIZE + 1], vec![3; 5]]);
+        assert_eq!(
+            table_heap.delete(small, None).unwrap().data,
+            vec![2; MAX_TUPLE_SIZE + 1]
+        );
+    }
+
+    #[test]
+    fn overflow_pages_are_reused() {
+        let buffer_pool = new_test_buffer_pool("heap_reuses_overflow_pages");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let large = table_heap.insert(vec![1; 100_000], None).unwrap();
+        let page_count = buffer_pool.lock().unwrap().get_page_count().unwrap();
+
+        // The pages of replaced and removed data are used by the following chains.
+        table_heap.update(large, vec![2; 100_000], None).unwrap();
+        assert_eq!(
+            buffer_pool.lock().unwrap().get_page_count().unwrap(),
+            page_count + 25
+        );
+        table_heap.update(large, vec![3; 10], None).unwrap();
+        let second = table_heap.insert(vec![4; 150_000], None).unwrap();
+        table_heap.delete(second, None).unwrap();
+        table_heap.update(large, vec![5; 200_000], None).unwrap();
+        assert_eq!(
+            buffer_pool.lock().unwrap().get_page_count().unwrap(),
+            page_count + 25
+        );
+        assert_eq!(
+            table_heap.get(large).unwrap().unwrap().data,
+            vec![5; 200_000]
+        );
+    }
+
+    #[test]
+    fn insert_spans_multiple_directory_pages() {
+        let buffer_pool = new_test_buffer_pool("heap_multiple_directory_pages");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        let rids: Vec<_> = (0..900_u32)
+            .map(|i| {
+                let mut data = vec![0; MAX_TUPLE_SIZE];
+                data[0..4].copy_from_slice(&i.to_le_bytes());
+                table_heap.insert(data, None).unwrap()
+            })
+            .collect();
+        buffer_pool
+            .lock()
+            .unwrap()
+            .unload_all_pages_and_write_to_file()
+            .unwrap();
+
+        let table_heap = TableHeap::new(buffer_pool, table_heap.first_directory_page_id);
+        assert_eq!(table_heap.get_page_ids().len(), 900);
+        for (i, rid) in rids.into_iter().enumerate() {
+            let data = table_heap.get(rid).unwrap().unwrap().data;
+            assert_eq!(data[0..4], (i as u32).to_le_bytes());
+        }
+    }
+}
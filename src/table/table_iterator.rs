@@ -0,0 +1,221 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::disk_management::buffer_pool::BufferPool;
+
+use super::{
+    table_heap::{read_data, read_tuple, try_load_directory_page, try_load_table_page},
+    table_page::{Rid, TablePage, Tuple},
+};
+
+/// Sequentially iterates over all tuples of a table heap. The iterator walks the directory pages
+/// and keeps only the table page it is currently reading pinned in the buffer pool. Relocated
+/// tuples are returned with their original Rid when the forwarding slot is reached. A page or an
+/// overflow chain that cannot be read is returned as an error.
+pub struct TableIterator {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    next_directory: Option<u32>,
+    remaining_page_ids: VecDeque<u32>,
+    current_page: Option<TablePage>,
+    current_page_id: u32,
+    next_slot: usize,
+}
+
+impl TableIterator {
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>, first_directory_page_id: u32) -> TableIterator {
+        TableIterator {
+            buffer_pool,
+            next_directory: Some(first_directory_page_id),
+            remaining_page_ids: VecDeque::new(),
+            current_page: None,
+            current_page_id: 0,
+            next_slot: 0,
+        }
+    }
+
+    /// Unpins the current table page and pins the next one. Returns false if there are no more
+    /// table pages.
+    fn advance_page(&mut self) -> Result<bool, &'static str> {
+        let mut buffer_pool_lock = self.buffer_pool.lock().expect("Could not lock buffer pool");
+        if self.current_page.take().is_some() {
+            buffer_pool_lock
+                .unload_page_id(self.current_page_id as usize)
+                .expect("Could not unload the table page");
+        }
+
+        while self.remaining_page_ids.is_empty() {
+            let directory_page_id = match self.next_directory {
+                Some(directory_page_id) => directory_page_id,
+                None => return Ok(false),
+            };
+            let directory_page = try_load_directory_page(&mut buffer_pool_lock, directory_page_id)?;
+            buffer_pool_lock
+                .unload_page_id(directory_page_id as usize)
+                .expect("Could not unload the directory page");
+            self.remaining_page_ids
+                .extend(directory_page.get_page_ids());
+            self.next_directory = directory_page.get_next_directory();
+        }
+
+        let page_id = self.remaining_page_ids.pop_front().unwrap();
+        self.current_page = Some(try_load_table_page(&mut buffer_pool_lock, page_id)?);
+        self.current_page_id = page_id;
+        self.next_slot = 0;
+        Ok(true)
+    }
+}
+
+impl Iterator for TableIterator {
+    type Item = Result<(Rid, Tuple), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(table_page) = &self.current_page {
+                while self.next_slot < table_page.get_tuple_count() as usize {
+                    let slot = self.next_slot;
+                    self.next_slot += 1;
+                    if table_page.is_relocated(slot) {
+                        continue;
+                    }
+                    if let Some(tuple) = table_page.get(slot) {
+                        if !table_page.is_overflow(slot) {
+                            return Some(Ok((tuple.own_rid, tuple.clone())));
+                        }
+                        let mut buffer_pool_lock =
+                            self.buffer_pool.lock().expect("Could not lock buffer pool");
+                        let data = match read_data(&mut buffer_pool_lock, table_page, slot) {
+                            Ok(data) => data?,
+                            Err(error) => return Some(Err(error)),
+                        };
+                        let tuple = Tuple {
+                            data,
+                            own_rid: tuple.own_rid,
+                            version: tuple.version,
+                        };
+                        return Some(Ok((tuple.own_rid, tuple)));
+                    }
+                    if table_page.get_forward(slot).is_some() {
+                        let rid = Rid::new(self.current_page_id, slot as u32);
+                        let mut buffer_pool_lock =
+                            self.buffer_pool.lock().expect("Could not lock buffer pool");
+                        match read_tuple(&mut buffer_pool_lock, rid) {
+                            Ok(Some(tuple)) => return Some(Ok((rid, tuple))),
+                            Ok(None) => {}
+                            Err(error) => return Some(Err(error)),
+                        }
+                    }
+                }
+            }
+            match self.advance_page() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(error) => {
+                    // Do not retry the page that could not be read on the next call.
+                    self.remaining_page_ids.clear();
+                    self.next_directory = None;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TableIterator {
+    fn drop(&mut self) {
+        if self.current_page.is_some() {
+            if let Ok(mut buffer_pool_lock) = self.buffer_pool.lock() {
+                let _ = buffer_pool_lock.unload_page_id(self.current_page_id as usize);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod table_iterator_tests {
+    use crate::{
+        disk_management::{
+            buffer_pool::{RawPage, PAGE_SIZE},
+            test_utils::new_test_buffer_pool,
+        },
+        table::{table_heap::TableHeap, table_page::MAX_TUPLE_SIZE},
+    };
+
+    #[test]
+    fn iterate_empty_heap() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("iterate_empty_heap"), None)
+                .unwrap();
+        assert_eq!(table_heap.iter().count(), 0);
+    }
+
+    #[test]
+    fn iterate_skips_removed_tuples() {
+        let table_heap =
+            TableHeap::setup_new_table_heap(new_test_buffer_pool("iterate_skips_removed"), None)
+                .unwrap();
+        let rids: Vec<_> = (0..10_u8)
+            .map(|i| {
+                table_heap
+                    .insert(vec![i; MAX_TUPLE_SIZE / 4], None)
+                    .unwrap()
+            })
+            .collect();
+        for rid in rids.iter().step_by(3) {
+            table_heap.delete(*rid, None);
+        }
+
+        let tuples: Vec<_> = table_heap.iter().map(Result::unwrap).collect();
+        let expected: Vec<_> = (0..10_u8).filter(|i| i % 3 != 0).collect();
+        assert_eq!(tuples.len(), expected.len());
+        for ((rid, tuple), i) in tuples.into_iter().zip(expected) {
+            assert_eq!(rid, rids[i as usize]);
+            assert_eq!(tuple.data[0], i);
+        }
+    }
+
+    #[test]
+    fn iterator_releases_pins() {
+        let buffer_pool = new_test_buffer_pool("iterator_releases_pins");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        for i in 0..300_u32 {
+            table_heap
+                .insert(i.to_le_bytes().repeat(500), None)
+                .unwrap();
+        }
+
+        // The pool only has 100 frames, so iterating twice would fail if pages stayed pinned.
+        assert_eq!(table_heap.iter().count(), 300);
+        let mut iterator = table_heap.iter();
+        iterator.next();
+        drop(iterator);
+        assert_eq!(table_heap.iter().count(), 300);
+    }
+
+    #[test]
+    fn broken_overflow_chains_are_errors() {
+        let buffer_pool = new_test_buffer_pool("iterator_broken_overflow_chain");
+        let table_heap = TableHeap::setup_new_table_heap(buffer_pool.clone(), None).unwrap();
+        table_heap.insert(vec![1; 10], None).unwrap();
+        let page_count = buffer_pool.lock().unwrap().get_page_count().unwrap();
+        let large = table_heap.insert(vec![2; 3 * PAGE_SIZE], None).unwrap();
+
+        // Lose the overflow pages, like a crash before they reached the disk.
+        let mut buffer_pool_lock = buffer_pool.lock().unwrap();
+        for page_id in page_count..buffer_pool_lock.get_page_count().unwrap() {
+            buffer_pool_lock.load_page(page_id).unwrap();
+            buffer_pool_lock
+                .set_page_data(page_id, RawPage::new([0; PAGE_SIZE]), None)
+                .unwrap();
+            buffer_pool_lock.unload_page_id(page_id).unwrap();
+        }
+        drop(buffer_pool_lock);
+
+        let mut iterator = table_heap.iter();
+        assert_eq!(iterator.next().unwrap().unwrap().1.data, vec![1; 10]);
+        assert!(iterator.next().unwrap().is_err());
+        assert!(iterator.next().is_none());
+        assert!(table_heap.get(large).is_err());
+    }
+}
@@ -1,15 +1,38 @@
 use bincode::{Decode, Encode};
 
-use crate::disk_management::buffer_pool::{RawPage, PAGE_SIZE};
+use crate::{
+    disk_management::buffer_pool::{RawPage, PAGE_SIZE},
+    recovery::log_record::TransactionId,
+};
 
 // | HEADER | ... FREE SPACE ... | TUPLE (n) | ... | TUPLE (1) |
 // HEADER:
-// | OWN_PID [u32] | FREE_SPACE_POINTER [u16] | TUPLE_COUNT [u16] | TUPLE_OFFSET (1) [u16] | TUPLE_SIZE (1) [u16] |
+// | OWN_PID [u32] | FREE_SPACE_POINTER [u16] | TUPLE_COUNT [u16] | TUPLE_HEADER (1) | ... | TUPLE_HEADER (n) |
+// TUPLE_HEADER:
+// | TUPLE_OFFSET [u16] | TUPLE_SIZE [u16] | FREE [bool] | FORWARDED [bool] | RELOCATED [bool] | OVERFLOW [bool] |
+// | CREATED_BY [u64] | DELETED_BY [u64] | PREVIOUS_PAGE_ID [u32] | PREVIOUS_SLOT_ID [u32] |
+//
+// CREATED_BY and DELETED_BY are the ids of the transactions that created and deleted this version
+// of the tuple, or 0 if there is none. PREVIOUS is the Rid of the version of the row this version
+// replaced, or has a page id of u32::MAX if there is none. A forwarded tuple keeps its version in
+// the forwarding slot.
+//
+// A forwarded tuple did not fit into its page anymore after an update. Its data is the Rid of the
+// tuple's new location, which is marked as relocated and only reachable through the forwarding
+// slot.
+// The data of an overflow tuple is a reference to a chain of overflow pages that store the tuple.
 #[derive(Encode, Decode, Debug)]
 struct TupleHeader {
     tuple_offset: u16,
     tuple_size: u16,
     free: bool,
+    forwarded: bool,
+    relocated: bool,
+    overflow: bool,
+    created_by: TransactionId,
+    deleted_by: TransactionId,
+    previous_page_id: u32,
+    previous_slot_id: u32,
 }
 impl TupleHeader {
     fn new_occupied(tuple_offset: u16, tuple_size: u16) -> TupleHeader {
@@ -17,20 +40,61 @@ impl TupleHeader {
             tuple_offset,
             tuple_size,
             free: false,
+            forwarded: false,
+            relocated: false,
+            overflow: false,
+            created_by: 0,
+            deleted_by: 0,
+            previous_page_id: NO_PREVIOUS_PAGE_ID,
+            previous_slot_id: 0,
         }
     }
+
+    fn version(&self) -> TupleVersion {
+        let id = |transaction| match transaction {
+            0 => None,
+            transaction => Some(transaction),
+        };
+        let previous = match self.previous_page_id {
+            NO_PREVIOUS_PAGE_ID => None,
+            page_id => Some(Rid::new(page_id, self.previous_slot_id)),
+        };
+        TupleVersion {
+            created_by: id(self.created_by),
+            deleted_by: id(self.deleted_by),
+            previous,
+        }
+    }
+}
+const NO_PREVIOUS_PAGE_ID: u32 = u32::MAX;
+pub const TUPLE_HEADER_SIZE: u16 = 32;
+const TABLE_PAGE_HEADER_SIZE: u16 = 8;
+/// The largest tuple that fits into an otherwise empty table page.
+/// The size of the Rid stored in a forwarding slot.
+const FORWARD_SIZE: u16 = 8;
+pub const MAX_TUPLE_SIZE: usize = PAGE_SIZE - (TABLE_PAGE_HEADER_SIZE + TUPLE_HEADER_SIZE) as usize;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tuple {
+    pub data: Vec<u8>,
+    pub own_rid: Rid,
+    pub version: TupleVersion,
 }
-const TUPLE_HEADER_SIZE: u16 = 5;
-#[derive(Debug, PartialEq)]
-struct Tuple {
-    data: Vec<u8>,
-    own_rid: Rid,
+
+/// The transactions that created and deleted a version of a tuple. Tuples written outside of a
+/// transaction have no creator. A version created by an update links to the version of the row
+/// it replaced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TupleVersion {
+    pub created_by: Option<TransactionId>,
+    pub deleted_by: Option<TransactionId>,
+    pub previous: Option<Rid>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-struct Rid {
-    page_id: u32,
-    slot_id: u32,
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rid {
+    pub page_id: u32,
+    pub slot_id: u32,
 }
 impl Rid {
     pub fn new(page_id: u32, slot_id: u32) -> Rid {
@@ -47,6 +111,16 @@ pub struct TablePage {
 }
 
 impl TablePage {
+    pub fn new_empty(own_pid: u32) -> TablePage {
+        TablePage {
+            own_pid,
+            free_space_pointer: PAGE_SIZE as u16,
+            tuple_count: 0,
+            tuple_headers: Vec::new(),
+            tuples: Vec::new(),
+        }
+    }
+
     pub fn from_raw_page(raw_page: &RawPage) -> Result<TablePage, &str> {
         let data = raw_page.data.read().unwrap();
         let config = bincode::config::standard().with_fixed_int_encoding();
@@ -70,14 +144,18 @@ impl TablePage {
                 bincode::decode_from_slice(&data[i..i + TUPLE_HEADER_SIZE as usize], config)
                     .unwrap()
                     .0;
-            println!("{:?}", tuple_header);
 
-            let tuple_data = data[tuple_header.tuple_offset as usize
-                ..(tuple_header.tuple_offset as usize + tuple_header.tuple_size as usize)]
-                .to_vec();
+            let tuple_data = if tuple_header.free {
+                vec![]
+            } else {
+                data[tuple_header.tuple_offset as usize
+                    ..(tuple_header.tuple_offset as usize + tuple_header.tuple_size as usize)]
+                    .to_vec()
+            };
             tuples.push(Tuple {
                 data: tuple_data,
                 own_rid: Rid::new(own_pid, slot_id as u32),
+                version: tuple_header.version(),
             });
 
             tuple_headers.push(tuple_header);
@@ -112,44 +190,71 @@ impl TablePage {
                 config,
             )
             .unwrap();
-            bincode::encode_into_slice(
-                &self.tuples[i].data,
-                &mut result_data[tuple_header.tuple_offset as usize
-                    ..(tuple_header.tuple_offset + tuple_header.tuple_size) as usize],
-                config,
-            )
-            .unwrap();
+            let tuple_data = &self.tuples[i].data;
+            result_data[tuple_header.tuple_offset as usize
+                ..tuple_header.tuple_offset as usize + tuple_data.len()]
+                .copy_from_slice(tuple_data);
             index += TUPLE_HEADER_SIZE as usize;
         }
         RawPage::new(result_data)
     }
 
-    /// Inserts data into the table page and returns the Rid of the value.
+    /// Inserts data into the table page and returns the Rid of the value. The slot of a removed
+    /// tuple is reused if there is one, and the page is compacted if the data only fits into the
+    /// space reclaimed from removed tuples.
     pub fn insert(&mut self, tuple_data: Vec<u8>) -> Option<Rid> {
-        self.free_space_pointer -= tuple_data.len() as u16;
-        if (self.tuple_count + 1) * TUPLE_HEADER_SIZE >= self.free_space_pointer {
-            return None;
-        }
-        let tuple_header = TupleHeader {
-            tuple_offset: self.free_space_pointer,
-            tuple_size: tuple_data.len() as u16,
-            free: false,
-        };
-
-        let rid = Rid::new(self.own_pid, self.tuple_headers.len() as u32);
-        self.tuple_headers.push(tuple_header);
+        let slot_id = self
+            .tuple_headers
+            .iter()
+            .position(|header| header.free)
+            .unwrap_or(self.tuple_headers.len());
+        self.insert_at(slot_id, tuple_data).ok()?;
+        Some(Rid::new(self.own_pid, slot_id as u32))
+    }
 
-        self.tuples.push(Tuple {
+    /// Inserts data into the given slot, e.g. to put a removed tuple back. Free slots are added
+    /// up to the slot if the page has fewer. Returns an error and leaves the page unchanged if
+    /// the slot holds a tuple or the data does not fit into the page.
+    pub fn insert_at(&mut self, slot_id: usize, tuple_data: Vec<u8>) -> Result<(), &str> {
+        if self
+            .tuple_headers
+            .get(slot_id)
+            .is_some_and(|header| !header.free)
+        {
+            return Err("The slot already contains a tuple");
+        }
+        let new_slots = (slot_id + 1).saturating_sub(self.tuple_headers.len());
+        let required_space = tuple_data.len() + new_slots * TUPLE_HEADER_SIZE as usize;
+        if required_space > self.available_space() as usize {
+            return Err("The tuple does not fit into the page");
+        }
+        if required_space > self.free_space() as usize {
+            self.compact();
+        }
+        while self.tuple_headers.len() <= slot_id {
+            let mut tuple_header = TupleHeader::new_occupied(self.free_space_pointer, 0);
+            tuple_header.free = true;
+            self.tuple_headers.push(tuple_header);
+            self.tuples.push(Tuple {
+                data: vec![],
+                own_rid: Rid::new(self.own_pid, self.tuples.len() as u32),
+                version: TupleVersion::default(),
+            });
+            self.tuple_count += 1;
+        }
+        self.free_space_pointer -= tuple_data.len() as u16;
+        self.tuple_headers[slot_id] =
+            TupleHeader::new_occupied(self.free_space_pointer, tuple_data.len() as u16);
+        self.tuples[slot_id] = Tuple {
             data: tuple_data,
-            own_rid: rid.clone(),
-        });
-        self.tuple_count += 1;
-        Some(rid)
+            own_rid: Rid::new(self.own_pid, slot_id as u32),
+            version: TupleVersion::default(),
+        };
+        Ok(())
     }
 
     pub fn remove(&mut self, slot_id: usize) -> Option<Tuple> {
-        if (self.tuple_count as usize) <= slot_id || self.tuple_headers[slot_id].free == true {
-            println!("{:?}", self.tuple_headers[slot_id].free);
+        if (self.tuple_count as usize) <= slot_id || self.tuple_headers[slot_id].free {
             return None;
         }
         self.tuple_headers[slot_id].free = true;
@@ -158,11 +263,203 @@ impl TablePage {
             Tuple {
                 data: vec![],
                 own_rid: Rid::new(self.own_pid, slot_id as u32),
+                version: TupleVersion::default(),
             },
         );
 
         Some(previous)
     }
+
+    /// Returns the tuple at the given slot, if the slot exists, has not been removed and is not
+    /// forwarded to another page.
+    pub fn get(&self, slot_id: usize) -> Option<&Tuple> {
+        match self.tuple_headers.get(slot_id) {
+            Some(tuple_header) if !tuple_header.free && !tuple_header.forwarded => {
+                self.tuples.get(slot_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the Rid the tuple at the given slot has been relocated to, if it is forwarded.
+    pub fn get_forward(&self, slot_id: usize) -> Option<Rid> {
+        match self.tuple_headers.get(slot_id) {
+            Some(tuple_header) if !tuple_header.free && tuple_header.forwarded => {
+                let data = &self.tuples[slot_id].data;
+                Some(Rid::new(
+                    u32::from_le_bytes(data[0..4].try_into().unwrap()),
+                    u32::from_le_bytes(data[4..8].try_into().unwrap()),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the version of the tuple at the given slot, which may be forwarded.
+    pub fn get_version(&self, slot_id: usize) -> Option<TupleVersion> {
+        match self.tuple_headers.get(slot_id) {
+            Some(tuple_header) if !tuple_header.free => Some(tuple_header.version()),
+            _ => None,
+        }
+    }
+
+    pub fn set_version(&mut self, slot_id: usize, version: TupleVersion) -> Result<(), &str> {
+        match self.tuple_headers.get_mut(slot_id) {
+            Some(tuple_header) if !tuple_header.free => {
+                tuple_header.created_by = version.created_by.unwrap_or(0);
+                tuple_header.deleted_by = version.deleted_by.unwrap_or(0);
+                let previous = version.previous.unwrap_or(Rid::new(NO_PREVIOUS_PAGE_ID, 0));
+                tuple_header.previous_page_id = previous.page_id;
+                tuple_header.previous_slot_id = previous.slot_id;
+                self.tuples[slot_id].version = version;
+                Ok(())
+            }
+            _ => Err("The requested slot does not contain a tuple"),
+        }
+    }
+
+    /// Returns true if the tuple at the given slot has been relocated here from another page.
+    pub fn is_relocated(&self, slot_id: usize) -> bool {
+        self.tuple_headers
+            .get(slot_id)
+            .is_some_and(|tuple_header| tuple_header.relocated)
+    }
+
+    pub fn set_relocated(&mut self, slot_id: usize) -> Result<(), &str> {
+        let tuple_header = self
+            .tuple_headers
+            .get_mut(slot_id)
+            .ok_or("The slot is out of bounds")?;
+        tuple_header.relocated = true;
+        Ok(())
+    }
+
+    /// Returns true if the data of the tuple at the given slot is a reference to overflow pages.
+    pub fn is_overflow(&self, slot_id: usize) -> bool {
+        self.tuple_headers
+            .get(slot_id)
+            .is_some_and(|tuple_header| tuple_header.overflow)
+    }
+
+    pub fn set_overflow(&mut self, slot_id: usize, overflow: bool) -> Result<(), &str> {
+        let tuple_header = self
+            .tuple_headers
+            .get_mut(slot_id)
+            .ok_or("The slot is out of bounds")?;
+        tuple_header.overflow = overflow;
+        Ok(())
+    }
+
+    /// Replaces the data of the tuple at the given slot while keeping its Rid. The new data is
+    /// written in place if it is not larger than the old data, otherwise it is moved into the free
+    /// space of the page, which is compacted first if necessary. Returns an error and leaves the
+    /// page unchanged if the data does not fit into the page.
+    pub fn update(&mut self, slot_id: usize, tuple_data: Vec<u8>) -> Result<(), &str> {
+        if self.get(slot_id).is_none() {
+            return Err("The requested slot does not contain a tuple");
+        }
+        self.write_slot(slot_id, tuple_data)
+    }
+
+    /// Returns true if the slot holds a tuple and has room for a forwarding pointer.
+    pub fn can_forward(&self, slot_id: usize) -> bool {
+        match self.tuple_headers.get(slot_id) {
+            Some(tuple_header) if !tuple_header.free => {
+                tuple_header.tuple_size + self.available_space() >= FORWARD_SIZE
+            }
+            _ => false,
+        }
+    }
+
+    /// Turns the slot into a forwarding slot pointing to the new location of its tuple.
+    pub fn set_forward(&mut self, slot_id: usize, new_location: Rid) -> Result<(), &str> {
+        match self.tuple_headers.get(slot_id) {
+            Some(tuple_header) if !tuple_header.free => (),
+            _ => return Err("The requested slot does not contain a tuple"),
+        }
+        let mut forward = Vec::with_capacity(FORWARD_SIZE as usize);
+        forward.extend(new_location.page_id.to_le_bytes());
+        forward.extend(new_location.slot_id.to_le_bytes());
+        self.write_slot(slot_id, forward)?;
+        self.tuple_headers[slot_id].forwarded = true;
+        self.tuple_headers[slot_id].overflow = false;
+        Ok(())
+    }
+
+    fn write_slot(&mut self, slot_id: usize, tuple_data: Vec<u8>) -> Result<(), &'static str> {
+        let tuple_size = tuple_data.len() as u16;
+        if tuple_size > self.tuple_headers[slot_id].tuple_size {
+            if tuple_size > self.free_space() {
+                let free_space_after_compaction =
+                    self.available_space() + self.tuple_headers[slot_id].tuple_size;
+                if tuple_size > free_space_after_compaction {
+                    return Err("The updated tuple does not fit into the page");
+                }
+                self.tuple_headers[slot_id].tuple_size = 0;
+                self.compact();
+            }
+            self.free_space_pointer -= tuple_size;
+            self.tuple_headers[slot_id].tuple_offset = self.free_space_pointer;
+        }
+        self.tuple_headers[slot_id].tuple_size = tuple_size;
+        self.tuples[slot_id].data = tuple_data;
+        Ok(())
+    }
+
+    /// Moves the data of all tuples to the end of the page, so that the space of removed tuples
+    /// and of tuples that shrunk is reclaimed. The slot ids of all tuples stay the same.
+    pub fn compact(&mut self) {
+        let mut slot_ids: Vec<usize> = (0..self.tuple_headers.len())
+            .filter(|slot_id| !self.tuple_headers[*slot_id].free)
+            .collect();
+        // Keep the relative order of the tuple data to avoid moving tuples unnecessarily.
+        slot_ids
+            .sort_by_key(|slot_id| std::cmp::Reverse(self.tuple_headers[*slot_id].tuple_offset));
+
+        self.free_space_pointer = PAGE_SIZE as u16;
+        for slot_id in slot_ids {
+            let tuple_header = &mut self.tuple_headers[slot_id];
+            self.free_space_pointer -= tuple_header.tuple_size;
+            tuple_header.tuple_offset = self.free_space_pointer;
+        }
+        for tuple_header in self.tuple_headers.iter_mut().filter(|header| header.free) {
+            tuple_header.tuple_offset = self.free_space_pointer;
+            tuple_header.tuple_size = 0;
+        }
+    }
+
+    /// Drops the removed slots at the end of the page, so that their headers no longer take space.
+    pub fn truncate_free_slots(&mut self) {
+        while self.tuple_headers.last().is_some_and(|header| header.free) {
+            self.tuple_headers.pop();
+            self.tuples.pop();
+            self.tuple_count -= 1;
+        }
+    }
+
+    /// Returns the number of bytes between the tuple headers and the tuple data.
+    pub fn free_space(&self) -> u16 {
+        self.free_space_pointer - TABLE_PAGE_HEADER_SIZE - self.tuple_count * TUPLE_HEADER_SIZE
+    }
+
+    /// Returns the number of bytes that are free after compacting the page, which includes the
+    /// space of removed tuples and the unused space of tuples that shrunk.
+    pub fn available_space(&self) -> u16 {
+        let used_space: u16 = self
+            .tuple_headers
+            .iter()
+            .filter(|header| !header.free)
+            .map(|header| header.tuple_size)
+            .sum();
+        PAGE_SIZE as u16
+            - TABLE_PAGE_HEADER_SIZE
+            - self.tuple_count * TUPLE_HEADER_SIZE
+            - used_space
+    }
+
+    pub fn get_tuple_count(&self) -> u16 {
+        self.tuple_count
+    }
 }
 
 #[test]
@@ -216,6 +513,7 @@ fn test_remove() {
         tuples: vec![Tuple {
             data: vec![0, 1],
             own_rid: Rid::new(0, 0),
+            version: TupleVersion::default(),
         }],
     };
     println!("{:?}", table_page);
@@ -224,7 +522,192 @@ fn test_remove() {
     let expected = Some(Tuple {
         data: vec![0, 1],
         own_rid: Rid::new(0, 0),
+        version: TupleVersion::default(),
     });
     assert_eq!(old_table, expected);
     assert!(table_page.remove(0).is_none());
 }
+
+#[test]
+fn test_insert_into_full_page() {
+    let mut table_page = TablePage::new_empty(0);
+    assert!(table_page.insert(vec![1; MAX_TUPLE_SIZE + 1]).is_none());
+    assert_eq!(table_page.free_space_pointer, PAGE_SIZE as u16);
+
+    assert!(table_page.insert(vec![1; MAX_TUPLE_SIZE]).is_some());
+    assert_eq!(table_page.free_space(), 0);
+    assert!(table_page.insert(vec![]).is_none());
+}
+
+#[test]
+fn test_update() {
+    let mut table_page = TablePage::new_empty(3);
+    table_page.insert(vec![1, 2, 3]);
+    table_page.insert(vec![4]);
+
+    table_page.update(0, vec![5, 6]).unwrap();
+    assert_eq!(table_page.tuple_headers[0].tuple_offset, 4093);
+    assert_eq!(table_page.get(0).unwrap().data, vec![5, 6]);
+
+    table_page.update(1, vec![7, 8, 9]).unwrap();
+    assert_eq!(table_page.tuple_headers[1].tuple_offset, 4089);
+    assert_eq!(table_page.free_space_pointer, 4089);
+
+    let table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert_eq!(table_page.get(0).unwrap().data, vec![5, 6]);
+    assert_eq!(table_page.get(1).unwrap().data, vec![7, 8, 9]);
+    assert_eq!(table_page.get(1).unwrap().own_rid, Rid::new(3, 1));
+}
+
+#[test]
+fn test_raw_page_round_trip_with_removed_tuple() {
+    let mut table_page = TablePage::new_empty(1);
+    table_page.insert(vec![1, 2]);
+    table_page.insert(vec![3, 4]);
+    table_page.remove(0);
+
+    let table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert!(table_page.get(0).is_none());
+    assert_eq!(table_page.get(1).unwrap().data, vec![3, 4]);
+}
+
+#[test]
+fn test_compact() {
+    let mut table_page = TablePage::new_empty(0);
+    table_page.insert(vec![1; 10]);
+    table_page.insert(vec![2; 20]);
+    table_page.insert(vec![3; 30]);
+    table_page.remove(1);
+    table_page.update(2, vec![4; 5]).unwrap();
+    assert_eq!(table_page.free_space_pointer, 4036);
+
+    table_page.compact();
+    assert_eq!(table_page.free_space_pointer, 4081);
+    assert_eq!(table_page.tuple_headers[0].tuple_offset, 4086);
+    assert_eq!(table_page.tuple_headers[2].tuple_offset, 4081);
+
+    let table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert_eq!(table_page.get(0).unwrap().data, vec![1; 10]);
+    assert!(table_page.get(1).is_none());
+    assert_eq!(table_page.get(2).unwrap().data, vec![4; 5]);
+}
+
+#[test]
+fn test_truncate_free_slots() {
+    let mut table_page = TablePage::new_empty(0);
+    table_page.insert(vec![1; 10]);
+    table_page.insert(vec![2; 10]);
+    table_page.insert(vec![3; 10]);
+    table_page.remove(0);
+    table_page.remove(2);
+    table_page.truncate_free_slots();
+    assert_eq!(table_page.tuple_count, 2);
+    assert_eq!(table_page.available_space(), 4096 - 8 - 2 * 32 - 10);
+
+    table_page.remove(1);
+    table_page.truncate_free_slots();
+    assert_eq!(table_page.tuple_count, 0);
+    let table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert_eq!(table_page.tuple_count, 0);
+}
+
+#[test]
+fn test_forward() {
+    let mut table_page = TablePage::new_empty(0);
+    table_page.insert(vec![1; 10]);
+    table_page.insert(vec![2; 2]);
+    table_page.set_forward(0, Rid::new(7, 3)).unwrap();
+    table_page.set_forward(1, Rid::new(8, 1)).unwrap();
+    table_page.set_relocated(1).unwrap();
+
+    let table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert!(table_page.get(0).is_none());
+    assert_eq!(table_page.get_forward(0), Some(Rid::new(7, 3)));
+    assert_eq!(table_page.get_forward(1), Some(Rid::new(8, 1)));
+    assert!(!table_page.is_relocated(0));
+    assert!(table_page.is_relocated(1));
+}
+
+#[test]
+fn test_update_compacts_page() {
+    let mut table_page = TablePage::new_empty(0);
+    table_page.insert(vec![1; 2000]);
+    table_page.insert(vec![2; 2000]);
+    table_page.remove(1);
+
+    assert!(table_page.update(0, vec![3; 4025]).is_err());
+    assert_eq!(table_page.get(0).unwrap().data, vec![1; 2000]);
+
+    table_page.update(0, vec![3; 4012]).unwrap();
+    assert_eq!(table_page.free_space_pointer, 84);
+    assert_eq!(table_page.free_space(), 12);
+}
+
+#[test]
+fn test_versions() {
+    let mut table_page = TablePage::new_empty(2);
+    table_page.insert(vec![1; 10]);
+    table_page.insert(vec![2; 10]);
+    let version = TupleVersion {
+        created_by: Some(3),
+        deleted_by: Some(u64::MAX),
+        previous: Some(Rid::new(7, 1)),
+    };
+    table_page.set_version(1, version).unwrap();
+    table_page.set_forward(1, Rid::new(5, 0)).unwrap();
+
+    let mut table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert_eq!(table_page.get(0).unwrap().version, TupleVersion::default());
+    assert_eq!(table_page.get_version(1), Some(version));
+    table_page.remove(1);
+    assert_eq!(table_page.get_version(1), None);
+    assert!(table_page.set_version(1, version).is_err());
+    table_page.insert(vec![3; 10]);
+    assert_eq!(table_page.get_version(1), Some(TupleVersion::default()));
+}
+
+#[test]
+fn test_insert_reuses_free_slots() {
+    let mut table_page = TablePage::new_empty(4);
+    table_page.insert(vec![1; 1964]);
+    table_page.insert(vec![2; 1964]);
+    table_page.insert(vec![3; 30]);
+    table_page.remove(0);
+    assert_eq!(table_page.free_space(), 34);
+    assert_eq!(table_page.available_space(), 1998);
+
+    // The new tuple only fits after compacting the page.
+    assert_eq!(table_page.insert(vec![4; 1998]), Some(Rid::new(4, 0)));
+    assert_eq!(table_page.tuple_count, 3);
+    assert_eq!(table_page.available_space(), 0);
+    assert_eq!(table_page.get(1).unwrap().data, vec![2; 1964]);
+    assert_eq!(table_page.get(2).unwrap().data, vec![3; 30]);
+
+    table_page.remove(2);
+    table_page.remove(1);
+    assert_eq!(table_page.insert(vec![5; 10]), Some(Rid::new(4, 1)));
+    assert_eq!(table_page.insert(vec![6; 10]), Some(Rid::new(4, 2)));
+    assert_eq!(table_page.insert(vec![7; 10]), Some(Rid::new(4, 3)));
+    assert_eq!(table_page.tuple_count, 4);
+}
+
+#[test]
+fn test_insert_at() {
+    let mut table_page = TablePage::new_empty(4);
+    table_page.insert(vec![1; 10]);
+    assert!(table_page.insert_at(0, vec![2; 10]).is_err());
+
+    // Free slots are added before the slot.
+    table_page.insert_at(2, vec![3; 10]).unwrap();
+    assert_eq!(table_page.tuple_count, 3);
+    let mut table_page = TablePage::from_raw_page(&table_page.to_raw_page()).unwrap();
+    assert!(table_page.get(1).is_none());
+    assert_eq!(table_page.get(2).unwrap().data, vec![3; 10]);
+    assert_eq!(table_page.insert(vec![4; 10]), Some(Rid::new(4, 1)));
+
+    table_page.remove(1);
+    assert!(table_page.insert_at(1, vec![5; 4000]).is_err());
+    table_page.insert_at(1, vec![5; 20]).unwrap();
+    assert_eq!(table_page.get(1).unwrap().data, vec![5; 20]);
+    assert_eq!(table_page.available_space(), 4096 - 8 - 3 * 32 - 40);
+}
@@ -0,0 +1,533 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+    sync::{Arc, Condvar, Mutex, MutexGuard, Weak},
+    thread,
+    time::Duration,
+};
+
+use crate::{recovery::log_record::TransactionId, table::table_page::Rid};
+
+/// How often the background thread looks for deadlocks.
+pub const DEADLOCK_DETECTION_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LockMode {
+    IntentionShared,
+    IntentionExclusive,
+    Shared,
+    /// Shared with the intention to lock rows exclusively, e.g. for a table that is read by a
+    /// scan and changed by an update.
+    SharedIntentionExclusive,
+    Exclusive,
+}
+
+impl LockMode {
+    /// Returns whether two transactions can hold locks of the modes on the same target.
+    pub fn is_compatible(self, other: LockMode) -> bool {
+        use LockMode::*;
+        match (self, other) {
+            (Exclusive, _) | (_, Exclusive) => false,
+            (IntentionShared, _) | (_, IntentionShared) => true,
+            (IntentionExclusive, IntentionExclusive) | (Shared, Shared) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the weakest mode that allows everything both modes allow. A lock is upgraded to
+    /// it when its holder requests another mode.
+    fn combine(self, other: LockMode) -> LockMode {
+        use LockMode::*;
+        match (self, other) {
+            (mode, IntentionShared) | (IntentionShared, mode) => mode,
+            (Exclusive, _) | (_, Exclusive) => Exclusive,
+            (Shared, Shared) => Shared,
+            (IntentionExclusive, IntentionExclusive) => IntentionExclusive,
+            _ => SharedIntentionExclusive,
+        }
+    }
+}
+
+/// What a lock is taken on. Rows are locked with S and X locks after their table was locked
+/// with a lock that allows it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LockTarget {
+    Table(u32),
+    Row(u32, Rid),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockError {
+    /// The transaction was chosen as the victim of a deadlock. It must be rolled back.
+    Deadlock,
+    /// Another transaction is already waiting to upgrade its lock on the target.
+    UpgradeConflict,
+    /// A row was locked without a lock of its table that allows it.
+    TableLockNotHeld,
+    /// A row was locked with an intention lock.
+    IntentionLockOnRow,
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Deadlock => write!(f, "The transaction was aborted to resolve a deadlock"),
+            LockError::UpgradeConflict => {
+                write!(f, "Another transaction is upgrading its lock on the target")
+            }
+            LockError::TableLockNotHeld => write!(f, "The table of the row is not locked"),
+            LockError::IntentionLockOnRow => {
+                write!(f, "Rows cannot be locked with intention locks")
+            }
+        }
+    }
+}
+
+/// The granted and waiting requests for the lock of a target. Requests wait in FIFO order,
+/// except for an upgrade, which is granted before all of them.
+#[derive(Default)]
+struct LockQueue {
+    granted: HashMap<TransactionId, LockMode>,
+    waiting: VecDeque<(TransactionId, LockMode)>,
+    /// The transaction waiting to upgrade its granted lock, and the mode it waits for.
+    upgrading: Option<(TransactionId, LockMode)>,
+}
+
+impl LockQueue {
+    /// Returns whether the request of the transaction is compatible with the locks granted to
+    /// the other transactions.
+    fn is_compatible(&self, transaction: TransactionId, mode: LockMode) -> bool {
+        self.granted
+            .iter()
+            .all(|(holder, granted)| *holder == transaction || granted.is_compatible(mode))
+    }
+
+    /// Returns whether the waiting request of the transaction can be granted: it is compatible
+    /// with the granted locks, a pending upgrade and all requests before it.
+    fn can_grant(&self, transaction: TransactionId, mode: LockMode) -> bool {
+        if !self.is_compatible(transaction, mode) {
+            return false;
+        }
+        if let Some((upgrading, upgrade_mode)) = self.upgrading {
+            if upgrading != transaction && !upgrade_mode.is_compatible(mode) {
+                return false;
+            }
+        }
+        self.waiting
+            .iter()
+            .take_while(|(waiting, _)| *waiting != transaction)
+            .all(|(_, waiting_mode)| waiting_mode.is_compatible(mode))
+    }
+
+    /// Returns the transactions the transaction waits for.
+    fn blockers(&self, transaction: TransactionId, mode: LockMode) -> Vec<TransactionId> {
+        let mut blockers: Vec<TransactionId> = self
+            .granted
+            .iter()
+            .filter(|(holder, granted)| **holder != transaction && !granted.is_compatible(mode))
+            .map(|(holder, _)| *holder)
+            .collect();
+        blockers.extend(
+            self.waiting
+                .iter()
+                .take_while(|(waiting, _)| *waiting != transaction)
+                .filter(|(_, waiting_mode)| !waiting_mode.is_compatible(mode))
+                .map(|(waiting, _)| *waiting),
+        );
+        blockers
+    }
+}
+
+#[derive(Default)]
+struct LockTable {
+    queues: HashMap<LockTarget, LockQueue>,
+    /// The targets each transaction holds or waits for a lock on.
+    targets: HashMap<TransactionId, HashSet<LockTarget>>,
+    /// The victims of deadlocks whose waiting requests must fail.
+    aborted: HashSet<TransactionId>,
+}
+
+/// Grants locks on tables and rows to transactions under strict two-phase locking: a
+/// transaction keeps all of its locks until it commits or rolls back. A request that is not
+/// compatible with the locks of other transactions waits. Deadlocks are resolved by aborting
+/// the youngest transaction of a cycle in the waits-for graph.
+#[derive(Default)]
+pub struct LockManager {
+    lock_table: Mutex<LockTable>,
+    /// Notified whenever locks are released or a transaction is aborted.
+    changed: Condvar,
+}
+
+impl LockManager {
+    pub fn new() -> LockManager {
+        LockManager::default()
+    }
+
+    /// Starts a thread that looks for deadlocks at the interval until the lock manager is
+    /// dropped.
+    pub fn start_deadlock_detection(lock_manager: &Arc<LockManager>, interval: Duration) {
+        let lock_manager: Weak<LockManager> = Arc::downgrade(lock_manager);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match lock_manager.upgrade() {
+                Some(lock_manager) => {
+                    lock_manager.detect_deadlocks();
+                }
+                None => break,
+            }
+        });
+    }
+
+    pub fn lock_table(
+        &self,
+        transaction: TransactionId,
+        table_id: u32,
+        mode: LockMode,
+    ) -> Result<(), LockError> {
+        self.lock(transaction, LockTarget::Table(table_id), mode)
+    }
+
+    /// Locks a row with an S or X lock. The transaction must hold a lock of the table that
+    /// allows it: any lock for S, and IX, SIX or X for X.
+    pub fn lock_row(
+        &self,
+        transaction: TransactionId,
+        table_id: u32,
+        rid: Rid,
+        mode: LockMode,
+    ) -> Result<(), LockError> {
+        let table_lock = self.held_mode(transaction, LockTarget::Table(table_id));
+        let allowed = match (mode, table_lock) {
+            (LockMode::Shared, Some(_)) => true,
+            (LockMode::Exclusive, Some(table_lock)) => matches!(
+                table_lock,
+                LockMode::IntentionExclusive
+                    | LockMode::SharedIntentionExclusive
+                    | LockMode::Exclusive
+            ),
+            (LockMode::Shared | LockMode::Exclusive, None) => false,
+            _ => return Err(LockError::IntentionLockOnRow),
+        };
+        if !allowed {
+            return Err(LockError::TableLockNotHeld);
+        }
+        self.lock(transaction, LockTarget::Row(table_id, rid), mode)
+    }
+
+    /// Returns the mode of the lock the transaction holds on the target.
+    pub fn held_mode(&self, transaction: TransactionId, target: LockTarget) -> Option<LockMode> {
+        self.lock_lock_table()
+            .queues
+            .get(&target)
+            .and_then(|queue| queue.granted.get(&transaction).copied())
+    }
+
+    /// Acquires a lock on the target, or upgrades the lock the transaction holds on it, and
+    /// waits until it is granted.
+    fn lock(
+        &self,
+        transaction: TransactionId,
+        target: LockTarget,
+        mode: LockMode,
+    ) -> Result<(), LockError> {
+        let mut lock_table = self.lock_lock_table();
+        if lock_table.aborted.contains(&transaction) {
+            return Err(LockError::Deadlock);
+        }
+        let queue = lock_table.queues.entry(target).or_default();
+        let upgrade = match queue.granted.get(&transaction) {
+            Some(&granted) if granted.combine(mode) == granted => return Ok(()),
+            Some(&granted) => {
+                if queue.upgrading.is_some() {
+                    return Err(LockError::UpgradeConflict);
+                }
+                queue.upgrading = Some((transaction, granted.combine(mode)));
+                true
+            }
+            None => {
+                queue.waiting.push_back((transaction, mode));
+                false
+            }
+        };
+        lock_table
+            .targets
+            .entry(transaction)
+            .or_default()
+            .insert(target);
+
+        loop {
+            let aborted = lock_table.aborted.contains(&transaction);
+            let queue = lock_table
+                .queues
+                .get_mut(&target)
+                .expect("The queue of a waiting request exists");
+            if upgrade {
+                let (_, mode) = queue.upgrading.expect("The upgrade is pending");
+                if aborted || queue.is_compatible(transaction, mode) {
+                    queue.upgrading = None;
+                    if !aborted {
+                        queue.granted.insert(transaction, mode);
+                    }
+                }
+            } else if aborted || queue.can_grant(transaction, mode) {
+                queue.waiting.retain(|(waiting, _)| *waiting != transaction);
+                if !aborted {
+                    queue.granted.insert(transaction, mode);
+                }
+            }
+            let waiting = match upgrade {
+                true => queue.upgrading.is_some(),
+                false => !queue.granted.contains_key(&transaction),
+            };
+            if aborted {
+                // Requests behind the failed one may be grantable now.
+                self.changed.notify_all();
+                return Err(LockError::Deadlock);
+            }
+            if !waiting {
+                return Ok(());
+            }
+            lock_table = self
+                .changed
+                .wait(lock_table)
+                .expect("Could not lock the lock table");
+        }
+    }
+
+    /// Releases all locks of the transaction when it commits or rolls back.
+    pub fn release_all(&self, transaction: TransactionId) {
+        let mut lock_table = self.lock_lock_table();
+        lock_table.aborted.remove(&transaction);
+        for target in lock_table.targets.remove(&transaction).unwrap_or_default() {
+            // The queue is gone if the transaction only had a request that failed.
+            let Some(queue) = lock_table.queues.get_mut(&target) else {
+                continue;
+            };
+            queue.granted.remove(&transaction);
+            if queue.granted.is_empty() && queue.waiting.is_empty() && queue.upgrading.is_none() {
+                lock_table.queues.remove(&target);
+            }
+        }
+        self.changed.notify_all();
+    }
+
+    /// Aborts the youngest transaction of every cycle in the waits-for graph and returns the
+    /// victims. Their waiting requests fail with [LockError::Deadlock].
+    pub fn detect_deadlocks(&self) -> Vec<TransactionId> {
+        let mut lock_table = self.lock_lock_table();
+        let mut waits_for: HashMap<TransactionId, Vec<TransactionId>> = HashMap::new();
+        for queue in lock_table.queues.values() {
+            let requests = queue.upgrading.iter().chain(queue.waiting.iter());
+            for &(transaction, mode) in requests {
+                if lock_table.aborted.contains(&transaction) {
+                    continue;
+                }
+                waits_for
+                    .entry(transaction)
+                    .or_default()
+                    .extend(queue.blockers(transaction, mode));
+            }
+        }
+        let mut victims = Vec::new();
+        while let Some(cycle) = find_cycle(&waits_for) {
+            let victim = *cycle.iter().max().expect("A cycle is not empty");
+            waits_for.remove(&victim);
+            victims.push(victim);
+        }
+        if !victims.is_empty() {
+            lock_table.aborted.extend(victims.iter().copied());
+            self.changed.notify_all();
+        }
+        victims
+    }
+
+    fn lock_lock_table(&self) -> MutexGuard<'_, LockTable> {
+        self.lock_table
+            .lock()
+            .expect("Could not lock the lock table")
+    }
+}
+
+/// Returns the transactions of a cycle in the waits-for graph, if there is one. The graph is
+/// searched in the order of the transaction ids, so that the same cycle is found every time.
+fn find_cycle(
+    waits_for: &HashMap<TransactionId, Vec<TransactionId>>,
+) -> Option<Vec<TransactionId>> {
+    fn visit(
+        transaction: TransactionId,
+        waits_for: &HashMap<TransactionId, Vec<TransactionId>>,
+        path: &mut Vec<TransactionId>,
+        done: &mut HashSet<TransactionId>,
+    ) -> Option<Vec<TransactionId>> {
+        if let Some(start) = path.iter().position(|&on_path| on_path == transaction) {
+            return Some(path[start..].to_vec());
+        }
+        if !done.insert(transaction) {
+            return None;
+        }
+        path.push(transaction);
+        let mut blockers = waits_for.get(&transaction).cloned().unwrap_or_default();
+        blockers.sort_unstable();
+        for blocker in blockers {
+            if let Some(cycle) = visit(blocker, waits_for, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+
+    let mut transactions: Vec<TransactionId> = waits_for.keys().copied().collect();
+    transactions.sort_unstable();
+    let mut done = HashSet::new();
+    transactions
+        .into_iter()
+        .find_map(|transaction| visit(transaction, waits_for, &mut Vec::new(), &mut done))
+}
+
+#[cfg(test)]
+mod lock_manager_tests {
+    use std::{
+        sync::{mpsc, Arc},
+        thread,
+        time::Duration,
+    };
+
+    use crate::table::table_page::Rid;
+
+    use super::{LockError, LockManager, LockMode, LockTarget};
+
+    #[test]
+    fn lock_mode_compatibility() {
+        use LockMode::*;
+        let modes = [
+            IntentionShared,
+            IntentionExclusive,
+            Shared,
+            SharedIntentionExclusive,
+            Exclusive,
+        ];
+        let expected = [
+            [true, true, true, true, false],
+            [true, true, false, false, false],
+            [true, false, true, false, false],
+            [true, false, false, false, false],
+            [false, false, false, false, false],
+        ];
+        for (i, first) in modes.iter().enumerate() {
+            for (j, second) in modes.iter().enumerate() {
+                assert_eq!(first.is_compatible(*second), expected[i][j]);
+            }
+        }
+        assert_eq!(Shared.combine(IntentionExclusive), SharedIntentionExclusive);
+        assert_eq!(IntentionShared.combine(Shared), Shared);
+        assert_eq!(Exclusive.combine(IntentionShared), Exclusive);
+    }
+
+    #[test]
+    fn exclusive_lock_waits_for_shared_locks() {
+        let lock_manager = Arc::new(LockManager::new());
+        lock_manager.lock_table(1, 7, LockMode::Shared).unwrap();
+        lock_manager
+            .lock_table(2, 7, LockMode::IntentionShared)
+            .unwrap();
+        assert_eq!(
+            lock_manager.lock_row(2, 7, Rid::new(1, 0), LockMode::Exclusive),
+            Err(LockError::TableLockNotHeld)
+        );
+        assert_eq!(
+            lock_manager.lock_row(2, 7, Rid::new(1, 0), LockMode::IntentionShared),
+            Err(LockError::IntentionLockOnRow)
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        let waiting = {
+            let lock_manager = lock_manager.clone();
+            thread::spawn(move || {
+                lock_manager.lock_table(3, 7, LockMode::Exclusive).unwrap();
+                sender.send(()).unwrap();
+            })
+        };
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        lock_manager.release_all(1);
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        lock_manager.release_all(2);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        waiting.join().unwrap();
+        assert_eq!(
+            lock_manager.held_mode(3, LockTarget::Table(7)),
+            Some(LockMode::Exclusive)
+        );
+    }
+
+    #[test]
+    fn lock_upgrade_waits_for_other_holders() {
+        let lock_manager = Arc::new(LockManager::new());
+        let rid = Rid::new(1, 3);
+        for transaction in [1, 2] {
+            lock_manager
+                .lock_table(transaction, 7, LockMode::IntentionExclusive)
+                .unwrap();
+            lock_manager
+                .lock_row(transaction, 7, rid, LockMode::Shared)
+                .unwrap();
+        }
+        // The lock already allows S.
+        lock_manager
+            .lock_table(1, 7, LockMode::IntentionShared)
+            .unwrap();
+
+        let upgrading = {
+            let lock_manager = lock_manager.clone();
+            thread::spawn(move || lock_manager.lock_row(1, 7, rid, LockMode::Exclusive))
+        };
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(
+            lock_manager.held_mode(1, LockTarget::Row(7, rid)),
+            Some(LockMode::Shared)
+        );
+        assert_eq!(
+            lock_manager.lock_row(2, 7, rid, LockMode::Exclusive),
+            Err(LockError::UpgradeConflict)
+        );
+        lock_manager.release_all(2);
+        assert_eq!(upgrading.join().unwrap(), Ok(()));
+        assert_eq!(
+            lock_manager.held_mode(1, LockTarget::Row(7, rid)),
+            Some(LockMode::Exclusive)
+        );
+    }
+
+    #[test]
+    fn deadlock_aborts_youngest_transaction() {
+        let lock_manager = Arc::new(LockManager::new());
+        LockManager::start_deadlock_detection(&lock_manager, Duration::from_millis(10));
+        let (first, second) = (Rid::new(1, 0), Rid::new(1, 1));
+        for (transaction, rid) in [(1, first), (2, second)] {
+            lock_manager
+                .lock_table(transaction, 7, LockMode::IntentionExclusive)
+                .unwrap();
+            lock_manager
+                .lock_row(transaction, 7, rid, LockMode::Exclusive)
+                .unwrap();
+        }
+
+        let older = {
+            let lock_manager = lock_manager.clone();
+            thread::spawn(move || lock_manager.lock_row(1, 7, second, LockMode::Exclusive))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(
+            lock_manager.lock_row(2, 7, first, LockMode::Exclusive),
+            Err(LockError::Deadlock)
+        );
+        // The victim is rolled back, which releases its locks.
+        lock_manager.release_all(2);
+        assert_eq!(older.join().unwrap(), Ok(()));
+        assert!(lock_manager.detect_deadlocks().is_empty());
+        assert_eq!(
+            lock_manager.held_mode(1, LockTarget::Row(7, second)),
+            Some(LockMode::Exclusive)
+        );
+    }
+}
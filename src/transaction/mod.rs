@@ -0,0 +1,4 @@
+pub mod lock_manager;
+pub mod transaction_manager;
+pub mod transaction_state;
+pub mod vacuum;
@@ -0,0 +1,463 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    catalog::system_catalog::{Catalog, CatalogError, IndexInfo},
+    disk_management::buffer_pool::BufferPool,
+    execution::executor::{ExecutionContext, ExecutionError, TableModifier},
+    recovery::{checkpoint::checkpoint_if_due, log_record::TransactionId},
+};
+
+use super::{
+    lock_manager::{LockManager, DEADLOCK_DETECTION_INTERVAL},
+    transaction_state::{IsolationLevel, Snapshot, Transaction, WriteRecord},
+};
+
+/// Starts, commits and rolls back transactions. The changes of a transaction are logged under
+/// its id, so that a crash undoes them unless it committed; its changes of rows are also
+/// recorded in its write set, so that a rollback can undo them while the database runs. The
+/// locks of a transaction are released once it committed or rolled back.
+///
+/// Every transaction reads from the snapshot taken when it began. Concurrent updates of the same
+/// row are prevented by the row locks and the versions of rows; concurrent changes of rows that
+/// a serializable transaction read are detected when it commits.
+pub struct TransactionManager {
+    buffer_pool: Arc<Mutex<BufferPool>>,
+    lock_manager: Arc<LockManager>,
+    next_transaction_id: TransactionId,
+    active: HashMap<TransactionId, Arc<Transaction>>,
+    /// The committed transactions that are concurrent to an active transaction, with the ids of
+    /// the tables they changed.
+    committed: Vec<(TransactionId, HashSet<u32>)>,
+}
+
+impl TransactionManager {
+    /// Creates a transaction manager whose ids follow those already in the log of the buffer
+    /// pool.
+    pub fn new(buffer_pool: Arc<Mutex<BufferPool>>) -> TransactionManager {
+        let next_transaction_id = buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .log_manager()
+            .map(|log_manager| log_manager.lock().unwrap().next_transaction_id())
+            .unwrap_or(1);
+        let lock_manager = Arc::new(LockManager::new());
+        LockManager::start_deadlock_detection(&lock_manager, DEADLOCK_DETECTION_INTERVAL);
+        TransactionManager {
+            buffer_pool,
+            lock_manager,
+            next_transaction_id,
+            active: HashMap::new(),
+            committed: Vec::new(),
+        }
+    }
+
+    pub fn lock_manager(&self) -> Arc<LockManager> {
+        self.lock_manager.clone()
+    }
+
+    /// Starts a transaction and takes its snapshot.
+    pub fn begin(&mut self, isolation_level: IsolationLevel) -> Arc<Transaction> {
+        let snapshot = Snapshot {
+            transaction: self.next_transaction_id,
+            active: self.active.keys().copied().collect(),
+        };
+        let transaction = Arc::new(Transaction::new(isolation_level, snapshot));
+        self.buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .begin_transaction(transaction.id());
+        self.next_transaction_id += 1;
+        self.active.insert(transaction.id(), transaction.clone());
+        transaction
+    }
+
+    /// Returns the oldest transaction that the snapshot of an active transaction does not see.
+    /// The versions deleted by transactions before it can be vacuumed.
+    pub fn vacuum_horizon(&self) -> TransactionId {
+        self.active
+            .values()
+            .flat_map(|transaction| {
+                let snapshot = transaction.snapshot();
+                snapshot
+                    .active
+                    .iter()
+                    .copied()
+                    .chain([snapshot.transaction])
+            })
+            .min()
+            .unwrap_or(self.next_transaction_id)
+    }
+
+    /// Commits the transaction. Its changes survive a crash once this returns and can no longer
+    /// be rolled back.
+    ///
+    /// A serializable transaction fails with a serialization failure if a concurrent transaction
+    /// committed changes to a table it read. It then stays active and must be rolled back.
+    pub fn commit(&mut self, transaction: &Transaction) -> Result<(), ExecutionError> {
+        if transaction.isolation_level() == IsolationLevel::Serializable {
+            self.validate(transaction)?;
+        }
+        let written_tables = transaction.written_tables();
+        transaction.take_writes_after(0);
+        let buffer_pool = self.buffer_pool.clone();
+        let buffer_pool_lock = buffer_pool.lock().expect("Could not lock buffer pool");
+        let result = buffer_pool_lock
+            .commit_transaction(transaction.id())
+            .map_err(ExecutionError::Storage);
+        self.lock_manager.release_all(transaction.id());
+        self.end(transaction.id(), Some(written_tables));
+        result?;
+        checkpoint_if_due(&buffer_pool_lock).map_err(ExecutionError::Storage)
+    }
+
+    /// Fails if a transaction that is not in the snapshot of the transaction committed changes
+    /// to a table the transaction read.
+    fn validate(&self, transaction: &Transaction) -> Result<(), ExecutionError> {
+        let read_tables = transaction.read_tables();
+        let conflict = self.committed.iter().any(|(committed, written_tables)| {
+            !transaction.snapshot().sees(*committed) && !written_tables.is_disjoint(&read_tables)
+        });
+        match conflict {
+            true => Err(ExecutionError::SerializationFailure),
+            false => Ok(()),
+        }
+    }
+
+    /// Removes the transaction from the active ones and forgets the committed transactions that
+    /// no active transaction is concurrent to anymore.
+    fn end(&mut self, transaction: TransactionId, written_tables: Option<HashSet<u32>>) {
+        self.active.remove(&transaction);
+        if let Some(written_tables) = written_tables {
+            self.committed.push((transaction, written_tables));
+        }
+        let active = &self.active;
+        self.committed.retain(|(committed, _)| {
+            active
+                .values()
+                .any(|transaction| !transaction.snapshot().sees(*committed))
+        });
+    }
+
+    /// Undoes all changes of the transaction and ends it. The tables it changed must still
+    /// exist in the catalog.
+    pub fn rollback(
+        &mut self,
+        transaction: &Transaction,
+        catalog: &Catalog,
+    ) -> Result<(), ExecutionError> {
+        self.buffer_pool
+            .lock()
+            .expect("Could not lock buffer pool")
+            .abort_transaction(transaction.id());
+        let result = self.undo(transaction.take_writes_after(0), catalog);
+        let buffer_pool = self.buffer_pool.clone();
+        let buffer_pool_lock = buffer_pool.lock().expect("Could not lock buffer pool");
+        buffer_pool_lock.end_transaction(transaction.id());
+        self.lock_manager.release_all(transaction.id());
+        self.end(transaction.id(), None);
+        checkpoint_if_due(&buffer_pool_lock).map_err(ExecutionError::Storage)?;
+        result
+    }
+
+    /// Undoes the changes the transaction made after the savepoint, e.g. those of a failed
+    /// statement. The transaction stays active.
+    pub fn rollback_to_savepoint(
+        &self,
+        transaction: &Transaction,
+        savepoint: usize,
+        catalog: &Catalog,
+    ) -> Result<(), ExecutionError> {
+        self.undo(transaction.take_writes_after(savepoint), catalog)
+    }
+
+    /// Undoes the changes of the transaction in reverse order: the versions it created are
+    /// removed and those it deleted become current again. The undoing changes are neither
+    /// recorded in a write set nor logged for the transaction: if it does not end before a crash,
+    /// recovery undoes its logical changes again, which skips those already undone.
+    fn undo(&self, writes: Vec<WriteRecord>, catalog: &Catalog) -> Result<(), ExecutionError> {
+        let context = ExecutionContext::new(self.buffer_pool.clone());
+        let mut modifiers: HashMap<u32, TableModifier> = HashMap::new();
+        for write in writes.into_iter().rev() {
+            let modifier = match modifiers.entry(write.table_id()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let table_id = *entry.key();
+                    let table = catalog
+                        .get_table_by_id(table_id)
+                        .ok_or_else(|| CatalogError::TableNotFound(table_id.to_string()))?;
+                    let indexes: Vec<IndexInfo> = catalog
+                        .get_table_indexes(table_id)
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    entry.insert(TableModifier::new(&context, table, &indexes)?)
+                }
+            };
+            match write {
+                WriteRecord::Insert { rid, row, .. } => modifier.remove_version(rid, &row)?,
+                WriteRecord::Update {
+                    rid,
+                    new_rid,
+                    old_row,
+                    new_row,
+                    ..
+                } => modifier.undo_update(rid, &old_row, new_rid, &new_row)?,
+                WriteRecord::Delete { rid, .. } => modifier.restore_version(rid)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod transaction_manager_tests {
+    use std::{
+        slice,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        catalog::system_catalog::{Catalog, IndexInfo, IndexType, TableInfo},
+        disk_management::{buffer_pool::BufferPool, test_utils::new_test_buffer_pool},
+        execution::executor::{
+            collect_rows, create_executor, ExecutionContext, ExecutionError, TableModifier,
+        },
+        index::table_index::Index,
+        planner::plan::PlanNode,
+        transaction::transaction_state::{IsolationLevel, Transaction},
+        types::{
+            row::Row,
+            schema::{Column, DataType, Schema},
+            value::Value,
+        },
+    };
+
+    use super::TransactionManager;
+
+    fn row(id: i32, name: &str) -> Row {
+        Row::new(vec![Value::Integer(id), Value::String(name.to_string())])
+    }
+
+    /// Returns the current versions of the rows of the table.
+    fn rows(catalog: &Catalog) -> Vec<Row> {
+        let table = catalog.get_table("users").unwrap();
+        let mut rows: Vec<Row> = catalog
+            .get_table_heap(table)
+            .iter()
+            .map(Result::unwrap)
+            .filter(|(_, tuple)| tuple.version.deleted_by.is_none())
+            .map(|(_, tuple)| Row::from_tuple_data(&tuple.data, &table.schema).unwrap())
+            .collect();
+        rows.sort_by_key(|row| format!("{:?}", row.values));
+        rows
+    }
+
+    fn create_users(buffer_pool: &Arc<Mutex<BufferPool>>) -> (Catalog, TableInfo, IndexInfo) {
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        catalog
+            .create_table(
+                "users",
+                Schema::new(vec![
+                    Column::new("id", DataType::Integer, false),
+                    Column::new("name", DataType::Text, true),
+                ]),
+                None,
+            )
+            .unwrap();
+        let index_info = catalog
+            .create_index("users_id", "users", &["id"], IndexType::Hash, true, None)
+            .unwrap()
+            .clone();
+        let table = catalog.get_table("users").unwrap().clone();
+        (catalog, table, index_info)
+    }
+
+    fn transaction_context(
+        transaction_manager: &TransactionManager,
+        transaction: &Arc<Transaction>,
+    ) -> ExecutionContext {
+        ExecutionContext::with_transaction(
+            transaction_manager.buffer_pool.clone(),
+            transaction.clone(),
+            transaction_manager.lock_manager(),
+        )
+    }
+
+    /// Returns the rows of the table the transaction sees.
+    fn scan(
+        transaction_manager: &TransactionManager,
+        transaction: &Arc<Transaction>,
+        table: &TableInfo,
+    ) -> Vec<Row> {
+        let plan = PlanNode::SeqScan {
+            table: table.clone(),
+        };
+        let context = transaction_context(transaction_manager, transaction);
+        let mut executor = create_executor(&plan, &context).unwrap();
+        let mut rows = collect_rows(executor.as_mut()).unwrap();
+        rows.sort_by_key(|row| format!("{:?}", row.values));
+        rows
+    }
+
+    #[test]
+    fn rollback_undoes_changes_of_rows_and_indexes() {
+        let buffer_pool = new_test_buffer_pool("transaction_rollback");
+        let (catalog, table, index_info) = create_users(&buffer_pool);
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+
+        let transaction = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &transaction);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        let ada = modifier.insert(&row(1, "ada")).unwrap();
+        let alan = modifier.insert(&row(2, "alan")).unwrap();
+        transaction_manager.commit(&transaction).unwrap();
+        assert!(transaction.write_set().is_empty());
+
+        let first_id = transaction.id();
+        let transaction = transaction_manager.begin(IsolationLevel::default());
+        assert!(transaction.id() > first_id);
+        let context = transaction_context(&transaction_manager, &transaction);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        let updated_ada = modifier
+            .update(ada, &row(1, "ada"), &row(10, "ada"))
+            .unwrap();
+        assert_ne!(updated_ada, ada);
+        modifier.delete(updated_ada, &row(10, "ada")).unwrap();
+        modifier.delete(alan, &row(2, "alan")).unwrap();
+        let savepoint = transaction.savepoint();
+        modifier.insert(&row(1, "grace")).unwrap();
+        assert_eq!(transaction.write_set().len(), 4);
+
+        transaction_manager
+            .rollback_to_savepoint(&transaction, savepoint, &catalog)
+            .unwrap();
+        assert_eq!(transaction.write_set().len(), 3);
+        assert!(rows(&catalog).is_empty());
+
+        transaction_manager
+            .rollback(&transaction, &catalog)
+            .unwrap();
+        assert_eq!(rows(&catalog), vec![row(1, "ada"), row(2, "alan")]);
+        let index = Index::open(buffer_pool.clone(), &index_info).unwrap();
+        assert_eq!(index.scan_key(&[Value::Integer(1)]), vec![ada]);
+        assert_eq!(index.scan_key(&[Value::Integer(2)]), vec![alan]);
+        assert!(index.scan_key(&[Value::Integer(10)]).is_empty());
+    }
+
+    #[test]
+    fn snapshot_hides_concurrent_changes() {
+        let buffer_pool = new_test_buffer_pool("transaction_snapshot");
+        let (_catalog, table, index_info) = create_users(&buffer_pool);
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+        let setup = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &setup);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        let ada = modifier.insert(&row(1, "ada")).unwrap();
+        transaction_manager.commit(&setup).unwrap();
+
+        let reader = transaction_manager.begin(IsolationLevel::default());
+        let writer = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &writer);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        modifier
+            .update(ada, &row(1, "ada"), &row(1, "grace"))
+            .unwrap();
+        modifier.insert(&row(2, "alan")).unwrap();
+        assert_eq!(
+            scan(&transaction_manager, &writer, &table),
+            vec![row(1, "grace"), row(2, "alan")]
+        );
+        assert_eq!(
+            scan(&transaction_manager, &reader, &table),
+            vec![row(1, "ada")]
+        );
+
+        // The reader keeps its snapshot after the writer committed.
+        transaction_manager.commit(&writer).unwrap();
+        assert_eq!(
+            scan(&transaction_manager, &reader, &table),
+            vec![row(1, "ada")]
+        );
+        transaction_manager.commit(&reader).unwrap();
+        let transaction = transaction_manager.begin(IsolationLevel::default());
+        assert_eq!(
+            scan(&transaction_manager, &transaction, &table),
+            vec![row(1, "grace"), row(2, "alan")]
+        );
+    }
+
+    #[test]
+    fn first_committer_wins() {
+        let buffer_pool = new_test_buffer_pool("transaction_first_committer");
+        let (catalog, table, index_info) = create_users(&buffer_pool);
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+        let setup = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &setup);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        let ada = modifier.insert(&row(1, "ada")).unwrap();
+        transaction_manager.commit(&setup).unwrap();
+
+        let first = transaction_manager.begin(IsolationLevel::default());
+        let second = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &first);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        modifier
+            .update(ada, &row(1, "ada"), &row(1, "grace"))
+            .unwrap();
+        transaction_manager.commit(&first).unwrap();
+
+        // The second transaction still sees the version the first one replaced.
+        let context = transaction_context(&transaction_manager, &second);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        assert_eq!(
+            modifier.update(ada, &row(1, "ada"), &row(1, "alan")),
+            Err(ExecutionError::SerializationFailure)
+        );
+        assert_eq!(
+            modifier.delete(ada, &row(1, "ada")),
+            Err(ExecutionError::SerializationFailure)
+        );
+        transaction_manager.rollback(&second, &catalog).unwrap();
+        assert_eq!(rows(&catalog), vec![row(1, "grace")]);
+    }
+
+    #[test]
+    fn serializable_transactions_detect_write_skew() {
+        let buffer_pool = new_test_buffer_pool("transaction_write_skew");
+        let (catalog, table, index_info) = create_users(&buffer_pool);
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+        let setup = transaction_manager.begin(IsolationLevel::default());
+        let context = transaction_context(&transaction_manager, &setup);
+        let modifier = TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+        let ada = modifier.insert(&row(1, "ada")).unwrap();
+        let alan = modifier.insert(&row(2, "alan")).unwrap();
+        transaction_manager.commit(&setup).unwrap();
+
+        // Both transactions read both rows and delete the one the other does not delete.
+        let first = transaction_manager.begin(IsolationLevel::Serializable);
+        let second = transaction_manager.begin(IsolationLevel::Serializable);
+        assert_eq!(scan(&transaction_manager, &first, &table).len(), 2);
+        assert_eq!(scan(&transaction_manager, &second, &table).len(), 2);
+        for (transaction, rid, old_row) in [
+            (&first, ada, row(1, "ada")),
+            (&second, alan, row(2, "alan")),
+        ] {
+            let context = transaction_context(&transaction_manager, transaction);
+            let modifier =
+                TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap();
+            modifier.delete(rid, &old_row).unwrap();
+        }
+        transaction_manager.commit(&first).unwrap();
+        assert_eq!(
+            transaction_manager.commit(&second),
+            Err(ExecutionError::SerializationFailure)
+        );
+        transaction_manager.rollback(&second, &catalog).unwrap();
+        assert_eq!(rows(&catalog), vec![row(2, "alan")]);
+        assert!(transaction_manager.active.is_empty());
+        assert!(transaction_manager.committed.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use crate::{
+    recovery::log_record::TransactionId,
+    table::table_page::{Rid, TupleVersion},
+    types::row::Row,
+};
+
+/// How a transaction is isolated from concurrent transactions. Both levels read from a snapshot
+/// and abort a transaction that updates a row a concurrent transaction updated first.
+/// Serializable transactions are also aborted on commit if a concurrent transaction committed
+/// changes to a table they read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    #[default]
+    SnapshotIsolation,
+    Serializable,
+}
+
+/// The transactions whose changes a transaction sees: those that committed before it began, and
+/// its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub transaction: TransactionId,
+    /// The transactions that were active when the snapshot was taken.
+    pub active: HashSet<TransactionId>,
+}
+
+impl Snapshot {
+    /// Returns true if the changes of the given transaction are visible. Transactions with a
+    /// greater id began after the snapshot was taken.
+    pub fn sees(&self, transaction: TransactionId) -> bool {
+        transaction == self.transaction
+            || (transaction < self.transaction && !self.active.contains(&transaction))
+    }
+
+    /// Returns true if the version of a tuple is visible. Versions without a creator were
+    /// written outside of transactions and are visible to all.
+    pub fn is_visible(&self, version: TupleVersion) -> bool {
+        self.sees_creation(version)
+            && !version
+                .deleted_by
+                .is_some_and(|deleted_by| self.sees(deleted_by))
+    }
+
+    /// Returns true if the creation of the version of a tuple is visible. Otherwise, the previous
+    /// version of the row may be visible instead.
+    pub fn sees_creation(&self, version: TupleVersion) -> bool {
+        version
+            .created_by
+            .is_none_or(|created_by| self.sees(created_by))
+    }
+}
+
+/// A change of a row by a transaction, with the values needed to undo it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WriteRecord {
+    Insert {
+        table_id: u32,
+        rid: Rid,
+        row: Row,
+    },
+    /// An update replaces the version at rid by a new version at new_rid.
+    Update {
+        table_id: u32,
+        rid: Rid,
+        new_rid: Rid,
+        old_row: Row,
+        new_row: Row,
+    },
+    Delete {
+        table_id: u32,
+        rid: Rid,
+        row: Row,
+    },
+}
+
+impl WriteRecord {
+    pub fn table_id(&self) -> u32 {
+        match self {
+            WriteRecord::Insert { table_id, .. }
+            | WriteRecord::Update { table_id, .. }
+            | WriteRecord::Delete { table_id, .. } => *table_id,
+        }
+    }
+}
+
+/// A unit of work whose changes of rows are committed or rolled back together. Transactions are
+/// started by the [super::transaction_manager::TransactionManager].
+#[derive(Debug)]
+pub struct Transaction {
+    id: TransactionId,
+    isolation_level: IsolationLevel,
+    snapshot: Snapshot,
+    /// The changes of rows, in the order they were made.
+    write_set: Mutex<Vec<WriteRecord>>,
+    /// The ids of the tables the transaction read rows of.
+    read_tables: Mutex<HashSet<u32>>,
+}
+
+impl Transaction {
+    pub fn new(isolation_level: IsolationLevel, snapshot: Snapshot) -> Transaction {
+        Transaction {
+            id: snapshot.transaction,
+            isolation_level,
+            snapshot,
+            write_set: Mutex::new(Vec::new()),
+            read_tables: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
+    pub fn isolation_level(&self) -> IsolationLevel {
+        self.isolation_level
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    pub fn record_read(&self, table_id: u32) {
+        self.read_tables
+            .lock()
+            .expect("Could not lock read set")
+            .insert(table_id);
+    }
+
+    pub fn read_tables(&self) -> HashSet<u32> {
+        self.read_tables
+            .lock()
+            .expect("Could not lock read set")
+            .clone()
+    }
+
+    /// Returns the ids of the tables the transaction changed rows of.
+    pub fn written_tables(&self) -> HashSet<u32> {
+        self.write_set
+            .lock()
+            .expect("Could not lock write set")
+            .iter()
+            .map(WriteRecord::table_id)
+            .collect()
+    }
+
+    pub fn record_write(&self, record: WriteRecord) {
+        self.write_set
+            .lock()
+            .expect("Could not lock write set")
+            .push(record);
+    }
+
+    pub fn write_set(&self) -> Vec<WriteRecord> {
+        self.write_set
+            .lock()
+            .expect("Could not lock write set")
+            .clone()
+    }
+
+    /// Returns a savepoint: rolling back to it undoes all changes made after it.
+    pub fn savepoint(&self) -> usize {
+        self.write_set
+            .lock()
+            .expect("Could not lock write set")
+            .len()
+    }
+
+    /// Removes the changes made after the savepoint from the write set and returns them.
+    pub fn take_writes_after(&self, savepoint: usize) -> Vec<WriteRecord> {
+        let mut write_set = self.write_set.lock().expect("Could not lock write set");
+        let savepoint = savepoint.min(write_set.len());
+        write_set.split_off(savepoint)
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    catalog::system_catalog::{IndexInfo, TableInfo},
+    disk_management::buffer_pool::BufferPool,
+    execution::executor::{ExecutionError, TableIndex},
+    index::table_index::index_key,
+    recovery::log_record::TransactionId,
+    table::{table_heap::TableHeap, table_page::TupleVersion},
+    types::row::Row,
+};
+
+/// Returns true if the version of a row was deleted by a transaction before the horizon. Every
+/// active snapshot sees such a deletion, so no active or future transaction sees the version.
+pub fn is_dead(version: TupleVersion, horizon: TransactionId) -> bool {
+    version
+        .deleted_by
+        .is_some_and(|deleted_by| deleted_by < horizon)
+}
+
+/// Removes the dead versions of the rows of a table, compacts its pages and removes the index
+/// entries of the removed versions. Returns the number of removed versions. The horizon must
+/// not be newer than the oldest transaction an active snapshot does not see, and no transaction
+/// may change the table meanwhile.
+pub fn vacuum_table(
+    buffer_pool: &Arc<Mutex<BufferPool>>,
+    table: &TableInfo,
+    indexes: &[IndexInfo],
+    horizon: TransactionId,
+    transaction: Option<TransactionId>,
+) -> Result<usize, ExecutionError> {
+    let indexes: Vec<TableIndex> = indexes
+        .iter()
+        .map(|index| TableIndex::open(buffer_pool, index))
+        .collect::<Result<_, _>>()?;
+    let table_heap = TableHeap::new(buffer_pool.clone(), table.first_directory_page_id);
+    let removed = table_heap.vacuum(|version| is_dead(version, horizon), transaction);
+    for tuple in &removed {
+        let row =
+            Row::from_tuple_data(&tuple.data, &table.schema).map_err(ExecutionError::Storage)?;
+        for table_index in &indexes {
+            let key = index_key(&table_index.info, &row.values);
+            table_index
+                .index
+                .delete_entry(&key, tuple.own_rid, transaction);
+        }
+    }
+    Ok(removed.len())
+}
+
+#[cfg(test)]
+mod vacuum_tests {
+    use std::slice;
+
+    use crate::{
+        catalog::system_catalog::{Catalog, IndexType},
+        disk_management::test_utils::new_test_buffer_pool,
+        execution::executor::{ExecutionContext, TableModifier},
+        index::table_index::Index,
+        transaction::{transaction_manager::TransactionManager, transaction_state::IsolationLevel},
+        types::{
+            row::Row,
+            schema::{Column, DataType, Schema},
+            value::Value,
+        },
+    };
+
+    use super::vacuum_table;
+
+    fn row(id: i32) -> Row {
+        Row::new(vec![Value::Integer(id)])
+    }
+
+    #[test]
+    fn vacuum_removes_versions_no_snapshot_sees() {
+        let buffer_pool = new_test_buffer_pool("vacuum_table");
+        let mut catalog = Catalog::open(buffer_pool.clone(), None).unwrap();
+        catalog
+            .create_table(
+                "numbers",
+                Schema::new(vec![Column::new("n", DataType::Integer, false)]),
+                None,
+            )
+            .unwrap();
+        let index_info = catalog
+            .create_index("numbers_n", "numbers", &["n"], IndexType::Hash, true, None)
+            .unwrap()
+            .clone();
+        let table = catalog.get_table("numbers").unwrap().clone();
+        let mut transaction_manager = TransactionManager::new(buffer_pool.clone());
+        let lock_manager = transaction_manager.lock_manager();
+        let modifier = |transaction| {
+            let context = ExecutionContext::with_transaction(
+                buffer_pool.clone(),
+                transaction,
+                lock_manager.clone(),
+            );
+            TableModifier::new(&context, &table, slice::from_ref(&index_info)).unwrap()
+        };
+
+        let transaction = transaction_manager.begin(IsolationLevel::default());
+        let one = modifier(transaction.clone()).insert(&row(1)).unwrap();
+        let two = modifier(transaction.clone()).insert(&row(2)).unwrap();
+        transaction_manager.commit(&transaction).unwrap();
+
+        let reader = transaction_manager.begin(IsolationLevel::default());
+        let writer = transaction_manager.begin(IsolationLevel::default());
+        modifier(writer.clone()).delete(one, &row(1)).unwrap();
+        modifier(writer.clone())
+            .update(two, &row(2), &row(3))
+            .unwrap();
+        transaction_manager.commit(&writer).unwrap();
+
+        // The reader still sees the old versions.
+        let horizon = transaction_manager.vacuum_horizon();
+        assert_eq!(horizon, reader.id());
+        assert_eq!(
+            vacuum_table(
+                &buffer_pool,
+                &table,
+                slice::from_ref(&index_info),
+                horizon,
+                None
+            ),
+            Ok(0)
+        );
+
+        transaction_manager.commit(&reader).unwrap();
+        let horizon = transaction_manager.vacuum_horizon();
+        assert_eq!(
+            vacuum_table(
+                &buffer_pool,
+                &table,
+                slice::from_ref(&index_info),
+                horizon,
+                None
+            ),
+            Ok(2)
+        );
+        let rows: Vec<_> = catalog
+            .get_table_heap(&table)
+            .iter()
+            .map(Result::unwrap)
+            .map(|(_, tuple)| Row::from_tuple_data(&tuple.data, &table.schema).unwrap())
+            .collect();
+        assert_eq!(rows, vec![row(3)]);
+        let index = Index::open(buffer_pool.clone(), &index_info).unwrap();
+        assert!(index.scan_key(&[Value::Integer(1)]).is_empty());
+        assert!(index.scan_key(&[Value::Integer(2)]).is_empty());
+        assert_eq!(index.scan_key(&[Value::Integer(3)]).len(), 1);
+    }
+}
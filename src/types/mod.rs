@@ -0,0 +1,3 @@
+pub mod row;
+pub mod schema;
+pub mod value;
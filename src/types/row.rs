@@ -0,0 +1,219 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use super::{
+    schema::{DataType, Schema},
+    value::Value,
+};
+
+// ROW FORMAT:
+// | NULL BITMAP [ceil(columns / 8) bytes] | FIXED-SIZE SECTION | VARIABLE-LENGTH DATA |
+//
+// Bit i of the null bitmap is set if column i is NULL. The fixed-size section stores one entry per
+// column in schema order: fixed-size values directly (little endian), variable-length values as
+// OFFSET [u32] + LENGTH [u32], where the offset is relative to the start of the row. NULL values
+// keep their zeroed entry in the fixed-size section.
+//
+// DATE values are stored as days since 1970-01-01 [i32], TIMESTAMP values as microseconds since
+// 1970-01-01 00:00:00 [i64].
+
+/// Number of days between 0001-01-01 and 1970-01-01.
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Row {
+    pub values: Vec<Value>,
+}
+
+impl Row {
+    pub fn new(values: Vec<Value>) -> Row {
+        Row { values }
+    }
+
+    /// Serializes the row into tuple data. Fails if the row does not conform to the schema.
+    pub fn to_tuple_data(&self, schema: &Schema) -> Result<Vec<u8>, &'static str> {
+        if self.values.len() != schema.columns.len() {
+            return Err("The number of values does not match the number of columns");
+        }
+
+        let mut data = vec![0; schema.fixed_size()];
+        let mut fixed_offset = schema.null_bitmap_size();
+        for (i, (value, column)) in self.values.iter().zip(&schema.columns).enumerate() {
+            let entry_size = column.data_type.fixed_size();
+            if !value.matches_type(&column.data_type) {
+                return Err("The value does not match the type of the column");
+            }
+
+            let entry: Vec<u8> = match value {
+                Value::Null => {
+                    if !column.nullable {
+                        return Err("NULL value in a column that is not nullable");
+                    }
+                    data[i / 8] |= 1 << (i % 8);
+                    vec![0; entry_size]
+                }
+                Value::Integer(value) => value.to_le_bytes().to_vec(),
+                Value::BigInt(value) => value.to_le_bytes().to_vec(),
+                Value::Float(value) => value.to_le_bytes().to_vec(),
+                Value::Boolean(value) => vec![*value as u8],
+                Value::Date(value) => (value.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE)
+                    .to_le_bytes()
+                    .to_vec(),
+                Value::Timestamp(value) => {
+                    value.and_utc().timestamp_micros().to_le_bytes().to_vec()
+                }
+                Value::String(value) => {
+                    if let DataType::Varchar(max_length) = column.data_type {
+                        if value.chars().count() > max_length as usize {
+                            return Err("The value is too long for the VARCHAR column");
+                        }
+                    }
+                    append_variable_length(&mut data, value.as_bytes())
+                }
+                Value::Blob(value) => append_variable_length(&mut data, value),
+            };
+            data[fixed_offset..fixed_offset + entry_size].copy_from_slice(&entry);
+            fixed_offset += entry_size;
+        }
+        Ok(data)
+    }
+
+    /// Deserializes a row that was serialized with [Row::to_tuple_data] using the same schema.
+    pub fn from_tuple_data(data: &[u8], schema: &Schema) -> Result<Row, &'static str> {
+        if data.len() < schema.fixed_size() {
+            return Err("The tuple is too short for the schema");
+        }
+
+        let mut values = Vec::with_capacity(schema.columns.len());
+        let mut fixed_offset = schema.null_bitmap_size();
+        for (i, column) in schema.columns.iter().enumerate() {
+            let entry_size = column.data_type.fixed_size();
+            let entry = &data[fixed_offset..fixed_offset + entry_size];
+            fixed_offset += entry_size;
+            if data[i / 8] & (1 << (i % 8)) != 0 {
+                values.push(Value::Null);
+                continue;
+            }
+
+            let value = match column.data_type {
+                DataType::Integer => Value::Integer(i32::from_le_bytes(entry.try_into().unwrap())),
+                DataType::BigInt => Value::BigInt(i64::from_le_bytes(entry.try_into().unwrap())),
+                DataType::Float => Value::Float(f64::from_le_bytes(entry.try_into().unwrap())),
+                DataType::Boolean => Value::Boolean(entry[0] != 0),
+                DataType::Date => {
+                    let days = i32::from_le_bytes(entry.try_into().unwrap());
+                    Value::Date(
+                        NaiveDate::from_num_days_from_ce_opt(days + UNIX_EPOCH_DAYS_FROM_CE)
+                            .ok_or("Invalid DATE value")?,
+                    )
+                }
+                DataType::Timestamp => {
+                    let micros = i64::from_le_bytes(entry.try_into().unwrap());
+                    Value::Timestamp(
+                        DateTime::from_timestamp_micros(micros)
+                            .ok_or("Invalid TIMESTAMP value")?
+                            .naive_utc(),
+                    )
+                }
+                DataType::Varchar(_) | DataType::Text => {
+                    let bytes = read_variable_length(data, entry)?;
+                    Value::String(
+                        String::from_utf8(bytes.to_vec()).or(Err("Invalid UTF-8 string"))?,
+                    )
+                }
+                DataType::Blob => Value::Blob(read_variable_length(data, entry)?.to_vec()),
+            };
+            values.push(value);
+        }
+        Ok(Row::new(values))
+    }
+}
+
+/// Appends the bytes to the variable-length section and returns the fixed-size entry pointing to
+/// them.
+fn append_variable_length(data: &mut Vec<u8>, bytes: &[u8]) -> Vec<u8> {
+    let mut entry = (data.len() as u32).to_le_bytes().to_vec();
+    entry.extend((bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bytes);
+    entry
+}
+
+fn read_variable_length<'a>(data: &'a [u8], entry: &[u8]) -> Result<&'a [u8], &'static str> {
+    let offset = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as usize;
+    let length = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as usize;
+    data.get(offset..offset + length)
+        .ok_or("Variable-length value out of bounds")
+}
+
+#[cfg(test)]
+mod row_tests {
+    use chrono::NaiveDate;
+
+    use crate::types::{
+        schema::{Column, DataType, Schema},
+        value::Value,
+    };
+
+    use super::Row;
+
+    fn all_types_schema() -> Schema {
+        Schema::new(vec![
+            Column::new("id", DataType::Integer, false),
+            Column::new("big", DataType::BigInt, true),
+            Column::new("ratio", DataType::Float, true),
+            Column::new("flag", DataType::Boolean, true),
+            Column::new("name", DataType::Varchar(8), true),
+            Column::new("description", DataType::Text, true),
+            Column::new("payload", DataType::Blob, true),
+            Column::new("day", DataType::Date, true),
+            Column::new("created", DataType::Timestamp, true),
+        ])
+    }
+
+    #[test]
+    fn round_trip_all_types() {
+        let schema = all_types_schema();
+        let date = NaiveDate::from_ymd_opt(1969, 7, 20).unwrap();
+        let row = Row::new(vec![
+            Value::Integer(-42),
+            Value::BigInt(1 << 40),
+            Value::Float(2.5),
+            Value::Boolean(true),
+            Value::String("héllo".to_string()),
+            Value::String("x".repeat(1000)),
+            Value::Blob(vec![0, 1, 2, 255]),
+            Value::Date(date),
+            Value::Timestamp(date.and_hms_micro_opt(20, 17, 40, 123_456).unwrap()),
+        ]);
+
+        let data = row.to_tuple_data(&schema).unwrap();
+        assert_eq!(Row::from_tuple_data(&data, &schema).unwrap(), row);
+    }
+
+    #[test]
+    fn round_trip_nulls() {
+        let schema = all_types_schema();
+        let mut values = vec![Value::Null; 9];
+        values[0] = Value::Integer(1);
+        values[5] = Value::String(String::new());
+        let row = Row::new(values);
+
+        let data = row.to_tuple_data(&schema).unwrap();
+        assert_eq!(data[0..2], [0b1101_1110, 0b1]);
+        assert_eq!(Row::from_tuple_data(&data, &schema).unwrap(), row);
+    }
+
+    #[test]
+    fn invalid_rows_are_rejected() {
+        let schema = Schema::new(vec![
+            Column::new("id", DataType::Integer, false),
+            Column::new("name", DataType::Varchar(3), true),
+        ]);
+        let encode = |values: Vec<Value>| Row::new(values).to_tuple_data(&schema);
+
+        assert!(encode(vec![Value::Null, Value::Null]).is_err());
+        assert!(encode(vec![Value::BigInt(1), Value::Null]).is_err());
+        assert!(encode(vec![Value::Integer(1), Value::String("abcd".to_string())]).is_err());
+        assert!(encode(vec![Value::Integer(1)]).is_err());
+        assert!(encode(vec![Value::Integer(1), Value::String("abc".to_string())]).is_ok());
+    }
+}
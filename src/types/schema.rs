@@ -0,0 +1,136 @@
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Integer,
+    BigInt,
+    Float,
+    Boolean,
+    /// A string with a maximum length in characters.
+    Varchar(u32),
+    Text,
+    Blob,
+    Date,
+    Timestamp,
+}
+
+impl DataType {
+    /// Returns the number of bytes a value of this type occupies in the fixed-size section of a
+    /// row. Variable-length types store an offset and a length there.
+    pub fn fixed_size(&self) -> usize {
+        match self {
+            DataType::Integer | DataType::Date => 4,
+            DataType::BigInt | DataType::Float | DataType::Timestamp => 8,
+            DataType::Boolean => 1,
+            DataType::Varchar(_) | DataType::Text | DataType::Blob => 8,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, DataType::Integer | DataType::BigInt | DataType::Float)
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, DataType::Varchar(_) | DataType::Text)
+    }
+
+    /// Returns the smallest numeric type both numeric types can be converted to without losing
+    /// their range.
+    pub fn common_numeric_type(&self, other: &DataType) -> Option<DataType> {
+        if !self.is_numeric() || !other.is_numeric() {
+            return None;
+        }
+        let rank = |data_type: &DataType| match data_type {
+            DataType::Integer => 0,
+            DataType::BigInt => 1,
+            _ => 2,
+        };
+        Some(match rank(self).max(rank(other)) {
+            0 => DataType::Integer,
+            1 => DataType::BigInt,
+            _ => DataType::Float,
+        })
+    }
+
+    /// Returns true if values of this type can be converted to the other type with an explicit
+    /// CAST. The conversion of a single value may still fail, e.g. for strings that are not
+    /// numbers.
+    pub fn can_cast_to(&self, other: &DataType) -> bool {
+        if self == other || other.is_string() || self.is_string() {
+            return true;
+        }
+        match (self, other) {
+            (from, to) if from.is_numeric() && to.is_numeric() => true,
+            (DataType::Boolean, DataType::Integer | DataType::BigInt) => true,
+            (DataType::Integer | DataType::BigInt, DataType::Boolean) => true,
+            (DataType::Date, DataType::Timestamp) | (DataType::Timestamp, DataType::Date) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for DataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataType::Integer => write!(f, "INTEGER"),
+            DataType::BigInt => write!(f, "BIGINT"),
+            DataType::Float => write!(f, "FLOAT"),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Varchar(length) => write!(f, "VARCHAR({})", length),
+            DataType::Text => write!(f, "TEXT"),
+            DataType::Blob => write!(f, "BLOB"),
+            DataType::Date => write!(f, "DATE"),
+            DataType::Timestamp => write!(f, "TIMESTAMP"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub fn new(name: &str, data_type: DataType, nullable: bool) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type,
+            nullable,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+impl Schema {
+    pub fn new(columns: Vec<Column>) -> Schema {
+        Schema { columns }
+    }
+
+    /// Returns the index of the column with the given name. Column names are case insensitive.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the number of bytes of the null bitmap of a row.
+    pub fn null_bitmap_size(&self) -> usize {
+        self.columns.len().div_ceil(8)
+    }
+
+    /// Returns the number of bytes of the null bitmap and the fixed-size section of a row.
+    pub fn fixed_size(&self) -> usize {
+        self.null_bitmap_size()
+            + self
+                .columns
+                .iter()
+                .map(|column| column.data_type.fixed_size())
+                .sum::<usize>()
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use super::schema::DataType;
+
+/// A single typed value of a row. VARCHAR and TEXT columns both hold [Value::String] values.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Null,
+    Integer(i32),
+    BigInt(i64),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+    Blob(Vec<u8>),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Returns true if the value can be stored in a column of the given type. Null values match
+    /// every type, the nullability is checked by the row codec.
+    pub fn matches_type(&self, data_type: &DataType) -> bool {
+        matches!(
+            (self, data_type),
+            (Value::Null, _)
+                | (Value::Integer(_), DataType::Integer)
+                | (Value::BigInt(_), DataType::BigInt)
+                | (Value::Float(_), DataType::Float)
+                | (Value::Boolean(_), DataType::Boolean)
+                | (Value::String(_), DataType::Varchar(_) | DataType::Text)
+                | (Value::Blob(_), DataType::Blob)
+                | (Value::Date(_), DataType::Date)
+                | (Value::Timestamp(_), DataType::Timestamp)
+        )
+    }
+
+    /// Converts the value to the given type. Fails if the types are not convertible or if the
+    /// value does not fit into the target type.
+    pub fn cast_to(&self, data_type: &DataType) -> Result<Value, String> {
+        let error = || format!("cannot cast {} to {}", self, data_type);
+        let value = match (self, data_type) {
+            (Value::Null, _) => Value::Null,
+            (_, DataType::Varchar(_) | DataType::Text) => {
+                let string = match self {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                if let DataType::Varchar(max_length) = data_type {
+                    if string.chars().count() > *max_length as usize {
+                        return Err(format!("value too long for {}: '{}'", data_type, string));
+                    }
+                }
+                Value::String(string)
+            }
+            (Value::Integer(value), _) => return Value::BigInt(*value as i64).cast_to(data_type),
+            (Value::BigInt(value), DataType::Integer) => {
+                Value::Integer(i32::try_from(*value).map_err(|_| error())?)
+            }
+            (Value::BigInt(value), DataType::BigInt) => Value::BigInt(*value),
+            (Value::BigInt(value), DataType::Float) => Value::Float(*value as f64),
+            (Value::BigInt(value), DataType::Boolean) => Value::Boolean(*value != 0),
+            (Value::Float(value), DataType::Float) => Value::Float(*value),
+            (Value::Float(value), DataType::Integer | DataType::BigInt) => {
+                let rounded = value.round();
+                if !(i64::MIN as f64..i64::MAX as f64).contains(&rounded) {
+                    return Err(error());
+                }
+                return Value::BigInt(rounded as i64).cast_to(data_type);
+            }
+            (Value::Boolean(value), DataType::Boolean) => Value::Boolean(*value),
+            (Value::Boolean(value), DataType::Integer) => Value::Integer(*value as i32),
+            (Value::Boolean(value), DataType::BigInt) => Value::BigInt(*value as i64),
+            (Value::String(value), _) => parse_string(value.trim(), data_type).ok_or_else(error)?,
+            (Value::Blob(value), DataType::Blob) => Value::Blob(value.clone()),
+            (Value::Date(value), DataType::Date) => Value::Date(*value),
+            (Value::Date(value), DataType::Timestamp) => {
+                Value::Timestamp(value.and_hms_opt(0, 0, 0).unwrap())
+            }
+            (Value::Timestamp(value), DataType::Timestamp) => Value::Timestamp(*value),
+            (Value::Timestamp(value), DataType::Date) => Value::Date(value.date()),
+            _ => return Err(error()),
+        };
+        Ok(value)
+    }
+
+    /// The position of the variant, used to order values of different types.
+    pub fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) => 1,
+            Value::BigInt(_) => 2,
+            Value::Float(_) => 3,
+            Value::Boolean(_) => 4,
+            Value::String(_) => 5,
+            Value::Blob(_) => 6,
+            Value::Date(_) => 7,
+            Value::Timestamp(_) => 8,
+        }
+    }
+}
+
+fn parse_string(string: &str, data_type: &DataType) -> Option<Value> {
+    match data_type {
+        DataType::Integer => string.parse().ok().map(Value::Integer),
+        DataType::BigInt => string.parse().ok().map(Value::BigInt),
+        DataType::Float => string.parse().ok().map(Value::Float),
+        DataType::Boolean => match string.to_lowercase().as_str() {
+            "true" | "t" | "1" => Some(Value::Boolean(true)),
+            "false" | "f" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        DataType::Blob => Some(Value::Blob(string.as_bytes().to_vec())),
+        DataType::Date => NaiveDate::parse_from_str(string, "%Y-%m-%d")
+            .ok()
+            .map(Value::Date),
+        DataType::Timestamp => NaiveDateTime::parse_from_str(string, "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(string, "%Y-%m-%d")
+                    .ok()
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+            })
+            .map(Value::Timestamp),
+        DataType::Varchar(_) | DataType::Text => Some(Value::String(string.to_string())),
+    }
+}
+
+// Values are compared by type first, so that they can be used as keys of sorts, groups and
+// joins. Floats use their total order, NULL is equal to itself and sorts before everything else.
+// SQL comparison semantics are left to the expression evaluation.
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::BigInt(a), Value::BigInt(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Blob(a), Value::Blob(b)) => a.cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+        match self {
+            Value::Null => {}
+            Value::Integer(value) => value.hash(state),
+            Value::BigInt(value) => value.hash(state),
+            Value::Float(value) => value.to_bits().hash(state),
+            Value::Boolean(value) => value.hash(state),
+            Value::String(value) => value.hash(state),
+            Value::Blob(value) => value.hash(state),
+            Value::Date(value) => value.hash(state),
+            Value::Timestamp(value) => value.hash(state),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::BigInt(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Blob(value) => {
+                write!(f, "x'")?;
+                for byte in value {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Value::Date(value) => write!(f, "{}", value.format("%Y-%m-%d")),
+            Value::Timestamp(value) => write!(f, "{}", value.format("%Y-%m-%d %H:%M:%S%.f")),
+        }
+    }
+}
+
+#[test]
+fn values_are_ordered_within_their_type() {
+    assert!(Value::Integer(-3) < Value::Integer(2));
+    assert!(Value::Float(-0.5) < Value::Float(1.5));
+    assert!(Value::String("abc".to_string()) < Value::String("abd".to_string()));
+    assert!(Value::Null < Value::Integer(i32::MIN));
+    assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+    assert_ne!(Value::Integer(1), Value::BigInt(1));
+}
+
+#[test]
+fn cast_values() {
+    let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+    assert_eq!(
+        Value::String(" 2024-02-29 ".to_string()).cast_to(&DataType::Date),
+        Ok(Value::Date(date))
+    );
+    assert_eq!(
+        Value::Date(date).cast_to(&DataType::Text),
+        Ok(Value::String("2024-02-29".to_string()))
+    );
+    assert_eq!(
+        Value::Float(2.5).cast_to(&DataType::Integer),
+        Ok(Value::Integer(3))
+    );
+    assert_eq!(
+        Value::Integer(7).cast_to(&DataType::Float),
+        Ok(Value::Float(7.0))
+    );
+    assert!(Value::BigInt(1 << 40).cast_to(&DataType::Integer).is_err());
+    assert!(Value::String("abc".to_string())
+        .cast_to(&DataType::Integer)
+        .is_err());
+    assert!(Value::String("abcd".to_string())
+        .cast_to(&DataType::Varchar(3))
+        .is_err());
+    assert_eq!(Value::Null.cast_to(&DataType::Date), Ok(Value::Null));
+}